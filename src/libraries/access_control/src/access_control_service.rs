@@ -0,0 +1,406 @@
+use std::cell::RefCell;
+use candid::Principal;
+
+use errors::internal_error::error::{InternalError, InternalErrorKind};
+use errors::internal_error::error_codes::module::areas::{
+    libraries as libraries_area,
+    libraries::domains::access_control as access_control_domain,
+    libraries::domains::access_control::components as access_control_domain_components,
+};
+
+use crate::role::{AccessControlState, PendingRoleGrant, PendingRoleRevocation, Role, RoleAssignment};
+
+// Module code: "02-05-01"
+errors::define_error_code_builder_fn!(
+    build_error_code,
+    libraries_area::AREA_CODE,              // Area code: "02"
+    access_control_domain::DOMAIN_CODE,     // Domain code: "05"
+    access_control_domain_components::CORE  // Component code: "01"
+);
+
+thread_local! {
+    static ACCESS_CONTROL: RefCell<AccessControlState> = RefCell::new(AccessControlState::default());
+}
+
+pub fn get_state() -> AccessControlState {
+    ACCESS_CONTROL.with(|state| state.borrow().clone())
+}
+
+pub fn set_state(state: AccessControlState) {
+    ACCESS_CONTROL.with(|cell| *cell.borrow_mut() = state);
+}
+
+/// Assigns the admin role to the principals that don't have it yet.
+pub fn init_admins(admins: Vec<Principal>) {
+    for principal in admins {
+        init_role(principal, Role::Admin);
+    }
+}
+
+/// Assigns the role without a grant, for the principals set at install or upgrade.
+pub fn init_role(principal: Principal, role: Role) {
+    ACCESS_CONTROL.with(|state| {
+        let mut state = state.borrow_mut();
+
+        if !is_assigned(&state, principal, role) {
+            state.assignments.push(RoleAssignment { principal, role });
+        }
+    });
+}
+
+/// Checks the role of the principal, the canister controllers and admins have every role.
+pub fn has_role(principal: Principal, role: Role) -> bool {
+    if is_canister_controller(&principal) {
+        return true;
+    }
+
+    ACCESS_CONTROL.with(|state| {
+        let state = state.borrow();
+
+        is_assigned(&state, principal, Role::Admin) || is_assigned(&state, principal, role)
+    })
+}
+
+pub fn require_role(principal: Principal, role: Role) -> Result<(), InternalError> {
+    if has_role(principal, role) {
+        return Ok(());
+    }
+
+    Err(InternalError::access_denied(
+        build_error_code(InternalErrorKind::AccessDenied, 1), // Error code: "02-05-01 05 01"
+        "access_control_service::require_role".to_string(),
+        format!("Caller doesn't have the {role:?} role"),
+        errors::error_extra! {
+            "principal" => principal,
+            "role" => role,
+        },
+    ))
+}
+
+/// First step of a grant, the role is effective once the principal accepts it.
+///
+/// A previous pending grant of the same role to the principal is replaced.
+pub fn grant_role(caller: Principal, principal: Principal, role: Role) -> Result<(), InternalError> {
+    require_role(caller, Role::Admin)?;
+
+    ACCESS_CONTROL.with(|state| {
+        let mut state = state.borrow_mut();
+
+        if is_assigned(&state, principal, role) {
+            return Err(InternalError::validation(
+                build_error_code(InternalErrorKind::Validation, 2), // Error code: "02-05-01 02 02"
+                "access_control_service::grant_role".to_string(),
+                "Principal already has the role".to_string(),
+                errors::error_extra! {
+                    "principal" => principal,
+                    "role" => role,
+                },
+            ));
+        }
+
+        state.pending_grants.retain(|grant| !(grant.principal == principal && grant.role == role));
+        state.pending_grants.push(PendingRoleGrant { principal, role, granted_by: caller });
+
+        Ok(())
+    })
+}
+
+/// Second step of a grant, made by the grantee.
+pub fn accept_role(caller: Principal, role: Role) -> Result<(), InternalError> {
+    ACCESS_CONTROL.with(|state| {
+        let mut state = state.borrow_mut();
+
+        let index = state.pending_grants.iter()
+            .position(|grant| grant.principal == caller && grant.role == role)
+            .ok_or_else(|| {
+                InternalError::not_found(
+                    build_error_code(InternalErrorKind::NotFound, 3), // Error code: "02-05-01 01 03"
+                    "access_control_service::accept_role".to_string(),
+                    "Pending role grant not found".to_string(),
+                    errors::error_extra! {
+                        "caller" => caller,
+                        "role" => role,
+                    },
+                )
+            })?;
+
+        state.pending_grants.remove(index);
+        state.assignments.push(RoleAssignment { principal: caller, role });
+
+        Ok(())
+    })
+}
+
+/// First step of a revocation, the role is removed once another admin confirms it.
+///
+/// A pending grant of the role is cancelled at once, the principal doesn't have the role yet.
+pub fn revoke_role(caller: Principal, principal: Principal, role: Role) -> Result<(), InternalError> {
+    require_role(caller, Role::Admin)?;
+
+    ACCESS_CONTROL.with(|state| {
+        let mut state = state.borrow_mut();
+
+        let pending_grants_count = state.pending_grants.len();
+        state.pending_grants.retain(|grant| !(grant.principal == principal && grant.role == role));
+
+        if is_assigned(&state, principal, role) {
+            state.pending_revocations.retain(|revocation| !(revocation.principal == principal && revocation.role == role));
+            state.pending_revocations.push(PendingRoleRevocation { principal, role, revoked_by: caller });
+
+            return Ok(());
+        }
+
+        if state.pending_grants.len() == pending_grants_count {
+            return Err(InternalError::not_found(
+                build_error_code(InternalErrorKind::NotFound, 4), // Error code: "02-05-01 01 04"
+                "access_control_service::revoke_role".to_string(),
+                "Role not found for principal".to_string(),
+                errors::error_extra! {
+                    "principal" => principal,
+                    "role" => role,
+                },
+            ));
+        }
+
+        Ok(())
+    })
+}
+
+/// Second step of a revocation, made by an admin other than the one who requested it.
+pub fn confirm_revoke_role(caller: Principal, principal: Principal, role: Role) -> Result<(), InternalError> {
+    require_role(caller, Role::Admin)?;
+
+    ACCESS_CONTROL.with(|state| {
+        let mut state = state.borrow_mut();
+
+        let index = state.pending_revocations.iter()
+            .position(|revocation| revocation.principal == principal && revocation.role == role)
+            .ok_or_else(|| {
+                InternalError::not_found(
+                    build_error_code(InternalErrorKind::NotFound, 5), // Error code: "02-05-01 01 05"
+                    "access_control_service::confirm_revoke_role".to_string(),
+                    "Pending role revocation not found".to_string(),
+                    errors::error_extra! {
+                        "principal" => principal,
+                        "role" => role,
+                    },
+                )
+            })?;
+
+        if state.pending_revocations[index].revoked_by == caller {
+            return Err(InternalError::access_denied(
+                build_error_code(InternalErrorKind::AccessDenied, 6), // Error code: "02-05-01 05 06"
+                "access_control_service::confirm_revoke_role".to_string(),
+                "Revocation must be confirmed by another admin".to_string(),
+                errors::error_extra! {
+                    "caller" => caller,
+                    "principal" => principal,
+                    "role" => role,
+                },
+            ));
+        }
+
+        state.pending_revocations.remove(index);
+        state.assignments.retain(|assignment| !(assignment.principal == principal && assignment.role == role));
+
+        Ok(())
+    })
+}
+
+fn is_assigned(state: &AccessControlState, principal: Principal, role: Role) -> bool {
+    state.assignments.iter()
+        .any(|assignment| assignment.principal == principal && assignment.role == role)
+}
+
+/// Controllers of the canister keep the admin access, so a lost admin can always be replaced.
+fn is_canister_controller(principal: &Principal) -> bool {
+    #[cfg(target_arch = "wasm32")]
+    {
+        ic_cdk::api::is_controller(principal)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let _ = principal;
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn principal(id: u8) -> Principal {
+        Principal::from_slice(&[id; 29])
+    }
+
+    fn setup_admin() -> Principal {
+        set_state(AccessControlState::default());
+        init_admins(vec![principal(1)]);
+
+        principal(1)
+    }
+
+    mod init_admins {
+        use super::*;
+
+        #[test]
+        fn does_not_duplicate_admins() {
+            setup_admin();
+            init_admins(vec![principal(1), principal(2)]);
+
+            assert_eq!(get_state().assignments.len(), 2);
+        }
+    }
+
+    mod require_role {
+        use super::*;
+
+        #[test]
+        fn admin_has_every_role() {
+            let admin = setup_admin();
+
+            assert!(require_role(admin, Role::Operator).is_ok());
+            assert!(require_role(admin, Role::Viewer).is_ok());
+        }
+
+        #[test]
+        fn returns_access_denied_without_role() {
+            setup_admin();
+
+            let error = require_role(principal(2), Role::Operator).unwrap_err();
+
+            assert!(matches!(error.kind, InternalErrorKind::AccessDenied));
+        }
+    }
+
+    mod grant_role {
+        use super::*;
+
+        #[test]
+        fn role_is_effective_after_acceptance() {
+            let admin = setup_admin();
+
+            grant_role(admin, principal(2), Role::Operator).unwrap();
+            assert!(!has_role(principal(2), Role::Operator));
+
+            accept_role(principal(2), Role::Operator).unwrap();
+            assert!(has_role(principal(2), Role::Operator));
+            assert!(get_state().pending_grants.is_empty());
+        }
+
+        #[test]
+        fn requires_admin() {
+            setup_admin();
+
+            let result = grant_role(principal(2), principal(3), Role::Operator);
+
+            assert!(matches!(result.unwrap_err().kind, InternalErrorKind::AccessDenied));
+        }
+
+        #[test]
+        fn rejects_already_assigned_role() {
+            let admin = setup_admin();
+
+            let result = grant_role(admin, admin, Role::Admin);
+
+            assert!(matches!(result.unwrap_err().kind, InternalErrorKind::Validation));
+        }
+
+        #[test]
+        fn replaces_pending_grant() {
+            let admin = setup_admin();
+
+            grant_role(admin, principal(2), Role::Pauser).unwrap();
+            grant_role(admin, principal(2), Role::Pauser).unwrap();
+
+            assert_eq!(get_state().pending_grants.len(), 1);
+        }
+    }
+
+    mod accept_role {
+        use super::*;
+
+        #[test]
+        fn fails_without_pending_grant() {
+            setup_admin();
+
+            let result = accept_role(principal(2), Role::Viewer);
+
+            assert!(matches!(result.unwrap_err().kind, InternalErrorKind::NotFound));
+        }
+    }
+
+    mod revoke_role {
+        use super::*;
+
+        #[test]
+        fn keeps_role_until_confirmed() {
+            let admin = setup_admin();
+            init_role(principal(2), Role::Operator);
+
+            revoke_role(admin, principal(2), Role::Operator).unwrap();
+
+            assert!(has_role(principal(2), Role::Operator));
+            assert_eq!(get_state().pending_revocations.len(), 1);
+        }
+
+        #[test]
+        fn cancels_pending_grant() {
+            let admin = setup_admin();
+            grant_role(admin, principal(2), Role::Operator).unwrap();
+
+            revoke_role(admin, principal(2), Role::Operator).unwrap();
+
+            assert!(accept_role(principal(2), Role::Operator).is_err());
+        }
+
+        #[test]
+        fn fails_for_missing_role() {
+            let admin = setup_admin();
+
+            let result = revoke_role(admin, principal(2), Role::Viewer);
+
+            assert!(matches!(result.unwrap_err().kind, InternalErrorKind::NotFound));
+        }
+    }
+
+    mod confirm_revoke_role {
+        use super::*;
+
+        #[test]
+        fn removes_role_confirmed_by_another_admin() {
+            let admin = setup_admin();
+            init_admins(vec![principal(3)]);
+            init_role(principal(2), Role::Operator);
+            revoke_role(admin, principal(2), Role::Operator).unwrap();
+
+            confirm_revoke_role(principal(3), principal(2), Role::Operator).unwrap();
+
+            assert!(!has_role(principal(2), Role::Operator));
+            assert!(get_state().pending_revocations.is_empty());
+        }
+
+        #[test]
+        fn rejects_confirmation_by_requesting_admin() {
+            let admin = setup_admin();
+            init_role(principal(2), Role::Operator);
+            revoke_role(admin, principal(2), Role::Operator).unwrap();
+
+            let result = confirm_revoke_role(admin, principal(2), Role::Operator);
+
+            assert!(matches!(result.unwrap_err().kind, InternalErrorKind::AccessDenied));
+            assert!(has_role(principal(2), Role::Operator));
+        }
+
+        #[test]
+        fn fails_without_pending_revocation() {
+            let admin = setup_admin();
+            init_role(principal(2), Role::Operator);
+
+            let result = confirm_revoke_role(admin, principal(2), Role::Operator);
+
+            assert!(matches!(result.unwrap_err().kind, InternalErrorKind::NotFound));
+        }
+    }
+}
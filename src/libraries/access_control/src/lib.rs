@@ -0,0 +1,14 @@
+pub mod role;
+pub mod access_control_service;
+
+/// Returns the access denied error in the endpoint result when the caller doesn't have the role.
+///
+/// `$result` is the tuple struct wrapping the `Result` returned by the endpoint.
+#[macro_export]
+macro_rules! return_if_unauthorized {
+    ($role:expr, $result:path) => {
+        if let Err(error) = $crate::access_control_service::require_role(::ic_cdk::caller(), $role) {
+            return $result(Err(::errors::response_error::error::ResponseError::from_internal_error(error)));
+        }
+    };
+}
@@ -0,0 +1,51 @@
+use candid::{CandidType, Deserialize, Principal};
+use serde::Serialize;
+
+use errors::response_error::error::ResponseError;
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// Manages the configuration and the roles, passes the check of every other role
+    Admin,
+    /// Runs rebalances, compounding and snapshots
+    Operator,
+    /// Pauses and resumes operations
+    Pauser,
+    /// Reads the access control and operational data
+    Viewer,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct RoleAssignment {
+    pub principal: Principal,
+    pub role: Role,
+}
+
+/// Role granted by an admin, effective once the grantee accepts it.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct PendingRoleGrant {
+    pub principal: Principal,
+    pub role: Role,
+    pub granted_by: Principal,
+}
+
+/// Role revocation requested by an admin, effective once another admin confirms it.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct PendingRoleRevocation {
+    pub principal: Principal,
+    pub role: Role,
+    pub revoked_by: Principal,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccessControlState {
+    pub assignments: Vec<RoleAssignment>,
+    pub pending_grants: Vec<PendingRoleGrant>,
+    pub pending_revocations: Vec<PendingRoleRevocation>,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct AccessControlResult(pub Result<(), ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct GetAccessControlStateResult(pub Result<AccessControlState, ResponseError>);
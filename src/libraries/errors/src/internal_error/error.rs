@@ -147,6 +147,21 @@ impl InternalError {
             extra
         )
     }
+
+    pub fn access_denied(
+        code: ErrorCode,
+        context: String,
+        message: String,
+        extra: ErrorExtra
+    ) -> Self {
+        Self::new(
+            code,
+            InternalErrorKind::AccessDenied,
+            context,
+            message,
+            extra
+        )
+    }
 }
 
 #[macro_export]
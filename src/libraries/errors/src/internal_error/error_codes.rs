@@ -63,6 +63,12 @@ pub mod module {
                         pub const MOCK_ICP_SWAP: &str = "52";
                     }
                 }
+                pub mod access_control {
+                    pub const DOMAIN_CODE: &str = "05";
+                    pub mod components {
+                        pub const CORE: &str = "01";
+                    }
+                }
             }
         }
         pub mod canisters {
@@ -73,6 +79,13 @@ pub mod module {
                     pub mod components {
                         pub const CORE: &str = "01";
                         pub const STRATEGIES: &str = "02";
+                        pub const REBALANCE: &str = "03";
+                        pub const OPERATION_LOCK: &str = "04";
+                        pub const FEES: &str = "05";
+                        pub const STRATEGY_DEFINITIONS: &str = "06";
+                        pub const COMPOUNDING: &str = "07";
+                        pub const PREVIEW: &str = "08";
+                        pub const EMERGENCY: &str = "09";
                     }
                 }
                 pub mod pool_stats {
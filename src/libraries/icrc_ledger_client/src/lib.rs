@@ -28,7 +28,9 @@ errors::define_error_code_builder_fn!(
 #[async_trait::async_trait]
 pub trait ICRCLedgerClient: Send + Sync + Debug {
     async fn icrc1_decimals(&self, canister_id: CanisterId) -> Result<u8, InternalError>;
+    async fn icrc1_symbol(&self, canister_id: CanisterId) -> Result<String, InternalError>;
     async fn icrc1_fee(&self, canister_id: CanisterId) -> Result<Nat, InternalError>;
+    async fn icrc1_balance_of(&self, canister_id: CanisterId, account: Account) -> Result<Nat, InternalError>;
     async fn icrc2_approve(
         &self, spender: Principal,
         canister_id: CanisterId,
@@ -36,7 +38,7 @@ pub trait ICRCLedgerClient: Send + Sync + Debug {
     ) -> Result<Nat, InternalError>;
     async fn icrc2_transfer_from(
         &self,
-        from: Principal,
+        from: Account,
         canister_id: CanisterId,
         amount: Nat
     ) -> Result<Nat, InternalError>;
@@ -62,6 +64,21 @@ impl ICRCLedgerClient for DefaultICRCLedgerClient {
             })
     }
 
+    async fn icrc1_symbol(&self, canister_id: CanisterId) -> Result<String, InternalError> {
+        icrc_ledger_canister_c2c_client::icrc1_symbol(canister_id)
+            .await
+            .map_err(|error| {
+                InternalError::external_service(
+                    build_error_code(InternalErrorKind::ExternalService, 7), // Error code: "01-03-01 04 07"
+                    "icrc_ledger_client::icrc1_symbol".to_string(),
+                    format!("IC error calling 'icrc_ledger_canister_c2c_client::icrc1_symbol': {error:?}"),
+                    errors::error_extra! {
+                        "canister_id" => canister_id.to_text(),
+                    }
+                )
+            })
+    }
+
     async fn icrc2_approve(
         &self,
         spender: Principal,
@@ -118,13 +135,13 @@ impl ICRCLedgerClient for DefaultICRCLedgerClient {
 
     async fn icrc2_transfer_from(
         &self,
-        from: Principal,
+        from: Account,
         canister_id: CanisterId,
         amount: Nat,
     ) -> Result<Nat, InternalError> {
         let args = Icrc2TransferFromArgs {
             spender_subaccount: None,
-            from: Account { owner: from, subaccount: None },
+            from,
             to: Account { owner: id(), subaccount: None },
             amount: amount.clone(),
             fee: None,
@@ -142,7 +159,7 @@ impl ICRCLedgerClient for DefaultICRCLedgerClient {
                     "icrc_ledger_client::icrc2_transfer_from".to_string(),
                     format!("IC error calling 'icrc_ledger_canister_c2c_client::icrc2_transfer_from': {error:?}"),
                     errors::error_extra! {
-                        "from" => from.to_text(),
+                        "from" => from.to_string(),
                         "canister_id" => canister_id.to_text(),
                         "amount" => amount,
                     }
@@ -154,7 +171,7 @@ impl ICRCLedgerClient for DefaultICRCLedgerClient {
                     "icrc_ledger_client::icrc2_transfer_from".to_string(),
                     format!("Error calling 'icrc_ledger_canister_c2c_client::icrc2_transfer_from': {err:?}"),
                     errors::error_extra! {
-                        "from" => from.to_text(),
+                        "from" => from.to_string(),
                         "canister_id" => canister_id.to_text(),
                         "amount" => amount,
                     }
@@ -177,4 +194,20 @@ impl ICRCLedgerClient for DefaultICRCLedgerClient {
                 )
             })
     }
+
+    async fn icrc1_balance_of(&self, canister_id: CanisterId, account: Account) -> Result<Nat, InternalError> {
+        icrc_ledger_canister_c2c_client::icrc1_balance_of(canister_id, &account)
+            .await
+            .map_err(|error| {
+                InternalError::external_service(
+                    build_error_code(InternalErrorKind::ExternalService, 8), // Error code: "01-03-01 04 08"
+                    "icrc_ledger_client::icrc1_balance_of".to_string(),
+                    format!("IC error calling 'icrc_ledger_canister_c2c_client::icrc1_balance_of': {error:?}"),
+                    errors::error_extra! {
+                        "canister_id" => canister_id.to_text(),
+                        "account" => account.to_string(),
+                    }
+                )
+            })
+    }
 }
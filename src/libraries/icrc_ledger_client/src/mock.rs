@@ -10,6 +10,8 @@ use errors::internal_error::error_codes::module::areas::{
     external_services::domains::icrc_ledger::components as icrc_ledger_domain_components,
 };
 
+use icrc_ledger_types::icrc1::account::Account;
+
 use crate::ICRCLedgerClient;
 
 // Module code: "01-03-51"
@@ -23,18 +25,22 @@ errors::define_error_code_builder_fn!(
 #[derive(CandidType, Debug, Clone, Serialize, Deserialize)]
 pub struct MockICRCLedgerClient {
     decimals_responses: HashMap<CanisterId, Result<u8, InternalError>>,
+    symbol_responses: HashMap<CanisterId, Result<String, InternalError>>,
     approve_responses: HashMap<(String, String, String), Result<Nat, InternalError>>,
     transfer_from_responses: HashMap<(String, String, String), Result<Nat, InternalError>>,
     fee_responses: HashMap<CanisterId, Result<Nat, InternalError>>,
+    balance_of_responses: HashMap<(String, String), Result<Nat, InternalError>>,
 }
 
 impl Default for MockICRCLedgerClient {
     fn default() -> Self {
         Self {
             decimals_responses: HashMap::new(),
+            symbol_responses: HashMap::new(),
             approve_responses: HashMap::new(),
             transfer_from_responses: HashMap::new(),
             fee_responses: HashMap::new(),
+            balance_of_responses: HashMap::new(),
         }
     }
 }
@@ -48,6 +54,10 @@ impl MockICRCLedgerClient {
         self.decimals_responses.insert(canister_id, response);
     }
 
+    pub fn mock_symbol(&mut self, canister_id: CanisterId, response: Result<String, InternalError>) {
+        self.symbol_responses.insert(canister_id, response);
+    }
+
     pub fn mock_approve(
         &mut self,
         spender: Principal,
@@ -63,13 +73,13 @@ impl MockICRCLedgerClient {
 
     pub fn mock_transfer_from(
         &mut self,
-        from: Principal,
+        from: Account,
         canister_id: CanisterId,
         amount: Nat,
         response: Result<Nat, InternalError>,
     ) {
         self.transfer_from_responses.insert(
-            (from.to_text(), canister_id.to_text(), amount.to_string()),
+            (from.to_string(), canister_id.to_text(), amount.to_string()),
             response
         );
     }
@@ -77,6 +87,10 @@ impl MockICRCLedgerClient {
     pub fn mock_fee(&mut self, canister_id: CanisterId, response: Result<Nat, InternalError>) {
         self.fee_responses.insert(canister_id, response);
     }
+
+    pub fn mock_balance_of(&mut self, canister_id: CanisterId, account: Account, response: Result<Nat, InternalError>) {
+        self.balance_of_responses.insert((canister_id.to_text(), account.to_string()), response);
+    }
 }
 
 #[async_trait::async_trait]
@@ -94,6 +108,19 @@ impl ICRCLedgerClient for MockICRCLedgerClient {
         })
     }
 
+    async fn icrc1_symbol(&self, canister_id: CanisterId) -> Result<String, InternalError> {
+        self.symbol_responses.get(&canister_id).cloned().unwrap_or_else(|| {
+            Err(InternalError::not_found(
+                build_error_code(InternalErrorKind::NotFound, 5), // Error code: "01-03-51 01 05"
+                "MockICRCLedgerClient::icrc1_symbol".to_string(),
+                "Mock response not set for symbol".to_string(),
+                errors::error_extra! {
+                    "canister_id" => canister_id,
+                }
+            ))
+        })
+    }
+
     async fn icrc2_approve(
         &self,
         spender: Principal,
@@ -118,12 +145,12 @@ impl ICRCLedgerClient for MockICRCLedgerClient {
 
     async fn icrc2_transfer_from(
         &self,
-        from: Principal,
+        from: Account,
         canister_id: CanisterId,
         amount: Nat
     ) -> Result<Nat, InternalError> {
         self.transfer_from_responses
-            .get(&(from.to_text(), canister_id.to_text(), amount.to_string()))
+            .get(&(from.to_string(), canister_id.to_text(), amount.to_string()))
             .map_or_else(
                 || Err(InternalError::not_found(
                     build_error_code(InternalErrorKind::NotFound, 3), // Error code: "01-03-51 01 03"
@@ -150,4 +177,21 @@ impl ICRCLedgerClient for MockICRCLedgerClient {
             ))
         })
     }
+
+    async fn icrc1_balance_of(&self, canister_id: CanisterId, account: Account) -> Result<Nat, InternalError> {
+        self.balance_of_responses
+            .get(&(canister_id.to_text(), account.to_string()))
+            .cloned()
+            .unwrap_or_else(|| {
+                Err(InternalError::not_found(
+                    build_error_code(InternalErrorKind::NotFound, 6), // Error code: "01-03-51 01 06"
+                    "MockICRCLedgerClient::icrc1_balance_of".to_string(),
+                    "Mock response not set for balance_of".to_string(),
+                    errors::error_extra! {
+                        "canister_id" => canister_id,
+                        "account" => account,
+                    }
+                ))
+            })
+    }
 }
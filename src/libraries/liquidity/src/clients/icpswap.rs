@@ -28,6 +28,10 @@ use types::liquidity::{
     TokensFee,
     GetPositionByIdResponse,
     GetPoolDataResponse,
+    LiquidityPosition,
+    PositionFeesResponse,
+    ClaimFeesResponse,
+    IncreaseLiquidityResponse,
 };
 use errors::internal_error::error::{InternalError, InternalErrorKind};
 use errors::internal_error::error_codes::module::areas::{
@@ -162,6 +166,80 @@ impl ICPSwapLiquidityClient {
         }
     }
 
+    /// Swaps the order of amounts given in the pool token order if the client tokens are reversed.
+    /// The conversion is symmetric, so it also converts amounts from the client token order.
+    fn to_client_token_order(&self, amount0: Nat, amount1: Nat) -> Result<(Nat, Nat), InternalError> {
+        if self.is_zero_for_one_swap_direction()? {
+            Ok((amount0, amount1))
+        } else {
+            Ok((amount1, amount0))
+        }
+    }
+
+    /// Moves the amount of the token from the caller balance in the pool to the caller wallet.
+    /// Amounts not covering the transfer fee are left in the pool.
+    async fn withdraw_to_wallet(&self, token: CanisterId, amount: Nat) -> Result<Nat, InternalError> {
+        let token_fee = self.icrc_ledger_client.icrc1_fee(token.clone()).await?;
+
+        if amount <= token_fee {
+            return Ok(Nat::from(0u64));
+        }
+
+        let withdrawn_amount = self.withdraw(token, amount, token_fee.clone()).await?;
+
+        // The transfer fee is paid from the withdrawn amount
+        if withdrawn_amount > token_fee {
+            Ok(withdrawn_amount - token_fee)
+        } else {
+            Ok(Nat::from(0u64))
+        }
+    }
+
+    /// Deposits the amount of the token from the caller wallet to the caller balance in the pool.
+    /// Amounts not covering the transfer fee are not deposited.
+    async fn deposit_from_wallet(&self, token: CanisterId, amount: Nat) -> Result<Nat, InternalError> {
+        let token_fee = self.icrc_ledger_client.icrc1_fee(token.clone()).await?;
+
+        // Reserve fee before deposit to avoid InsufficientFunds error
+        if amount <= token_fee.clone() * Nat::from(2u64) {
+            return Ok(Nat::from(0u64));
+        }
+
+        self.icrc_ledger_client.icrc2_approve(
+            self.canister_id(),
+            token.clone(),
+            amount.clone()
+        ).await?;
+
+        self.deposit_from(
+            token,
+            amount - token_fee.clone() * Nat::from(2u64),
+            token_fee
+        ).await
+    }
+
+    /// Checks that the position is held by the caller, so a position id from another
+    /// principal is never increased or decreased.
+    async fn ensure_position_owned(&self, position_id: u64) -> Result<(), InternalError> {
+        let user_position_ids = self.get_user_position_ids_by_principal().await?;
+
+        if !user_position_ids.contains(&Nat::from(position_id)) {
+            return Err(InternalError::business_logic(
+                build_error_code(InternalErrorKind::BusinessLogic, 6), // Error code: "02-02-03 03 06"
+                "ICPSwapLiquidityClient::ensure_position_owned".to_string(),
+                "Position is not held by the caller".to_string(),
+                errors::error_extra! {
+                    "provider" => PROVIDER,
+                    "token0" => self.token0,
+                    "token1" => self.token1,
+                    "position_id" => position_id,
+                },
+            ));
+        }
+
+        Ok(())
+    }
+
     async fn get_pool(
         &self,
         token0: CanisterId,
@@ -456,10 +534,11 @@ impl LiquidityClient for ICPSwapLiquidityClient {
 
     async fn add_liquidity_to_pool(
         &self, 
-        amount: Nat
+        amount: Nat,
+        position_id: Option<u64>
     ) -> Result<AddLiquidityResponse, InternalError> {
         // Flow:
-        // 1. Get user position ids
+        // 1. Check the position belongs to the caller
         // 2. Get token fees
         // 3. Get metadata
         // 4. Approve before deposit
@@ -470,8 +549,10 @@ impl LiquidityClient for ICPSwapLiquidityClient {
 
         let error_context = "ICPSwapLiquidityClient::add_liquidity_to_pool".to_string();
 
-        // 1. Get user position ids
-        let user_position_ids = self.get_user_position_ids_by_principal().await?;
+        // 1. Check the position belongs to the caller
+        if let Some(position_id) = position_id {
+            self.ensure_position_owned(position_id).await?;
+        }
 
         // 2. Get token fees
         let token0_fee = self.icrc_ledger_client.icrc1_fee(self.token0.clone()).await?;
@@ -600,11 +681,11 @@ impl LiquidityClient for ICPSwapLiquidityClient {
             }
         };
 
-        // In case of no position exists, mint new position
-        // In case of position exists, increase liquidity
-        let position_id = match user_position_ids.as_slice() {
-            [] => {
-                // 8. Mint new position if no position exists
+        // Positions of the caller are never shared, so a new position is minted
+        // unless the position to increase is given
+        let position_id = match position_id {
+            None => {
+                // 8. Mint new position
                 self.mint(
                     metadata.token0.address.clone(),
                     metadata.token1.address.clone(),
@@ -615,10 +696,10 @@ impl LiquidityClient for ICPSwapLiquidityClient {
                     TICK_UPPER,
                 ).await?
             }
-            [position_id, ..] => {
-                // 8. Increase liquidity if position already exists
+            Some(position_id) => {
+                // 8. Increase liquidity of the given position
                 self.increase_liquidity(
-                    position_id.clone(),
+                    Nat::from(position_id),
                     amount0_for_position.to_string(),
                     amount1_for_position.to_string(),
                 ).await?
@@ -644,16 +725,18 @@ impl LiquidityClient for ICPSwapLiquidityClient {
             token_1_amount: Nat::from(amount1_swapped_for_pool),
             position_id: nat_to_u64(&position_id),
             token0_equivalent_total,
+            lp_amount: None,
         })
     }
 
     async fn withdraw_liquidity_from_pool(
         &self,
         total_shares: Nat,
-        shares: Nat
+        shares: Nat,
+        position: LiquidityPosition
     ) -> Result<WithdrawLiquidityResponse, InternalError> {
         // Flow:
-        // 1. Get user position ids
+        // 1. Check the position belongs to the caller
         // 2. Get user position
         // 3. Calculate how much liquidity to withdraw
         // 4. Decrease liquidity
@@ -661,19 +744,22 @@ impl LiquidityClient for ICPSwapLiquidityClient {
 
         let error_context = "ICPSwapLiquidityClient::withdraw_liquidity_from_pool".to_string();
 
-        // 1. Get user position ids
-        let user_position_ids = self.get_user_position_ids_by_principal().await?;
-
-        if user_position_ids.is_empty() {
-            return Err(InternalError::business_logic(
+        // 1. Check the position belongs to the caller
+        let position_id = position.position_id
+            .ok_or_else(|| InternalError::business_logic(
                 build_error_code(InternalErrorKind::BusinessLogic, 4), // Error code: "02-02-03 03 04"
                 error_context.clone(),
-                "No position ids found for user".to_string(),
-                None,
-            ));
-        }
+                "No position id given to withdraw from".to_string(),
+                errors::error_extra! {
+                    "provider" => PROVIDER,
+                    "token0" => self.token0,
+                    "token1" => self.token1,
+                },
+            ))?;
+
+        self.ensure_position_owned(position_id).await?;
 
-        let position_id = user_position_ids[0].clone();
+        let position_id = Nat::from(position_id);
 
         let metadata = self.metadata().await?;
 
@@ -728,13 +814,26 @@ impl LiquidityClient for ICPSwapLiquidityClient {
         Ok(WithdrawLiquidityResponse {
             token_0_amount: amount0_to_withdraw,
             token_1_amount: amount1_to_withdraw,
+            lp_amount: None,
         })
     }
 
-    async fn get_position_by_id(
+    async fn get_position(
         &self,
-        position_id: u64
+        position: LiquidityPosition
     ) -> Result<GetPositionByIdResponse, InternalError> {
+        let position_id = position.position_id
+            .ok_or_else(|| InternalError::business_logic(
+                build_error_code(InternalErrorKind::BusinessLogic, 7), // Error code: "02-02-03 03 07"
+                "ICPSwapLiquidityClient::get_position".to_string(),
+                "No position id given".to_string(),
+                errors::error_extra! {
+                    "provider" => PROVIDER,
+                    "token0" => self.token0,
+                    "token1" => self.token1,
+                },
+            ))?;
+
         // 1. Get metadata
         let metadata = self.metadata().await?;
 
@@ -829,4 +928,80 @@ impl LiquidityClient for ICPSwapLiquidityClient {
 
         Ok(GetPoolDataResponse { tvl })
     }
+
+    async fn get_position_fees(
+        &self,
+        position_id: u64
+    ) -> Result<PositionFeesResponse, InternalError> {
+        let user_position = self.get_user_position(Nat::from(position_id)).await?;
+
+        let (token0_fees, token1_fees) = self.to_client_token_order(
+            user_position.tokensOwed0,
+            user_position.tokensOwed1,
+        )?;
+
+        Ok(PositionFeesResponse {
+            token_0_amount: token0_fees,
+            token_1_amount: token1_fees,
+        })
+    }
+
+    async fn claim_fees(
+        &self,
+        position_id: u64
+    ) -> Result<ClaimFeesResponse, InternalError> {
+        // Flow:
+        // 1. Claim fees of the position to the user balance in the pool
+        // 2. Withdraw claimed fees from the pool to the caller wallet
+
+        // 1. Claim fees of the position to the user balance in the pool
+        let claim_response = self.claim(Nat::from(position_id)).await?;
+
+        let (token0_claimed, token1_claimed) = self.to_client_token_order(
+            claim_response.amount0,
+            claim_response.amount1,
+        )?;
+
+        // 2. Withdraw claimed fees from the pool to the caller wallet
+        let token0_received = self.withdraw_to_wallet(self.token0.clone(), token0_claimed).await?;
+        let token1_received = self.withdraw_to_wallet(self.token1.clone(), token1_claimed).await?;
+
+        Ok(ClaimFeesResponse {
+            token_0_amount: token0_received,
+            token_1_amount: token1_received,
+        })
+    }
+
+    async fn increase_position_liquidity(
+        &self,
+        position_id: u64,
+        token_0_amount: Nat,
+        token_1_amount: Nat,
+    ) -> Result<IncreaseLiquidityResponse, InternalError> {
+        // Flow:
+        // 1. Deposit both tokens to the user balance in the pool
+        // 2. Increase liquidity of the position
+
+        // 1. Deposit both tokens to the user balance in the pool
+        let token0_deposited = self.deposit_from_wallet(self.token0.clone(), token_0_amount).await?;
+        let token1_deposited = self.deposit_from_wallet(self.token1.clone(), token_1_amount).await?;
+
+        // 2. Increase liquidity of the position
+        let (amount0_for_position, amount1_for_position) = self.to_client_token_order(
+            token0_deposited.clone(),
+            token1_deposited.clone(),
+        )?;
+
+        let position_id = self.increase_liquidity(
+            Nat::from(position_id),
+            amount0_for_position.to_string(),
+            amount1_for_position.to_string(),
+        ).await?;
+
+        Ok(IncreaseLiquidityResponse {
+            token_0_amount: token0_deposited,
+            token_1_amount: token1_deposited,
+            position_id: nat_to_u64(&position_id),
+        })
+    }
 }
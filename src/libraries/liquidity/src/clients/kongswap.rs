@@ -7,14 +7,18 @@ use types::{CanisterId, exchange_id::ExchangeId};
 use service_resolver::ProviderImpls;
 use providers::kongswap::KongSwapProvider;
 use providers::icpswap::ICPSwapProvider;
-use kongswap_canister::user_balances::UserBalancesReply;
+use kongswap_canister::user_balances::{UserBalancesReply, LPReply};
 use utils::util::{nat_to_f64, nat_to_u128};
 use swap::swap_service;
 use types::liquidity::{
     AddLiquidityResponse,
     WithdrawLiquidityResponse,
     GetPositionByIdResponse,
-    GetPoolDataResponse
+    GetPoolDataResponse,
+    LiquidityPosition,
+    PositionFeesResponse,
+    ClaimFeesResponse,
+    IncreaseLiquidityResponse,
 };
 use icrc_ledger_client::ICRCLedgerClient;
 use utils::constants::CKUSDT_TOKEN_CANISTER_ID;
@@ -31,6 +35,9 @@ use crate::liquidity_calculator::LiquidityCalculator;
 
 pub const PROVIDER: ExchangeId = ExchangeId::KongSwap;
 
+// LP token balances are returned in whole tokens, the LP token has 8 decimals
+const LP_TOKEN_BASE_UNIT: f64 = 100000000.0;
+
 // Module code: "02-02-02"
 errors::define_error_code_builder_fn!(
     build_error_code,
@@ -76,6 +83,43 @@ impl KongSwapLiquidityClient {
     fn icpswap_provider(&self) -> &Arc<dyn ICPSwapProvider + Send + Sync> {
         &self.provider_impls.icpswap
     }
+
+    /// LP balance of the caller in the pool of the client tokens
+    async fn get_lp_balance(&self) -> Result<Option<LPReply>, InternalError> {
+        let canister_id = ic_cdk::id();
+
+        let user_balances_response = self.kongswap_provider()
+            .user_balances(canister_id.to_string())
+            .await?;
+
+        let lp_balance = user_balances_response
+            .into_iter()
+            .filter_map(|reply| match reply {
+                UserBalancesReply::LP(lp) => Some(lp)
+            })
+            .find(|balance|
+                (
+                    balance.address_0 == self.token0.to_text() 
+                        && balance.address_1 == self.token1.to_text()
+                ) || (
+                    balance.address_0 == self.token1.to_text() 
+                        && balance.address_1 == self.token0.to_text()
+                )
+            );
+
+        Ok(lp_balance)
+    }
+}
+
+/// LP tokens of the position, limited by the LP balance of the caller.
+/// Without an LP amount the whole LP balance belongs to the position.
+fn get_position_lp_amount(position: &LiquidityPosition, lp_balance: f64) -> Nat {
+    let lp_balance = Nat::from((lp_balance * LP_TOKEN_BASE_UNIT).round() as u128);
+
+    match position.lp_amount.clone() {
+        Some(lp_amount) => lp_amount.min(lp_balance),
+        None => lp_balance,
+    }
 }
 
 #[async_trait]
@@ -84,9 +128,12 @@ impl LiquidityClient for KongSwapLiquidityClient {
         self.canister_id
     }
 
+    // LP tokens are fungible, so the liquidity is always added to the pool and the minted
+    // LP tokens are returned for the accounting of the caller
     async fn add_liquidity_to_pool(
         &self,
-        amount: Nat
+        amount: Nat,
+        _position_id: Option<u64>
     ) -> Result<AddLiquidityResponse, InternalError> {
         let provider_add_liquidity_amounts =
             self.kongswap_provider().add_liquidity_amounts(
@@ -218,36 +265,18 @@ impl LiquidityClient for KongSwapLiquidityClient {
             token_1_amount: Nat::from(token1_amount_for_pool_u128),
             position_id: response.request_id,
             token0_equivalent_total,
+            lp_amount: Some(response.add_lp_token_amount),
         })
     }
 
     async fn withdraw_liquidity_from_pool(
         &self,
         total_shares: Nat,
-        shares: Nat
+        shares: Nat,
+        position: LiquidityPosition
     ) -> Result<WithdrawLiquidityResponse, InternalError> {
-        let canister_id = ic_cdk::id();
-
-        // Fetch LP positions in pool
-        let user_balances_response = self.kongswap_provider()
-            .user_balances(canister_id.to_string())
-            .await?;
-
         // Get user balance in pool
-        let balance = user_balances_response
-            .into_iter()
-            .filter_map(|reply| match reply {
-                UserBalancesReply::LP(lp) => Some(lp)
-            })
-            .find(|balance|
-                (
-                    balance.address_0 == self.token0.to_text() 
-                        && balance.address_1 == self.token1.to_text()
-                ) || (
-                    balance.address_0 == self.token1.to_text() 
-                        && balance.address_1 == self.token0.to_text()
-                )
-            )
+        let balance = self.get_lp_balance().await?
             .map(|balance_reply| balance_reply.balance)
             .ok_or_else(|| {
                 InternalError::business_logic(
@@ -264,78 +293,71 @@ impl LiquidityClient for KongSwapLiquidityClient {
                 )
             })?;
 
-        // Calculate how much LP tokens to withdraw
-        let lp_tokens_to_withdraw: f64 = balance
-            .mul(nat_to_f64(&shares))
-            .div(nat_to_f64(&total_shares))
-            .mul(100000000.0);
+        // Calculate how much LP tokens to withdraw from the LP tokens of the position only
+        let lp_tokens_to_withdraw = get_position_lp_amount(&position, balance)
+            .mul(shares)
+            .div(total_shares);
 
         // Remove liquidity from pool
         let remove_liquidity_response = self.kongswap_provider()
             .remove_liquidity(
                 self.token_kongswap_format(self.token0.clone()),
                 self.token_kongswap_format(self.token1.clone()),
-                Nat::from(lp_tokens_to_withdraw.round() as u128),
+                lp_tokens_to_withdraw,
             ).await?;
 
         Ok(WithdrawLiquidityResponse {
             token_0_amount: remove_liquidity_response.amount_0,
             token_1_amount: remove_liquidity_response.amount_1,
+            lp_amount: Some(remove_liquidity_response.remove_lp_token_amount),
         })
     }
 
-    async fn get_position_by_id(
+    async fn get_position(
         &self,
-        position_id: u64
+        position: LiquidityPosition
     ) -> Result<GetPositionByIdResponse, InternalError> {
-        let canister_id = ic_cdk::id();
-
-        // Fetch user positions in pool
-        let user_balances_response = self.kongswap_provider().user_balances(
-            canister_id.to_string()
-        ).await?;
-
-        let user_balance = user_balances_response
-            .into_iter()
-            .filter_map(|reply| match reply {
-                UserBalancesReply::LP(lp) => Some(lp)
-            })
-            .find(|balance|
-                (balance.address_0 == self.token0.to_text() && balance.address_1 == self.token1.to_text()) ||
-                (balance.address_0 == self.token1.to_text() && balance.address_1 == self.token0.to_text())
-            )
+        let user_balance = self.get_lp_balance().await?
             .ok_or_else(|| InternalError::business_logic(
                 build_error_code(InternalErrorKind::BusinessLogic, 2), // Error code: "02-02-02 03 02"
-                "KongSwapLiquidityClient::get_position_by_id".to_string(),
+                "KongSwapLiquidityClient::get_position".to_string(),
                 "No user LP balance".to_string(),
                 errors::error_extra! {
                     "provider" => PROVIDER,
                     "token0" => self.token0,
                     "token1" => self.token1,
-                    "position_id" => position_id,
+                    "position" => position,
                 },
             ))?;
 
+        // Part of the LP balance of the caller owned by the position
+        let position_ratio = if user_balance.balance > 0.0 {
+            nat_to_f64(&get_position_lp_amount(&position, user_balance.balance))
+                / (user_balance.balance * LP_TOKEN_BASE_UNIT)
+        } else {
+            0.0
+        };
+
         let token0_decimals = self.icrc_ledger_client.icrc1_decimals(self.token0.clone()).await?;
         let token1_decimals = self.icrc_ledger_client.icrc1_decimals(self.token1.clone()).await?;
         let usdt_decimals = self.icrc_ledger_client.icrc1_decimals(*CKUSDT_TOKEN_CANISTER_ID).await?;
 
         let token0_position_balance = Nat::from(
-            (user_balance.amount_0 * 10f64.powi(token0_decimals as i32)).round() as u128
+            (user_balance.amount_0 * position_ratio * 10f64.powi(token0_decimals as i32)).round() as u128
         );
         let token1_position_balance = Nat::from(
-            (user_balance.amount_1 * 10f64.powi(token1_decimals as i32)).round() as u128
+            (user_balance.amount_1 * position_ratio * 10f64.powi(token1_decimals as i32)).round() as u128
         );
 
         let token0_usd_amount = Nat::from(
-            (user_balance.usd_amount_0 * 10f64.powi(usdt_decimals as i32)).round() as u128
+            (user_balance.usd_amount_0 * position_ratio * 10f64.powi(usdt_decimals as i32)).round() as u128
         );
         let token1_usd_amount = Nat::from(
-            (user_balance.usd_amount_1 * 10f64.powi(usdt_decimals as i32)).round() as u128
+            (user_balance.usd_amount_1 * position_ratio * 10f64.powi(usdt_decimals as i32)).round() as u128
         );
 
         Ok(GetPositionByIdResponse {
-            position_id: position_id,
+            position_id: position.position_id.unwrap_or_default(),
             token_0_amount: token0_position_balance,
             token_1_amount: token1_position_balance,
             usd_amount_0: token0_usd_amount,
@@ -412,5 +434,62 @@ impl LiquidityClient for KongSwapLiquidityClient {
             tvl: tvl,
         })
     }
+
+    // KongSwap adds the trading fees to the pool reserves, so they are compounded by the LP tokens
+
+    async fn get_position_fees(
+        &self,
+        position_id: u64
+    ) -> Result<PositionFeesResponse, InternalError> {
+        Err(InternalError::business_logic(
+            build_error_code(InternalErrorKind::BusinessLogic, 5), // Error code: "02-02-02 03 05"
+            "KongSwapLiquidityClient::get_position_fees".to_string(),
+            "KongSwap fees are compounded by the pool".to_string(),
+            errors::error_extra! {
+                "provider" => PROVIDER,
+                "token0" => self.token0,
+                "token1" => self.token1,
+                "position_id" => position_id,
+            },
+        ))
+    }
+
+    async fn claim_fees(
+        &self,
+        position_id: u64
+    ) -> Result<ClaimFeesResponse, InternalError> {
+        Err(InternalError::business_logic(
+            build_error_code(InternalErrorKind::BusinessLogic, 6), // Error code: "02-02-02 03 06"
+            "KongSwapLiquidityClient::claim_fees".to_string(),
+            "KongSwap fees are compounded by the pool".to_string(),
+            errors::error_extra! {
+                "provider" => PROVIDER,
+                "token0" => self.token0,
+                "token1" => self.token1,
+                "position_id" => position_id,
+            },
+        ))
+    }
+
+    async fn increase_position_liquidity(
+        &self,
+        position_id: u64,
+        token_0_amount: Nat,
+        token_1_amount: Nat,
+    ) -> Result<IncreaseLiquidityResponse, InternalError> {
+        Err(InternalError::business_logic(
+            build_error_code(InternalErrorKind::BusinessLogic, 7), // Error code: "02-02-02 03 07"
+            "KongSwapLiquidityClient::increase_position_liquidity".to_string(),
+            "KongSwap positions can't be increased, use add_liquidity_to_pool".to_string(),
+            errors::error_extra! {
+                "provider" => PROVIDER,
+                "token0" => self.token0,
+                "token1" => self.token1,
+                "position_id" => position_id,
+                "token_0_amount" => token_0_amount,
+                "token_1_amount" => token_1_amount,
+            },
+        ))
+    }
 }
 
@@ -15,7 +15,9 @@ pub struct CalculatePoolLiquidityAmountsResponse {
 
 // TODO: move methods to separate services
 impl LiquidityCalculator {
-    pub fn calculate_shares_for_deposit(amount: Nat, total_balance: Nat, total_shares: Nat) -> Nat {
+    /// Calculates the shares minted for a deposit, `total_value` is the current value
+    /// of the position in the same token as `amount`.
+    pub fn calculate_shares_for_deposit(amount: Nat, total_value: Nat, total_shares: Nat) -> Nat {
         let zero = Nat::from(0u64);
 
         // First deposit: 1:1 share price
@@ -23,14 +25,14 @@ impl LiquidityCalculator {
             return amount;
         }
 
-        // For subsequent deposits, compute shares as amount * total_shares / total_balance
-        // This avoids precision loss from computing share_price = total_balance / total_shares first
-        if total_balance == zero {
+        // For subsequent deposits, compute shares as amount * total_shares / total_value
+        // This avoids precision loss from computing share_price = total_value / total_shares first
+        if total_value == zero {
             // Defensive: avoid division by zero, fallback to minting amount shares
             return amount;
         }
 
-        amount * total_shares / total_balance
+        amount * total_shares / total_value
     }
 
     pub fn calculate_token_amounts_for_deposit(
@@ -105,6 +107,16 @@ mod tests {
             let shares = LiquidityCalculator::calculate_shares_for_deposit(amount, total_balance, total_shares);
             assert_eq!(shares, Nat::from(50u64));
         }
+
+        #[test]
+        fn test_with_appreciated_position() {
+            let amount = Nat::from(100u64);
+            let total_value = Nat::from(2000u64);
+            let total_shares = Nat::from(1000u64);
+
+            let shares = LiquidityCalculator::calculate_shares_for_deposit(amount, total_value, total_shares);
+            assert_eq!(shares, Nat::from(50u64));
+        }
     }
 
     mod calculate_token_amounts_for_deposit {
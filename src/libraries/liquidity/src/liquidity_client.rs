@@ -2,14 +2,41 @@ use async_trait::async_trait;
 use types::CanisterId;
 use candid::Nat;
 
-use types::liquidity::{AddLiquidityResponse, WithdrawLiquidityResponse, GetPositionByIdResponse, GetPoolDataResponse};
+use types::liquidity::{
+    AddLiquidityResponse,
+    WithdrawLiquidityResponse,
+    GetPositionByIdResponse,
+    GetPoolDataResponse,
+    LiquidityPosition,
+    PositionFeesResponse,
+    ClaimFeesResponse,
+    IncreaseLiquidityResponse,
+};
 use errors::internal_error::error::InternalError;
 
 #[async_trait]
 pub trait LiquidityClient: Send + Sync + 'static {
     fn canister_id(&self) -> CanisterId;
-    async fn add_liquidity_to_pool(&self, amount: Nat) -> Result<AddLiquidityResponse, InternalError>;
-    async fn withdraw_liquidity_from_pool(&self, total_shares: Nat, shares: Nat) -> Result<WithdrawLiquidityResponse, InternalError>;
-    async fn get_position_by_id(&self, position_id: u64) -> Result<GetPositionByIdResponse, InternalError>;
+    /// Adds liquidity to the given position, a new position is opened without it.
+    async fn add_liquidity_to_pool(&self, amount: Nat, position_id: Option<u64>) -> Result<AddLiquidityResponse, InternalError>;
+    /// Withdraws `shares / total_shares` of the liquidity of the position.
+    async fn withdraw_liquidity_from_pool(
+        &self,
+        total_shares: Nat,
+        shares: Nat,
+        position: LiquidityPosition,
+    ) -> Result<WithdrawLiquidityResponse, InternalError>;
+    async fn get_position(&self, position: LiquidityPosition) -> Result<GetPositionByIdResponse, InternalError>;
     async fn get_pool_data(&self) -> Result<GetPoolDataResponse, InternalError>;
+    /// Trading fees accumulated by the position and not collected yet.
+    async fn get_position_fees(&self, position_id: u64) -> Result<PositionFeesResponse, InternalError>;
+    /// Collects the trading fees of the position to the caller balance.
+    async fn claim_fees(&self, position_id: u64) -> Result<ClaimFeesResponse, InternalError>;
+    /// Adds both tokens from the caller balance to an existing position.
+    async fn increase_position_liquidity(
+        &self,
+        position_id: u64,
+        token_0_amount: Nat,
+        token_1_amount: Nat,
+    ) -> Result<IncreaseLiquidityResponse, InternalError>;
 }
@@ -1,6 +1,6 @@
 use candid::Nat;
 
-use crate::types::{Params, StrategyProfile, Weights};
+use crate::types::{Params, StrategyParamsOverrides, StrategyProfile, Weights};
 
 pub const SECONDS_IN_HOUR: u64 = 3600;
 
@@ -71,6 +71,14 @@ pub fn default_params_for_profile(profile: StrategyProfile) -> Params {
         StrategyProfile::StableOnly => (60 * SECONDS_IN_HOUR, 6.0, 2.5),
     };
 
+    let max_weight_shift_bps = match profile {
+        StrategyProfile::Conservative | StrategyProfile::StableOnly => 2_500,
+        StrategyProfile::Balanced
+        | StrategyProfile::TokenAccumulator
+        | StrategyProfile::IncentiveFarmer => 5_000,
+        StrategyProfile::Aggressive => 10_000,
+    };
+
     Params {
         cooldown_secs,
         score_threshold,
@@ -80,5 +88,24 @@ pub fn default_params_for_profile(profile: StrategyProfile) -> Params {
         gas_cost: Nat::from(0_u64),
         long_term_apy_usd_min: 0.0, // filter out pools with negative long-term APY
         sma_window_hours: 72,
+        max_weight_shift_bps,
+        max_slippage_bps: 100, // 1%
+    }
+}
+
+/// Returns the default params of the profile with the overridden fields replaced.
+pub fn params_for_profile(profile: StrategyProfile, overrides: &StrategyParamsOverrides) -> Params {
+    let defaults = default_params_for_profile(profile);
+
+    Params {
+        cooldown_secs: overrides.cooldown_secs.unwrap_or(defaults.cooldown_secs),
+        score_threshold: overrides.score_threshold.unwrap_or(defaults.score_threshold),
+        gain_cost_multiplier: overrides.gain_cost_multiplier.unwrap_or(defaults.gain_cost_multiplier),
+        weights: overrides.weights.unwrap_or(defaults.weights),
+        dex_fee_percent_bps: overrides.dex_fee_percent_bps.unwrap_or(defaults.dex_fee_percent_bps),
+        long_term_apy_usd_min: overrides.long_term_apy_usd_min.unwrap_or(defaults.long_term_apy_usd_min),
+        max_weight_shift_bps: overrides.max_weight_shift_bps.unwrap_or(defaults.max_weight_shift_bps),
+        max_slippage_bps: overrides.max_slippage_bps.unwrap_or(defaults.max_slippage_bps),
+        ..defaults
     }
 }
@@ -1,26 +1,7 @@
 use candid::{CandidType, Deserialize, Nat};
 use serde::Serialize;
 
-#[derive(Clone, Copy, CandidType, Deserialize, Serialize, Debug)]
-pub enum StrategyProfile {
-    Conservative,
-    Balanced,
-    Aggressive,
-    TokenAccumulator,
-    IncentiveFarmer,
-    StableOnly,
-}
-
-#[derive(Clone, Copy, CandidType, Deserialize, Serialize, Debug)]
-pub struct Weights {
-    pub w1_usd_apy_sma: f64,
-    pub w2_token_apy_sma: f64,
-    pub w3_log_tvl: f64,
-    pub w4_capital_efficiency: f64,
-    pub w5_apy_volatility: f64,
-    pub w6_rebalance_cost: f64,
-    pub w7_token_price_volatility: f64,
-}
+pub use types::strategies::{StrategyProfile, StrategyParamsOverrides, Weights};
 
 #[derive(Clone, CandidType, Deserialize, Serialize, Debug)]
 pub struct Params {
@@ -32,6 +13,10 @@ pub struct Params {
     pub gas_cost: Nat,
     pub long_term_apy_usd_min: f64,
     pub sma_window_hours: u32,
+    /// Max share of the strategy capital moved to another pool by a rebalance
+    pub max_weight_shift_bps: u32,
+    /// Max shortfall of a swap output against its quote
+    pub max_slippage_bps: u32,
 }
 
 #[derive(Clone, CandidType, Deserialize, Serialize, Debug)]
@@ -118,12 +118,27 @@ pub async fn quote_swap_icrc2_optimal(
         amount
     ).await;
 
-    //Return the quote with the highest amount_out
-    Ok(std::cmp::max_by(
-        kong_quote.unwrap(),
-        icp_quote.unwrap(),
-        |a, b| a.amount_out.cmp(&b.amount_out)
-    ))
+    // Return the quote with the highest amount_out among the providers having a route
+    match (kong_quote, icp_quote) {
+        (Ok(kong_quote), Ok(icp_quote)) => Ok(std::cmp::max_by(
+            kong_quote,
+            icp_quote,
+            |a, b| a.amount_out.cmp(&b.amount_out)
+        )),
+        (Ok(quote), Err(_)) | (Err(_), Ok(quote)) => Ok(quote),
+        (Err(kong_error), Err(icp_error)) => Err(InternalError::not_found(
+            build_error_code(InternalErrorKind::NotFound, 3), // Error code: "02-01-01 01 03"
+            "swap_service::quote_swap_icrc2_optimal".to_string(),
+            "No swap route found for the tokens".to_string(),
+            errors::error_extra! {
+                "caller_canister_id" => get_caller_canister_id(),
+                "input_token" => input_token,
+                "output_token" => output_token,
+                "kongswap_error" => kong_error.message,
+                "icpswap_error" => icp_error.message,
+            },
+        )),
+    }
 }
 
 pub async fn quote_swap_icrc2(
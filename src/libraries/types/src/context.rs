@@ -1,15 +1,21 @@
 use candid::{CandidType, Principal};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use serde::{Deserialize, Serialize};
 
 use crate::strategies::StrategyId;
 
 pub type CorrelationId = String;
 
+/// Subaccount of the default account of a principal
+pub const DEFAULT_SUBACCOUNT: Subaccount = [0u8; 32];
+
 #[derive(Clone, Debug, CandidType, Deserialize, Serialize)]
 pub struct Context {
     pub correlation_id: CorrelationId,
     pub user: Option<Principal>,
     pub strategy_id: Option<StrategyId>,
+    /// Subaccount of the user holding the position, `None` for the default account
+    pub subaccount: Option<Subaccount>,
 }
 
 impl Context {
@@ -18,7 +24,7 @@ impl Context {
         user: Option<Principal>,
         strategy_id: Option<StrategyId>
     ) -> Self {
-        Self { correlation_id, user, strategy_id }
+        Self { correlation_id, user, strategy_id, subaccount: None }
     }
 
     pub fn generate(user: Option<Principal>, strategy_id: Option<StrategyId>) -> Self {
@@ -26,11 +32,27 @@ impl Context {
             correlation_id: Self::generate_correlation_id(),
             user,
             strategy_id,
+            subaccount: None,
         }
     }
 
+    pub fn with_subaccount(mut self, subaccount: Option<Subaccount>) -> Self {
+        self.subaccount = position_subaccount(subaccount);
+        self
+    }
+
+    /// Account of the user position the operation is made for.
+    pub fn account(&self) -> Option<Account> {
+        self.user.map(|owner| Account { owner, subaccount: self.subaccount })
+    }
+
     fn generate_correlation_id() -> String {
         // TODO: replace with uuid or another library
         ic_cdk::api::time().to_string()
     }
 }
+
+/// Subaccount identifying a user position, the all-zero subaccount is the default account like `None`.
+pub fn position_subaccount(subaccount: Option<Subaccount>) -> Option<Subaccount> {
+    subaccount.filter(|subaccount| *subaccount != DEFAULT_SUBACCOUNT)
+}
@@ -5,6 +5,8 @@ use serde::{Deserialize, Serialize};
 pub struct WithdrawLiquidityResponse {
     pub token_0_amount: Nat,
     pub token_1_amount: Nat,
+    // LP tokens burned by the withdrawal, only for pools with fungible LP tokens (KongSwap)
+    pub lp_amount: Option<Nat>,
 }
 
 #[derive(CandidType, Deserialize, Clone, Debug, Serialize)]
@@ -14,6 +16,18 @@ pub struct AddLiquidityResponse {
     pub position_id: u64,
     // Total liquidity added, expressed in token0 units (token_0_amount + token_1 converted to token0)
     pub token0_equivalent_total: Nat,
+    // LP tokens minted by the deposit, only for pools with fungible LP tokens (KongSwap)
+    pub lp_amount: Option<Nat>,
+}
+
+/// Liquidity owned by the caller in a pool.
+///
+/// ICPSwap positions are identified by the position id. KongSwap LP tokens are fungible,
+/// so the amount of LP tokens owned is used instead, the whole LP balance of the caller without it.
+#[derive(CandidType, Deserialize, Clone, Debug, Serialize, Default, PartialEq)]
+pub struct LiquidityPosition {
+    pub position_id: Option<u64>,
+    pub lp_amount: Option<Nat>,
 }
 
 #[derive(CandidType, Deserialize, Clone, Serialize)]
@@ -35,3 +49,24 @@ pub struct GetPositionByIdResponse {
 pub struct GetPoolDataResponse {
     pub tvl: Nat,
 }
+
+/// Fees accumulated by a position, in the token order of the client.
+#[derive(CandidType, Deserialize, Clone, Debug, Serialize)]
+pub struct PositionFeesResponse {
+    pub token_0_amount: Nat,
+    pub token_1_amount: Nat,
+}
+
+/// Fees collected from a position and received by the caller.
+#[derive(CandidType, Deserialize, Clone, Debug, Serialize)]
+pub struct ClaimFeesResponse {
+    pub token_0_amount: Nat,
+    pub token_1_amount: Nat,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, Serialize)]
+pub struct IncreaseLiquidityResponse {
+    pub token_0_amount: Nat,
+    pub token_1_amount: Nat,
+    pub position_id: u64,
+}
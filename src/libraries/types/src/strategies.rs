@@ -1,14 +1,19 @@
-use candid::{CandidType, Deserialize, Nat, Principal};
+use candid::{CandidType, Deserialize, Nat};
+use icrc_ledger_types::icrc1::account::Account;
 use serde::Serialize;
 use std::collections::HashMap;
 
 use crate::CanisterId;
 use crate::pool::PoolTrait;
 use crate::exchange_id::ExchangeId;
+use crate::liquidity::LiquidityPosition;
 
 
 pub type StrategyId = u16;
 
+/// Scale of the share prices, a share price is the value of a share in base token times 10^12.
+pub const SHARE_PRICE_SCALE: u64 = 1_000_000_000_000;
+
 #[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
 pub struct StrategyResponse {
     pub name: String,
@@ -19,13 +24,92 @@ pub struct StrategyResponse {
     pub current_pool: Option<Pool>,
     pub total_balance: Nat,
     pub total_shares: Nat,
-    pub user_shares: HashMap<Principal, Nat>,
-    pub initial_deposit: HashMap<Principal, Nat>,
+    pub user_shares: HashMap<Account, Nat>,
+    pub initial_deposit: HashMap<Account, Nat>,
     pub users_count: u32,
     pub current_liquidity: Option<Nat>,
     pub current_liquidity_updated_at: Option<u64>,
     pub position_id: Option<u64>,
     pub enabled: bool,
+    pub profile: StrategyProfile,
+    pub params_overrides: StrategyParamsOverrides,
+    pub fees: StrategyFees,
+    pub allocations: Vec<StrategyAllocation>,
+    /// Last recorded value of a share in base token, scaled by 10^12
+    pub share_price: Option<Nat>,
+}
+
+/// Part of the strategy capital provided as liquidity to a pool.
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug, PartialEq)]
+pub struct StrategyAllocation {
+    pub pool: Pool,
+    pub position_id: Option<u64>,
+    /// Share of the strategy capital held in the pool, the weights of a strategy sum up to 10_000
+    pub target_weight_bps: u32,
+    /// KongSwap LP tokens owned by the strategy, `None` for ICPSwap positions
+    /// and for KongSwap allocations created before the LP tokens were accounted per strategy
+    pub lp_amount: Option<Nat>,
+}
+
+impl StrategyAllocation {
+    /// Liquidity of the strategy in the pool of the allocation
+    pub fn liquidity_position(&self) -> LiquidityPosition {
+        LiquidityPosition {
+            position_id: self.position_id,
+            lp_amount: self.lp_amount.clone(),
+        }
+    }
+}
+
+/// Protocol fee rates of a strategy and the state of their accrual.
+/// Fees are taken by minting strategy shares to the treasury.
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug, Default, PartialEq)]
+pub struct StrategyFees {
+    /// Share of the position value gain above the high-water mark
+    pub performance_fee_bps: u32,
+    /// Annual share of the position value
+    pub management_fee_bps: u32,
+    /// Highest share price the performance fee was charged on, scaled by 10^12
+    pub high_water_mark: Option<Nat>,
+    pub last_management_fee_at: Option<u64>,
+    pub total_performance_fee_shares: Nat,
+    pub total_management_fee_shares: Nat,
+}
+
+/// Risk profile that defines the default rebalance params of a strategy.
+#[derive(CandidType, Deserialize, Clone, Copy, Serialize, Debug, Default, PartialEq)]
+pub enum StrategyProfile {
+    Conservative,
+    #[default]
+    Balanced,
+    Aggressive,
+    TokenAccumulator,
+    IncentiveFarmer,
+    StableOnly,
+}
+
+#[derive(CandidType, Deserialize, Clone, Copy, Serialize, Debug, PartialEq)]
+pub struct Weights {
+    pub w1_usd_apy_sma: f64,
+    pub w2_token_apy_sma: f64,
+    pub w3_log_tvl: f64,
+    pub w4_capital_efficiency: f64,
+    pub w5_apy_volatility: f64,
+    pub w6_rebalance_cost: f64,
+    pub w7_token_price_volatility: f64,
+}
+
+/// Strategy specific values that replace the profile defaults of the rebalance params.
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug, Default, PartialEq)]
+pub struct StrategyParamsOverrides {
+    pub weights: Option<Weights>,
+    pub cooldown_secs: Option<u64>,
+    pub score_threshold: Option<f64>,
+    pub gain_cost_multiplier: Option<f64>,
+    pub dex_fee_percent_bps: Option<u32>,
+    pub long_term_apy_usd_min: Option<f64>,
+    pub max_weight_shift_bps: Option<u32>,
+    pub max_slippage_bps: Option<u32>,
 }
 
 #[derive(CandidType, Deserialize, Clone, Serialize, Debug, PartialEq)]
@@ -1,8 +1,8 @@
-use candid::{Nat, Principal};
+use candid::Nat;
 use std::convert::TryInto;
 
 use icrc_ledger_types::icrc1::account::Account;
-use icrc_ledger_types::icrc1::transfer::TransferArg;
+use icrc_ledger_types::icrc1::transfer::{Memo, TransferArg, TransferError};
 use icrc_ledger_canister::updates::icrc1_transfer::Response as Icrc1TransferResponse;
 use canister_client;
 use types::CanisterId;
@@ -26,18 +26,27 @@ errors::define_error_code_builder_fn!(
     canister_domain_components::CORE   // Component code: "01"
 );
 
+/// Transfers an amount held by the canister to an account of a user.
+///
+/// With a `created_at_time` the ledger deduplicates the transfer, so a retry with the same
+/// `created_at_time` and `memo` returns the block of the first transfer instead of sending again.
+///
+/// A failed call is an `ExternalService` error, the ledger may have made the transfer.
+/// A transfer rejected by the ledger is a `BusinessLogic` error, nothing was transferred.
 pub async fn icrc1_transfer_to_user(
     environment: &Environment,
-    user: Principal,
+    to: Account,
     canister_id: CanisterId,
     amount: Nat,
+    created_at_time: Option<u64>,
+    memo: Option<Memo>,
 ) -> Result<Nat, InternalError> {
     let args = TransferArg {
         from_subaccount: None,
-        to: Account { owner: user, subaccount: None },
+        to,
         fee: None,
-        created_at_time: None,
-        memo: None,
+        created_at_time,
+        memo,
         amount: amount.clone(),
     };
 
@@ -58,19 +67,24 @@ pub async fn icrc1_transfer_to_user(
                 "Utils::icrc1_transfer_to_user".to_string(),
                 format!("IC error calling 'canister_client::make_c2c_call': {error:?}"),
                 errors::error_extra! {
-                    "user" => user,
+                    "to" => to,
                     "canister_id" => canister_id,
                     "amount" => amount,
                 },
             )
         })?
+        .or_else(|err| match err {
+            // The transfer was already made by a previous attempt
+            TransferError::Duplicate { duplicate_of } => Ok(duplicate_of),
+            err => Err(err),
+        })
         .map_err(|err| {
             InternalError::business_logic(
                 build_error_code(InternalErrorKind::BusinessLogic, 2), // Error code: "01-04-01 03 02"
                 "Utils::icrc1_transfer_to_user".to_string(),
                 format!("Error calling 'canister_client::make_c2c_call': {err:?}"),
                 errors::error_extra! {
-                    "user" => user,
+                    "to" => to,
                     "canister_id" => canister_id,
                     "amount" => amount,
                 },
@@ -1,8 +1,6 @@
 use candid::{CandidType, Deserialize, Principal, Nat};
 use serde::Serialize;
-use std::cell::RefCell;
-use ic_cdk::{call, id, trap, update, caller, query};
-use ic_cdk::api::call::CallResult;
+use ic_cdk::{update, caller, query};
 use candid::{candid_method, export_service};
 
 use ::types::exchange_id::ExchangeId;
@@ -17,18 +15,9 @@ use errors::internal_error::error_codes::module::areas::{
     canisters::domains::pool_stats::components as pool_stats_domain_components,
 };
 use ic_cdk_macros::{init, post_upgrade, pre_upgrade};
-
-// Macro for operator authorization check
-macro_rules! trap_if_not_authenticated {
-    () => {
-        let caller = ic_cdk::caller();
-        let operator = CONFIG.with(|config| config.borrow().operator);
-
-        if operator.is_none() || operator.unwrap() != caller {
-            trap("Unauthorized: caller is not a operator");
-        }
-    }
-}
+use access_control::access_control_service;
+use access_control::role::{Role, AccessControlResult, GetAccessControlStateResult};
+use access_control::return_if_unauthorized;
 
 use crate::pool_snapshots::pool_snapshot::PoolSnapshot;
 use crate::pool_snapshots::{pool_snapshot_service, test_snapshots_service};
@@ -70,25 +59,6 @@ errors::define_error_code_builder_fn!(
     pool_stats_domain_components::CORE // Component code: "01"
 );
 
-#[derive(CandidType, Debug, Clone, Deserialize)]
-pub struct CanisterIdRequest {
-    #[serde(rename = "canister_id")]
-    pub canister_id: Principal,
-}
-
-#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
-struct Config {
-    operator: Option<Principal>,
-}
-
-thread_local! {
-    static CONFIG: RefCell<Config> = RefCell::new(
-        Config {
-            operator: None
-        },
-    );
-}
-
 
 // ========================== Test methods ==========================
 
@@ -106,8 +76,8 @@ pub struct PoolSnapshotArgs {
 
 // TODO: test method, remove after testing
 #[update]
-pub fn test_add_pool_snapshot(args: PoolSnapshotArgs) {
-    trap_if_not_authenticated!();
+pub fn test_add_pool_snapshot(args: PoolSnapshotArgs) -> AccessControlResult {
+    return_if_unauthorized!(Role::Operator, AccessControlResult);
 
     let snapshot = PoolSnapshot::new(
         (pools_repo::get_pool_snapshots_count(args.pool_id.clone()) + 1).to_string(),
@@ -117,36 +87,44 @@ pub fn test_add_pool_snapshot(args: PoolSnapshotArgs) {
         args.pool_data,
     );
     pools_repo::save_pool_snapshot(snapshot);
+
+    AccessControlResult(Ok(()))
 }
 
 // TODO: test method, remove after testing
 #[update]
-pub fn test_delete_pool_snapshots(pool_id: String) {
-    trap_if_not_authenticated!();
+pub fn test_delete_pool_snapshots(pool_id: String) -> AccessControlResult {
+    return_if_unauthorized!(Role::Admin, AccessControlResult);
 
     pools_repo::delete_pool_snapshots(pool_id);
+
+    AccessControlResult(Ok(()))
 }
 
 // TODO: test method, remove after testing
 #[update]
-pub fn test_delete_all_snapshots() {
-    trap_if_not_authenticated!();
+pub fn test_delete_all_snapshots() -> AccessControlResult {
+    return_if_unauthorized!(Role::Admin, AccessControlResult);
 
     pools_repo::delete_all_snapshots();
+
+    AccessControlResult(Ok(()))
 }
 
 // TODO: test method, remove after testing
 #[update]
-pub fn test_delete_pool_snapshot(pool_id: String, snapshot_id: String) {
-    trap_if_not_authenticated!();
+pub fn test_delete_pool_snapshot(pool_id: String, snapshot_id: String) -> AccessControlResult {
+    return_if_unauthorized!(Role::Admin, AccessControlResult);
 
     pools_repo::delete_pool_snapshot(pool_id, snapshot_id);
+
+    AccessControlResult(Ok(()))
 }
 
 // TODO: test method, remove after testing
 #[update]
-pub fn test_update_pool_ids() {
-    trap_if_not_authenticated!();
+pub fn test_update_pool_ids() -> AccessControlResult {
+    return_if_unauthorized!(Role::Admin, AccessControlResult);
 
     let pools = pools_repo::get_pools();
     for mut pool in pools {
@@ -154,14 +132,18 @@ pub fn test_update_pool_ids() {
         pool.id = new_id;
         pools_repo::save_pool(pool);
     }
+
+    AccessControlResult(Ok(()))
 }
 
 // TODO: test method, remove after testing
 #[update]
-pub fn test_delete_all_pools_and_snapshots() {
-    trap_if_not_authenticated!();
+pub fn test_delete_all_pools_and_snapshots() -> AccessControlResult {
+    return_if_unauthorized!(Role::Admin, AccessControlResult);
+
+    pools_repo::delete_all_pools_and_snapshots();
 
-    pools_repo::delete_all_pools_and_snapshots()
+    AccessControlResult(Ok(()))
 }
 
 // TODO: test method, remove after testing
@@ -170,7 +152,7 @@ pub struct TestCreatePoolSnapshotResult(pub Result<PoolSnapshot, ResponseError>)
 
 #[update]
 pub async fn test_create_pool_snapshot(pool_id: String) -> TestCreatePoolSnapshotResult {
-    trap_if_not_authenticated!();
+    return_if_unauthorized!(Role::Operator, TestCreatePoolSnapshotResult);
 
     let context = Context::generate(None, None);
 
@@ -205,7 +187,7 @@ pub struct TestCreateTestSnapshotsResult(pub Result<(), ResponseError>);
 
 #[update]
 pub fn test_create_test_snapshots(pool_id: String, tvl: u128, target_apy: f64) -> TestCreateTestSnapshotsResult {
-    trap_if_not_authenticated!();
+    return_if_unauthorized!(Role::Operator, TestCreateTestSnapshotsResult);
 
     let result = 
         test_snapshots_service::create_test_snapshots(pool_id, tvl, target_apy)
@@ -227,7 +209,7 @@ fn get_runtime_config() -> RuntimeConfig {
 
 #[update]
 pub fn add_pool(token0: CanisterId, token1: CanisterId, provider: ExchangeId) -> AddPoolResult {
-    trap_if_not_authenticated!();
+    return_if_unauthorized!(Role::Admin, AddPoolResult);
 
     let result = service::add_pool(token0, token1, provider)
         .map_err(|error| ResponseError::from_internal_error(error));
@@ -237,7 +219,7 @@ pub fn add_pool(token0: CanisterId, token1: CanisterId, provider: ExchangeId) ->
 
 #[update]
 pub fn delete_pool(id: String) -> DeletePoolResult {
-    trap_if_not_authenticated!();
+    return_if_unauthorized!(Role::Admin, DeletePoolResult);
 
     let result = service::delete_pool(id)
         .map_err(|error| ResponseError::from_internal_error(error));
@@ -285,7 +267,7 @@ pub fn get_pools_history(request: GetPoolsHistoryRequest) -> GetPoolsHistoryResu
 
 #[update]
 pub async fn deposit_test_liquidity_to_pool(pool_id: String) -> AddLiquidityResult {
-    trap_if_not_authenticated!();
+    return_if_unauthorized!(Role::Admin, AddLiquidityResult);
 
     let context = generate_context();
 
@@ -304,7 +286,7 @@ pub async fn add_liquidity_to_pool(
     pool_id: String,
     amount: Nat
 ) -> AddLiquidityResult {
-    trap_if_not_authenticated!();
+    return_if_unauthorized!(Role::Admin, AddLiquidityResult);
 
     let context = generate_context();
 
@@ -321,7 +303,7 @@ pub async fn add_liquidity_to_pool(
 
 #[update]
 pub async fn withdraw_liquidity_from_pool(pool_id: String) -> WithdrawLiquidityResult {
-    trap_if_not_authenticated!();
+    return_if_unauthorized!(Role::Admin, WithdrawLiquidityResult);
 
     let context = generate_context();
 
@@ -351,8 +333,9 @@ pub fn get_event_records(offset: u64, limit: u64) -> GetEventRecordsResult {
 // ========================== Vault management ==========================
 #[init]
 #[candid_method(init)]
-async fn init(runtime_config: RuntimeConfig) {
+async fn init(runtime_config: RuntimeConfig, operator: Option<Principal>) {
     runtime_config_repo::set_runtime_config(runtime_config);
+    init_operator(operator);
 
     // pool_service::init_pools();
     pool_snapshot_service::start_pool_snapshots_timer(SNAPSHOTS_FETCHING_INTERVAL);
@@ -365,38 +348,61 @@ fn pre_upgrade() {
     pool_snapshot_service::stop_pool_snapshots_timer();
 }
 
+// The runtime config is restored from the stable state
 #[post_upgrade]
-fn post_upgrade() {
+fn post_upgrade(_runtime_config: Option<RuntimeConfig>, operator: Option<Principal>) {
     stable_state::stable_restore();
+    init_operator(operator);
     pool_snapshot_service::start_pool_snapshots_timer(SNAPSHOTS_FETCHING_INTERVAL);
 }
 
-// Sets the operator principal.
-#[update]
-async fn set_operator(operator: Principal) {
-    let controllers = get_controllers().await;
-    if !controllers.contains(&ic_cdk::caller()) {
-        trap("Unauthorized: caller is not a controller");
+// Gives the operator role to the principal of the removed `set_operator`.
+// It was only kept in the heap, so the upgrade to the access control passes it again
+fn init_operator(operator: Option<Principal>) {
+    if let Some(operator) = operator {
+        access_control_service::init_role(operator, Role::Operator);
     }
-    CONFIG.with(|config| {
-        let mut config = config.borrow_mut();
-        config.operator = Some(operator);
-    });
 }
 
-async fn get_controllers() -> Vec<Principal> {
-    let res: CallResult<(ic_cdk::api::management_canister::main::CanisterStatusResponse,)> = call(
-        Principal::management_canister(),
-        "canister_status",
-        (CanisterIdRequest { canister_id: id() },),
-    )
-        .await;
-    res
-        .expect(
-            "Get controllers function exited unexpectedly:\n\
-            inter-canister call to management canister for canister_status returned an empty result."
-        )
-        .0.settings.controllers
+// ========================== Access control ==========================
+
+#[update]
+pub fn grant_role(principal: Principal, role: Role) -> AccessControlResult {
+    let result = access_control_service::grant_role(caller(), principal, role)
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    AccessControlResult(result)
+}
+
+#[update]
+pub fn accept_role(role: Role) -> AccessControlResult {
+    let result = access_control_service::accept_role(caller(), role)
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    AccessControlResult(result)
+}
+
+#[update]
+pub fn revoke_role(principal: Principal, role: Role) -> AccessControlResult {
+    let result = access_control_service::revoke_role(caller(), principal, role)
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    AccessControlResult(result)
+}
+
+#[update]
+pub fn confirm_revoke_role(principal: Principal, role: Role) -> AccessControlResult {
+    let result = access_control_service::confirm_revoke_role(caller(), principal, role)
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    AccessControlResult(result)
+}
+
+#[query]
+pub fn get_access_control() -> GetAccessControlStateResult {
+    return_if_unauthorized!(Role::Viewer, GetAccessControlStateResult);
+
+    GetAccessControlStateResult(Ok(access_control_service::get_state()))
 }
 
 export_service!();
@@ -1,7 +1,7 @@
 use candid::Nat;
 
 use liquidity::liquidity_router::get_liquidity_client;
-use types::liquidity::{AddLiquidityResponse, WithdrawLiquidityResponse};
+use types::liquidity::{AddLiquidityResponse, WithdrawLiquidityResponse, LiquidityPosition};
 use liquidity::liquidity_client::LiquidityClient;
 use types::context::Context;
 use errors::internal_error::error::InternalError;
@@ -28,8 +28,10 @@ pub async fn add_liquidity_to_pool(
 
     let liquidity_client = liquidity_client(pool.clone()).await;
 
+    // Every pool holds a single position, added only when the pool has no liquidity
     let add_liquidity_response = liquidity_client.add_liquidity_to_pool(
-        amount.clone()
+        amount.clone(),
+        None
     ).await
         .map_err(|error| {
             // Event: Add liquidity to pool failed
@@ -73,9 +75,16 @@ pub async fn withdraw_liquidity_from_pool(
 
     let liquidity_client = liquidity_client(pool.clone()).await;
 
+    // The whole LP balance in the pool is owned by the pool position
+    let position = LiquidityPosition {
+        position_id: pool.position_id,
+        lp_amount: None,
+    };
+
     let withdraw_liquidity_response = liquidity_client.withdraw_liquidity_from_pool(
         total_shares.clone(),
-        shares.clone()
+        shares.clone(),
+        position
     ).await
         .map_err(|error| {
             // Event: Withdraw liquidity from pool failed
@@ -5,6 +5,7 @@ use ic_cdk_timers::TimerId;
 use liquidity::liquidity_router;
 use liquidity::liquidity_client::LiquidityClient;
 use types::context::Context;
+use types::liquidity::LiquidityPosition;
 use errors::internal_error::error::{InternalError, InternalErrorKind};
 use errors::internal_error::error_codes::module::areas::{
     canisters as canister_area,
@@ -97,7 +98,12 @@ async fn get_position_data(_context: Context, pool: &Pool) -> Result<Option<Posi
     let liquidity_client = get_liquidity_client(pool).await;
 
     if let Some(position_id) = pool.position_id.as_ref().cloned() {
-        let position_response = liquidity_client.get_position_by_id(position_id).await?;
+        let position = LiquidityPosition {
+            position_id: Some(position_id),
+            lp_amount: None,
+        };
+
+        let position_response = liquidity_client.get_position(position).await?;
 
         let current_position = PositionData {
             id: position_response.position_id,
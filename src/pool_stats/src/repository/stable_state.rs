@@ -3,6 +3,9 @@ use serde::Serialize;
 use ic_cdk::storage;
 use std::collections::HashMap;
 
+use access_control::access_control_service;
+use access_control::role::AccessControlState;
+
 use crate::event_records::event_record::EventRecord;
 
 use crate::pools::pool::Pool;
@@ -18,6 +21,7 @@ pub struct StableState {
     pub pools: HashMap<String, Pool>,
     pub pool_snapshots: HashMap<String, Vec<PoolSnapshot>>,
     pub event_records: Vec<EventRecord>,
+    pub access_control: Option<AccessControlState>,
 }
 
 pub fn stable_save() {
@@ -39,7 +43,8 @@ pub fn stable_save() {
         runtime_config: Some(runtime_config),
         pools,
         pool_snapshots,
-        event_records
+        event_records,
+        access_control: Some(access_control_service::get_state()),
     };
 
     storage::stable_save((state,)).expect("failed to save stable state");
@@ -49,6 +54,7 @@ pub fn stable_restore() {
     let (state,): (StableState,) = storage::stable_restore().expect("failed to restore stable state");
 
     runtime_config_repo::set_runtime_config(state.runtime_config.clone().unwrap_or_default());
+    access_control_service::set_state(state.access_control.clone().unwrap_or_default());
 
     POOLS.with(|pools| {
         pools.borrow_mut();
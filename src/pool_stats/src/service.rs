@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use candid::Nat;
 use ic_cdk::caller;
+use icrc_ledger_types::icrc1::account::Account;
 
 use types::exchange_id::ExchangeId;
 use types::liquidity::{AddLiquidityResponse, WithdrawLiquidityResponse};
@@ -318,7 +319,7 @@ pub async fn add_liquidity_to_pool(
     let icrc_ledger_client = service_resolver.icrc_ledger_client();
 
     icrc_ledger_client.icrc2_transfer_from(
-        caller(),
+        Account::from(caller()),
         ledger,
         amount.clone()
     ).await?;
@@ -6,15 +6,16 @@ pub mod utils;
 pub mod vault;
 
 use candid::export_service;
-use ic_cdk::{call, id, trap};
-use ic_cdk::api::call::CallResult;
+use candid::Principal;
+use ic_cdk::caller;
 use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
 use std::cell::RefCell;
 use ic_cdk_timers::TimerId;
-use candid::{CandidType, Deserialize, Principal};
-use serde::Serialize;
 
 use errors::response_error::error::ResponseError;
+use access_control::access_control_service;
+use access_control::role::{Role, AccessControlResult, GetAccessControlStateResult};
+use access_control::return_if_unauthorized;
 use ::types::strategies::StrategyId;
 
 use crate::repository::stable_state;
@@ -39,44 +40,17 @@ use crate::types::types::{
 
 const STRATEGY_HISTORY_FETCHING_INTERVAL: u64 = 3600; // 1 hour
 
-// Macro for operator authorization check
-macro_rules! trap_if_not_authenticated {
-    () => {
-        let caller = ic_cdk::caller();
-        let operator = CONFIG.with(|config| config.borrow().operator);
-
-        if operator.is_none() || operator.unwrap() != caller {
-            trap("Unauthorized: caller is not a operator");
-        }
-    }
-}
-
-#[derive(CandidType, Debug, Clone, Deserialize)]
-pub struct CanisterIdRequest {
-    #[serde(rename = "canister_id")]
-    pub canister_id: Principal,
-}
-
-#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
-struct Config {
-    operator: Option<Principal>,
-}
-
 thread_local! {
     static FETCHING_TIMER_ID: RefCell<Option<TimerId>> = RefCell::new(None);
-    static CONFIG: RefCell<Config> = RefCell::new(
-        Config {
-            operator: None
-        },
-    );
 }
 
 
 // =============== Initialization ===============
 
 #[init]
-fn init(runtime_config: RuntimeConfig) {
+fn init(runtime_config: RuntimeConfig, operator: Option<Principal>) {
     runtime_config_repo::set_runtime_config(runtime_config);
+    init_operator(operator);
 
     scheduler_service::start_fetching_timer(STRATEGY_HISTORY_FETCHING_INTERVAL);
 }
@@ -87,12 +61,23 @@ fn pre_upgrade() {
     scheduler_service::stop_fetching_timer();
 }
 
+/// The runtime config is restored from the stable state.
 #[post_upgrade]
-fn post_upgrade() {
+fn post_upgrade(_runtime_config: Option<RuntimeConfig>, operator: Option<Principal>) {
     stable_state::stable_restore();
+    init_operator(operator);
     scheduler_service::start_fetching_timer(STRATEGY_HISTORY_FETCHING_INTERVAL);
 }
 
+/// Gives the operator role to the principal of the removed `set_operator`.
+///
+/// It was only kept in the heap, so the upgrade to the access control passes it again.
+fn init_operator(operator: Option<Principal>) {
+    if let Some(operator) = operator {
+        access_control_service::init_role(operator, Role::Operator);
+    }
+}
+
 // =============== API Methods ===============
 
 #[query]
@@ -128,7 +113,7 @@ fn get_strategy_state(strategy_id: StrategyId) -> Option<StrategyState> {
 /// Fetch and save current strategies from vault
 #[update]
 async fn test_initialize_strategy_states_and_create_snapshots(strategy_ids: Option<Vec<StrategyId>>) -> InitializeStrategyStatesAndCreateSnapshotsResult {
-    trap_if_not_authenticated!();
+    return_if_unauthorized!(Role::Operator, InitializeStrategyStatesAndCreateSnapshotsResult);
 
     let result =
         strategy_history_service::initialize_strategy_states_and_create_snapshots(strategy_ids)
@@ -140,7 +125,7 @@ async fn test_initialize_strategy_states_and_create_snapshots(strategy_ids: Opti
 
 #[update]
 async fn test_initialize_strategy_states(strategy_ids: Option<Vec<StrategyId>>) -> InitializeStrategyStatesResult {
-    trap_if_not_authenticated!();
+    return_if_unauthorized!(Role::Operator, InitializeStrategyStatesResult);
 
     let result =
         strategy_history_service::initialize_strategy_states(strategy_ids)
@@ -153,7 +138,7 @@ async fn test_initialize_strategy_states(strategy_ids: Option<Vec<StrategyId>>)
 /// Save a strategy snapshot
 #[update]
 async fn test_save_strategy_snapshot(snapshot: StrategySnapshot) -> SaveStrategySnapshotResult {
-    trap_if_not_authenticated!();
+    return_if_unauthorized!(Role::Operator, SaveStrategySnapshotResult);
 
     let result =
         strategy_snapshots_service::save_strategy_snapshot(snapshot)
@@ -165,7 +150,7 @@ async fn test_save_strategy_snapshot(snapshot: StrategySnapshot) -> SaveStrategy
 /// Create test snapshots for a strategy with controlled APY
 #[update]
 async fn test_create_snapshots(request: CreateTestSnapshotsRequest) -> CreateTestSnapshotsResult {
-    trap_if_not_authenticated!();
+    return_if_unauthorized!(Role::Operator, CreateTestSnapshotsResult);
 
     let result =
         test_snapshots_service::create_test_snapshots(request)
@@ -176,31 +161,39 @@ async fn test_create_snapshots(request: CreateTestSnapshotsRequest) -> CreateTes
 }
 
 #[update]
-async fn test_remove_zero_liquidity_snapshots() {
-    trap_if_not_authenticated!();
+async fn test_remove_zero_liquidity_snapshots() -> AccessControlResult {
+    return_if_unauthorized!(Role::Admin, AccessControlResult);
 
     repository::snapshots_repo::remove_zero_liquidity_snapshots();
+
+    AccessControlResult(Ok(()))
 }
 
 #[update]
-fn test_delete_strategy_state(strategy_id: StrategyId) {
-    trap_if_not_authenticated!();
+fn test_delete_strategy_state(strategy_id: StrategyId) -> AccessControlResult {
+    return_if_unauthorized!(Role::Admin, AccessControlResult);
 
     strategy_states_service::delete_strategy_state(strategy_id);
+
+    AccessControlResult(Ok(()))
 }
 
 #[update]
-fn test_delete_all_snapshots() {
-    trap_if_not_authenticated!();
+fn test_delete_all_snapshots() -> AccessControlResult {
+    return_if_unauthorized!(Role::Admin, AccessControlResult);
 
     repository::snapshots_repo::delete_all_snapshots();
+
+    AccessControlResult(Ok(()))
 }
 
 #[update]
-fn test_delete_all_snapshots_for_strategy(strategy_id: StrategyId) {
-    trap_if_not_authenticated!();
+fn test_delete_all_snapshots_for_strategy(strategy_id: StrategyId) -> AccessControlResult {
+    return_if_unauthorized!(Role::Admin, AccessControlResult);
 
     repository::snapshots_repo::delete_all_snapshots_for_strategy(strategy_id);
+
+    AccessControlResult(Ok(()))
 }
 
 #[query]
@@ -209,32 +202,45 @@ fn get_runtime_config() -> RuntimeConfig {
 }
 
 
-// Sets the operator principal.
+// =============== Access control ===============
+
 #[update]
-async fn set_operator(operator: Principal) {
-    let controllers = get_controllers().await;
-    if !controllers.contains(&ic_cdk::caller()) {
-        trap("Unauthorized: caller is not a controller");
-    }
-    CONFIG.with(|config| {
-        let mut config = config.borrow_mut();
-        config.operator = Some(operator);
-    });
-}
-
-async fn get_controllers() -> Vec<Principal> {
-    let res: CallResult<(ic_cdk::api::management_canister::main::CanisterStatusResponse,)> = call(
-        Principal::management_canister(),
-        "canister_status",
-        (CanisterIdRequest { canister_id: id() },),
-    )
-        .await;
-    res
-        .expect(
-            "Get controllers function exited unexpectedly:\n\
-            inter-canister call to management canister for canister_status returned an empty result."
-        )
-        .0.settings.controllers
+fn grant_role(principal: Principal, role: Role) -> AccessControlResult {
+    let result = access_control_service::grant_role(caller(), principal, role)
+        .map_err(|e| ResponseError::from_internal_error(e));
+
+    AccessControlResult(result)
+}
+
+#[update]
+fn accept_role(role: Role) -> AccessControlResult {
+    let result = access_control_service::accept_role(caller(), role)
+        .map_err(|e| ResponseError::from_internal_error(e));
+
+    AccessControlResult(result)
+}
+
+#[update]
+fn revoke_role(principal: Principal, role: Role) -> AccessControlResult {
+    let result = access_control_service::revoke_role(caller(), principal, role)
+        .map_err(|e| ResponseError::from_internal_error(e));
+
+    AccessControlResult(result)
+}
+
+#[update]
+fn confirm_revoke_role(principal: Principal, role: Role) -> AccessControlResult {
+    let result = access_control_service::confirm_revoke_role(caller(), principal, role)
+        .map_err(|e| ResponseError::from_internal_error(e));
+
+    AccessControlResult(result)
+}
+
+#[query]
+fn get_access_control() -> GetAccessControlStateResult {
+    return_if_unauthorized!(Role::Viewer, GetAccessControlStateResult);
+
+    GetAccessControlStateResult(Ok(access_control_service::get_state()))
 }
 
 // =============== Candid Export ===============
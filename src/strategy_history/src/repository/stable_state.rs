@@ -3,6 +3,8 @@ use ic_cdk::storage;
 use serde::Serialize;
 
 use types::strategies::StrategyId;
+use access_control::access_control_service;
+use access_control::role::AccessControlState;
 
 use crate::repository::runtime_config_repo::{self, RuntimeConfig};
 use crate::strategy_snapshot::strategy_snapshot::StrategySnapshot;
@@ -14,13 +16,15 @@ pub struct StableState {
     pub runtime_config: RuntimeConfig,
     pub snapshots: Vec<StrategySnapshot>,
     pub strategy_states: Vec<(StrategyId, StrategyState)>,
+    pub access_control: Option<AccessControlState>,
 }
 
 pub fn stable_save() {
     let snapshots = snapshots_repo::get_all_snapshots();
     let strategy_states = strategy_states_repo::get_all_strategy_states();
     let runtime_config = runtime_config_repo::get_runtime_config();
-    let state = StableState { snapshots, strategy_states, runtime_config };
+    let access_control = Some(access_control_service::get_state());
+    let state = StableState { snapshots, strategy_states, runtime_config, access_control };
     storage::stable_save((state,)).unwrap();
 }
 
@@ -28,6 +32,7 @@ pub fn stable_restore() {
     let (state,): (StableState,) = storage::stable_restore().unwrap();
 
     runtime_config_repo::set_runtime_config(state.runtime_config.clone());
+    access_control_service::set_state(state.access_control.unwrap_or_default());
 
     for snapshot in state.snapshots {
         snapshots_repo::save_snapshot(snapshot);
@@ -1,5 +1,6 @@
 use candid::Nat;
 use std::ops::{Div, Mul};
+use icrc_ledger_types::icrc1::account::Account;
 
 use errors::internal_error::error::{InternalError, InternalErrorKind};
 use errors::internal_error::error_codes::module::areas::{
@@ -112,6 +113,7 @@ fn build_strategy_snapshot(
         vault_strategy.current_liquidity_updated_at,
         vault_strategy.current_pool.clone(),
         test_liquidity_amount,
+        vault_strategy.share_price.clone(),
         apy
     ))
 }
@@ -120,7 +122,7 @@ fn current_test_liquidity_amount(vault_strategy: &StrategyResponse) -> Option<Na
     let canister_principal = ic_cdk::api::id();
     let test_liquidity_shares = vault_strategy
         .user_shares
-        .get(&canister_principal);
+        .get(&Account::from(canister_principal));
 
     test_liquidity_shares.map(|shares| {
         vault_strategy
@@ -152,6 +152,7 @@ pub async fn deposit_test_liquidity_to_strategy(
         strategy_id: vault_strategy.id,
         ledger: base_token,
         amount: available_for_deposit.clone(),
+        min_amount_out: None,
     };
 
     // Call vault deposit
@@ -14,13 +14,22 @@ impl YieldSnapshot for StrategySnapshot {
     }
 }
 
+/// Calculates the APY from the share price growth, so deposits and withdrawals don't count as yield.
+///
+/// Snapshots taken before the share price was recorded are ignored.
 pub fn calculate_strategy_yield(snapshots: &[StrategySnapshot], now: u64) -> f64 {
+    let priced_snapshots: Vec<StrategySnapshot> = snapshots
+        .iter()
+        .filter(|snapshot| snapshot.share_price.is_some())
+        .cloned()
+        .collect();
+
     yield_calculator::calculate_snapshot_yield_for_period(
-        snapshots,
+        &priced_snapshots,
         DEFAULT_PERIOD,
         now,
         |snapshot: &StrategySnapshot| {
-            snapshot.test_liquidity_amount.clone().unwrap_or_else(|| Nat::from(0u64))
+            snapshot.share_price.clone().unwrap_or_else(|| Nat::from(0u64))
         },
     )
 }
@@ -1,10 +1,11 @@
 use candid::Nat;
 use std::ops::{Div, Mul};
+use icrc_ledger_types::icrc1::account::Account;
 use noise::NoiseFn;
 
 use ::utils::util::current_timestamp_secs;
 use utils::util::nat_to_u128;
-use ::types::strategies::StrategyResponse;
+use ::types::strategies::{StrategyResponse, SHARE_PRICE_SCALE};
 use errors::internal_error::error::{InternalError, InternalErrorKind};
 use errors::internal_error::error_codes::module::areas::{
     canisters as canister_area,
@@ -194,6 +195,7 @@ pub async fn create_test_snapshots(
             current_liquidity_updated_at,
             current_pool.clone(),
             current_test_liquidity_amount(vault_strategy), // Calculate our test liquidity share
+            Some(test_amount.clone().mul(Nat::from(SHARE_PRICE_SCALE)).div(test_liquidity_data_shares.clone())),
             apy,
         );
 
@@ -253,7 +255,7 @@ fn current_test_liquidity_amount(vault_strategy: &StrategyResponse) -> Option<Na
     let canister_principal = ic_cdk::api::id();
     let test_liquidity_shares = vault_strategy
         .user_shares
-        .get(&canister_principal);
+        .get(&Account::from(canister_principal));
 
     test_liquidity_shares.map(|shares| {
         vault_strategy
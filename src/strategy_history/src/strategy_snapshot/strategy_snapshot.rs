@@ -21,6 +21,8 @@ pub struct StrategySnapshot {
     pub users_count: u32,
     pub current_pool: Option<Pool>,
     pub test_liquidity_amount: Option<Nat>,
+    /// Value of a share in base token scaled by 10^12, empty for snapshots taken before it was recorded
+    pub share_price: Option<Nat>,
     pub apy: f64,
 }
 
@@ -37,6 +39,7 @@ impl StrategySnapshot {
         current_liquidity_updated_at: Option<u64>,
         current_pool: Option<Pool>,
         test_liquidity_amount: Option<Nat>,
+        share_price: Option<Nat>,
         apy: f64,
     ) -> Self {
         Self {
@@ -51,6 +54,7 @@ impl StrategySnapshot {
             current_liquidity_updated_at,
             current_pool,
             test_liquidity_amount,
+            share_price,
             apy,
         }
     }
@@ -65,6 +69,7 @@ impl StrategySnapshot {
         current_liquidity_updated_at: Option<u64>,
         current_pool: Option<Pool>,
         test_liquidity_amount: Option<Nat>,
+        share_price: Option<Nat>,
         apy: f64,
     ) -> Self {
         let id = get_next_snapshot_id(strategy_id);
@@ -82,6 +87,7 @@ impl StrategySnapshot {
             current_liquidity_updated_at,
             current_pool,
             test_liquidity_amount,
+            share_price,
             apy,
         )
     }
@@ -69,6 +69,7 @@ pub struct StrategyDepositArgs {
     pub strategy_id: StrategyId,
     pub ledger: ::types::CanisterId,
     pub amount: Nat,
+    pub min_amount_out: Option<Nat>,
 }
 
 #[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
@@ -84,10 +85,13 @@ pub struct StrategyWithdrawArgs {
     pub strategy_id: StrategyId,
     pub ledger: CanisterId,
     pub percentage: Nat,
+    pub output_token: Option<CanisterId>,
+    pub min_amount_out: Option<Nat>,
 }
 
 #[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
 pub struct StrategyWithdrawResponse {
     pub amount: Nat,
     pub current_shares: Nat,
+    pub token: CanisterId,
 }
@@ -6,18 +6,26 @@ use event_records::generic_event_record::GenericEventRecord;
 use event_records::events::pool_events::*;
 use errors::internal_error::error::InternalError;
 
+use crate::impl_candid_storable;
 use crate::event_records::events::strategy_events::*;
 use crate::event_records::events::swap_events::*;
+use crate::event_records::events::emergency_events::*;
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct EventRecord(pub GenericEventRecord<Event>);
 
+impl_candid_storable!(EventRecord);
+
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub enum Event {
     // Strategy Deposit
     StrategyDepositStarted(StrategyDepositStarted),
     StrategyDepositCompleted(StrategyDepositCompleted),
     StrategyDepositFailed(StrategyDepositFailed),
+    // Strategy Deposit Zap
+    StrategyZapStarted(StrategyZapStarted),
+    StrategyZapCompleted(StrategyZapCompleted),
+    StrategyZapFailed(StrategyZapFailed),
     // Strategy Withdraw
     StrategyWithdrawStarted(StrategyWithdrawStarted),
     StrategyWithdrawCompleted(StrategyWithdrawCompleted),
@@ -26,6 +34,14 @@ pub enum Event {
     StrategyRebalanceStarted(StrategyRebalanceStarted),
     StrategyRebalanceCompleted(StrategyRebalanceCompleted),
     StrategyRebalanceFailed(StrategyRebalanceFailed),
+    // Strategy Fees
+    StrategyManagementFeeAccrued(StrategyManagementFeeAccrued),
+    StrategyPerformanceFeeAccrued(StrategyPerformanceFeeAccrued),
+    // Strategy Compounding
+    StrategyCompoundStarted(StrategyCompoundStarted),
+    StrategyCompoundCompleted(StrategyCompoundCompleted),
+    StrategyCompoundSkipped(StrategyCompoundSkipped),
+    StrategyCompoundFailed(StrategyCompoundFailed),
     // Add liquidity to pool
     AddLiquidityToPoolStarted(AddLiquidityToPoolStarted),
     AddLiquidityToPoolCompleted(AddLiquidityToPoolCompleted),
@@ -38,6 +54,16 @@ pub enum Event {
     SwapTokenStarted(SwapTokenStarted),
     SwapTokenCompleted(SwapTokenCompleted),
     SwapTokenFailed(SwapTokenFailed),
+    // Operations pause
+    OperationsPaused(OperationsPaused),
+    OperationsUnpaused(OperationsUnpaused),
+    // Circuit breaker
+    CircuitBreakerTripped(CircuitBreakerTripped),
+    // Strategy unwind
+    StrategyUnwindStarted(StrategyUnwindStarted),
+    StrategyUnwindCompleted(StrategyUnwindCompleted),
+    StrategyUnwindFailed(StrategyUnwindFailed),
+    UnwoundSharesRedeemed(UnwoundSharesRedeemed),
 }
 
 impl Event {
@@ -47,6 +73,10 @@ impl Event {
             Self::StrategyDepositStarted(_) => "StrategyDepositStarted",
             Self::StrategyDepositCompleted(_) => "StrategyDepositCompleted",
             Self::StrategyDepositFailed(_) => "StrategyDepositFailed",
+            // Strategy Deposit Zap
+            Self::StrategyZapStarted(_) => "StrategyZapStarted",
+            Self::StrategyZapCompleted(_) => "StrategyZapCompleted",
+            Self::StrategyZapFailed(_) => "StrategyZapFailed",
             // Strategy Withdraw
             Self::StrategyWithdrawStarted(_) => "StrategyWithdrawStarted",
             Self::StrategyWithdrawCompleted(_) => "StrategyWithdrawCompleted",
@@ -55,6 +85,14 @@ impl Event {
             Self::StrategyRebalanceStarted(_) => "StrategyRebalanceStarted",
             Self::StrategyRebalanceCompleted(_) => "StrategyRebalanceCompleted",
             Self::StrategyRebalanceFailed(_) => "StrategyRebalanceFailed",
+            // Strategy Fees
+            Self::StrategyManagementFeeAccrued(_) => "StrategyManagementFeeAccrued",
+            Self::StrategyPerformanceFeeAccrued(_) => "StrategyPerformanceFeeAccrued",
+            // Strategy Compounding
+            Self::StrategyCompoundStarted(_) => "StrategyCompoundStarted",
+            Self::StrategyCompoundCompleted(_) => "StrategyCompoundCompleted",
+            Self::StrategyCompoundSkipped(_) => "StrategyCompoundSkipped",
+            Self::StrategyCompoundFailed(_) => "StrategyCompoundFailed",
             // Add liquidity to pool
             Self::AddLiquidityToPoolStarted(_) => "AddLiquidityToPoolStarted",
             Self::AddLiquidityToPoolCompleted(_) => "AddLiquidityToPoolCompleted",
@@ -67,6 +105,16 @@ impl Event {
             Self::SwapTokenStarted(_) => "SwapTokenStarted",
             Self::SwapTokenCompleted(_) => "SwapTokenCompleted",
             Self::SwapTokenFailed(_) => "SwapTokenFailed",
+            // Operations pause
+            Self::OperationsPaused(_) => "OperationsPaused",
+            Self::OperationsUnpaused(_) => "OperationsUnpaused",
+            // Circuit breaker
+            Self::CircuitBreakerTripped(_) => "CircuitBreakerTripped",
+            // Strategy unwind
+            Self::StrategyUnwindStarted(_) => "StrategyUnwindStarted",
+            Self::StrategyUnwindCompleted(_) => "StrategyUnwindCompleted",
+            Self::StrategyUnwindFailed(_) => "StrategyUnwindFailed",
+            Self::UnwoundSharesRedeemed(_) => "UnwoundSharesRedeemed",
         }
     }
 
@@ -74,20 +122,32 @@ impl Event {
         Self::StrategyDepositStarted(StrategyDepositStarted { strategy_id, pool_id, amount0 })
     }
 
-    pub fn strategy_deposit_completed(strategy_id: String, pool_id: Option<String>, amount0: Option<Nat>) -> Self {
-        Self::StrategyDepositCompleted(StrategyDepositCompleted { strategy_id, pool_id, amount0 })
+    pub fn strategy_deposit_completed(strategy_id: String, pool_id: Option<String>, amount0: Option<Nat>, nav: Option<Nat>) -> Self {
+        Self::StrategyDepositCompleted(StrategyDepositCompleted { strategy_id, pool_id, amount0, nav })
     }
 
     pub fn strategy_deposit_failed(strategy_id: String, pool_id: Option<String>, amount0: Option<Nat>, error: InternalError) -> Self {
         Self::StrategyDepositFailed(StrategyDepositFailed { strategy_id, pool_id, amount0, error })
     }
+
+    pub fn strategy_zap_started(strategy_id: String, token_in: CanisterId, token_out: CanisterId, amount_in: Nat, min_amount_out: Nat) -> Self {
+        Self::StrategyZapStarted(StrategyZapStarted { strategy_id, token_in, token_out, amount_in, min_amount_out })
+    }
+
+    pub fn strategy_zap_completed(strategy_id: String, token_in: CanisterId, token_out: CanisterId, amount_in: Nat, amount_out: Nat) -> Self {
+        Self::StrategyZapCompleted(StrategyZapCompleted { strategy_id, token_in, token_out, amount_in, amount_out })
+    }
+
+    pub fn strategy_zap_failed(strategy_id: String, token_in: CanisterId, token_out: CanisterId, amount_in: Nat, error: InternalError) -> Self {
+        Self::StrategyZapFailed(StrategyZapFailed { strategy_id, token_in, token_out, amount_in, error })
+    }
     
     pub fn strategy_withdraw_started(strategy_id: String, pool_id: Option<String>, shares: Option<Nat>) -> Self {
         Self::StrategyWithdrawStarted(StrategyWithdrawStarted { strategy_id, pool_id, shares })
     }
 
-    pub fn strategy_withdraw_completed(strategy_id: String, pool_id: Option<String>, shares: Option<Nat>, amount0: Option<Nat>) -> Self {
-        Self::StrategyWithdrawCompleted(StrategyWithdrawCompleted { strategy_id, pool_id, shares, amount0 })
+    pub fn strategy_withdraw_completed(strategy_id: String, pool_id: Option<String>, shares: Option<Nat>, amount0: Option<Nat>, nav: Option<Nat>) -> Self {
+        Self::StrategyWithdrawCompleted(StrategyWithdrawCompleted { strategy_id, pool_id, shares, amount0, nav })
     }
 
     pub fn strategy_withdraw_failed(strategy_id: String, pool_id: Option<String>, shares: Option<Nat>, error: InternalError) -> Self {
@@ -105,6 +165,30 @@ impl Event {
     pub fn strategy_rebalance_failed(strategy_id: String, previous_pool_id: Option<String>, new_pool_id: Option<String>, error: InternalError) -> Self {
         Self::StrategyRebalanceFailed(StrategyRebalanceFailed { strategy_id, previous_pool_id, new_pool_id, error })
     }
+
+    pub fn strategy_management_fee_accrued(strategy_id: String, nav: Nat, fee_shares: Nat, period_secs: u64) -> Self {
+        Self::StrategyManagementFeeAccrued(StrategyManagementFeeAccrued { strategy_id, nav, fee_shares, period_secs })
+    }
+
+    pub fn strategy_performance_fee_accrued(strategy_id: String, nav: Nat, fee_shares: Nat, previous_high_water_mark: Nat, high_water_mark: Nat) -> Self {
+        Self::StrategyPerformanceFeeAccrued(StrategyPerformanceFeeAccrued { strategy_id, nav, fee_shares, previous_high_water_mark, high_water_mark })
+    }
+
+    pub fn strategy_compound_started(strategy_id: String, pool_id: String, fees0: Nat, fees1: Nat) -> Self {
+        Self::StrategyCompoundStarted(StrategyCompoundStarted { strategy_id, pool_id, fees0, fees1 })
+    }
+
+    pub fn strategy_compound_completed(strategy_id: String, pool_id: String, claimed0: Nat, claimed1: Nat, amount0: Nat, amount1: Nat) -> Self {
+        Self::StrategyCompoundCompleted(StrategyCompoundCompleted { strategy_id, pool_id, claimed0, claimed1, amount0, amount1 })
+    }
+
+    pub fn strategy_compound_skipped(strategy_id: String, pool_id: String, fees_value: Nat, cost: Nat) -> Self {
+        Self::StrategyCompoundSkipped(StrategyCompoundSkipped { strategy_id, pool_id, fees_value, cost })
+    }
+
+    pub fn strategy_compound_failed(strategy_id: String, pool_id: String, error: InternalError) -> Self {
+        Self::StrategyCompoundFailed(StrategyCompoundFailed { strategy_id, pool_id, error })
+    }
     
     pub fn add_liquidity_to_pool_started(pool_id: String, amount0: Option<Nat>, amount1: Option<Nat>) -> Self {
         Self::AddLiquidityToPoolStarted(AddLiquidityToPoolStarted { pool_id, amount0, amount1 })
@@ -141,4 +225,32 @@ impl Event {
     pub fn swap_token_failed(pool_id: String, token_in: CanisterId, token_out: CanisterId, amount_in: Option<Nat>, error: InternalError) -> Self {
         Self::SwapTokenFailed(SwapTokenFailed { pool_id, token_in, token_out, amount_in, error })
     }
+
+    pub fn operations_paused(strategy_id: Option<String>, deposit: bool, withdraw: bool, rebalance: bool, reason: Option<String>) -> Self {
+        Self::OperationsPaused(OperationsPaused { strategy_id, deposit, withdraw, rebalance, reason })
+    }
+
+    pub fn operations_unpaused(strategy_id: Option<String>, deposit: bool, withdraw: bool, rebalance: bool) -> Self {
+        Self::OperationsUnpaused(OperationsUnpaused { strategy_id, deposit, withdraw, rebalance })
+    }
+
+    pub fn circuit_breaker_tripped(strategy_id: String, breaker: CircuitBreaker, value_bps: u64, bound_bps: u64) -> Self {
+        Self::CircuitBreakerTripped(CircuitBreakerTripped { strategy_id, breaker, value_bps, bound_bps })
+    }
+
+    pub fn strategy_unwind_started(strategy_id: String, shares: Nat) -> Self {
+        Self::StrategyUnwindStarted(StrategyUnwindStarted { strategy_id, shares })
+    }
+
+    pub fn strategy_unwind_completed(strategy_id: String, shares: Nat, amount0: Nat) -> Self {
+        Self::StrategyUnwindCompleted(StrategyUnwindCompleted { strategy_id, shares, amount0 })
+    }
+
+    pub fn strategy_unwind_failed(strategy_id: String, shares: Nat, error: InternalError) -> Self {
+        Self::StrategyUnwindFailed(StrategyUnwindFailed { strategy_id, shares, error })
+    }
+
+    pub fn unwound_shares_redeemed(strategy_id: String, shares: Nat, amount0: Nat) -> Self {
+        Self::UnwoundSharesRedeemed(UnwoundSharesRedeemed { strategy_id, shares, amount0 })
+    }
 }
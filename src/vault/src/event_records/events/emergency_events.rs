@@ -0,0 +1,70 @@
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+use errors::internal_error::error::InternalError;
+
+/// Automatic pause of a strategy.
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum CircuitBreaker {
+    Slippage,
+    NavDrop,
+}
+
+// Operations Pause
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct OperationsPaused {
+    /// Empty for a pause of every strategy
+    pub strategy_id: Option<String>,
+    pub deposit: bool,
+    pub withdraw: bool,
+    pub rebalance: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct OperationsUnpaused {
+    /// Empty for a pause of every strategy
+    pub strategy_id: Option<String>,
+    pub deposit: bool,
+    pub withdraw: bool,
+    pub rebalance: bool,
+}
+
+// Circuit Breaker
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct CircuitBreakerTripped {
+    pub strategy_id: String,
+    pub breaker: CircuitBreaker,
+    /// Measured slippage or NAV drop and the bound it exceeded
+    pub value_bps: u64,
+    pub bound_bps: u64,
+}
+
+// Strategy Unwind
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyUnwindStarted {
+    pub strategy_id: String,
+    pub shares: Nat,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyUnwindCompleted {
+    pub strategy_id: String,
+    pub shares: Nat,
+    /// Base token parked in the vault for the shares
+    pub amount0: Nat,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyUnwindFailed {
+    pub strategy_id: String,
+    pub shares: Nat,
+    pub error: InternalError,
+}
+
+// Unwound Shares Redemption
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct UnwoundSharesRedeemed {
+    pub strategy_id: String,
+    pub shares: Nat,
+    pub amount0: Nat,
+}
@@ -1,2 +1,3 @@
 pub mod strategy_events;
 pub mod swap_events;
+pub mod emergency_events;
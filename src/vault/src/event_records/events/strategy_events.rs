@@ -1,6 +1,7 @@
 use candid::{CandidType, Deserialize, Nat};
 use serde::Serialize;
 use errors::internal_error::error::InternalError;
+use types::CanisterId;
 
 // Strategy Deposit
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
@@ -15,6 +16,8 @@ pub struct StrategyDepositCompleted {
     pub strategy_id: String,
     pub pool_id: Option<String>,
     pub amount0: Option<Nat>,
+    /// Position value in base token the shares were priced against
+    pub nav: Option<Nat>,
 }
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
@@ -25,6 +28,36 @@ pub struct StrategyDepositFailed {
     pub error: InternalError,
 }
 
+// Strategy Zap
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyZapStarted {
+    pub strategy_id: String,
+    /// Token swapped from, the deposited token or the base token on withdrawals
+    pub token_in: CanisterId,
+    /// Token swapped to, the base token on deposits or the withdrawal output token
+    pub token_out: CanisterId,
+    pub amount_in: Nat,
+    pub min_amount_out: Nat,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyZapCompleted {
+    pub strategy_id: String,
+    pub token_in: CanisterId,
+    pub token_out: CanisterId,
+    pub amount_in: Nat,
+    pub amount_out: Nat,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyZapFailed {
+    pub strategy_id: String,
+    pub token_in: CanisterId,
+    pub token_out: CanisterId,
+    pub amount_in: Nat,
+    pub error: InternalError,
+}
+
 // Strategy Withdraw
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct StrategyWithdrawStarted {
@@ -39,6 +72,8 @@ pub struct StrategyWithdrawCompleted {
     pub pool_id: Option<String>,
     pub shares: Option<Nat>,
     pub amount0: Option<Nat>,
+    /// Position value in base token before the withdrawal
+    pub nav: Option<Nat>,
 }
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
@@ -70,3 +105,59 @@ pub struct StrategyRebalanceFailed {
     pub new_pool_id: Option<String>,
     pub error: InternalError,
 }
+
+// Strategy Fees
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyManagementFeeAccrued {
+    pub strategy_id: String,
+    pub nav: Nat,
+    pub fee_shares: Nat,
+    pub period_secs: u64,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyPerformanceFeeAccrued {
+    pub strategy_id: String,
+    pub nav: Nat,
+    pub fee_shares: Nat,
+    pub previous_high_water_mark: Nat,
+    pub high_water_mark: Nat,
+}
+
+// Strategy Compounding
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyCompoundStarted {
+    pub strategy_id: String,
+    pub pool_id: String,
+    /// Fees accumulated by the position
+    pub fees0: Nat,
+    pub fees1: Nat,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyCompoundCompleted {
+    pub strategy_id: String,
+    pub pool_id: String,
+    /// Fees received from the position
+    pub claimed0: Nat,
+    pub claimed1: Nat,
+    /// Token amounts added back to the position
+    pub amount0: Nat,
+    pub amount1: Nat,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyCompoundSkipped {
+    pub strategy_id: String,
+    pub pool_id: String,
+    /// Value of the fees and estimated cost of compounding them in pool token0
+    pub fees_value: Nat,
+    pub cost: Nat,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyCompoundFailed {
+    pub strategy_id: String,
+    pub pool_id: String,
+    pub error: InternalError,
+}
@@ -8,89 +8,65 @@ mod pool_stats;
 mod service;
 mod utils;
 
-use std::cell::RefCell;
-use candid::{candid_method, export_service, Nat, Principal, CandidType, Deserialize, decode_one};
+use candid::{candid_method, export_service, Nat, Principal, CandidType, Deserialize};
 
-use ic_cdk::{call, id, trap, caller};
-use ic_cdk::api::call::CallResult;
+use ic_cdk::caller;
 use ic_cdk_macros::{init, post_upgrade, pre_upgrade, update, query};
 
-use icrc_ledger_types::icrc21::errors::{ErrorInfo, Icrc21Error};
-use icrc_ledger_types::icrc21::responses::{ConsentInfo, ConsentMessage};
-use icrc_ledger_types::icrc21::requests::{
-    ConsentMessageRequest,
-    ConsentMessageSpec,
-    ConsentMessageMetadata
-};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
+use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
+use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
+use icrc_ledger_types::icrc21::errors::Icrc21Error;
+use icrc_ledger_types::icrc21::responses::ConsentInfo;
+use icrc_ledger_types::icrc21::requests::ConsentMessageRequest;
 
 use errors::response_error::error::ResponseError;
+use access_control::access_control_service;
+use access_control::role::{Role, AccessControlResult, GetAccessControlStateResult};
+use access_control::return_if_unauthorized;
 use ::types::CanisterId;
-use ::types::context::Context;
+use ::types::context::{Context, position_subaccount};
 use ::types::strategies::StrategyResponse;
 
 use crate::repository::stable_state;
 use crate::repository::strategies_repo;
 use crate::repository::runtime_config_repo::{self, RuntimeConfig};
 use crate::repository::config_repo::{self, Conf};
+use crate::repository::emergency_repo::CircuitBreakerConfig;
+use crate::repository::unwinds_repo::StrategyUnwind;
 use crate::strategies::strategy_service;
 use crate::types::types::*;
 use crate::strategies::stats::strategy_stats_service;
+use crate::strategies::rebalance_scheduler_service;
+use crate::strategies::smart_rebalance_service;
+use crate::strategies::fee_service;
+use crate::strategies::compounding_service;
+use crate::strategies::strategy_definitions_service;
+use crate::strategies::emergency_service;
+use crate::strategies::unwind_service;
+use crate::strategies::share_token_service;
+use crate::user::pending_withdrawals_service;
+use crate::user::consent_message_service;
+use crate::repository::pending_withdrawals_repo::PendingWithdrawal;
 use crate::utils::service_resolver::get_service_resolver;
 
 const STRATEGY_STATS_FETCHING_INTERVAL: u64 = 3600; // 1 hour
-
-#[derive(CandidType, Debug, Clone, Deserialize)]
-pub struct CanisterIdRequest {
-    #[serde(rename = "canister_id")]
-    pub canister_id: Principal,
-}
-
-thread_local! {
-    pub static HEARTBEAT: RefCell<u64> = RefCell::new(0);
-}
-
-/// The heartbeat function is called periodically to perform maintenance tasks.
-///
-/// This function increments a counter and checks if a day has passed (based on the counter value).
-/// TODO make unique for each strategy
-/// If a day has passed, it triggers the rebalance operation for all strategies.
-// #[heartbeat]
-#[allow(unused)]
-fn heartbeat() {
-    let n = (3600 * 24) as u64;
-    HEARTBEAT.with(|store| {
-        // TODO: uncomment
-        // let count = store.borrow_mut().clone();
-        // if count % n == 0 {
-        //     STRATEGIES.with(|strategies| {
-        //         let mut strategies = strategies.borrow_mut();
-        //         for strategy in strategies.iter_mut() {
-        //             strategy.rebalance();
-        //         }
-        //     });
-        // }
-        // store.replace(count + 1)
-    });
-}
-
-// Macro for operator authorization check
-macro_rules! trap_if_not_authenticated {
-    () => {
-        let caller = ic_cdk::caller();
-        let controllers = config_repo::get_controllers();
-
-        if controllers.is_none() || !controllers.unwrap().contains(&caller) {
-            trap("Unauthorized: caller is not a controller");
-        }   
-    }
-}
+const REBALANCE_SCHEDULER_INTERVAL: u64 = 600; // 10 minutes
+const PENDING_WITHDRAWALS_RETRY_INTERVAL: u64 = 3600; // 1 hour
+const COMPOUNDING_INTERVAL: u64 = 86400; // 1 day
 
 // =============== Test functions ===============
 
+// TODO: Test function. Remove after testing.
+#[derive(CandidType, Deserialize, Clone, Debug)]
+pub struct TestIcpswapWithdrawResult(pub Result<Nat, ResponseError>);
+
 // TODO: Test function. Remove after testing.
 #[update]
-async fn test_icpswap_withdraw(token_out: CanisterId, amount: Nat, token_fee: Nat) -> Nat {
-    trap_if_not_authenticated!();
+async fn test_icpswap_withdraw(token_out: CanisterId, amount: Nat, token_fee: Nat) -> TestIcpswapWithdrawResult {
+    return_if_unauthorized!(Role::Admin, TestIcpswapWithdrawResult);
 
     let canister_id = Principal::from_text("5fq4w-lyaaa-aaaag-qjqta-cai").unwrap();
 
@@ -105,38 +81,257 @@ async fn test_icpswap_withdraw(token_out: CanisterId, amount: Nat, token_fee: Na
         token_fee
     ).await;
 
-    icpswap_quote_result.unwrap()
+    let result = icpswap_quote_result
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    TestIcpswapWithdrawResult(result)
 }
 
 // TODO: Test function. Remove after testing.
 #[update]
-async fn test_reset_strategy(strategy_id: u16) {
-    trap_if_not_authenticated!();
+async fn test_reset_strategy(strategy_id: u16) -> AccessControlResult {
+    return_if_unauthorized!(Role::Admin, AccessControlResult);
 
     let mut strategy = strategies_repo::get_strategy_by_id(strategy_id).unwrap();
     strategy.test_reset_strategy().await;
+
+    AccessControlResult(Ok(()))
 }
 
 // TODO: Test function. Remove after testing.
 #[update]
-async fn test_update_strategy_stats() {
-    trap_if_not_authenticated!();
+async fn test_update_strategy_stats() -> AccessControlResult {
+    return_if_unauthorized!(Role::Operator, AccessControlResult);
 
     strategy_stats_service::update_all_strategy_liquidity().await;
+
+    AccessControlResult(Ok(()))
 }
 
-// TODO: Test function. Remove after testing.
+// =============== Rebalance ===============
+
 #[update]
 async fn rebalance_strategy(strategy_id: u16) -> StrategyRebalanceResult {
-    trap_if_not_authenticated!();
+    return_if_unauthorized!(Role::Operator, StrategyRebalanceResult);
 
-    let mut strategy = strategies_repo::get_strategy_by_id(strategy_id).unwrap();
-    let result = strategy.rebalance().await
+    let result = rebalance_scheduler_service::rebalance_strategy(strategy_id).await
         .map_err(|error| ResponseError::from_internal_error(error));
 
     StrategyRebalanceResult(result)
 }
 
+#[query]
+fn get_strategy_rebalance_history(
+    strategy_id: u16,
+    request: ListItemsPaginationRequest
+) -> GetRebalanceHistoryResult {
+    let result = service::get_strategy_rebalance_history(strategy_id, request)
+        .map_err(|error| ResponseError::from_internal_error(error))
+        .map(|response| RebalanceHistoryPaginationResponse(response));
+
+    GetRebalanceHistoryResult(result)
+}
+
+/// Retrieves a page of the share price series of a strategy between two timestamps, with one point per interval.
+#[query]
+fn get_share_price_history(
+    strategy_id: u16,
+    from_timestamp: u64,
+    to_timestamp: u64,
+    interval_secs: u64,
+    pagination: ListItemsPaginationRequest,
+) -> GetSharePriceHistoryResult {
+    let result = service::get_share_price_history(
+        strategy_id,
+        from_timestamp,
+        to_timestamp,
+        interval_secs,
+        pagination,
+    )
+        .map(|response| SharePriceHistoryPaginationResponse(response))
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    GetSharePriceHistoryResult(result)
+}
+
+#[query]
+fn get_rebalance_schedules() -> GetRebalanceSchedulesResult {
+    return_if_unauthorized!(Role::Viewer, GetRebalanceSchedulesResult);
+
+    GetRebalanceSchedulesResult(Ok(rebalance_scheduler_service::get_rebalance_schedules()))
+}
+
+#[update]
+fn update_rebalance_schedule(args: UpdateRebalanceScheduleArgs) -> RebalanceScheduleResult {
+    return_if_unauthorized!(Role::Admin, RebalanceScheduleResult);
+
+    let result = rebalance_scheduler_service::update_rebalance_schedule(args)
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    RebalanceScheduleResult(result)
+}
+
+#[update]
+fn update_strategy_rebalance_params(
+    args: UpdateStrategyRebalanceParamsArgs
+) -> StrategyRebalanceParamsResult {
+    return_if_unauthorized!(Role::Admin, StrategyRebalanceParamsResult);
+
+    let result = smart_rebalance_service::update_strategy_rebalance_params(args)
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    StrategyRebalanceParamsResult(result)
+}
+
+// =============== Fees ===============
+
+#[update]
+fn update_strategy_fees(args: UpdateStrategyFeesArgs) -> StrategyFeesResult {
+    return_if_unauthorized!(Role::Admin, StrategyFeesResult);
+
+    let result = fee_service::update_strategy_fees(args)
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    StrategyFeesResult(result)
+}
+
+#[update]
+fn set_treasury(treasury: Option<Principal>) -> AccessControlResult {
+    return_if_unauthorized!(Role::Admin, AccessControlResult);
+
+    config_repo::set_treasury(treasury);
+
+    AccessControlResult(Ok(()))
+}
+
+// =============== Compounding ===============
+
+#[update]
+async fn compound_strategy_fees(strategy_id: u16) -> StrategyCompoundResult {
+    return_if_unauthorized!(Role::Operator, StrategyCompoundResult);
+
+    let result = compounding_service::compound_strategy_fees(strategy_id).await
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    StrategyCompoundResult(result)
+}
+
+// =============== Emergency ===============
+
+/// Pauses deposits, withdrawals or rebalances of a strategy, or of every strategy without strategy ID.
+#[update]
+fn pause_operations(args: PauseOperationsArgs, reason: Option<String>) -> PauseResult {
+    return_if_unauthorized!(Role::Pauser, PauseResult);
+
+    let context = Context::generate(Some(caller()), args.strategy_id);
+
+    let result = emergency_service::pause_operations(context, args, reason)
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    PauseResult(result)
+}
+
+#[update]
+fn unpause_operations(args: PauseOperationsArgs) -> PauseResult {
+    return_if_unauthorized!(Role::Pauser, PauseResult);
+
+    let context = Context::generate(Some(caller()), args.strategy_id);
+
+    let result = emergency_service::unpause_operations(context, args)
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    PauseResult(result)
+}
+
+/// Sets the bounds of the circuit breakers that pause a strategy automatically.
+#[update]
+fn update_circuit_breaker_config(config: CircuitBreakerConfig) -> CircuitBreakerConfigResult {
+    return_if_unauthorized!(Role::Admin, CircuitBreakerConfigResult);
+
+    let result = emergency_service::update_circuit_breaker_config(config)
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    CircuitBreakerConfigResult(result)
+}
+
+#[query]
+fn get_emergency_state() -> EmergencyStateResponse {
+    emergency_service::get_emergency_state()
+}
+
+/// Exits every position of a strategy into its base token and freezes it,
+/// its shares can then only be redeemed for the parked amount.
+///
+/// An unwind that failed on a position is resumed by calling it again.
+#[update]
+async fn emergency_unwind(strategy_id: u16) -> StrategyUnwindResult {
+    return_if_unauthorized!(Role::Operator, StrategyUnwindResult);
+
+    let context = Context::generate(Some(caller()), Some(strategy_id));
+
+    let result = unwind_service::emergency_unwind(context).await
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    StrategyUnwindResult(result)
+}
+
+#[query]
+fn get_strategy_unwinds() -> Vec<StrategyUnwind> {
+    unwind_service::get_strategy_unwinds()
+}
+
+// =============== Strategy definitions ===============
+
+#[query]
+fn get_strategy_definitions() -> GetStrategyDefinitionsResult {
+    return_if_unauthorized!(Role::Viewer, GetStrategyDefinitionsResult);
+
+    GetStrategyDefinitionsResult(Ok(strategy_definitions_service::get_strategy_definitions()))
+}
+
+/// Defines a new strategy from its metadata and candidate pools.
+///
+/// The strategy is added disabled.
+#[update]
+async fn create_strategy(args: CreateStrategyArgs) -> StrategyDefinitionResult {
+    return_if_unauthorized!(Role::Admin, StrategyDefinitionResult);
+
+    let result = strategy_definitions_service::create_strategy(args).await
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    StrategyDefinitionResult(result)
+}
+
+#[update]
+fn update_strategy_metadata(args: UpdateStrategyMetadataArgs) -> StrategyDefinitionResult {
+    return_if_unauthorized!(Role::Admin, StrategyDefinitionResult);
+
+    let result = strategy_definitions_service::update_strategy_metadata(args)
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    StrategyDefinitionResult(result)
+}
+
+#[update]
+async fn add_strategy_pool(args: UpdateStrategyPoolArgs) -> StrategyDefinitionResult {
+    return_if_unauthorized!(Role::Admin, StrategyDefinitionResult);
+
+    let result = strategy_definitions_service::add_strategy_pool(args).await
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    StrategyDefinitionResult(result)
+}
+
+#[update]
+fn remove_strategy_pool(args: UpdateStrategyPoolArgs) -> StrategyDefinitionResult {
+    return_if_unauthorized!(Role::Admin, StrategyDefinitionResult);
+
+    let result = strategy_definitions_service::remove_strategy_pool(args)
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    StrategyDefinitionResult(result)
+}
+
 // =============== Events ===============
 
 #[update]
@@ -152,7 +347,8 @@ async fn get_event_records(request: ListItemsPaginationRequest) -> GetEventRecor
 
 #[update]
 async fn deposit(args: StrategyDepositArgs) -> StrategyDepositResult {
-    let context = Context::generate(Some(caller()), Some(args.strategy_id));
+    let context = Context::generate(Some(caller()), Some(args.strategy_id))
+        .with_subaccount(args.from_subaccount);
 
     let result = service::deposit(context, args).await
         .map_err(|error| ResponseError::from_internal_error(error));
@@ -162,7 +358,8 @@ async fn deposit(args: StrategyDepositArgs) -> StrategyDepositResult {
 
 #[update]
 async fn withdraw(args: StrategyWithdrawArgs) -> StrategyWithdrawResult {
-    let context = Context::generate(Some(caller()), Some(args.strategy_id));
+    let context = Context::generate(Some(caller()), Some(args.strategy_id))
+        .with_subaccount(args.subaccount);
 
     let result = service::withdraw(context, args).await
         .map_err(|error| ResponseError::from_internal_error(error));
@@ -170,6 +367,83 @@ async fn withdraw(args: StrategyWithdrawArgs) -> StrategyWithdrawResult {
     StrategyWithdrawResult(result)
 }
 
+/// Estimates the shares a deposit would get with the current NAV, DEX quotes and ledger fees.
+///
+/// An update call, the DEXes and ledgers quoted are on other subnets and composite queries
+/// can only call canisters of their own subnet.
+#[update]
+async fn preview_deposit(strategy_id: u16, ledger: CanisterId, amount: Nat) -> StrategyDepositPreviewResult {
+    let result = service::preview_deposit(strategy_id, ledger, amount).await
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    StrategyDepositPreviewResult(result)
+}
+
+/// Estimates the base token amount a withdrawal of a percentage of the caller's shares would return.
+///
+/// The shares are those of the caller's `subaccount`, or of its default account when it is omitted.
+/// An update call for the same reason as `preview_deposit`.
+#[update]
+async fn preview_withdraw(strategy_id: u16, percentage: Nat, subaccount: Option<Subaccount>) -> StrategyWithdrawPreviewResult {
+    let account = Account { owner: caller(), subaccount: position_subaccount(subaccount) };
+
+    let result = service::preview_withdraw(account, strategy_id, percentage).await
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    StrategyWithdrawPreviewResult(result)
+}
+
+/// Redeems all the caller's shares of an unwound strategy for their pro-rata part of the parked amount.
+///
+/// The shares are those of the caller's `subaccount`, or of its default account when it is omitted.
+#[update]
+async fn redeem_unwound_shares(strategy_id: u16, subaccount: Option<Subaccount>) -> StrategyRedeemResult {
+    let context = Context::generate(Some(caller()), Some(strategy_id))
+        .with_subaccount(subaccount);
+
+    let result = unwind_service::redeem_unwound_shares(context).await
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    StrategyRedeemResult(result)
+}
+
+/// Transfers the caller's withdrawals and deposit refunds that are held by the vault.
+///
+/// # Returns
+///
+/// The claimed withdrawals with their updated status.
+/// Withdrawals whose transfer failed again stay claimable.
+#[update]
+async fn claim_pending_withdrawals() -> Vec<PendingWithdrawal> {
+    service::claim_pending_withdrawals(caller()).await
+}
+
+/// Retrieves the not completed withdrawals and deposit refunds of a user.
+#[query]
+fn get_pending_withdrawals(user: Principal) -> Vec<PendingWithdrawal> {
+    service::get_pending_withdrawals(user)
+}
+
+/// Retrieves the ledger of deposits, withdrawals, share mints and burns and claims of a user.
+#[query]
+fn get_user_transactions(user: Principal, filters: UserTransactionFilters) -> GetUserTransactionsResult {
+    let result = service::get_user_transactions(user, filters)
+        .map_err(|error| ResponseError::from_internal_error(error))
+        .map(|response| UserTransactionsPaginationResponse(response));
+
+    GetUserTransactionsResult(result)
+}
+
+/// Retrieves the value, USD value, PnL and TVL share of the positions of a user, one per strategy and account.
+#[query]
+fn get_user_positions(user: Principal, pagination: ListItemsPaginationRequest) -> GetUserPositionsResult {
+    let result = service::get_user_positions(user, pagination)
+        .map_err(|error| ResponseError::from_internal_error(error))
+        .map(|response| UserPositionsPaginationResponse(response));
+
+    GetUserPositionsResult(result)
+}
+
 /// Retrieves the strategies for a specific user.
 ///
 /// # Arguments
@@ -178,7 +452,8 @@ async fn withdraw(args: StrategyWithdrawArgs) -> StrategyWithdrawResult {
 ///
 /// # Returns
 ///
-/// A vector of `UserStrategyResponse` containing the strategies information for the user.
+/// A vector of `UserStrategyResponse` containing the strategies information for the user,
+/// one per strategy and account of the user that holds shares.
 #[update]
 async fn user_strategies(user: Principal) -> Vec<UserStrategyResponse> {
     // TODO: rename user_strategies to user_positions
@@ -187,20 +462,26 @@ async fn user_strategies(user: Principal) -> Vec<UserStrategyResponse> {
     let mut user_strategies = Vec::new();
 
     for strategy in strategies {
-        let user_shares = strategy.get_user_shares().get(&user).cloned().unwrap_or(Nat::from(0u64));
-        let initial_deposit = strategy.get_initial_deposit().get(&user).cloned().unwrap_or(Nat::from(0u64));
         let current_pool = strategy.get_current_pool();
 
         if let Some(pool) = current_pool {
-            // Add only if current pool is set and user has shares
-            if user_shares > Nat::from(0u64) {
+            let initial_deposits = strategy.get_initial_deposit();
+
+            for (account, user_shares) in strategy.get_user_shares() {
+                // Add only if current pool is set and the user account has shares
+                if account.owner != user || user_shares == Nat::from(0u64) {
+                    continue;
+                }
+
                 let user_strategy = UserStrategyResponse {
                     strategy_id: strategy.get_id(),
                     strategy_name: strategy.get_name(),
-                    strategy_current_pool: pool,
+                    strategy_current_pool: pool.clone(),
+                    allocations: strategy.get_allocations(),
                     total_shares: strategy.get_total_shares(),
+                    subaccount: account.subaccount,
                     user_shares,
-                    initial_deposit,
+                    initial_deposit: initial_deposits.get(&account).cloned().unwrap_or(Nat::from(0u64)),
                     users_count: strategy.get_users_count(),
                 };
 
@@ -213,12 +494,14 @@ async fn user_strategies(user: Principal) -> Vec<UserStrategyResponse> {
 }
 
 #[update]
-async fn test_set_strategy_enabled(strategy_id: u16, enabled: bool) {
-    trap_if_not_authenticated!();
+async fn test_set_strategy_enabled(strategy_id: u16, enabled: bool) -> AccessControlResult {
+    return_if_unauthorized!(Role::Pauser, AccessControlResult);
 
     let mut strategy = strategies_repo::get_strategy_by_id(strategy_id).unwrap();
     strategy.set_enabled(enabled);
     strategies_repo::save_strategy(strategy);
+
+    AccessControlResult(Ok(()))
 }
 
 #[query]
@@ -236,31 +519,48 @@ fn get_config() -> Conf {
     config_repo::get_config()
 }
 
+// =============== Access control ===============
+
+/// Grants a role, effective once the principal accepts it.
 #[update]
-async fn set_controller(controller: Principal) {
-    let canister_controllers = get_canister_controllers().await;
-    if !canister_controllers.contains(&ic_cdk::caller()) {
-        trap("Unauthorized: caller is not a controller");
-    }
-    config_repo::add_controller(controller);
+fn grant_role(principal: Principal, role: Role) -> AccessControlResult {
+    let result = access_control_service::grant_role(caller(), principal, role)
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    AccessControlResult(result)
 }
 
-#[query]
-async fn get_canister_controllers() -> Vec<Principal> {
-    let res: CallResult<(ic_cdk::api::management_canister::main::CanisterStatusResponse,)> = call(
-        Principal::management_canister(),
-        "canister_status",
-        (CanisterIdRequest { canister_id: id() },),
-    ).await;
+#[update]
+fn accept_role(role: Role) -> AccessControlResult {
+    let result = access_control_service::accept_role(caller(), role)
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    AccessControlResult(result)
+}
+
+/// Removes a role once another admin confirms it, a pending grant is cancelled at once.
+#[update]
+fn revoke_role(principal: Principal, role: Role) -> AccessControlResult {
+    let result = access_control_service::revoke_role(caller(), principal, role)
+        .map_err(|error| ResponseError::from_internal_error(error));
 
-    res
-        .expect(
-            "Get controllers function exited unexpectedly:\n\
-            inter-canister call to management canister for canister_status returned an empty result."
-        )
-        .0.settings.controllers
+    AccessControlResult(result)
 }
 
+#[update]
+fn confirm_revoke_role(principal: Principal, role: Role) -> AccessControlResult {
+    let result = access_control_service::confirm_revoke_role(caller(), principal, role)
+        .map_err(|error| ResponseError::from_internal_error(error));
+
+    AccessControlResult(result)
+}
+
+#[query]
+fn get_access_control() -> GetAccessControlStateResult {
+    return_if_unauthorized!(Role::Viewer, GetAccessControlStateResult);
+
+    GetAccessControlStateResult(Ok(access_control_service::get_state()))
+}
 
 // =============== ICRC ===============
 
@@ -291,6 +591,60 @@ fn icrc10_supported_standards() -> Vec<SupportedStandard> {
     ]
 }
 
+// =============== Share tokens ===============
+//
+// The shares of a position are held in a share subaccount of the strategy, see `share_token_service::get_share_account`.
+// Transfers and approvals take the ICRC-1 and ICRC-2 arguments, but the vault doesn't claim these standards:
+// every strategy has its own share token with the decimals of its base token, while a ledger has a single token.
+
+/// Retrieves the share token of a strategy, with its name, symbol, the decimals of its base token and its supply.
+#[query]
+fn get_strategy_share_token(strategy_id: u16) -> Option<StrategyShareToken> {
+    share_token_service::get_share_token(strategy_id)
+}
+
+/// Retrieves the share account holding the shares of a position in a strategy,
+/// none if the subaccount of the position has no share subaccount yet.
+#[query]
+fn get_share_account(strategy_id: u16, account: Account) -> Option<Account> {
+    share_token_service::get_share_account(strategy_id, account)
+}
+
+/// Gives a subaccount of the caller a share subaccount in a strategy, so it can receive shares.
+///
+/// Positions opened by a deposit have one already.
+#[update]
+fn register_share_account(strategy_id: u16, subaccount: Option<Subaccount>) -> Account {
+    share_token_service::register_share_account(strategy_id, Account { owner: caller(), subaccount })
+}
+
+/// Retrieves the shares of a strategy held by the owner of a share account.
+#[query]
+fn icrc1_balance_of(account: Account) -> Nat {
+    share_token_service::balance_of(account)
+}
+
+/// Transfers shares of a strategy with their cost basis to a share account of the same strategy.
+#[update]
+fn icrc1_transfer(args: TransferArg) -> Result<Nat, TransferError> {
+    share_token_service::transfer(caller(), args)
+}
+
+#[query]
+fn icrc2_allowance(args: AllowanceArgs) -> Allowance {
+    share_token_service::allowance(args)
+}
+
+#[update]
+fn icrc2_approve(args: ApproveArgs) -> Result<Nat, ApproveError> {
+    share_token_service::approve(caller(), args)
+}
+
+#[update]
+fn icrc2_transfer_from(args: TransferFromArgs) -> Result<Nat, TransferFromError> {
+    share_token_service::transfer_from(caller(), args)
+}
+
 /// Retrieves the trusted origins for ICRC-28.
 ///
 /// # Returns
@@ -307,65 +661,17 @@ fn icrc28_trusted_origins() -> Icrc28TrustedOriginsResponse {
     Icrc28TrustedOriginsResponse { trusted_origins }
 }
 
-#[query(name = "icrc21_canister_call_consent_message")]
-fn icrc21_canister_call_consent_message(
+/// Builds the consent message of the user-facing update methods, in the requested language
+/// and display format.
+///
+/// An update call, so the previews can quote the DEXes.
+#[update(name = "icrc21_canister_call_consent_message")]
+async fn icrc21_canister_call_consent_message(
     req: ConsentMessageRequest,
 ) -> Result<ConsentInfo, Icrc21Error> {
-    let method = req.method.as_str();
-
-    let ok = |text: String| -> Result<ConsentInfo, Icrc21Error> {
-        Ok(ConsentInfo {
-            consent_message: ConsentMessage::GenericDisplayMessage(text),
-            metadata: ConsentMessageMetadata {
-                language: "en".to_string(),
-                utc_offset_minutes: None,
-            },
-        })
-    };
-
-    match method {
-        "deposit" => {
-            let (args,): (StrategyDepositArgs,) = decode_one(&req.arg)
-                .map_err(|e| Icrc21Error::ConsentMessageUnavailable(ErrorInfo {
-                    description: format!("Can't decode deposit args: {e}"),
-                }))?;
-
-            ok(format!(
-                "Confirm deposit
-                    • Strategy #{strategy_id}\n 
-                    • Amount {amount}\n
-                    • Ledger {ledger}",
-                strategy_id = args.strategy_id,
-                amount = args.amount,
-                ledger = args.ledger
-            ))
-        }
-        "withdraw" => {
-            let (args,): (StrategyWithdrawArgs,) = decode_one(&req.arg)
-                .map_err(|e| Icrc21Error::ConsentMessageUnavailable(ErrorInfo {
-                    description: format!("Can't decode withdraw args: {e}"),
-                }))?;
-
-            ok(format!(
-                "Confirm withdraw\n 
-                    • Strategy #{strategy_id}\n 
-                    • Percentage {percentage}‰\n
-                    • Ledger {ledger}",
-                strategy_id = args.strategy_id,
-                percentage = args.percentage,
-                ledger = args.ledger
-            ))
-        }
-
-        unknown => Err(Icrc21Error::UnsupportedCanisterCall(ErrorInfo {
-            description: format!("Unsupported method for consent message: {unknown}"),
-        })),
-    }
+    consent_message_service::build_consent_message(caller(), req).await
 }
 
-
-
-
 // =============== Vault management ===============
 
 #[query]
@@ -387,26 +693,37 @@ fn get_runtime_config() -> RuntimeConfig {
 #[init]
 #[candid_method(init)]
 fn init(conf: Option<Conf>, runtime_config: RuntimeConfig) {
-    let conf = conf.unwrap_or_default();
+    let mut conf = conf.unwrap_or_default();
+    access_control_service::init_admins(conf.controllers.take().unwrap_or_default());
     config_repo::set_config(conf);
 
     runtime_config_repo::set_runtime_config(runtime_config);
 
     strategy_service::init_strategies();
     strategy_stats_service::start_strategy_stats_update_timer(STRATEGY_STATS_FETCHING_INTERVAL);
+    rebalance_scheduler_service::start_rebalance_scheduler_timer(REBALANCE_SCHEDULER_INTERVAL);
+    pending_withdrawals_service::start_pending_withdrawals_retry_timer(PENDING_WITHDRAWALS_RETRY_INTERVAL);
+    compounding_service::start_compounding_timer(COMPOUNDING_INTERVAL);
 }
 
 #[pre_upgrade]
 fn pre_upgrade() {
     stable_state::stable_save();
     strategy_stats_service::stop_strategy_stats_update_timer();
+    rebalance_scheduler_service::stop_rebalance_scheduler_timer();
+    pending_withdrawals_service::stop_pending_withdrawals_retry_timer();
+    compounding_service::stop_compounding_timer();
 }
 
 #[post_upgrade]
 fn post_upgrade() {
     stable_state::stable_restore();
     strategy_service::init_strategies();
+    share_token_service::register_position_subaccounts();
     strategy_stats_service::start_strategy_stats_update_timer(STRATEGY_STATS_FETCHING_INTERVAL);
+    rebalance_scheduler_service::start_rebalance_scheduler_timer(REBALANCE_SCHEDULER_INTERVAL);
+    pending_withdrawals_service::start_pending_withdrawals_retry_timer(PENDING_WITHDRAWALS_RETRY_INTERVAL);
+    compounding_service::start_compounding_timer(COMPOUNDING_INTERVAL);
 }
 
 export_service!();
@@ -1,7 +1,7 @@
 use candid::Nat;
 
 use types::context::Context;
-use types::liquidity::{AddLiquidityResponse, WithdrawLiquidityResponse};
+use types::liquidity::{AddLiquidityResponse, WithdrawLiquidityResponse, LiquidityPosition};
 use ::types::strategies::Pool;
 use liquidity::liquidity_router::get_liquidity_client;
 use errors::internal_error::error::InternalError;
@@ -11,8 +11,16 @@ use crate::types::types::PoolData;
 use crate::pool_stats::pool_stats_service;
 use crate::event_records::event_record_service;
 use crate::event_records::event_record::Event;
+use crate::strategies::emergency_service;
 use crate::utils::service_resolver::get_service_resolver;
 
+/// Liquidity withdrawn from a pool with token_1 swapped to token_0 (base token)
+pub struct WithdrawAndSwapResponse {
+    pub amount: Nat,
+    /// LP tokens burned, only for pools with fungible LP tokens
+    pub lp_amount: Option<Nat>,
+}
+
 pub async fn get_pools_data(pools: Vec<Pool>) -> Vec<PoolData> {
     let pool_ids: Vec<String> = pools.iter().map(|pool| pool.id.clone()).collect();
     let pool_stats_actor = pool_stats_service::get_pool_stats_actor().await.unwrap();
@@ -37,7 +45,8 @@ pub async fn get_pools_data(pools: Vec<Pool>) -> Vec<PoolData> {
 pub async fn add_liquidity_to_pool(
     context: Context,
     amount: Nat,
-    pool: Pool
+    pool: Pool,
+    position_id: Option<u64>
 ) -> Result<AddLiquidityResponse, InternalError> {
     let user = context.user.clone().unwrap();
 
@@ -60,7 +69,8 @@ pub async fn add_liquidity_to_pool(
     ).await;
 
     let add_liquidity_response = liquidity_client.add_liquidity_to_pool(
-        amount.clone()
+        amount.clone(),
+        position_id
     ).await
         .map_err(|error| {
             // Event: Add liquidity to pool failed
@@ -96,7 +106,8 @@ pub async fn withdraw_liquidity_from_pool(
     context: Context,
     total_shares: Nat,
     shares: Nat,
-    pool: Pool
+    pool: Pool,
+    position: LiquidityPosition
 ) -> Result<WithdrawLiquidityResponse, InternalError> {
     let user = context.user.clone().unwrap();
 
@@ -126,6 +137,7 @@ pub async fn withdraw_liquidity_from_pool(
         liquidity_client.withdraw_liquidity_from_pool(
             total_shares.clone(),
             shares.clone(),
+            position,
         ).await
             .map_err(|error| {
                 // Event: Withdraw liquidity from pool failed
@@ -164,8 +176,9 @@ pub async fn withdraw_liquidity_from_pool_and_swap(
     context: Context,
     total_shares: Nat,
     shares: Nat,
-    pool: Pool
-) -> Result<Nat, InternalError> {
+    pool: Pool,
+    position: LiquidityPosition
+) -> Result<WithdrawAndSwapResponse, InternalError> {
     let user = context.user.clone().unwrap();
 
     let withdraw_response = withdraw_liquidity_from_pool(
@@ -173,6 +186,7 @@ pub async fn withdraw_liquidity_from_pool_and_swap(
         total_shares.clone(),
         shares.clone(),
         pool.clone(),
+        position,
     ).await?;
 
     let service_resolver = get_service_resolver();
@@ -196,7 +210,10 @@ pub async fn withdraw_liquidity_from_pool_and_swap(
 
     // If token1 for swap is 0, skip swap
     if token1_for_swap <= Nat::from(0u64) {
-        return Ok(amount_0_to_withdraw);
+        return Ok(WithdrawAndSwapResponse {
+            amount: amount_0_to_withdraw,
+            lp_amount: withdraw_response.lp_amount,
+        });
     }
 
     // Event: Swap token started
@@ -212,14 +229,30 @@ pub async fn withdraw_liquidity_from_pool_and_swap(
         context.strategy_id,
     );
 
-    // Swap withdrawn token_1 to token_0 (base token)
-    let swap_response = swap_service::swap_icrc2_optimal(
-        service_resolver.provider_impls(),
-        service_resolver.icrc_ledger_client(),
-        pool.token1,
-        pool.token0,
-        token1_for_swap.clone(),
-    ).await
+    // Swap withdrawn token_1 to token_0 (base token),
+    // with the provider of the best quote, which is kept to measure the realized slippage
+    let swap_result = async {
+        let quote = swap_service::quote_swap_icrc2_optimal(
+            service_resolver.provider_impls(),
+            service_resolver.icrc_ledger_client(),
+            pool.token1,
+            pool.token0,
+            token1_for_swap.clone(),
+        ).await?;
+
+        let swap_response = swap_service::swap_icrc2(
+            service_resolver.provider_impls(),
+            service_resolver.icrc_ledger_client(),
+            pool.token1,
+            pool.token0,
+            token1_for_swap.clone(),
+            quote.provider,
+        ).await?;
+
+        Ok::<_, InternalError>((quote, swap_response))
+    }.await;
+
+    let (quote, swap_response) = swap_result
         .map_err(|error| {
             // Event: Swap token failed
             event_record_service::create_event_record(
@@ -252,7 +285,18 @@ pub async fn withdraw_liquidity_from_pool_and_swap(
         context.strategy_id,
     );
 
+    if let Some(strategy_id) = context.strategy_id {
+        emergency_service::check_swap_slippage(
+            strategy_id,
+            &Nat::from(quote.amount_out),
+            &Nat::from(swap_response.amount_out),
+        );
+    }
+
     amount_0_to_withdraw = amount_0_to_withdraw + swap_response.amount_out;
 
-    Ok(amount_0_to_withdraw)
+    Ok(WithdrawAndSwapResponse {
+        amount: amount_0_to_withdraw,
+        lp_amount: withdraw_response.lp_amount,
+    })
 }
@@ -0,0 +1,72 @@
+use std::cell::RefCell;
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+use ic_stable_structures::StableBTreeMap;
+
+use types::strategies::StrategyId;
+
+use crate::impl_candid_storable;
+use crate::repository::memory::{self, Memory};
+
+/// Trading fees claimed from a position and held by the vault until they are added back to it.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ClaimedFees {
+    pub token_0_amount: Nat,
+    pub token_1_amount: Nat,
+    pub updated_at: u64,
+}
+
+impl_candid_storable!(ClaimedFees);
+
+// Strategy ID and position ID -> fees claimed from the position
+type ClaimedFeesKey = (StrategyId, u64);
+
+thread_local! {
+    static CLAIMED_FEES: RefCell<StableBTreeMap<ClaimedFeesKey, ClaimedFees, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::CLAIMED_FEES_MEMORY_ID))
+    );
+}
+
+pub fn get_claimed_fees(strategy_id: StrategyId, position_id: u64) -> Option<ClaimedFees> {
+    CLAIMED_FEES.with(|fees| fees.borrow().get(&(strategy_id, position_id)))
+}
+
+pub fn save_claimed_fees(strategy_id: StrategyId, position_id: u64, claimed_fees: ClaimedFees) {
+    CLAIMED_FEES.with(|fees| {
+        fees.borrow_mut().insert((strategy_id, position_id), claimed_fees);
+    });
+}
+
+pub fn remove_claimed_fees(strategy_id: StrategyId, position_id: u64) {
+    CLAIMED_FEES.with(|fees| {
+        fees.borrow_mut().remove(&(strategy_id, position_id));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod save_claimed_fees {
+        use super::*;
+
+        #[test]
+        fn keeps_fees_per_position() {
+            let claimed_fees = ClaimedFees {
+                token_0_amount: Nat::from(10u64),
+                token_1_amount: Nat::from(20u64),
+                updated_at: 1,
+            };
+
+            save_claimed_fees(1, 7, claimed_fees.clone());
+
+            assert_eq!(get_claimed_fees(1, 7), Some(claimed_fees));
+            assert_eq!(get_claimed_fees(1, 8), None);
+            assert_eq!(get_claimed_fees(2, 7), None);
+
+            remove_claimed_fees(1, 7);
+
+            assert_eq!(get_claimed_fees(1, 7), None);
+        }
+    }
+}
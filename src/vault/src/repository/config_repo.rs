@@ -4,13 +4,17 @@ use serde::Serialize;
 
 #[derive(CandidType, Deserialize, Clone, Debug, Hash, PartialEq, Serialize)]
 pub struct Conf {
+    /// Admins granted at init, moved into the access control
     pub controllers: Option<Vec<Principal>>,
+    /// Receives the strategy shares minted as protocol fees
+    pub treasury: Option<Principal>,
 }
 
 impl Default for Conf {
     fn default() -> Self {
         Conf {
-            controllers: Default::default()
+            controllers: Default::default(),
+            treasury: Default::default(),
         }
     }
 }
@@ -40,15 +44,14 @@ pub fn set_controllers(controllers: Option<Vec<Principal>>) {
     });
 }
 
-pub fn add_controller(controller: Principal) {
-    let controllers = get_controllers();
-    if controllers.is_none() {
-        set_controllers(Some(vec![controller]));
-    } else {
-        let mut controllers_vec = controllers.unwrap().clone();
-        controllers_vec.push(controller);
-        set_controllers(Some(controllers_vec));
-    }
+pub fn get_treasury() -> Option<Principal> {
+    CONF.with(|c| c.borrow().treasury)
+}
+
+pub fn set_treasury(treasury: Option<Principal>) {
+    CONF.with(|c| {
+        c.borrow_mut().treasury = treasury;
+    });
 }
 
 #[cfg(test)]
@@ -75,6 +78,7 @@ mod tests {
 
             let new_conf = Conf {
                 controllers: Some(vec![fake_principal(1), fake_principal(2)]),
+                treasury: None,
             };
             set_config(new_conf.clone());
 
@@ -94,6 +98,7 @@ mod tests {
 
             let new_conf = Conf {
                 controllers: Some(vec![fake_principal(3)]),
+                treasury: Some(fake_principal(4)),
             };
             set_config(new_conf.clone());
 
@@ -118,6 +123,7 @@ mod tests {
 
             let new_conf = Conf {
                 controllers: Some(vec![fake_principal(42)]),
+                treasury: None,
             };
             set_config(new_conf.clone());
 
@@ -157,4 +163,21 @@ mod tests {
             set_config(original); // restore
         }
     }
+
+    mod set_treasury {
+        use super::*;
+
+        #[test]
+        fn keeps_controllers() {
+            let original = get_config();
+
+            set_controllers(Some(vec![fake_principal(11)]));
+            set_treasury(Some(fake_principal(12)));
+
+            assert_eq!(get_treasury(), Some(fake_principal(12)));
+            assert_eq!(get_controllers(), Some(vec![fake_principal(11)]));
+
+            set_config(original); // restore
+        }
+    }
 }
@@ -0,0 +1,153 @@
+use std::cell::RefCell;
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+use ic_stable_structures::StableBTreeMap;
+
+use types::strategies::StrategyId;
+
+use crate::impl_candid_storable;
+use crate::repository::memory::{self, Memory};
+
+/// Operations halted during an incident, for every strategy or for one of them.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct PauseState {
+    pub deposit: bool,
+    pub withdraw: bool,
+    pub rebalance: bool,
+    pub reason: Option<String>,
+    pub updated_at: Option<u64>,
+}
+
+/// Bounds above which a strategy is paused automatically, a bound left empty is not checked.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct CircuitBreakerConfig {
+    /// Realized slippage of a swap against its quote
+    pub max_slippage_bps: Option<u64>,
+    /// Share price drop between two NAV checks
+    pub max_nav_drop_bps: Option<u64>,
+}
+
+/// Pause state of a strategy and the share price its NAV drop breaker compares to.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+struct StrategyEmergencyState {
+    pause: PauseState,
+    /// Share price at the last NAV check, scaled by 10^12
+    last_share_price: Option<Nat>,
+}
+
+impl_candid_storable!(StrategyEmergencyState);
+
+thread_local! {
+    static GLOBAL_PAUSE: RefCell<PauseState> = RefCell::new(PauseState::default());
+    static CIRCUIT_BREAKER_CONFIG: RefCell<CircuitBreakerConfig> = RefCell::new(CircuitBreakerConfig::default());
+    static STRATEGY_STATES: RefCell<StableBTreeMap<StrategyId, StrategyEmergencyState, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::STRATEGY_PAUSES_MEMORY_ID))
+    );
+}
+
+pub fn get_global_pause() -> PauseState {
+    GLOBAL_PAUSE.with(|pause| pause.borrow().clone())
+}
+
+pub fn set_global_pause(pause: PauseState) {
+    GLOBAL_PAUSE.with(|cell| *cell.borrow_mut() = pause);
+}
+
+pub fn get_circuit_breaker_config() -> CircuitBreakerConfig {
+    CIRCUIT_BREAKER_CONFIG.with(|config| config.borrow().clone())
+}
+
+pub fn set_circuit_breaker_config(config: CircuitBreakerConfig) {
+    CIRCUIT_BREAKER_CONFIG.with(|cell| *cell.borrow_mut() = config);
+}
+
+/// Returns the pause state of the strategy, nothing is paused if it was never saved.
+pub fn get_strategy_pause(strategy_id: StrategyId) -> PauseState {
+    get_strategy_state(strategy_id).pause
+}
+
+pub fn save_strategy_pause(strategy_id: StrategyId, pause: PauseState) {
+    let mut state = get_strategy_state(strategy_id);
+    state.pause = pause;
+    save_strategy_state(strategy_id, state);
+}
+
+/// Returns the strategies with at least one paused operation.
+pub fn get_paused_strategies() -> Vec<(StrategyId, PauseState)> {
+    STRATEGY_STATES.with(|states| {
+        states.borrow()
+            .iter()
+            .map(|(strategy_id, state)| (strategy_id, state.pause))
+            .filter(|(_, pause)| pause.deposit || pause.withdraw || pause.rebalance)
+            .collect()
+    })
+}
+
+pub fn get_last_share_price(strategy_id: StrategyId) -> Option<Nat> {
+    get_strategy_state(strategy_id).last_share_price
+}
+
+pub fn save_last_share_price(strategy_id: StrategyId, share_price: Option<Nat>) {
+    let mut state = get_strategy_state(strategy_id);
+    state.last_share_price = share_price;
+    save_strategy_state(strategy_id, state);
+}
+
+fn get_strategy_state(strategy_id: StrategyId) -> StrategyEmergencyState {
+    STRATEGY_STATES.with(|states| states.borrow().get(&strategy_id))
+        .unwrap_or_default()
+}
+
+fn save_strategy_state(strategy_id: StrategyId, state: StrategyEmergencyState) {
+    STRATEGY_STATES.with(|states| {
+        states.borrow_mut().insert(strategy_id, state);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clear_strategy_states() {
+        STRATEGY_STATES.with(|states| states.borrow_mut().clear_new());
+    }
+
+    mod get_strategy_pause {
+        use super::*;
+
+        #[test]
+        fn returns_nothing_paused_if_not_saved() {
+            clear_strategy_states();
+
+            assert_eq!(get_strategy_pause(1), PauseState::default());
+        }
+
+        #[test]
+        fn keeps_last_share_price() {
+            clear_strategy_states();
+
+            save_last_share_price(1, Some(Nat::from(100u64)));
+            save_strategy_pause(1, PauseState { deposit: true, ..PauseState::default() });
+
+            assert!(get_strategy_pause(1).deposit);
+            assert_eq!(get_last_share_price(1), Some(Nat::from(100u64)));
+        }
+    }
+
+    mod get_paused_strategies {
+        use super::*;
+
+        #[test]
+        fn skips_strategies_without_paused_operation() {
+            clear_strategy_states();
+
+            save_strategy_pause(1, PauseState { rebalance: true, ..PauseState::default() });
+            save_last_share_price(2, Some(Nat::from(100u64)));
+
+            let paused = get_paused_strategies();
+
+            assert_eq!(paused.len(), 1);
+            assert_eq!(paused[0].0, 1);
+        }
+    }
+}
@@ -1,18 +1,29 @@
 use std::cell::RefCell;
+use ic_stable_structures::StableLog;
 
 use crate::event_records::event_record::EventRecord;
+use crate::repository::memory::{self, Memory};
 use crate::types::types::{ListItemsPaginationRequest, SortOrder};
 
 thread_local! {
-    pub static EVENT_RECORDS: RefCell<Vec<EventRecord>> = RefCell::new(Default::default());
+    pub static EVENT_RECORDS: RefCell<StableLog<EventRecord, Memory, Memory>> = RefCell::new(
+        StableLog::init(
+            memory::get_memory(memory::EVENT_RECORDS_INDEX_MEMORY_ID),
+            memory::get_memory(memory::EVENT_RECORDS_DATA_MEMORY_ID),
+        ).expect("failed to initialize event records log")
+    );
 }
 
 pub fn save_event_record(event: EventRecord) {
-    EVENT_RECORDS.with(|events| events.borrow_mut().push(event));
+    EVENT_RECORDS.with(|events| {
+        events.borrow()
+            .append(&event)
+            .expect("failed to append event record");
+    });
 }
 
 pub fn get_event_records_count() -> u64 {
-    EVENT_RECORDS.with(|events| events.borrow().len() as u64)
+    EVENT_RECORDS.with(|events| events.borrow().len())
 }
 
 pub fn get_event_records(request: ListItemsPaginationRequest) -> Vec<EventRecord> {
@@ -30,7 +41,6 @@ pub fn get_event_records(request: ListItemsPaginationRequest) -> Vec<EventRecord
                     true
                 }
             })
-            .cloned()
             .collect();
 
         // Sort by timestamp
@@ -113,6 +123,15 @@ mod tests {
         })
     }
 
+    fn clear_event_records() {
+        EVENT_RECORDS.with(|events| {
+            *events.borrow_mut() = StableLog::new(
+                memory::get_memory(memory::EVENT_RECORDS_INDEX_MEMORY_ID),
+                memory::get_memory(memory::EVENT_RECORDS_DATA_MEMORY_ID),
+            );
+        });
+    }
+
     fn timestamp(offset_secs: u64) -> u64 {
         SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() + offset_secs
     }
@@ -122,7 +141,7 @@ mod tests {
 
         #[test]
         fn saves_event_correctly() {
-            EVENT_RECORDS.with(|r| r.borrow_mut().clear());
+            clear_event_records();
 
             let event = mock_event_with_type(
                 "StrategyDepositStarted",
@@ -151,7 +170,7 @@ mod tests {
 
         #[test]
         fn returns_correct_count() {
-            EVENT_RECORDS.with(|r| r.borrow_mut().clear());
+            clear_event_records();
 
             let event1 = mock_event_with_type("AddLiquidityToPoolStarted", 1);
             let event2 = mock_event_with_type("AddLiquidityToPoolStarted", 2);
@@ -168,7 +187,7 @@ mod tests {
 
         #[test]
         fn returns_paginated_records_sorted_asc() {
-            EVENT_RECORDS.with(|r| r.borrow_mut().clear());
+            clear_event_records();
 
             let event1 = mock_event_with_type("AddLiquidityToPoolStarted", 20);
             let event2 = mock_event_with_type("StrategyDepositStarted", 10);
@@ -190,7 +209,7 @@ mod tests {
 
         #[test]
         fn returns_paginated_records_sorted_desc() {
-            EVENT_RECORDS.with(|r| r.borrow_mut().clear());
+            clear_event_records();
 
             let event1 = mock_event_with_type("StrategyDepositStarted", 5);
             let event2 = mock_event_with_type("AddLiquidityToPoolStarted", 10);
@@ -212,7 +231,7 @@ mod tests {
 
         #[test]
         fn filters_by_event_type() {
-            EVENT_RECORDS.with(|r| r.borrow_mut().clear());
+            clear_event_records();
 
             let event1 = mock_event_with_type("StrategyDepositStarted", 1);
             let event2 = mock_event_with_type("AddLiquidityToPoolStarted", 2);
@@ -233,7 +252,7 @@ mod tests {
 
         #[test]
         fn handles_pagination_correctly() {
-            EVENT_RECORDS.with(|r| r.borrow_mut().clear());
+            clear_event_records();
 
             for i in 1..=15 {
                 let event = mock_event_with_type("AddLiquidityToPoolStarted", i);
@@ -261,7 +280,7 @@ mod tests {
 
         #[test]
         fn sort_order_asc_and_desc() {
-            EVENT_RECORDS.with(|events| events.borrow_mut().clear());
+            clear_event_records();
 
             let ts1 = timestamp(10);
             let ts2 = timestamp(20);
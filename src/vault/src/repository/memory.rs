@@ -0,0 +1,56 @@
+use std::cell::RefCell;
+use ic_stable_structures::DefaultMemoryImpl;
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
+
+pub type Memory = VirtualMemory<DefaultMemoryImpl>;
+
+// Virtual memories of the vault canister.
+// IDs must never be reused or reordered, otherwise the data of existing canisters is lost.
+pub const STABLE_STATE_MEMORY_ID: MemoryId = MemoryId::new(0);
+pub const STRATEGIES_MEMORY_ID: MemoryId = MemoryId::new(1);
+pub const USER_SHARES_MEMORY_ID: MemoryId = MemoryId::new(2);
+pub const EVENT_RECORDS_INDEX_MEMORY_ID: MemoryId = MemoryId::new(3);
+pub const EVENT_RECORDS_DATA_MEMORY_ID: MemoryId = MemoryId::new(4);
+pub const REBALANCE_SCHEDULES_MEMORY_ID: MemoryId = MemoryId::new(5);
+pub const REBALANCE_HISTORY_MEMORY_ID: MemoryId = MemoryId::new(6);
+pub const PENDING_WITHDRAWALS_MEMORY_ID: MemoryId = MemoryId::new(7);
+pub const STRATEGY_DEFINITIONS_MEMORY_ID: MemoryId = MemoryId::new(8);
+pub const STRATEGY_PAUSES_MEMORY_ID: MemoryId = MemoryId::new(9);
+pub const STRATEGY_UNWINDS_MEMORY_ID: MemoryId = MemoryId::new(10);
+pub const USER_TRANSACTIONS_MEMORY_ID: MemoryId = MemoryId::new(11);
+pub const TOKEN_PRICES_MEMORY_ID: MemoryId = MemoryId::new(12);
+pub const SHARE_PRICES_MEMORY_ID: MemoryId = MemoryId::new(13);
+pub const SHARE_TOKEN_ALLOWANCES_MEMORY_ID: MemoryId = MemoryId::new(14);
+pub const SHARE_TOKEN_TRANSACTIONS_MEMORY_ID: MemoryId = MemoryId::new(15);
+pub const ACCOUNT_SHARES_MEMORY_ID: MemoryId = MemoryId::new(16);
+pub const SHARE_SUBACCOUNTS_MEMORY_ID: MemoryId = MemoryId::new(17);
+pub const SHARE_SUBACCOUNT_INDEXES_MEMORY_ID: MemoryId = MemoryId::new(18);
+pub const CLAIMED_FEES_MEMORY_ID: MemoryId = MemoryId::new(19);
+
+thread_local! {
+    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> =
+        RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
+}
+
+pub fn get_memory(memory_id: MemoryId) -> Memory {
+    MEMORY_MANAGER.with(|manager| manager.borrow().get(memory_id))
+}
+
+/// Implements `Storable` for a candid-serializable type with unbounded size.
+#[macro_export]
+macro_rules! impl_candid_storable {
+    ($type:ty) => {
+        impl ic_stable_structures::Storable for $type {
+            fn to_bytes(&self) -> std::borrow::Cow<'_, [u8]> {
+                std::borrow::Cow::Owned(candid::encode_one(self).unwrap())
+            }
+
+            fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+                candid::decode_one(bytes.as_ref()).unwrap()
+            }
+
+            const BOUND: ic_stable_structures::storable::Bound =
+                ic_stable_structures::storable::Bound::Unbounded;
+        }
+    };
+}
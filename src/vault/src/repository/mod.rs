@@ -1,5 +1,17 @@
+pub mod memory;
 pub mod stable_state;
 pub mod event_records_repo;
 pub mod strategies_repo;
+pub mod strategy_definitions_repo;
+pub mod rebalance_schedules_repo;
+pub mod rebalance_history_repo;
+pub mod pending_withdrawals_repo;
 pub mod runtime_config_repo;
 pub mod config_repo;
+pub mod emergency_repo;
+pub mod unwinds_repo;
+pub mod user_transactions_repo;
+pub mod token_prices_repo;
+pub mod share_prices_repo;
+pub mod share_token_repo;
+pub mod claimed_fees_repo;
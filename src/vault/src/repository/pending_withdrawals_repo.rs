@@ -0,0 +1,204 @@
+use std::cell::RefCell;
+use candid::{CandidType, Deserialize, Nat, Principal};
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::Memo;
+use serde::Serialize;
+use ic_stable_structures::StableBTreeMap;
+
+use types::CanisterId;
+use types::context::CorrelationId;
+use types::strategies::StrategyId;
+
+use crate::impl_candid_storable;
+use crate::repository::memory::{self, Memory};
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum PendingWithdrawalKind {
+    /// Tokens withdrawn from a strategy position
+    Withdraw,
+    /// Tokens accepted from the user for a deposit that failed
+    DepositRefund,
+    /// Share of the base token parked by the emergency unwind of a strategy
+    UnwindRedemption,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum PendingWithdrawalStatus {
+    /// Created before the liquidity is removed from the pool
+    Started,
+    /// Transfer to the user is in flight, or its outcome is unknown and it is sent again to be deduplicated
+    Transferring,
+    /// Tokens are held by the vault and can be claimed by the user
+    Claimable,
+    Completed,
+    /// Liquidity could not be removed from the pool, shares were not burned
+    Failed,
+    /// Transfer outcome could not be confirmed within the ledger deduplication window,
+    /// the refunded tokens could not be verified to be held by the vault,
+    /// or the withdrawal was interrupted before its amount was known, it needs a manual review
+    Unconfirmed,
+}
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct PendingWithdrawal {
+    pub id: u64,
+    pub kind: PendingWithdrawalKind,
+    pub status: PendingWithdrawalStatus,
+    pub user: Principal,
+    pub strategy_id: StrategyId,
+    pub token: CanisterId,
+    pub shares: Nat,
+    pub amount: Option<Nat>,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    /// Correlation ID of the operation that created the withdrawal, empty for older withdrawals
+    pub correlation_id: Option<CorrelationId>,
+    /// Account the tokens are transferred to, the default account of the user for older withdrawals
+    pub destination: Option<Account>,
+    /// Creation time of the ledger transfer in nanoseconds, every attempt sends the same one
+    pub transfer_created_at: Option<u64>,
+    /// Memo of the ledger transfer, every attempt sends the same one
+    pub transfer_memo: Option<Memo>,
+}
+
+impl PendingWithdrawal {
+    pub fn get_destination(&self) -> Account {
+        self.destination.unwrap_or(Account::from(self.user))
+    }
+}
+
+impl_candid_storable!(PendingWithdrawal);
+
+thread_local! {
+    static PENDING_WITHDRAWALS: RefCell<StableBTreeMap<u64, PendingWithdrawal, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::PENDING_WITHDRAWALS_MEMORY_ID))
+    );
+}
+
+pub fn next_id() -> u64 {
+    PENDING_WITHDRAWALS.with(|withdrawals| {
+        withdrawals.borrow()
+            .last_key_value()
+            .map_or(0, |(id, _)| id + 1)
+    })
+}
+
+pub fn save_pending_withdrawal(withdrawal: PendingWithdrawal) {
+    PENDING_WITHDRAWALS.with(|withdrawals| {
+        withdrawals.borrow_mut().insert(withdrawal.id, withdrawal);
+    });
+}
+
+pub fn get_pending_withdrawal(id: u64) -> Option<PendingWithdrawal> {
+    PENDING_WITHDRAWALS.with(|withdrawals| withdrawals.borrow().get(&id))
+}
+
+pub fn get_pending_withdrawals_by_status(status: PendingWithdrawalStatus) -> Vec<PendingWithdrawal> {
+    PENDING_WITHDRAWALS.with(|withdrawals| {
+        withdrawals.borrow()
+            .values()
+            .filter(|withdrawal| withdrawal.status == status)
+            .collect()
+    })
+}
+
+/// Returns all not completed withdrawals of the user.
+pub fn get_user_pending_withdrawals(user: Principal) -> Vec<PendingWithdrawal> {
+    PENDING_WITHDRAWALS.with(|withdrawals| {
+        withdrawals.borrow()
+            .values()
+            .filter(|withdrawal| {
+                withdrawal.user == user && withdrawal.status != PendingWithdrawalStatus::Completed
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clear_pending_withdrawals() {
+        PENDING_WITHDRAWALS.with(|withdrawals| withdrawals.borrow_mut().clear_new());
+    }
+
+    fn fake_principal(id: u8) -> Principal {
+        Principal::from_slice(&[id; 29])
+    }
+
+    fn withdrawal(id: u64, user: Principal, status: PendingWithdrawalStatus) -> PendingWithdrawal {
+        PendingWithdrawal {
+            id,
+            kind: PendingWithdrawalKind::Withdraw,
+            status,
+            user,
+            strategy_id: 1,
+            token: fake_principal(100),
+            shares: Nat::from(10u64),
+            amount: Some(Nat::from(1_000u64)),
+            attempts: 0,
+            last_error: None,
+            created_at: 0,
+            updated_at: 0,
+            correlation_id: None,
+            destination: None,
+            transfer_created_at: None,
+            transfer_memo: None,
+        }
+    }
+
+    mod next_id {
+        use super::*;
+
+        #[test]
+        fn increments_after_last_saved_id() {
+            clear_pending_withdrawals();
+            assert_eq!(next_id(), 0);
+
+            save_pending_withdrawal(withdrawal(0, fake_principal(1), PendingWithdrawalStatus::Started));
+            save_pending_withdrawal(withdrawal(1, fake_principal(1), PendingWithdrawalStatus::Started));
+
+            assert_eq!(next_id(), 2);
+        }
+    }
+
+    mod get_user_pending_withdrawals {
+        use super::*;
+
+        #[test]
+        fn skips_completed_and_other_users() {
+            clear_pending_withdrawals();
+
+            let user = fake_principal(1);
+            save_pending_withdrawal(withdrawal(0, user, PendingWithdrawalStatus::Claimable));
+            save_pending_withdrawal(withdrawal(1, user, PendingWithdrawalStatus::Completed));
+            save_pending_withdrawal(withdrawal(2, fake_principal(2), PendingWithdrawalStatus::Claimable));
+
+            let ids: Vec<u64> = get_user_pending_withdrawals(user)
+                .into_iter()
+                .map(|w| w.id)
+                .collect();
+
+            assert_eq!(ids, vec![0]);
+        }
+    }
+
+    mod get_pending_withdrawals_by_status {
+        use super::*;
+
+        #[test]
+        fn filters_by_status() {
+            clear_pending_withdrawals();
+
+            save_pending_withdrawal(withdrawal(0, fake_principal(1), PendingWithdrawalStatus::Claimable));
+            save_pending_withdrawal(withdrawal(1, fake_principal(2), PendingWithdrawalStatus::Transferring));
+
+            let claimable = get_pending_withdrawals_by_status(PendingWithdrawalStatus::Claimable);
+
+            assert_eq!(claimable.len(), 1);
+            assert_eq!(claimable[0].id, 0);
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::cell::RefCell;
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+use ic_stable_structures::StableBTreeMap;
+use smart_rebalance::types::RebalanceDecision;
+
+use errors::internal_error::error::InternalError;
+use types::strategies::{Pool, StrategyId};
+
+use crate::impl_candid_storable;
+use crate::repository::memory::{self, Memory};
+use crate::types::types::{ListItemsPaginationRequest, SortOrder};
+
+/// Rebalance of a strategy from one pool to another.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct RebalanceRecord {
+    pub timestamp: u64,
+    pub previous_pool: Pool,
+    pub target_pool: Pool,
+    pub decision: RebalanceDecision,
+    /// Base token amount withdrawn from the previous pool
+    pub withdrawn_amount: Nat,
+    /// Token amounts added to the target pool
+    pub token_0_amount: Nat,
+    pub token_1_amount: Nat,
+    pub position_id: u64,
+    /// Target weight moved from the previous pool to the target pool, none for records before allocations
+    pub shifted_weight_bps: Option<u32>,
+    /// Why the liquidity could not be added to the target pool, none for completed rebalances
+    pub error: Option<InternalError>,
+}
+
+impl_candid_storable!(RebalanceRecord);
+
+// Strategy ID -> sequence number of the rebalance within the strategy
+type RebalanceRecordKey = (StrategyId, u64);
+
+thread_local! {
+    static REBALANCE_HISTORY: RefCell<StableBTreeMap<RebalanceRecordKey, RebalanceRecord, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::REBALANCE_HISTORY_MEMORY_ID))
+    );
+}
+
+pub fn add_rebalance_record(strategy_id: StrategyId, record: RebalanceRecord) {
+    let sequence = get_rebalance_records_count(strategy_id);
+
+    REBALANCE_HISTORY.with(|history| {
+        history.borrow_mut().insert((strategy_id, sequence), record);
+    });
+}
+
+pub fn get_last_rebalance_record(strategy_id: StrategyId) -> Option<RebalanceRecord> {
+    REBALANCE_HISTORY.with(|history| {
+        history.borrow()
+            .range((strategy_id, 0)..=(strategy_id, u64::MAX))
+            .next_back()
+            .map(|(_, record)| record)
+    })
+}
+
+pub fn get_rebalance_records_count(strategy_id: StrategyId) -> u64 {
+    REBALANCE_HISTORY.with(|history| {
+        history.borrow()
+            .range((strategy_id, 0)..=(strategy_id, u64::MAX))
+            .next_back()
+            .map_or(0, |((_, sequence), _)| sequence + 1)
+    })
+}
+
+pub fn get_rebalance_records(
+    strategy_id: StrategyId,
+    request: ListItemsPaginationRequest,
+) -> Vec<RebalanceRecord> {
+    let skip = request.page.saturating_sub(1).saturating_mul(request.page_size) as usize;
+    let limit = request.page_size as usize;
+
+    REBALANCE_HISTORY.with(|history| {
+        let history = history.borrow();
+        let records = history
+            .range((strategy_id, 0)..=(strategy_id, u64::MAX))
+            .map(|(_, record)| record);
+
+        // Records are stored in chronological order
+        match request.sort_order {
+            SortOrder::Asc => records.skip(skip).take(limit).collect(),
+            SortOrder::Desc => records.rev().skip(skip).take(limit).collect(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Principal;
+    use types::exchange_id::ExchangeId;
+
+    fn clear_history() {
+        REBALANCE_HISTORY.with(|history| history.borrow_mut().clear_new());
+    }
+
+    fn pool(id: &str) -> Pool {
+        Pool {
+            id: id.to_string(),
+            token0: Principal::anonymous(),
+            token1: Principal::anonymous(),
+            provider: ExchangeId::KongSwap,
+        }
+    }
+
+    fn record(timestamp: u64) -> RebalanceRecord {
+        RebalanceRecord {
+            timestamp,
+            previous_pool: pool("pool1"),
+            target_pool: pool("pool2"),
+            decision: RebalanceDecision {
+                should_move: true,
+                target_pool_id: Some("pool2".to_string()),
+                score_diff: 1.0,
+                expected_gain: 2.0,
+                rebalance_cost: 0.5,
+            },
+            withdrawn_amount: Nat::from(100u64),
+            token_0_amount: Nat::from(50u64),
+            token_1_amount: Nat::from(10u64),
+            position_id: 1,
+            shifted_weight_bps: Some(10_000),
+            error: None,
+        }
+    }
+
+    fn pagination(page: u64, page_size: u64, sort_order: SortOrder) -> ListItemsPaginationRequest {
+        ListItemsPaginationRequest {
+            page,
+            page_size,
+            sort_order,
+            search: None,
+        }
+    }
+
+    mod get_last_rebalance_record {
+        use super::*;
+
+        #[test]
+        fn returns_none_without_history() {
+            clear_history();
+
+            assert!(get_last_rebalance_record(1).is_none());
+        }
+
+        #[test]
+        fn returns_latest_record_of_strategy() {
+            clear_history();
+
+            add_rebalance_record(1, record(100));
+            add_rebalance_record(1, record(200));
+            add_rebalance_record(2, record(300));
+
+            assert_eq!(get_last_rebalance_record(1).unwrap().timestamp, 200);
+            assert_eq!(get_rebalance_records_count(1), 2);
+            assert_eq!(get_rebalance_records_count(2), 1);
+        }
+    }
+
+    mod get_rebalance_records {
+        use super::*;
+
+        #[test]
+        fn paginates_in_requested_order() {
+            clear_history();
+
+            for timestamp in 1..=5 {
+                add_rebalance_record(1, record(timestamp));
+            }
+            add_rebalance_record(2, record(100));
+
+            let asc: Vec<u64> = get_rebalance_records(1, pagination(2, 2, SortOrder::Asc))
+                .into_iter()
+                .map(|r| r.timestamp)
+                .collect();
+            let desc: Vec<u64> = get_rebalance_records(1, pagination(1, 2, SortOrder::Desc))
+                .into_iter()
+                .map(|r| r.timestamp)
+                .collect();
+
+            assert_eq!(asc, vec![3, 4]);
+            assert_eq!(desc, vec![5, 4]);
+        }
+    }
+}
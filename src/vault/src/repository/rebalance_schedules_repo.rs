@@ -0,0 +1,85 @@
+use std::cell::RefCell;
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+use ic_stable_structures::StableBTreeMap;
+
+use types::strategies::StrategyId;
+
+use crate::impl_candid_storable;
+use crate::repository::memory::{self, Memory};
+
+/// Automatic rebalance schedule of a strategy.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct RebalanceSchedule {
+    pub enabled: bool,
+    pub last_run_at: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+impl Default for RebalanceSchedule {
+    fn default() -> Self {
+        RebalanceSchedule {
+            enabled: true,
+            last_run_at: None,
+            last_error: None,
+        }
+    }
+}
+
+impl_candid_storable!(RebalanceSchedule);
+
+thread_local! {
+    static REBALANCE_SCHEDULES: RefCell<StableBTreeMap<StrategyId, RebalanceSchedule, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::REBALANCE_SCHEDULES_MEMORY_ID))
+    );
+}
+
+/// Returns the schedule of the strategy or the default one if it was never saved.
+pub fn get_schedule(strategy_id: StrategyId) -> RebalanceSchedule {
+    REBALANCE_SCHEDULES.with(|schedules| schedules.borrow().get(&strategy_id))
+        .unwrap_or_default()
+}
+
+pub fn save_schedule(strategy_id: StrategyId, schedule: RebalanceSchedule) {
+    REBALANCE_SCHEDULES.with(|schedules| {
+        schedules.borrow_mut().insert(strategy_id, schedule);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clear_schedules() {
+        REBALANCE_SCHEDULES.with(|schedules| schedules.borrow_mut().clear_new());
+    }
+
+    mod get_schedule {
+        use super::*;
+
+        #[test]
+        fn returns_default_schedule_if_not_saved() {
+            clear_schedules();
+
+            let schedule = get_schedule(1);
+
+            assert_eq!(schedule, RebalanceSchedule::default());
+            assert!(schedule.enabled);
+        }
+
+        #[test]
+        fn returns_saved_schedule() {
+            clear_schedules();
+
+            let schedule = RebalanceSchedule {
+                enabled: false,
+                last_run_at: Some(100),
+                last_error: Some("error".to_string()),
+            };
+            save_schedule(2, schedule.clone());
+
+            assert_eq!(get_schedule(2), schedule);
+            assert_eq!(get_schedule(1), RebalanceSchedule::default());
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::cell::RefCell;
+use std::ops::Range;
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+use ic_stable_structures::StableBTreeMap;
+
+use types::strategies::StrategyId;
+
+use crate::impl_candid_storable;
+use crate::repository::memory::{self, Memory};
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum SharePriceSource {
+    Deposit,
+    Withdraw,
+    Rebalance,
+    /// Hourly update of the strategy liquidity
+    StatsUpdate,
+}
+
+/// Value of a share of a strategy at a point in time.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct SharePricePoint {
+    pub timestamp: u64,
+    /// Position value per share in base token, scaled by 10^12
+    pub share_price: Nat,
+    pub nav: Nat,
+    pub total_shares: Nat,
+    pub source: SharePriceSource,
+}
+
+impl_candid_storable!(SharePricePoint);
+
+// Strategy ID -> sequence number of the point within the strategy
+type SharePricePointKey = (StrategyId, u64);
+
+thread_local! {
+    static SHARE_PRICES: RefCell<StableBTreeMap<SharePricePointKey, SharePricePoint, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::SHARE_PRICES_MEMORY_ID))
+    );
+}
+
+pub fn add_share_price_point(strategy_id: StrategyId, point: SharePricePoint) {
+    let sequence = get_share_price_points_count(strategy_id);
+
+    SHARE_PRICES.with(|prices| {
+        prices.borrow_mut().insert((strategy_id, sequence), point);
+    });
+}
+
+pub fn get_last_share_price_point(strategy_id: StrategyId) -> Option<SharePricePoint> {
+    SHARE_PRICES.with(|prices| {
+        prices.borrow()
+            .range((strategy_id, 0)..=(strategy_id, u64::MAX))
+            .next_back()
+            .map(|(_, point)| point)
+    })
+}
+
+pub fn get_share_price_points_count(strategy_id: StrategyId) -> u64 {
+    SHARE_PRICES.with(|prices| {
+        prices.borrow()
+            .range((strategy_id, 0)..=(strategy_id, u64::MAX))
+            .next_back()
+            .map_or(0, |((_, sequence), _)| sequence + 1)
+    })
+}
+
+/// Returns the sequence numbers of the points of a strategy recorded between the timestamps, inclusive.
+///
+/// Points are added in chronological order, so both bounds are binary searched.
+pub fn get_share_price_sequences(
+    strategy_id: StrategyId,
+    from_timestamp: u64,
+    to_timestamp: u64,
+) -> Range<u64> {
+    let count = get_share_price_points_count(strategy_id);
+
+    SHARE_PRICES.with(|prices| {
+        let prices = prices.borrow();
+        let is_before = |sequence: u64, timestamp: u64| {
+            prices.get(&(strategy_id, sequence)).map_or(false, |point| point.timestamp < timestamp)
+        };
+
+        let start = partition_point(count, |sequence| is_before(sequence, from_timestamp));
+        let end = match to_timestamp.checked_add(1) {
+            Some(timestamp) => partition_point(count, |sequence| is_before(sequence, timestamp)),
+            None => count,
+        };
+
+        start..end.max(start)
+    })
+}
+
+/// Returns the points of a strategy with the sequence numbers, in chronological order.
+pub fn get_share_price_points(strategy_id: StrategyId, sequences: Range<u64>) -> Vec<SharePricePoint> {
+    SHARE_PRICES.with(|prices| {
+        prices.borrow()
+            .range((strategy_id, sequences.start)..(strategy_id, sequences.end))
+            .map(|(_, point)| point)
+            .collect()
+    })
+}
+
+// First sequence in 0..count for which the predicate is false, the predicate must be true before it
+fn partition_point(count: u64, predicate: impl Fn(u64) -> bool) -> u64 {
+    let (mut low, mut high) = (0, count);
+
+    while low < high {
+        let middle = low + (high - low) / 2;
+
+        if predicate(middle) {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+
+    low
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clear_prices() {
+        SHARE_PRICES.with(|prices| prices.borrow_mut().clear_new());
+    }
+
+    fn point(timestamp: u64, share_price: u64) -> SharePricePoint {
+        SharePricePoint {
+            timestamp,
+            share_price: Nat::from(share_price),
+            nav: Nat::from(1_000u64),
+            total_shares: Nat::from(1_000u64),
+            source: SharePriceSource::StatsUpdate,
+        }
+    }
+
+    mod get_share_price_points {
+        use super::*;
+
+        #[test]
+        fn returns_points_of_strategy_in_range() {
+            clear_prices();
+
+            for timestamp in [10, 20, 30, 40] {
+                add_share_price_point(1, point(timestamp, timestamp));
+            }
+            add_share_price_point(2, point(25, 1));
+
+            let points = get_share_price_points(1, get_share_price_sequences(1, 15, 30));
+            let timestamps: Vec<u64> = points.iter().map(|point| point.timestamp).collect();
+
+            assert_eq!(timestamps, vec![20, 30]);
+            assert_eq!(get_last_share_price_point(1), Some(point(40, 40)));
+            assert_eq!(get_share_price_points_count(2), 1);
+        }
+    }
+
+    mod get_share_price_sequences {
+        use super::*;
+
+        #[test]
+        fn finds_bounds_of_timestamp_range() {
+            clear_prices();
+
+            for timestamp in [10, 20, 20, 30, 40] {
+                add_share_price_point(1, point(timestamp, timestamp));
+            }
+
+            assert_eq!(get_share_price_sequences(1, 20, 30), 1..4);
+            assert_eq!(get_share_price_sequences(1, 0, u64::MAX), 0..5);
+            assert_eq!(get_share_price_sequences(1, 41, 50), 5..5);
+            assert_eq!(get_share_price_sequences(1, 21, 29), 3..3);
+            assert_eq!(get_share_price_sequences(2, 0, u64::MAX), 0..0);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::cell::RefCell;
+use candid::{CandidType, Deserialize, Nat, Principal};
+use serde::Serialize;
+use ic_stable_structures::StableBTreeMap;
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::icrc1::transfer::Memo;
+
+use types::strategies::StrategyId;
+
+use crate::impl_candid_storable;
+use crate::repository::memory::{self, Memory};
+
+/// Shares of a strategy an owner allows a spender to transfer.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ShareAllowanceKey {
+    pub strategy_id: StrategyId,
+    pub owner: Principal,
+    pub spender: Account,
+    /// Position subaccount of the owner, none for the default account
+    pub subaccount: Option<Subaccount>,
+}
+
+impl_candid_storable!(ShareAllowanceKey);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ShareAllowance {
+    pub allowance: Nat,
+    pub expires_at: Option<u64>,
+}
+
+impl_candid_storable!(ShareAllowance);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum ShareTokenOperation {
+    Transfer,
+    Approve,
+}
+
+/// Block of the share token ledger, the accounts are the share accounts of the strategy.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ShareTokenTransaction {
+    pub operation: ShareTokenOperation,
+    pub strategy_id: StrategyId,
+    pub from: Account,
+    pub to: Option<Account>,
+    pub spender: Option<Account>,
+    pub amount: Nat,
+    /// Base token cost basis moved with the shares
+    pub cost_basis: Option<Nat>,
+    pub expires_at: Option<u64>,
+    pub memo: Option<Memo>,
+    pub created_at_time: Option<u64>,
+    /// Ledger time in nanoseconds
+    pub timestamp: u64,
+}
+
+impl_candid_storable!(ShareTokenTransaction);
+
+thread_local! {
+    static SHARE_ALLOWANCES: RefCell<StableBTreeMap<ShareAllowanceKey, ShareAllowance, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::SHARE_TOKEN_ALLOWANCES_MEMORY_ID))
+    );
+
+    // Block index -> transaction
+    static SHARE_TRANSACTIONS: RefCell<StableBTreeMap<u64, ShareTokenTransaction, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::SHARE_TOKEN_TRANSACTIONS_MEMORY_ID))
+    );
+
+    // Index in the share subaccounts -> position subaccount
+    static SHARE_SUBACCOUNTS: RefCell<StableBTreeMap<u64, Subaccount, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::SHARE_SUBACCOUNTS_MEMORY_ID))
+    );
+
+    // Position subaccount -> index in the share subaccounts
+    static SHARE_SUBACCOUNT_INDEXES: RefCell<StableBTreeMap<Subaccount, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::SHARE_SUBACCOUNT_INDEXES_MEMORY_ID))
+    );
+}
+
+pub fn get_position_subaccount(index: u64) -> Option<Subaccount> {
+    SHARE_SUBACCOUNTS.with(|subaccounts| subaccounts.borrow().get(&index))
+}
+
+pub fn get_position_subaccount_index(subaccount: &Subaccount) -> Option<u64> {
+    SHARE_SUBACCOUNT_INDEXES.with(|indexes| indexes.borrow().get(subaccount))
+}
+
+/// Registers the position subaccount once and returns its index, indexes start at 1 since 0 is the default account.
+pub fn register_position_subaccount(subaccount: Subaccount) -> u64 {
+    if let Some(index) = get_position_subaccount_index(&subaccount) {
+        return index;
+    }
+
+    let index = SHARE_SUBACCOUNTS.with(|subaccounts| {
+        let mut subaccounts = subaccounts.borrow_mut();
+        let index = subaccounts.last_key_value().map_or(1, |(index, _)| index + 1);
+
+        subaccounts.insert(index, subaccount);
+
+        index
+    });
+
+    SHARE_SUBACCOUNT_INDEXES.with(|indexes| {
+        indexes.borrow_mut().insert(subaccount, index);
+    });
+
+    index
+}
+
+pub fn get_allowance(key: &ShareAllowanceKey) -> Option<ShareAllowance> {
+    SHARE_ALLOWANCES.with(|allowances| allowances.borrow().get(key))
+}
+
+/// Saves the allowance, a zero allowance is removed.
+pub fn save_allowance(key: ShareAllowanceKey, allowance: ShareAllowance) {
+    SHARE_ALLOWANCES.with(|allowances| {
+        let mut allowances = allowances.borrow_mut();
+
+        if allowance.allowance == Nat::from(0u64) {
+            allowances.remove(&key);
+        } else {
+            allowances.insert(key, allowance);
+        }
+    });
+}
+
+/// Appends the transaction to the ledger and returns its block index.
+pub fn add_transaction(transaction: ShareTokenTransaction) -> u64 {
+    SHARE_TRANSACTIONS.with(|transactions| {
+        let mut transactions = transactions.borrow_mut();
+        let block_index = transactions.last_key_value().map_or(0, |(index, _)| index + 1);
+
+        transactions.insert(block_index, transaction);
+
+        block_index
+    })
+}
+
+/// Returns the block of the first transaction recorded since the timestamp that matches.
+pub fn find_transaction_since(
+    since_timestamp: u64,
+    matches: impl Fn(&ShareTokenTransaction) -> bool,
+) -> Option<u64> {
+    SHARE_TRANSACTIONS.with(|transactions| {
+        transactions.borrow()
+            .iter()
+            .rev()
+            .take_while(|(_, transaction)| transaction.timestamp >= since_timestamp)
+            .filter(|(_, transaction)| matches(transaction))
+            .last()
+            .map(|(block_index, _)| block_index)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clear_share_token() {
+        SHARE_ALLOWANCES.with(|allowances| allowances.borrow_mut().clear_new());
+        SHARE_TRANSACTIONS.with(|transactions| transactions.borrow_mut().clear_new());
+        SHARE_SUBACCOUNTS.with(|subaccounts| subaccounts.borrow_mut().clear_new());
+        SHARE_SUBACCOUNT_INDEXES.with(|indexes| indexes.borrow_mut().clear_new());
+    }
+
+    fn fake_principal(id: u8) -> Principal {
+        Principal::from_slice(&[id; 29])
+    }
+
+    fn transaction(amount: u64, timestamp: u64) -> ShareTokenTransaction {
+        ShareTokenTransaction {
+            operation: ShareTokenOperation::Transfer,
+            strategy_id: 1,
+            from: Account::from(fake_principal(1)),
+            to: Some(Account::from(fake_principal(2))),
+            spender: None,
+            amount: Nat::from(amount),
+            cost_basis: None,
+            expires_at: None,
+            memo: None,
+            created_at_time: Some(timestamp),
+            timestamp,
+        }
+    }
+
+    mod save_allowance {
+        use super::*;
+
+        #[test]
+        fn removes_zero_allowance() {
+            clear_share_token();
+
+            let key = ShareAllowanceKey {
+                strategy_id: 1,
+                owner: fake_principal(1),
+                spender: Account::from(fake_principal(2)),
+                subaccount: None,
+            };
+
+            save_allowance(key.clone(), ShareAllowance { allowance: Nat::from(10u64), expires_at: None });
+            assert_eq!(get_allowance(&key).map(|allowance| allowance.allowance), Some(Nat::from(10u64)));
+
+            save_allowance(key.clone(), ShareAllowance { allowance: Nat::from(0u64), expires_at: None });
+            assert_eq!(get_allowance(&key), None);
+        }
+    }
+
+    mod register_position_subaccount {
+        use super::*;
+
+        #[test]
+        fn registers_subaccount_once() {
+            clear_share_token();
+
+            let first = register_position_subaccount([1; 32]);
+            let second = register_position_subaccount([2; 32]);
+
+            assert_eq!((first, second), (1, 2));
+            assert_eq!(register_position_subaccount([1; 32]), 1);
+            assert_eq!(get_position_subaccount(2), Some([2; 32]));
+            assert_eq!(get_position_subaccount_index(&[3; 32]), None);
+        }
+    }
+
+    mod find_transaction_since {
+        use super::*;
+
+        #[test]
+        fn finds_first_matching_transaction_in_window() {
+            clear_share_token();
+
+            add_transaction(transaction(5, 10));
+            add_transaction(transaction(5, 20));
+            add_transaction(transaction(7, 30));
+
+            assert_eq!(add_transaction(transaction(5, 40)), 3);
+            assert_eq!(find_transaction_since(15, |transaction| transaction.amount == Nat::from(5u64)), Some(1));
+            assert_eq!(find_transaction_since(35, |transaction| transaction.amount == Nat::from(7u64)), None);
+        }
+    }
+}
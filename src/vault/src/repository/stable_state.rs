@@ -1,68 +1,141 @@
+use std::cell::RefCell;
 use candid::{CandidType, Deserialize};
 use ic_cdk::storage;
+use ic_stable_structures::StableCell;
 use serde::Serialize;
 
-use crate::strategies::strategy::IStrategy;
+use access_control::access_control_service;
+use access_control::role::AccessControlState;
+
+use crate::impl_candid_storable;
 use crate::strategies::strategy_candid::{StrategyCandid, Candid as StrategyToCandid};
-use crate::repository::strategies_repo::STRATEGIES;
-use crate::repository::event_records_repo::EVENT_RECORDS;
+use crate::repository::memory::{self, Memory};
+use crate::repository::{strategies_repo, event_records_repo};
 use crate::repository::runtime_config_repo::{self, RuntimeConfig};
 use crate::repository::config_repo::{self, Conf};
+use crate::repository::emergency_repo::{self, PauseState, CircuitBreakerConfig};
 use crate::event_records::event_record::EventRecord;
 
-#[derive(Clone, Debug, CandidType, Serialize, Deserialize)]
+const MEMORY_MANAGER_MAGIC: &[u8; 3] = b"MGR";
+
+/// Heap-only state saved on upgrade.
+/// Strategies, user shares and event records live directly in stable structures.
+#[derive(Clone, Debug, Default, CandidType, Serialize, Deserialize)]
 pub struct StableState {
+    pub runtime_config: Option<RuntimeConfig>,
+    pub config: Conf,
+    pub access_control: Option<AccessControlState>,
+    pub global_pause: Option<PauseState>,
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+}
+
+impl_candid_storable!(StableState);
+
+/// Layout written by `storage::stable_save` before the vault moved to stable structures.
+#[derive(Clone, Debug, CandidType, Serialize, Deserialize)]
+struct LegacyStableState {
     pub runtime_config: Option<RuntimeConfig>,
     pub strategies: Vec<StrategyCandid>,
     pub event_records: Vec<EventRecord>,
     pub config: Conf,
 }
 
+thread_local! {
+    static STABLE_STATE: RefCell<StableCell<StableState, Memory>> = RefCell::new(
+        StableCell::init(
+            memory::get_memory(memory::STABLE_STATE_MEMORY_ID),
+            StableState::default(),
+        ).expect("failed to initialize stable state")
+    );
+}
+
 pub fn stable_save() {
-    let runtime_config = runtime_config_repo::get_runtime_config();
-    let conf = config_repo::get_config();
+    let state = StableState {
+        runtime_config: Some(runtime_config_repo::get_runtime_config()),
+        config: config_repo::get_config(),
+        access_control: Some(access_control_service::get_state()),
+        global_pause: Some(emergency_repo::get_global_pause()),
+        circuit_breaker: Some(emergency_repo::get_circuit_breaker_config()),
+    };
 
-    let strategies: Vec<StrategyCandid> = STRATEGIES.with(|strategies| {
-        strategies.borrow().iter().map(|strategy| strategy.to_candid()).collect()
+    STABLE_STATE.with(|cell| {
+        cell.borrow_mut()
+            .set(state)
+            .expect("failed to save stable state");
     });
+}
 
-    let event_records: Vec<EventRecord> = EVENT_RECORDS.with(|events| {
-        events.borrow().clone()
-    });
+pub fn stable_restore() {
+    // Must be checked before the memory manager is initialized,
+    // since the memory manager overwrites the header of a foreign layout
+    if is_legacy_layout() {
+        migrate_legacy_state();
+        return;
+    }
 
-    let state = StableState {
-        runtime_config: Some(runtime_config),
-        config: conf,
-        strategies,
-        event_records,
-    };
+    let state = STABLE_STATE.with(|cell| cell.borrow().get().clone());
+
+    // Runtime Config
+    runtime_config_repo::set_runtime_config(state.runtime_config.unwrap_or_default());
+
+    // Conf
+    config_repo::set_config(state.config);
 
-    storage::stable_save((state, )).unwrap();
+    // Access control
+    access_control_service::set_state(state.access_control.unwrap_or_default());
+    migrate_legacy_controllers();
+
+    // Emergency
+    emergency_repo::set_global_pause(state.global_pause.unwrap_or_default());
+    emergency_repo::set_circuit_breaker_config(state.circuit_breaker.unwrap_or_default());
+
+    // Strategies stored before they became generic
+    strategies_repo::migrate_legacy_strategies();
+
+    // Positions stored per principal before they were kept per account
+    strategies_repo::migrate_principal_user_shares();
 }
 
-pub fn stable_restore() {
-    let (state, ): (StableState, ) = storage::stable_restore().unwrap();
+fn is_legacy_layout() -> bool {
+    if ic_cdk::api::stable::stable_size() == 0 {
+        return false;
+    }
+
+    let mut magic = [0u8; 3];
+    ic_cdk::api::stable::stable_read(0, &mut magic);
+
+    &magic != MEMORY_MANAGER_MAGIC
+}
+
+/// One-time migration from the whole-heap `StableState` blob into stable structures.
+fn migrate_legacy_state() {
+    let (state, ): (LegacyStableState, ) = storage::stable_restore()
+        .expect("failed to restore legacy stable state");
 
     // Runtime Config
-    runtime_config_repo::set_runtime_config(state.runtime_config.clone().unwrap_or_default());
+    runtime_config_repo::set_runtime_config(state.runtime_config.unwrap_or_default());
 
     // Conf
-    config_repo::set_config(state.config.clone());
+    config_repo::set_config(state.config);
+    migrate_legacy_controllers();
 
-    // Strategies
-    let strategies: Vec<Box<dyn IStrategy>> = state.strategies.clone()
-        .into_iter()
-        .map(|x| x.to_strategy())
-        .collect();
+    // Strategies (user shares are split out by the repository)
+    for strategy in state.strategies {
+        strategies_repo::add_or_update_strategy(strategy.to_strategy());
+    }
 
-    STRATEGIES.with(|utrs| {
-        utrs.borrow_mut();
-        utrs.replace(strategies)
-    });
+    // EventRecords (appended in order, so event ids stay equal to log indexes)
+    for event_record in state.event_records {
+        event_records_repo::save_event_record(event_record);
+    }
 
-    // EventRecords
-    EVENT_RECORDS.with(|event_records| {
-        event_records.borrow_mut();
-        event_records.replace(state.event_records)
-    });
+    stable_save();
+}
+
+/// Controllers of the config were the admins before the access control was introduced.
+fn migrate_legacy_controllers() {
+    if let Some(controllers) = config_repo::get_controllers() {
+        access_control_service::init_admins(controllers);
+        config_repo::set_controllers(None);
+    }
 }
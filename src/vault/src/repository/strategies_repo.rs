@@ -1,201 +1,278 @@
 use std::cell::RefCell;
-use candid::Principal;
+use std::collections::{HashMap, HashSet};
+use candid::{CandidType, Deserialize, Nat, Principal};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use serde::Serialize;
+use ic_stable_structures::StableBTreeMap;
+
+use types::context::{position_subaccount, DEFAULT_SUBACCOUNT};
+use types::strategies::StrategyId;
+
+use crate::impl_candid_storable;
+use crate::repository::memory::{self, Memory};
+use crate::strategies::strategy::IStrategy;
+use crate::strategies::strategy_candid::{StrategyCandid, Candid};
+
+/// Per-user share balance of a strategy, stored outside of the strategy record
+/// so that the strategy itself stays small regardless of the number of users.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+pub struct UserShares {
+    pub shares: Nat,
+    pub initial_deposit: Nat,
+}
+
+impl_candid_storable!(UserShares);
+
+/// Position of an account in a strategy, the default subaccount is stored as zeros.
+type AccountSharesKey = (StrategyId, (Principal, Subaccount));
 
-use crate::strategies::strategy::{IStrategy, StrategyIterator};
+/// Position of a principal in a strategy, kept until the positions are migrated to accounts.
+type LegacyUserSharesKey = (StrategyId, Principal);
 
 thread_local! {
-    pub static STRATEGIES: RefCell<Vec<Box<dyn IStrategy >>> = RefCell::new(Default::default());
+    pub static STRATEGIES: RefCell<StableBTreeMap<StrategyId, StrategyCandid, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::STRATEGIES_MEMORY_ID))
+    );
+    pub static USER_SHARES: RefCell<StableBTreeMap<LegacyUserSharesKey, UserShares, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::USER_SHARES_MEMORY_ID))
+    );
+    pub static ACCOUNT_SHARES: RefCell<StableBTreeMap<AccountSharesKey, UserShares, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::ACCOUNT_SHARES_MEMORY_ID))
+    );
 }
 
 pub fn get_all_strategies() -> Vec<Box<dyn IStrategy>> {
     STRATEGIES.with(|strategies| {
-        StrategyIterator::new(strategies.borrow_mut())
-            .into_iter()
-            .collect()
+        strategies.borrow()
+            .values()
+            .collect::<Vec<_>>()
     })
+        .into_iter()
+        .map(load_strategy)
+        .collect()
 }
 
 pub fn get_enabled_strategies() -> Vec<Box<dyn IStrategy>> {
-    STRATEGIES.with(|strategies| {
-        StrategyIterator::new(strategies.borrow_mut())
-            .into_iter()
-            .filter(|s| s.get_enabled())
-            .collect()
-    })
+    get_all_strategies()
+        .into_iter()
+        .filter(|s| s.get_enabled())
+        .collect()
 }
 
+/// Returns the strategies in which any account of the user holds a position.
 pub fn get_user_strategies(user: Principal) -> Vec<Box<dyn IStrategy>> {
-    STRATEGIES.with(|strategies| {
-        StrategyIterator::new(strategies.borrow_mut())
-            .into_iter()
-            .filter(|s| s.get_user_shares().contains_key(&user))
-            .collect()
-    })
+    let strategy_ids: Vec<StrategyId> = STRATEGIES.with(|strategies| {
+        strategies.borrow().keys().collect()
+    });
+
+    strategy_ids
+        .into_iter()
+        .filter(|id| has_user_position(*id, user))
+        .filter_map(get_strategy_by_id)
+        .filter(|s| s.get_user_shares().keys().any(|account| account.owner == user))
+        .collect()
 }
 
-pub fn get_strategy_by_id(id: u16) -> Option<Box<dyn IStrategy>> {
-    STRATEGIES.with(|strategies| {
-        StrategyIterator::new(strategies.borrow_mut())
-            .into_iter()
-            .find(|s| s.get_id() == id)
-    })
+pub fn get_strategy_by_id(id: StrategyId) -> Option<Box<dyn IStrategy>> {
+    STRATEGIES.with(|strategies| strategies.borrow().get(&id))
+        .map(load_strategy)
 }
 
 pub fn add_or_update_strategy(strategy: Box<dyn IStrategy>) {
-    STRATEGIES.with(|strategies| {
-        let mut strategies = strategies.borrow_mut();
-        let index = strategies
-            .iter()
-            .position(|s| s.get_id() == strategy.get_id());
-
-        if let Some(index) = index {
-            strategies[index] = strategy;
-        } else {
-            strategies.push(strategy);
-        }
-    });
+    store_strategy(strategy);
 }
 
 pub fn add_if_not_exists(strategy: Box<dyn IStrategy>) {
-    STRATEGIES.with(|strategies| {
-        let mut strategies = strategies.borrow_mut();
-        let index = strategies
-            .iter()
-            .position(|s| s.get_id() == strategy.get_id());
-
-        if index.is_none() {
-            strategies.push(strategy);
-        }
-    });
+    if !strategy_exists(strategy.get_id()) {
+        store_strategy(strategy);
+    }
 }
 
 pub fn save_strategy(strategy: Box<dyn IStrategy>) {
-    STRATEGIES.with(|strategies| {
-        let mut strategies = strategies.borrow_mut();
-        let index = strategies
-            .iter()
-            .position(|s| s.get_id() == strategy.get_id());
-
-        if let Some(index) = index {
-            strategies[index] = strategy;
-        }
-    });
+    if strategy_exists(strategy.get_id()) {
+        store_strategy(strategy);
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::collections::HashMap;
-    use candid::{Nat, Principal};
-    use types::CanisterId;
-    use ::types::strategies::Pool;
+fn strategy_exists(id: StrategyId) -> bool {
+    STRATEGIES.with(|strategies| strategies.borrow().contains_key(&id))
+}
 
-    use crate::strategies::basic_strategy::BasicStrategy;
-    use crate::strategies::strategy_candid::StrategyCandid;
+/// Rewrites the strategies stored under the variants of the former per-strategy types
+/// as generic strategies. User share balances are stored separately and stay untouched.
+pub fn migrate_legacy_strategies() {
+    let legacy_strategies: Vec<(StrategyId, StrategyCandid)> = STRATEGIES.with(|strategies| {
+        strategies.borrow()
+            .iter()
+            .filter(|(_, candid)| candid.is_legacy())
+            .collect()
+    });
 
-    #[derive(Clone)]
-    struct MockStrategy {
-        id: u16,
-        user_shares: HashMap<Principal, Nat>,
+    for (strategy_id, candid) in legacy_strategies {
+        STRATEGIES.with(|strategies| {
+            strategies.borrow_mut().insert(strategy_id, candid.to_strategy().to_candid());
+        });
     }
+}
 
-    impl MockStrategy {
-        fn new(id: u16, user: Option<Principal>) -> Self {
-            let mut user_shares = HashMap::new();
-            if let Some(principal) = user.clone() {
-                user_shares.insert(principal, Nat::from(100u64));
-            }
-            Self { id, user_shares }
-        }
+/// Moves the positions stored per principal to the default account of the principal.
+pub fn migrate_principal_user_shares() {
+    let legacy_user_shares: Vec<(LegacyUserSharesKey, UserShares)> = USER_SHARES.with(|shares| {
+        shares.borrow().iter().collect()
+    });
+
+    if legacy_user_shares.is_empty() {
+        return;
     }
 
-    impl BasicStrategy for MockStrategy {
-        fn get_id(&self) -> u16 {
-            self.id
-        }
+    ACCOUNT_SHARES.with(|shares| {
+        let mut shares = shares.borrow_mut();
 
-        fn get_user_shares(&self) -> HashMap<Principal, Nat> {
-            self.user_shares.clone()
+        for ((strategy_id, user), balance) in legacy_user_shares {
+            shares.insert(to_account_shares_key(strategy_id, &Account::from(user)), balance);
         }
+    });
 
-        fn get_users_count(&self) -> u32 {
-            self.user_shares.len() as u32
-        }
+    USER_SHARES.with(|shares| shares.borrow_mut().clear_new());
+}
 
-        fn get_position_id(&self) -> Option<u64> {
-            None
-        }
+/// Restores a strategy from its stable record and attaches the user share balances.
+fn load_strategy(candid: StrategyCandid) -> Box<dyn IStrategy> {
+    let mut strategy = candid.to_strategy();
+    let mut user_shares = HashMap::new();
+    let mut initial_deposit = HashMap::new();
 
-        fn get_user_shares_by_principal(&self, user: Principal) -> Nat {
-            self.user_shares.get(&user).cloned().unwrap_or(Nat::from(0u64))
+    for (account, balance) in get_strategy_user_shares(strategy.get_id()) {
+        if balance.shares != Nat::from(0u64) {
+            user_shares.insert(account, balance.shares);
         }
-
-        fn get_name(&self) -> String {
-            "mock-strategy".to_string()
+        if balance.initial_deposit != Nat::from(0u64) {
+            initial_deposit.insert(account, balance.initial_deposit);
         }
+    }
 
-        fn get_description(&self) -> String {
-            "mock-desc".to_string()
-        }
+    strategy.set_user_shares(user_shares);
+    strategy.set_initial_deposit(initial_deposit);
+    strategy
+}
 
-        fn get_base_token(&self) -> CanisterId {
-            CanisterId::from_text("mock-base-token").unwrap()
-        }
+/// Splits a strategy into its record and user share balances and writes both to stable memory.
+fn store_strategy(mut strategy: Box<dyn IStrategy>) {
+    let strategy_id = strategy.get_id();
 
-        fn get_pools(&self) -> Vec<Pool> {
-            vec![]
-        }
+    save_strategy_user_shares(
+        strategy_id,
+        strategy.get_user_shares(),
+        strategy.get_initial_deposit(),
+    );
 
-        fn get_current_pool(&self) -> Option<Pool> {
-            None
-        }
+    strategy.set_user_shares(HashMap::new());
+    strategy.set_initial_deposit(HashMap::new());
 
-        fn get_total_shares(&self) -> Nat {
-            Nat::from(100u64)
-        }
+    STRATEGIES.with(|strategies| {
+        strategies.borrow_mut().insert(strategy_id, strategy.to_candid());
+    });
+}
 
-        fn get_total_balance(&self) -> Nat {
-            Nat::from(100u64)
-        }
+fn get_strategy_user_shares(strategy_id: StrategyId) -> Vec<(Account, UserShares)> {
+    ACCOUNT_SHARES.with(|shares| {
+        shares.borrow()
+            .range((strategy_id, (Principal::management_canister(), DEFAULT_SUBACCOUNT))..)
+            .take_while(|((id, _), _)| *id == strategy_id)
+            .map(|((_, (owner, subaccount)), balance)| (to_account(owner, subaccount), balance))
+            .collect()
+    })
+}
 
-        fn get_initial_deposit(&self) -> HashMap<Principal, Nat> {
-            HashMap::new()
-        }
+fn has_user_position(strategy_id: StrategyId, user: Principal) -> bool {
+    ACCOUNT_SHARES.with(|shares| {
+        shares.borrow()
+            .range((strategy_id, (user, DEFAULT_SUBACCOUNT))..)
+            .next()
+            .map_or(false, |((id, (owner, _)), _)| id == strategy_id && owner == user)
+    })
+}
 
-        fn get_current_liquidity(&self) -> Option<Nat> {
-            None
+fn save_strategy_user_shares(
+    strategy_id: StrategyId,
+    user_shares: HashMap<Account, Nat>,
+    initial_deposit: HashMap<Account, Nat>,
+) {
+    let accounts: HashSet<Account> = user_shares.keys()
+        .chain(initial_deposit.keys())
+        .cloned()
+        .collect();
+
+    let stored: HashMap<Account, UserShares> = get_strategy_user_shares(strategy_id)
+        .into_iter()
+        .collect();
+
+    ACCOUNT_SHARES.with(|shares| {
+        let mut shares = shares.borrow_mut();
+
+        // Remove accounts that no longer hold a position
+        for account in stored.keys() {
+            if !accounts.contains(account) {
+                shares.remove(&to_account_shares_key(strategy_id, account));
+            }
         }
 
-        fn get_current_liquidity_updated_at(&self) -> Option<u64> {
-            None
-        }
+        for account in accounts {
+            let balance = UserShares {
+                shares: user_shares.get(&account).cloned().unwrap_or(Nat::from(0u64)),
+                initial_deposit: initial_deposit.get(&account).cloned().unwrap_or(Nat::from(0u64)),
+            };
 
-        fn get_enabled(&self) -> bool {
-            false
+            if stored.get(&account) != Some(&balance) {
+                shares.insert(to_account_shares_key(strategy_id, &account), balance);
+            }
         }
+    });
+}
 
-        fn set_user_shares(&mut self, _shares: HashMap<Principal, Nat>) {}
-        fn set_total_shares(&mut self, _shares: Nat) {}
-        fn set_total_balance(&mut self, _balance: Nat) {}
-        fn set_initial_deposit(&mut self, _map: HashMap<Principal, Nat>) {}
-        fn set_current_pool(&mut self, _pool: Option<Pool>) {}
-        fn set_position_id(&mut self, _id: Option<u64>) {}
-        fn set_current_liquidity(&mut self, _liq: Option<Nat>) {}
-        fn set_current_liquidity_updated_at(&mut self, _ts: Option<u64>) {}
-        fn set_enabled(&mut self, _enabled: bool) {}
+fn to_account_shares_key(strategy_id: StrategyId, account: &Account) -> AccountSharesKey {
+    (strategy_id, (account.owner, *account.effective_subaccount()))
+}
+
+fn to_account(owner: Principal, subaccount: Subaccount) -> Account {
+    Account {
+        owner,
+        subaccount: position_subaccount(Some(subaccount)),
     }
+}
 
-    #[async_trait::async_trait]
-    impl IStrategy for MockStrategy {
-        fn to_candid(&self) -> StrategyCandid {
-            unimplemented!()
-        }
-        fn clone_self(&self) -> Box<dyn IStrategy> {
-            Box::new(Self {
-                id: self.id,
-                user_shares: self.user_shares.clone(),
-            })
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::{Nat, Principal};
+    use types::context::Context;
+
+    use crate::strategies::r#impl::generic_strategy::GenericStrategy;
+
+    fn clear_strategies() {
+        STRATEGIES.with(|s| s.borrow_mut().clear_new());
+        USER_SHARES.with(|s| s.borrow_mut().clear_new());
+        ACCOUNT_SHARES.with(|s| s.borrow_mut().clear_new());
+    }
+
+    fn fake_principal(id: u8) -> Principal {
+        Principal::from_slice(&[id; 29])
+    }
+
+    fn strategy_with_user(
+        mut strategy: Box<dyn IStrategy>,
+        user: Option<Principal>,
+    ) -> Box<dyn IStrategy> {
+        if let Some(principal) = user {
+            let mut user_shares = HashMap::new();
+            user_shares.insert(Account::from(principal), Nat::from(100u64));
+            strategy.set_user_shares(user_shares);
+
+            let mut initial_deposit = HashMap::new();
+            initial_deposit.insert(Account::from(principal), Nat::from(1_000u64));
+            strategy.set_initial_deposit(initial_deposit);
         }
+        strategy
     }
 
     mod get_all_strategies {
@@ -203,10 +280,10 @@ mod tests {
 
         #[test]
         fn returns_all_strategies() {
-            STRATEGIES.with(|s| s.borrow_mut().clear());
+            clear_strategies();
 
-            add_if_not_exists(Box::new(MockStrategy::new(1, None)));
-            add_if_not_exists(Box::new(MockStrategy::new(2, None)));
+            add_if_not_exists(Box::new(GenericStrategy::new(1)));
+            add_if_not_exists(Box::new(GenericStrategy::new(2)));
 
             let strategies = get_all_strategies();
             assert_eq!(strategies.len(), 2);
@@ -218,11 +295,11 @@ mod tests {
 
         #[test]
         fn filters_by_user() {
-            STRATEGIES.with(|s| s.borrow_mut().clear());
-            let user = Principal::anonymous();
+            clear_strategies();
+            let user = fake_principal(1);
 
-            add_if_not_exists(Box::new(MockStrategy::new(1, Some(user))));
-            add_if_not_exists(Box::new(MockStrategy::new(2, None)));
+            add_if_not_exists(strategy_with_user(Box::new(GenericStrategy::new(1)), Some(user)));
+            add_if_not_exists(strategy_with_user(Box::new(GenericStrategy::new(2)), None));
 
             let strategies = get_user_strategies(user);
             assert_eq!(strategies.len(), 1);
@@ -235,13 +312,25 @@ mod tests {
 
         #[test]
         fn finds_correct_strategy() {
-            STRATEGIES.with(|s| s.borrow_mut().clear());
+            clear_strategies();
 
-            add_if_not_exists(Box::new(MockStrategy::new(42, None)));
+            add_if_not_exists(Box::new(GenericStrategy::new(3)));
 
-            let strategy = get_strategy_by_id(42);
+            let strategy = get_strategy_by_id(3);
             assert!(strategy.is_some());
-            assert_eq!(strategy.unwrap().get_id(), 42);
+            assert_eq!(strategy.unwrap().get_id(), 3);
+        }
+
+        #[test]
+        fn restores_user_shares_and_initial_deposit() {
+            clear_strategies();
+            let user = fake_principal(2);
+
+            add_if_not_exists(strategy_with_user(Box::new(GenericStrategy::new(2)), Some(user)));
+
+            let strategy = get_strategy_by_id(2).unwrap();
+            assert_eq!(strategy.get_user_shares().get(&Account::from(user)), Some(&Nat::from(100u64)));
+            assert_eq!(strategy.get_initial_deposit().get(&Account::from(user)), Some(&Nat::from(1_000u64)));
         }
     }
 
@@ -250,10 +339,10 @@ mod tests {
 
         #[test]
         fn replaces_existing_strategy() {
-            STRATEGIES.with(|s| s.borrow_mut().clear());
+            clear_strategies();
 
-            let s1 = Box::new(MockStrategy::new(7, None));
-            let s2 = Box::new(MockStrategy::new(7, None));
+            let s1 = Box::new(GenericStrategy::new(2));
+            let s2 = Box::new(GenericStrategy::new(2));
 
             add_or_update_strategy(s1);
             add_or_update_strategy(s2);
@@ -261,6 +350,20 @@ mod tests {
             let strategies = get_all_strategies();
             assert_eq!(strategies.len(), 1);
         }
+
+        #[test]
+        fn removes_users_without_position() {
+            clear_strategies();
+            let user = fake_principal(3);
+
+            add_or_update_strategy(strategy_with_user(Box::new(GenericStrategy::new(2)), Some(user)));
+            add_or_update_strategy(strategy_with_user(Box::new(GenericStrategy::new(2)), None));
+
+            let strategy = get_strategy_by_id(2).unwrap();
+            assert!(strategy.get_user_shares().is_empty());
+            assert!(strategy.get_initial_deposit().is_empty());
+            assert!(ACCOUNT_SHARES.with(|s| s.borrow().is_empty()));
+        }
     }
 
     mod add_if_not_exists {
@@ -268,9 +371,9 @@ mod tests {
 
         #[test]
         fn does_not_add_duplicate() {
-            STRATEGIES.with(|s| s.borrow_mut().clear());
+            clear_strategies();
 
-            let s = Box::new(MockStrategy::new(8, None));
+            let s: Box<dyn IStrategy> = Box::new(GenericStrategy::new(2));
             add_if_not_exists(s.clone());
             add_if_not_exists(s);
 
@@ -282,11 +385,32 @@ mod tests {
     mod save_strategy {
         use super::*;
 
+        #[test]
+        fn keeps_zero_subaccount_deposit_in_default_position() {
+            clear_strategies();
+            let user = fake_principal(4);
+
+            add_or_update_strategy(strategy_with_user(Box::new(GenericStrategy::new(2)), Some(user)));
+
+            let deposit = Context::new("deposit".to_string(), Some(user), Some(2))
+                .with_subaccount(Some([0u8; 32]));
+            let mut strategy = get_strategy_by_id(2).unwrap();
+            strategy.increase_user_shares(deposit.account().unwrap(), Nat::from(50u64));
+            save_strategy(strategy);
+
+            let withdraw = Context::new("withdraw".to_string(), Some(user), Some(2))
+                .with_subaccount(None);
+            let strategy = get_strategy_by_id(2).unwrap();
+
+            assert_eq!(strategy.get_user_shares().len(), 1);
+            assert_eq!(strategy.get_user_shares_by_account(withdraw.account().unwrap()), Nat::from(150u64));
+        }
+
         #[test]
         fn updates_existing_strategy_only() {
-            STRATEGIES.with(|s| s.borrow_mut().clear());
+            clear_strategies();
 
-            let strategy = Box::new(MockStrategy::new(9, None));
+            let strategy: Box<dyn IStrategy> = Box::new(GenericStrategy::new(2));
             save_strategy(strategy.clone());
             assert_eq!(get_all_strategies().len(), 0);
 
@@ -295,4 +419,124 @@ mod tests {
             assert_eq!(get_all_strategies().len(), 1);
         }
     }
+
+    mod migrate_principal_user_shares {
+        use super::*;
+
+        #[test]
+        fn moves_positions_to_default_accounts() {
+            clear_strategies();
+            let user = fake_principal(4);
+
+            add_or_update_strategy(Box::new(GenericStrategy::new(1)));
+            USER_SHARES.with(|s| {
+                s.borrow_mut().insert((1, user), UserShares {
+                    shares: Nat::from(100u64),
+                    initial_deposit: Nat::from(1_000u64),
+                })
+            });
+
+            migrate_principal_user_shares();
+
+            let strategy = get_strategy_by_id(1).unwrap();
+            let account = Account { owner: user, subaccount: None };
+            assert_eq!(strategy.get_user_shares().get(&account), Some(&Nat::from(100u64)));
+            assert_eq!(strategy.get_initial_deposit().get(&account), Some(&Nat::from(1_000u64)));
+            assert!(strategy.get_user_shares().keys().all(|account| account.subaccount.is_none()));
+            assert!(USER_SHARES.with(|s| s.borrow().is_empty()));
+        }
+    }
+
+    mod save_strategy_user_shares {
+        use super::*;
+
+        #[test]
+        fn keeps_positions_of_subaccounts_apart() {
+            clear_strategies();
+            let user = fake_principal(5);
+            let default_account = Account { owner: user, subaccount: None };
+            let subaccount = Account { owner: user, subaccount: Some([1u8; 32]) };
+
+            let mut strategy: Box<dyn IStrategy> = Box::new(GenericStrategy::new(1));
+            strategy.set_user_shares(HashMap::from([
+                (default_account, Nat::from(100u64)),
+                (subaccount, Nat::from(200u64)),
+            ]));
+            add_or_update_strategy(strategy);
+
+            let strategy = get_strategy_by_id(1).unwrap();
+            assert_eq!(strategy.get_user_shares().get(&default_account), Some(&Nat::from(100u64)));
+            assert_eq!(strategy.get_user_shares().get(&subaccount), Some(&Nat::from(200u64)));
+            assert_eq!(get_user_strategies(user).len(), 1);
+        }
+    }
+
+    mod migrate_legacy_strategies {
+        use super::*;
+
+        #[test]
+        fn converts_legacy_variants() {
+            clear_strategies();
+            let user = fake_principal(4);
+
+            add_if_not_exists(strategy_with_user(Box::new(GenericStrategy::new(1)), Some(user)));
+            STRATEGIES.with(|s| {
+                s.borrow_mut().insert(2, StrategyCandid::ICPStrategyV(GenericStrategy::new(2)));
+            });
+
+            migrate_legacy_strategies();
+
+            let candids: Vec<StrategyCandid> = STRATEGIES.with(|s| s.borrow().values().collect());
+            assert_eq!(candids.len(), 2);
+            assert!(candids.iter().all(|candid| !candid.is_legacy()));
+
+            let strategy = get_strategy_by_id(1).unwrap();
+            assert_eq!(strategy.get_user_shares().get(&Account::from(user)), Some(&Nat::from(100u64)));
+        }
+
+        #[test]
+        fn decodes_legacy_record_with_base_token() {
+            #[derive(CandidType)]
+            enum LegacyStrategyCandid {
+                IcsStrategyV(LegacyStrategy),
+            }
+
+            #[derive(CandidType)]
+            struct LegacyStrategy {
+                id: StrategyId,
+                base_token: Principal,
+                current_pool: Option<types::strategies::Pool>,
+                position_id: Option<u64>,
+                total_balance: Nat,
+                total_shares: Nat,
+                user_shares: HashMap<Principal, Nat>,
+                initial_deposit: HashMap<Principal, Nat>,
+                current_liquidity: Option<Nat>,
+                current_liquidity_updated_at: Option<u64>,
+                enabled: bool,
+            }
+
+            let bytes = candid::encode_one(LegacyStrategyCandid::IcsStrategyV(LegacyStrategy {
+                id: 5,
+                base_token: fake_principal(5),
+                current_pool: None,
+                position_id: Some(7),
+                total_balance: Nat::from(10u64),
+                total_shares: Nat::from(10u64),
+                user_shares: HashMap::new(),
+                initial_deposit: HashMap::new(),
+                current_liquidity: None,
+                current_liquidity_updated_at: None,
+                enabled: true,
+            })).unwrap();
+
+            let candid: StrategyCandid = candid::decode_one(&bytes).unwrap();
+            assert!(candid.is_legacy());
+
+            let strategy = candid.to_strategy();
+            assert_eq!(strategy.get_id(), 5);
+            assert_eq!(strategy.get_position_id(), Some(7));
+            assert!(strategy.get_enabled());
+        }
+    }
 }
@@ -0,0 +1,114 @@
+use std::cell::RefCell;
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+use ic_stable_structures::StableBTreeMap;
+
+use types::CanisterId;
+use types::strategies::{StrategyId, Pool};
+
+use crate::impl_candid_storable;
+use crate::repository::memory::{self, Memory};
+
+/// Metadata and candidate pools of a strategy.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct StrategyDefinition {
+    pub name: String,
+    pub description: String,
+    pub base_token: CanisterId,
+    pub pools: Vec<Pool>,
+}
+
+impl_candid_storable!(StrategyDefinition);
+
+thread_local! {
+    static STRATEGY_DEFINITIONS: RefCell<StableBTreeMap<StrategyId, StrategyDefinition, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::STRATEGY_DEFINITIONS_MEMORY_ID))
+    );
+}
+
+pub fn get_strategy_definition(strategy_id: StrategyId) -> Option<StrategyDefinition> {
+    STRATEGY_DEFINITIONS.with(|definitions| definitions.borrow().get(&strategy_id))
+}
+
+pub fn get_strategy_definitions() -> Vec<(StrategyId, StrategyDefinition)> {
+    STRATEGY_DEFINITIONS.with(|definitions| definitions.borrow().iter().collect())
+}
+
+pub fn save_strategy_definition(strategy_id: StrategyId, definition: StrategyDefinition) {
+    STRATEGY_DEFINITIONS.with(|definitions| {
+        definitions.borrow_mut().insert(strategy_id, definition);
+    });
+}
+
+pub fn add_if_not_exists(strategy_id: StrategyId, definition: StrategyDefinition) {
+    if get_strategy_definition(strategy_id).is_none() {
+        save_strategy_definition(strategy_id, definition);
+    }
+}
+
+/// Returns the id following the highest defined strategy id.
+pub fn next_strategy_id() -> StrategyId {
+    STRATEGY_DEFINITIONS.with(|definitions| {
+        definitions.borrow()
+            .last_key_value()
+            .map_or(1, |(strategy_id, _)| strategy_id + 1)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+
+    use types::exchange_id::ExchangeId;
+    use types::pool::PoolTrait;
+
+    use super::*;
+
+    fn clear_definitions() {
+        STRATEGY_DEFINITIONS.with(|definitions| definitions.borrow_mut().clear_new());
+    }
+
+    fn definition(name: &str) -> StrategyDefinition {
+        let token0 = Principal::from_slice(&[1; 29]);
+        let token1 = Principal::from_slice(&[2; 29]);
+
+        StrategyDefinition {
+            name: name.to_string(),
+            description: "description".to_string(),
+            base_token: token0,
+            pools: vec![Pool::build(token0, token1, ExchangeId::KongSwap)],
+        }
+    }
+
+    mod add_if_not_exists {
+        use super::*;
+
+        #[test]
+        fn keeps_existing_definition() {
+            clear_definitions();
+
+            save_strategy_definition(1, definition("saved"));
+            add_if_not_exists(1, definition("default"));
+            add_if_not_exists(2, definition("default"));
+
+            assert_eq!(get_strategy_definition(1), Some(definition("saved")));
+            assert_eq!(get_strategy_definition(2), Some(definition("default")));
+        }
+    }
+
+    mod next_strategy_id {
+        use super::*;
+
+        #[test]
+        fn returns_id_after_highest_defined() {
+            clear_definitions();
+
+            assert_eq!(next_strategy_id(), 1);
+
+            save_strategy_definition(3, definition("first"));
+            save_strategy_definition(10, definition("second"));
+
+            assert_eq!(next_strategy_id(), 11);
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use std::cell::RefCell;
+use candid::{CandidType, Deserialize};
+use serde::Serialize;
+use ic_stable_structures::StableBTreeMap;
+
+use types::CanisterId;
+
+use crate::impl_candid_storable;
+use crate::repository::memory::{self, Memory};
+
+/// USD price of a whole token, quoted in ckUSDT.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct TokenPrice {
+    pub usd_price: f64,
+    pub decimals: u8,
+    pub updated_at: u64,
+}
+
+impl_candid_storable!(TokenPrice);
+
+thread_local! {
+    static TOKEN_PRICES: RefCell<StableBTreeMap<CanisterId, TokenPrice, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::TOKEN_PRICES_MEMORY_ID))
+    );
+}
+
+pub fn get_token_price(token: CanisterId) -> Option<TokenPrice> {
+    TOKEN_PRICES.with(|prices| prices.borrow().get(&token))
+}
+
+pub fn save_token_price(token: CanisterId, price: TokenPrice) {
+    TOKEN_PRICES.with(|prices| {
+        prices.borrow_mut().insert(token, price);
+    });
+}
@@ -0,0 +1,82 @@
+use std::cell::RefCell;
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+use ic_stable_structures::StableBTreeMap;
+
+use types::CanisterId;
+use types::strategies::StrategyId;
+
+use crate::impl_candid_storable;
+use crate::repository::memory::{self, Memory};
+
+/// Base token parked in the vault after the positions of a strategy were exited in an emergency.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct StrategyUnwind {
+    pub strategy_id: StrategyId,
+    pub token: CanisterId,
+    /// Parked amount not redeemed yet
+    pub balance: Nat,
+    /// Shares not redeemed yet, they are redeemed pro-rata of the balance
+    pub shares: Nat,
+    /// Shares can be redeemed once every position was exited, until then the unwind can be resumed
+    pub completed: bool,
+    pub started_at: u64,
+    pub completed_at: Option<u64>,
+}
+
+impl_candid_storable!(StrategyUnwind);
+
+thread_local! {
+    static STRATEGY_UNWINDS: RefCell<StableBTreeMap<StrategyId, StrategyUnwind, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::STRATEGY_UNWINDS_MEMORY_ID))
+    );
+}
+
+pub fn get_strategy_unwind(strategy_id: StrategyId) -> Option<StrategyUnwind> {
+    STRATEGY_UNWINDS.with(|unwinds| unwinds.borrow().get(&strategy_id))
+}
+
+pub fn get_strategy_unwinds() -> Vec<StrategyUnwind> {
+    STRATEGY_UNWINDS.with(|unwinds| unwinds.borrow().values().collect())
+}
+
+pub fn save_strategy_unwind(unwind: StrategyUnwind) {
+    STRATEGY_UNWINDS.with(|unwinds| {
+        unwinds.borrow_mut().insert(unwind.strategy_id, unwind);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Principal;
+
+    fn unwind(strategy_id: StrategyId) -> StrategyUnwind {
+        StrategyUnwind {
+            strategy_id,
+            token: Principal::from_slice(&[1; 29]),
+            balance: Nat::from(1_000u64),
+            shares: Nat::from(100u64),
+            completed: false,
+            started_at: 0,
+            completed_at: None,
+        }
+    }
+
+    mod save_strategy_unwind {
+        use super::*;
+
+        #[test]
+        fn replaces_unwind_of_strategy() {
+            STRATEGY_UNWINDS.with(|unwinds| unwinds.borrow_mut().clear_new());
+
+            save_strategy_unwind(unwind(1));
+            save_strategy_unwind(StrategyUnwind { completed: true, ..unwind(1) });
+            save_strategy_unwind(unwind(2));
+
+            assert!(get_strategy_unwind(1).unwrap().completed);
+            assert_eq!(get_strategy_unwinds().len(), 2);
+            assert!(get_strategy_unwind(3).is_none());
+        }
+    }
+}
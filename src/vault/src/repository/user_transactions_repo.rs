@@ -0,0 +1,294 @@
+use std::cell::RefCell;
+use candid::{CandidType, Deserialize, Nat, Principal};
+use serde::Serialize;
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use ic_stable_structures::StableBTreeMap;
+
+use types::CanisterId;
+use types::context::CorrelationId;
+use types::strategies::StrategyId;
+
+use crate::impl_candid_storable;
+use crate::repository::memory::{self, Memory};
+use crate::types::types::{SortOrder, UserTransactionFilters};
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum UserTransactionKind {
+    /// Tokens transferred from the user to the vault
+    Deposit,
+    /// Shares credited for a deposit or for the strategy fees
+    SharesMinted,
+    /// Base token withdrawn from the strategy positions
+    Withdraw,
+    /// Shares burned for a withdrawal or a redemption
+    SharesBurned,
+    /// Tokens transferred from the vault to the user
+    Claim,
+    /// Shares received from another holder, the amount is the cost basis moved with them
+    SharesTransferredIn,
+    /// Shares sent to another holder, the amount is the cost basis moved with them
+    SharesTransferredOut,
+}
+
+/// Entry of the ledger of a user, the fields that don't apply to its kind are empty.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct UserTransaction {
+    /// Sequence number of the transaction within the user ledger
+    pub id: u64,
+    pub kind: UserTransactionKind,
+    pub strategy_id: StrategyId,
+    /// Subaccount of the user position, `None` for the default account and for older transactions
+    pub subaccount: Option<Subaccount>,
+    pub token: Option<CanisterId>,
+    pub amount: Option<Nat>,
+    pub shares: Option<Nat>,
+    /// Position value in base token the shares were priced against
+    pub nav: Option<Nat>,
+    /// Block of the token ledger transfer
+    pub block_index: Option<Nat>,
+    pub correlation_id: CorrelationId,
+    pub timestamp: u64,
+}
+
+impl_candid_storable!(UserTransaction);
+
+// User -> sequence number of the transaction within the user ledger
+type UserTransactionKey = (Principal, u64);
+
+thread_local! {
+    static USER_TRANSACTIONS: RefCell<StableBTreeMap<UserTransactionKey, UserTransaction, Memory>> = RefCell::new(
+        StableBTreeMap::init(memory::get_memory(memory::USER_TRANSACTIONS_MEMORY_ID))
+    );
+}
+
+/// Appends the transaction to the ledger of the user, its ID is the next sequence number.
+pub fn add_user_transaction(user: Principal, transaction: UserTransaction) -> UserTransaction {
+    let transaction = UserTransaction {
+        id: get_user_transactions_count(user),
+        ..transaction
+    };
+
+    USER_TRANSACTIONS.with(|transactions| {
+        transactions.borrow_mut().insert((user, transaction.id), transaction.clone());
+    });
+
+    transaction
+}
+
+pub fn get_user_transactions_count(user: Principal) -> u64 {
+    USER_TRANSACTIONS.with(|transactions| {
+        transactions.borrow()
+            .range((user, 0)..=(user, u64::MAX))
+            .next_back()
+            .map_or(0, |((_, sequence), _)| sequence + 1)
+    })
+}
+
+/// Returns a page of the user transactions matching the filters and the number of matching transactions.
+pub fn get_user_transactions(
+    user: Principal,
+    filters: &UserTransactionFilters,
+) -> (Vec<UserTransaction>, u64) {
+    let pagination = &filters.pagination;
+    let skip = pagination.page.saturating_sub(1).saturating_mul(pagination.page_size) as usize;
+    let limit = pagination.page_size as usize;
+
+    USER_TRANSACTIONS.with(|transactions| {
+        let transactions = transactions.borrow();
+        let matching = || transactions
+            .range((user, 0)..=(user, u64::MAX))
+            .map(|(_, transaction)| transaction)
+            .filter(|transaction| matches_filters(transaction, filters));
+
+        let total = matching().count() as u64;
+
+        // Transactions are stored in chronological order
+        let items = match pagination.sort_order {
+            SortOrder::Asc => matching().skip(skip).take(limit).collect(),
+            SortOrder::Desc => matching().rev().skip(skip).take(limit).collect(),
+        };
+
+        (items, total)
+    })
+}
+
+/// Returns every transaction of the account in a strategy, in chronological order.
+pub fn get_account_strategy_transactions(account: Account, strategy_id: StrategyId) -> Vec<UserTransaction> {
+    let user = account.owner;
+
+    USER_TRANSACTIONS.with(|transactions| {
+        transactions.borrow()
+            .range((user, 0)..=(user, u64::MAX))
+            .map(|(_, transaction)| transaction)
+            .filter(|transaction| transaction.strategy_id == strategy_id)
+            .filter(|transaction| Account { owner: user, subaccount: transaction.subaccount } == account)
+            .collect()
+    })
+}
+
+fn matches_filters(transaction: &UserTransaction, filters: &UserTransactionFilters) -> bool {
+    filters.kind.map_or(true, |kind| transaction.kind == kind)
+        && filters.strategy_id.map_or(true, |strategy_id| transaction.strategy_id == strategy_id)
+        && filters.from_timestamp.map_or(true, |from| transaction.timestamp >= from)
+        && filters.to_timestamp.map_or(true, |to| transaction.timestamp <= to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::types::ListItemsPaginationRequest;
+
+    fn clear_transactions() {
+        USER_TRANSACTIONS.with(|transactions| transactions.borrow_mut().clear_new());
+    }
+
+    fn fake_principal(id: u8) -> Principal {
+        Principal::from_slice(&[id; 29])
+    }
+
+    fn transaction(kind: UserTransactionKind, strategy_id: StrategyId, timestamp: u64) -> UserTransaction {
+        UserTransaction {
+            id: 0,
+            kind,
+            strategy_id,
+            subaccount: None,
+            token: Some(fake_principal(100)),
+            amount: Some(Nat::from(1_000u64)),
+            shares: None,
+            nav: None,
+            block_index: Some(Nat::from(7u64)),
+            correlation_id: "1".to_string(),
+            timestamp,
+        }
+    }
+
+    fn no_filters() -> UserTransactionFilters {
+        UserTransactionFilters {
+            kind: None,
+            strategy_id: None,
+            from_timestamp: None,
+            to_timestamp: None,
+            pagination: pagination(1, 10, SortOrder::Asc),
+        }
+    }
+
+    fn pagination(page: u64, page_size: u64, sort_order: SortOrder) -> ListItemsPaginationRequest {
+        ListItemsPaginationRequest {
+            page,
+            page_size,
+            sort_order,
+            search: None,
+        }
+    }
+
+    mod add_user_transaction {
+        use super::*;
+
+        #[test]
+        fn numbers_transactions_per_user() {
+            clear_transactions();
+
+            let user = fake_principal(1);
+            add_user_transaction(user, transaction(UserTransactionKind::Deposit, 1, 10));
+            let other = add_user_transaction(fake_principal(2), transaction(UserTransactionKind::Deposit, 1, 10));
+            let second = add_user_transaction(user, transaction(UserTransactionKind::SharesMinted, 1, 10));
+
+            assert_eq!(other.id, 0);
+            assert_eq!(second.id, 1);
+            assert_eq!(get_user_transactions_count(user), 2);
+        }
+    }
+
+    mod get_user_transactions {
+        use super::*;
+
+        #[test]
+        fn filters_by_kind_strategy_and_time() {
+            clear_transactions();
+
+            let user = fake_principal(1);
+            add_user_transaction(user, transaction(UserTransactionKind::Deposit, 1, 10));
+            add_user_transaction(user, transaction(UserTransactionKind::Deposit, 2, 20));
+            add_user_transaction(user, transaction(UserTransactionKind::Claim, 1, 30));
+            add_user_transaction(user, transaction(UserTransactionKind::Deposit, 1, 40));
+
+            let filters = UserTransactionFilters {
+                kind: Some(UserTransactionKind::Deposit),
+                strategy_id: Some(1),
+                from_timestamp: Some(15),
+                ..no_filters()
+            };
+
+            let (items, total) = get_user_transactions(user, &filters);
+
+            assert_eq!(total, 1);
+            assert_eq!(items[0].id, 3);
+        }
+
+        #[test]
+        fn paginates_newest_first() {
+            clear_transactions();
+
+            let user = fake_principal(1);
+            for timestamp in 0..5 {
+                add_user_transaction(user, transaction(UserTransactionKind::Deposit, 1, timestamp));
+            }
+
+            let filters = UserTransactionFilters {
+                pagination: pagination(2, 2, SortOrder::Desc),
+                ..no_filters()
+            };
+
+            let (items, total) = get_user_transactions(user, &filters);
+            let ids: Vec<u64> = items.into_iter().map(|transaction| transaction.id).collect();
+
+            assert_eq!(total, 5);
+            assert_eq!(ids, vec![2, 1]);
+        }
+
+        #[test]
+        fn returns_empty_page_past_the_end() {
+            clear_transactions();
+
+            let user = fake_principal(1);
+            add_user_transaction(user, transaction(UserTransactionKind::Deposit, 1, 0));
+
+            let filters = UserTransactionFilters {
+                pagination: pagination(u64::MAX, u64::MAX, SortOrder::Asc),
+                ..no_filters()
+            };
+
+            let (items, total) = get_user_transactions(user, &filters);
+
+            assert_eq!(total, 1);
+            assert!(items.is_empty());
+        }
+    }
+    mod get_account_strategy_transactions {
+        use super::*;
+
+        #[test]
+        fn keeps_transactions_of_the_account() {
+            clear_transactions();
+
+            let user = fake_principal(1);
+            let subaccount = Some([1; 32]);
+            add_user_transaction(user, transaction(UserTransactionKind::Deposit, 1, 10));
+            add_user_transaction(user, UserTransaction { subaccount, ..transaction(UserTransactionKind::Deposit, 1, 20) });
+            add_user_transaction(user, UserTransaction { subaccount: Some([0; 32]), ..transaction(UserTransactionKind::Deposit, 1, 30) });
+            add_user_transaction(user, transaction(UserTransactionKind::Deposit, 2, 40));
+
+            let default_ids: Vec<u64> = get_account_strategy_transactions(Account::from(user), 1)
+                .into_iter()
+                .map(|transaction| transaction.id)
+                .collect();
+            let subaccount_ids: Vec<u64> = get_account_strategy_transactions(Account { owner: user, subaccount }, 1)
+                .into_iter()
+                .map(|transaction| transaction.id)
+                .collect();
+
+            assert_eq!(default_ids, vec![0, 2]);
+            assert_eq!(subaccount_ids, vec![1]);
+        }
+    }
+}
@@ -1,4 +1,9 @@
+use candid::{Nat, Principal};
+use icrc_ledger_types::icrc1::account::Account;
+
+use ::types::CanisterId;
 use ::types::context::Context;
+use ::types::strategies::StrategyId;
 use errors::internal_error::error::{InternalError, InternalErrorKind};
 
 use errors::internal_error::error_codes::module::areas::{
@@ -16,8 +21,20 @@ errors::define_error_code_builder_fn!(
 );
 
 use crate::repository::strategies_repo;
+use crate::repository::rebalance_history_repo::{self, RebalanceRecord};
+use crate::repository::share_prices_repo::SharePricePoint;
 use crate::user::user_service;
+use crate::user::user_positions_service;
+use crate::user::pending_withdrawals_service;
+use crate::user::zap_service;
+use crate::repository::pending_withdrawals_repo::PendingWithdrawal;
+use crate::repository::user_transactions_repo::UserTransaction;
 use crate::strategies::strategy::IStrategy;
+use crate::strategies::preview_service;
+use crate::strategies::strategy_lock_service::{self, StrategyOperation};
+use crate::strategies::emergency_service;
+use crate::strategies::share_price_service;
+use crate::strategies::share_token_service;
 use crate::types::types::*;
 use crate::event_records::event_record::EventRecord;
 use crate::event_records::event_record_service;
@@ -26,7 +43,10 @@ use crate::event_records::event_record_service;
 ///
 /// # Arguments
 ///
-/// * `args` - An `StrategyDepositArgs` struct containing the ledger, amount, and strategy ID.
+/// * `args` - An `StrategyDepositArgs` struct containing the ledger, amount, strategy ID and,
+///   for a deposit in another token than the base token, the minimum base token amount to receive.
+///
+/// A deposit in another token is swapped to the base token of the strategy before the shares are credited.
 ///
 /// # Returns
 ///
@@ -35,13 +55,23 @@ use crate::event_records::event_record_service;
 ///
 /// # Errors
 ///
-/// Returns a `InternalError` if the strategy is not found or if the deposit operation fails.
+/// Returns a `InternalError` if the strategy is not found, disabled or its deposits are paused,
+/// if another operation of the strategy or the caller is in progress, if the deposit token
+/// can't be swapped to the base token for the minimum amount or if the deposit operation fails.
 pub async fn deposit(
     context: Context,
     args: StrategyDepositArgs
 ) -> Result<StrategyDepositResponse, InternalError> {
     let strategy_id = context.strategy_id.unwrap();
 
+    emergency_service::check_operation_allowed(strategy_id, StrategyOperation::Deposit)?;
+
+    let _lock = strategy_lock_service::lock_strategy(
+        strategy_id,
+        context.user,
+        StrategyOperation::Deposit,
+    )?;
+
     let mut strategy = get_strategy_by_id(strategy_id)
         .ok_or_else(|| {
             InternalError::not_found(
@@ -54,19 +84,92 @@ pub async fn deposit(
                 },
             )
         })?;
-    
-    // TODO: Add validation for ledger
-    // if args.ledger != strategy.get_base_token() {}
+
+    if !strategy.get_enabled() {
+        return Err(InternalError::business_logic(
+            build_error_code(InternalErrorKind::BusinessLogic, 17), // Error code: "03-01-01 03 17"
+            "service::deposit".to_string(),
+            "Strategy is disabled".to_string(),
+            errors::error_extra! {
+                "context" => context,
+                "args" => args,
+            },
+        ));
+    }
+
+    let base_token = strategy.get_base_token();
+
+    // Deposits in another token are swapped to the base token,
+    // they are rejected before being accepted if they can't be
+    let zap_quote = if args.ledger != base_token {
+        Some(zap_service::quote_zap(
+            context.clone(),
+            args.ledger,
+            base_token,
+            args.amount.clone(),
+            args.min_amount_out.clone(),
+        ).await?)
+    } else {
+        None
+    };
 
     user_service::accept_deposit(context.clone(), args.amount.clone(), args.ledger).await?;
-    strategy.deposit(context.clone(), args.amount.clone()).await
+
+    // Refunds after a failure are limited to what is left of the deposit in the vault balance
+    let deposit_amount = match zap_quote {
+        Some(zap_quote) => {
+            let balance_before = pending_withdrawals_service::get_vault_balance(args.ledger).await;
+
+            match zap_service::zap(context.clone(), zap_quote).await {
+                Ok(amount) => amount,
+                Err(failure) => {
+                    if failure.ledger == args.ledger {
+                        pending_withdrawals_service::refund_verified_deposit(
+                            &context,
+                            failure.ledger,
+                            args.amount.clone(),
+                            failure.amount,
+                            balance_before,
+                        ).await;
+                    } else {
+                        // Swapped tokens were received by the vault
+                        pending_withdrawals_service::refund_deposit(&context, failure.ledger, failure.amount).await;
+                    }
+
+                    return Err(failure.error);
+                }
+            }
+        }
+        None => args.amount.clone(),
+    };
+
+    let balance_before = pending_withdrawals_service::get_vault_balance(base_token).await;
+    let result = strategy.deposit(context.clone(), deposit_amount.clone()).await;
+
+    match result.as_ref() {
+        // The shares of the position can be transferred from its share account
+        Ok(_) => {
+            share_token_service::register_share_account(strategy_id, context.account().unwrap());
+        }
+        Err(_) => {
+            pending_withdrawals_service::refund_verified_deposit(
+                &context,
+                base_token,
+                deposit_amount.clone(),
+                deposit_amount,
+                balance_before,
+            ).await;
+        }
+    }
+
+    result
 }
 
 /// Withdraws an amount from a specified strategy.
 ///
 /// # Arguments
 ///
-/// * `args` - A `StrategyWithdrawArgs` struct containing the ledger, amount, and strategy ID.
+/// * `args` - A `StrategyWithdrawArgs` struct containing the ledger, amount, strategy ID and optional output token.
 ///
 /// # Returns
 ///
@@ -75,12 +178,22 @@ pub async fn deposit(
 ///
 /// # Errors
 ///
-/// Returns a `InternalError` if the strategy is not found or if the withdrawal operation fails.
+/// Returns a `InternalError` if the strategy is not found or its withdrawals are paused,
+/// if another operation of the strategy or the caller is in progress or if the withdrawal operation fails.
 pub async fn withdraw(
     context: Context,
     args: StrategyWithdrawArgs
 ) -> Result<StrategyWithdrawResponse, InternalError> {
     let strategy_id = context.strategy_id.unwrap();
+
+    emergency_service::check_operation_allowed(strategy_id, StrategyOperation::Withdraw)?;
+
+    let _lock = strategy_lock_service::lock_strategy(
+        strategy_id,
+        context.user,
+        StrategyOperation::Withdraw,
+    )?;
+
     let mut strategy = get_strategy_by_id(strategy_id)
         .ok_or_else(|| {
             InternalError::not_found(
@@ -94,7 +207,86 @@ pub async fn withdraw(
             )
         })?;
 
-    strategy.withdraw(context.clone(), args.percentage.clone()).await
+    // Withdrawn tokens go back to the account holding the position unless another one is given
+    let destination = args.to.unwrap_or(context.account().unwrap());
+
+    strategy.withdraw(
+        context.clone(),
+        args.percentage.clone(),
+        args.output_token,
+        args.min_amount_out.clone(),
+        destination,
+    ).await
+}
+
+/// Estimates the shares a deposit into a strategy would get, without depositing.
+pub async fn preview_deposit(
+    strategy_id: StrategyId,
+    ledger: CanisterId,
+    amount: Nat,
+) -> Result<StrategyDepositPreview, InternalError> {
+    let strategy = get_strategy_by_id(strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(InternalErrorKind::NotFound, 15), // Error code: "03-01-01 01 15"
+                "service::preview_deposit".to_string(),
+                "Strategy not found".to_string(),
+                errors::error_extra! {
+                    "strategy_id" => strategy_id,
+                },
+            )
+        })?;
+
+    preview_service::preview_deposit(strategy.as_ref(), ledger, amount).await
+}
+
+/// Estimates the amount a withdrawal of a percentage of the account shares would return, without withdrawing.
+pub async fn preview_withdraw(
+    account: Account,
+    strategy_id: StrategyId,
+    percentage: Nat,
+) -> Result<StrategyWithdrawPreview, InternalError> {
+    let strategy = get_strategy_by_id(strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(InternalErrorKind::NotFound, 16), // Error code: "03-01-01 01 16"
+                "service::preview_withdraw".to_string(),
+                "Strategy not found".to_string(),
+                errors::error_extra! {
+                    "strategy_id" => strategy_id,
+                },
+            )
+        })?;
+
+    preview_service::preview_withdraw(strategy.as_ref(), account, percentage).await
+}
+
+// ========================== Pending withdrawals ==========================
+
+pub async fn claim_pending_withdrawals(user: Principal) -> Vec<PendingWithdrawal> {
+    pending_withdrawals_service::claim_pending_withdrawals(user).await
+}
+
+pub fn get_pending_withdrawals(user: Principal) -> Vec<PendingWithdrawal> {
+    pending_withdrawals_service::get_user_pending_withdrawals(user)
+}
+
+// ========================== User transactions ==========================
+
+pub fn get_user_transactions(
+    user: Principal,
+    filters: UserTransactionFilters,
+) -> Result<ListItemsPaginationResponse<UserTransaction>, InternalError> {
+    Ok(user_service::get_user_transactions(user, filters))
+}
+
+// ========================== User positions ==========================
+
+pub fn get_user_positions(
+    user: Principal,
+    pagination: ListItemsPaginationRequest,
+) -> Result<ListItemsPaginationResponse<UserPositionResponse>, InternalError> {
+    Ok(user_positions_service::get_user_positions(user, pagination))
 }
 
 // ========================== Event records ==========================
@@ -112,6 +304,91 @@ pub fn get_event_records(
     })
 }
 
+// ========================== Rebalance history ==========================
+
+pub fn get_strategy_rebalance_history(
+    strategy_id: StrategyId,
+    request: ListItemsPaginationRequest
+) -> Result<ListItemsPaginationResponse<RebalanceRecord>, InternalError> {
+    if get_strategy_by_id(strategy_id).is_none() {
+        return Err(InternalError::not_found(
+            build_error_code(InternalErrorKind::NotFound, 8), // Error code: "03-01-01 01 08"
+            "service::get_strategy_rebalance_history".to_string(),
+            "Strategy not found".to_string(),
+            errors::error_extra! {
+                "strategy_id" => strategy_id,
+            },
+        ));
+    }
+
+    let records = rebalance_history_repo::get_rebalance_records(strategy_id, request.clone());
+
+    Ok(ListItemsPaginationResponse {
+        items: records,
+        total: rebalance_history_repo::get_rebalance_records_count(strategy_id),
+        page: request.page,
+        page_size: request.page_size,
+    })
+}
+
+// ========================== Share price history ==========================
+
+/// Retrieves a page of the share price series of a strategy, with the last point of every interval.
+pub fn get_share_price_history(
+    strategy_id: StrategyId,
+    from_timestamp: u64,
+    to_timestamp: u64,
+    interval_secs: u64,
+    pagination: ListItemsPaginationRequest,
+) -> Result<ListItemsPaginationResponse<SharePricePoint>, InternalError> {
+    if get_strategy_by_id(strategy_id).is_none() {
+        return Err(InternalError::not_found(
+            build_error_code(InternalErrorKind::NotFound, 18), // Error code: "03-01-01 01 18"
+            "service::get_share_price_history".to_string(),
+            "Strategy not found".to_string(),
+            errors::error_extra! {
+                "strategy_id" => strategy_id,
+            },
+        ));
+    }
+
+    if from_timestamp > to_timestamp {
+        return Err(InternalError::validation(
+            build_error_code(InternalErrorKind::Validation, 19), // Error code: "03-01-01 02 19"
+            "service::get_share_price_history".to_string(),
+            "From timestamp is after to timestamp".to_string(),
+            errors::error_extra! {
+                "strategy_id" => strategy_id,
+                "from_timestamp" => from_timestamp,
+                "to_timestamp" => to_timestamp,
+            },
+        ));
+    }
+
+    if pagination.page_size == 0 || pagination.page_size > share_price_service::MAX_SHARE_PRICE_HISTORY_PAGE_SIZE {
+        return Err(InternalError::validation(
+            build_error_code(InternalErrorKind::Validation, 22), // Error code: "03-01-01 02 22"
+            "service::get_share_price_history".to_string(),
+            format!(
+                "Page size must be between 1 and {}",
+                share_price_service::MAX_SHARE_PRICE_HISTORY_PAGE_SIZE,
+            ),
+            errors::error_extra! {
+                "strategy_id" => strategy_id,
+                "page_size" => pagination.page_size,
+            },
+        ));
+    }
+
+    Ok(share_price_service::get_share_price_history(
+        strategy_id,
+        from_timestamp,
+        to_timestamp,
+        interval_secs,
+        pagination,
+    ))
+}
+
 /// Retrieves a strategy by its ID.
 ///
 /// # Arguments
@@ -0,0 +1,382 @@
+use candid::Nat;
+
+use types::exchange_id::ExchangeId;
+use types::pool::PoolTrait;
+use types::strategies::{Pool, StrategyAllocation, StrategyId};
+
+/// Sum of the target weights of a strategy
+pub const TOTAL_WEIGHT_BPS: u32 = 10_000;
+
+/// Returns the allocation with the largest target weight, the first one if several have the same weight.
+pub fn get_primary_allocation(allocations: &[StrategyAllocation]) -> Option<&StrategyAllocation> {
+    allocations.iter().reduce(|primary, allocation| {
+        if allocation.target_weight_bps > primary.target_weight_bps { allocation } else { primary }
+    })
+}
+
+/// Splits an amount between the allocations according to their target weights.
+///
+/// The rounding remainder goes to the last allocation with a weight,
+/// so the parts always sum up to the amount.
+pub fn split_amount(amount: &Nat, allocations: &[StrategyAllocation]) -> Vec<Nat> {
+    let total_weight: u64 = allocations.iter()
+        .map(|allocation| allocation.target_weight_bps as u64)
+        .sum();
+    let last_weighted_index = allocations.iter()
+        .rposition(|allocation| allocation.target_weight_bps > 0);
+
+    let mut allocated = Nat::from(0u64);
+
+    allocations.iter()
+        .enumerate()
+        .map(|(index, allocation)| {
+            let part = if Some(index) == last_weighted_index {
+                amount.clone() - allocated.clone()
+            } else if total_weight == 0 {
+                Nat::from(0u64)
+            } else {
+                amount.clone() * Nat::from(allocation.target_weight_bps) / Nat::from(total_weight)
+            };
+
+            allocated += part.clone();
+            part
+        })
+        .collect()
+}
+
+/// Moves `weight_bps` of target weight from the source pool to the target pool.
+///
+/// The source allocation is removed once its whole weight is moved,
+/// the target allocation is added if the strategy had no liquidity in the pool.
+pub fn shift_weight(
+    allocations: &[StrategyAllocation],
+    source_pool: &Pool,
+    target_pool: &Pool,
+    weight_bps: u32,
+    target_position_id: u64,
+    target_lp_amount: Option<Nat>,
+) -> Vec<StrategyAllocation> {
+    let mut allocations: Vec<StrategyAllocation> = allocations.iter()
+        .cloned()
+        .map(|mut allocation| {
+            if allocation.pool.is_same_pool(source_pool) {
+                allocation.target_weight_bps = allocation.target_weight_bps.saturating_sub(weight_bps);
+            }
+            allocation
+        })
+        .filter(|allocation| allocation.target_weight_bps > 0)
+        .collect();
+
+    match allocations.iter_mut().find(|allocation| allocation.pool.is_same_pool(target_pool)) {
+        Some(allocation) => {
+            allocation.target_weight_bps += weight_bps;
+            record_added_liquidity(allocation, target_position_id, target_lp_amount);
+        }
+        None => allocations.push(StrategyAllocation {
+            pool: target_pool.clone(),
+            position_id: Some(target_position_id),
+            target_weight_bps: weight_bps,
+            lp_amount: target_lp_amount,
+        }),
+    }
+
+    allocations
+}
+
+/// Records the liquidity added to the pool of the allocation.
+///
+/// KongSwap allocations invested before the LP tokens were accounted per strategy
+/// keep owning the whole LP balance of the vault, so their LP amount stays unknown.
+pub fn record_added_liquidity(allocation: &mut StrategyAllocation, position_id: u64, lp_amount: Option<Nat>) {
+    allocation.lp_amount = match (allocation.position_id, allocation.lp_amount.take(), lp_amount) {
+        (None, _, added) => added,
+        (Some(_), Some(owned), Some(added)) => Some(owned + added),
+        (Some(_), owned, _) => owned,
+    };
+    allocation.position_id = Some(position_id);
+}
+
+/// Records the LP tokens burned by a withdrawal from the pool of the allocation.
+pub fn record_withdrawn_liquidity(allocation: &mut StrategyAllocation, lp_amount: Option<Nat>) {
+    if let (Some(owned), Some(burned)) = (allocation.lp_amount.as_mut(), lp_amount) {
+        *owned = if *owned > burned { owned.clone() - burned } else { Nat::from(0u64) };
+    }
+}
+
+/// Returns the strategy holding the position of the allocation among the other strategies.
+///
+/// ICPSwap positions are shared if they have the same position id. KongSwap LP tokens
+/// are fungible, so a pool is shared if the LP tokens of one of the strategies are not accounted.
+pub fn find_position_owner(
+    allocation: &StrategyAllocation,
+    other_strategies: &[(StrategyId, Vec<StrategyAllocation>)],
+) -> Option<StrategyId> {
+    other_strategies.iter()
+        .find(|(_, other_allocations)| other_allocations.iter()
+            .any(|other_allocation| is_sharing_position(allocation, other_allocation)))
+        .map(|(strategy_id, _)| *strategy_id)
+}
+
+fn is_sharing_position(allocation: &StrategyAllocation, other_allocation: &StrategyAllocation) -> bool {
+    if other_allocation.position_id.is_none() || !allocation.pool.is_same_pool(&other_allocation.pool) {
+        return false;
+    }
+
+    match allocation.pool.provider {
+        ExchangeId::KongSwap => is_unaccounted_lp(allocation) || is_unaccounted_lp(other_allocation),
+        _ => allocation.position_id == other_allocation.position_id,
+    }
+}
+
+fn is_unaccounted_lp(allocation: &StrategyAllocation) -> bool {
+    allocation.position_id.is_some() && allocation.lp_amount.is_none()
+}
+
+/// Shares to burn for a withdrawal that unwound only the first `unwound_count` allocations.
+///
+/// The shares are split by the allocation values if they are known and by the target weights otherwise.
+pub fn calculate_unwound_shares(
+    shares: &Nat,
+    allocations: &[StrategyAllocation],
+    allocation_values: Option<&[Nat]>,
+    unwound_count: usize,
+) -> Nat {
+    let values: Vec<Nat> = match allocation_values {
+        Some(values) if values.len() == allocations.len() => values.to_vec(),
+        _ => allocations.iter()
+            .map(|allocation| Nat::from(allocation.target_weight_bps))
+            .collect(),
+    };
+
+    let total_value = values.iter().fold(Nat::from(0u64), |sum, value| sum + value.clone());
+    let unwound_value = values.iter()
+        .take(unwound_count)
+        .fold(Nat::from(0u64), |sum, value| sum + value.clone());
+
+    if total_value == 0u64 {
+        return Nat::from(0u64);
+    }
+
+    shares.clone() * unwound_value / total_value
+}
+
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+
+    use super::*;
+
+    fn pool(token1: u8) -> Pool {
+        Pool::build(Principal::from_slice(&[1; 29]), Principal::from_slice(&[token1; 29]), ExchangeId::KongSwap)
+    }
+
+    fn icpswap_pool(token1: u8) -> Pool {
+        Pool::build(Principal::from_slice(&[1; 29]), Principal::from_slice(&[token1; 29]), ExchangeId::ICPSwap)
+    }
+
+    fn allocation(token1: u8, target_weight_bps: u32) -> StrategyAllocation {
+        StrategyAllocation {
+            pool: pool(token1),
+            position_id: Some(token1 as u64),
+            target_weight_bps,
+            lp_amount: Some(Nat::from(100u64)),
+        }
+    }
+
+    mod get_primary_allocation {
+        use super::*;
+
+        #[test]
+        fn returns_largest_weight() {
+            let allocations = vec![allocation(2, 2_500), allocation(3, 5_000), allocation(4, 2_500)];
+
+            assert_eq!(get_primary_allocation(&allocations), Some(&allocations[1]));
+        }
+
+        #[test]
+        fn returns_first_on_tie() {
+            let allocations = vec![allocation(2, 5_000), allocation(3, 5_000)];
+
+            assert_eq!(get_primary_allocation(&allocations), Some(&allocations[0]));
+            assert_eq!(get_primary_allocation(&[]), None);
+        }
+    }
+
+    mod split_amount {
+        use super::*;
+
+        #[test]
+        fn splits_by_weights() {
+            let allocations = vec![allocation(2, 7_000), allocation(3, 3_000)];
+
+            let parts = split_amount(&Nat::from(1_000u64), &allocations);
+
+            assert_eq!(parts, vec![Nat::from(700u64), Nat::from(300u64)]);
+        }
+
+        #[test]
+        fn gives_remainder_to_last_weighted_allocation() {
+            let allocations = vec![allocation(2, 3_333), allocation(3, 6_667), allocation(4, 0)];
+
+            let parts = split_amount(&Nat::from(100u64), &allocations);
+
+            assert_eq!(parts, vec![Nat::from(33u64), Nat::from(67u64), Nat::from(0u64)]);
+        }
+    }
+
+    mod shift_weight {
+        use super::*;
+
+        #[test]
+        fn adds_target_allocation() {
+            let allocations = vec![allocation(2, 10_000)];
+
+            let shifted = shift_weight(&allocations, &pool(2), &pool(3), 4_000, 30, Some(Nat::from(40u64)));
+
+            assert_eq!(shifted, vec![
+                allocation(2, 6_000),
+                StrategyAllocation {
+                    pool: pool(3),
+                    position_id: Some(30),
+                    target_weight_bps: 4_000,
+                    lp_amount: Some(Nat::from(40u64)),
+                },
+            ]);
+        }
+
+        #[test]
+        fn removes_fully_shifted_source() {
+            let allocations = vec![allocation(2, 5_000), allocation(3, 5_000)];
+
+            let shifted = shift_weight(&allocations, &pool(2), &pool(3), 5_000, 30, Some(Nat::from(50u64)));
+
+            assert_eq!(shifted, vec![
+                StrategyAllocation {
+                    pool: pool(3),
+                    position_id: Some(30),
+                    target_weight_bps: 10_000,
+                    lp_amount: Some(Nat::from(150u64)),
+                },
+            ]);
+        }
+    }
+
+    mod record_added_liquidity {
+        use super::*;
+
+        #[test]
+        fn sets_lp_amount_of_new_position() {
+            let mut new_allocation = StrategyAllocation { position_id: None, lp_amount: None, ..allocation(2, 10_000) };
+
+            record_added_liquidity(&mut new_allocation, 7, Some(Nat::from(40u64)));
+
+            assert_eq!(new_allocation.position_id, Some(7));
+            assert_eq!(new_allocation.lp_amount, Some(Nat::from(40u64)));
+        }
+
+        #[test]
+        fn adds_lp_amount_to_owned() {
+            let mut owned_allocation = allocation(2, 10_000);
+
+            record_added_liquidity(&mut owned_allocation, 7, Some(Nat::from(40u64)));
+
+            assert_eq!(owned_allocation.lp_amount, Some(Nat::from(140u64)));
+        }
+
+        #[test]
+        fn keeps_unaccounted_lp_amount() {
+            let mut legacy_allocation = StrategyAllocation { lp_amount: None, ..allocation(2, 10_000) };
+
+            record_added_liquidity(&mut legacy_allocation, 7, Some(Nat::from(40u64)));
+
+            assert_eq!(legacy_allocation.lp_amount, None);
+        }
+    }
+
+    mod record_withdrawn_liquidity {
+        use super::*;
+
+        #[test]
+        fn subtracts_burned_lp_amount() {
+            let mut owned_allocation = allocation(2, 10_000);
+
+            record_withdrawn_liquidity(&mut owned_allocation, Some(Nat::from(30u64)));
+
+            assert_eq!(owned_allocation.lp_amount, Some(Nat::from(70u64)));
+        }
+
+        #[test]
+        fn stops_at_zero() {
+            let mut owned_allocation = allocation(2, 10_000);
+
+            record_withdrawn_liquidity(&mut owned_allocation, Some(Nat::from(130u64)));
+
+            assert_eq!(owned_allocation.lp_amount, Some(Nat::from(0u64)));
+        }
+    }
+
+    mod find_position_owner {
+        use super::*;
+
+        fn icpswap_allocation(position_id: Option<u64>) -> StrategyAllocation {
+            StrategyAllocation {
+                pool: icpswap_pool(2),
+                position_id,
+                target_weight_bps: 10_000,
+                lp_amount: None,
+            }
+        }
+
+        #[test]
+        fn finds_same_icpswap_position() {
+            let others = vec![
+                (1, vec![icpswap_allocation(Some(5))]),
+                (2, vec![icpswap_allocation(Some(7))]),
+            ];
+
+            assert_eq!(find_position_owner(&icpswap_allocation(Some(7)), &others), Some(2));
+            assert_eq!(find_position_owner(&icpswap_allocation(Some(9)), &others), None);
+            assert_eq!(find_position_owner(&icpswap_allocation(None), &others), None);
+        }
+
+        #[test]
+        fn allows_accounted_kongswap_lp() {
+            let others = vec![(1, vec![allocation(2, 10_000)])];
+
+            assert_eq!(find_position_owner(&allocation(2, 10_000), &others), None);
+        }
+
+        #[test]
+        fn finds_unaccounted_kongswap_lp() {
+            let legacy_allocation = StrategyAllocation { lp_amount: None, ..allocation(2, 10_000) };
+            let new_allocation = StrategyAllocation { position_id: None, lp_amount: None, ..allocation(2, 10_000) };
+
+            assert_eq!(find_position_owner(&legacy_allocation, &[(1, vec![allocation(2, 10_000)])]), Some(1));
+            assert_eq!(find_position_owner(&new_allocation, &[(1, vec![legacy_allocation.clone()])]), Some(1));
+            assert_eq!(find_position_owner(&legacy_allocation, &[(1, vec![allocation(3, 10_000)])]), None);
+        }
+    }
+
+    mod calculate_unwound_shares {
+        use super::*;
+
+        #[test]
+        fn splits_by_values() {
+            let allocations = vec![allocation(2, 5_000), allocation(3, 5_000)];
+            let values = vec![Nat::from(300u64), Nat::from(100u64)];
+
+            let shares = calculate_unwound_shares(&Nat::from(100u64), &allocations, Some(&values), 1);
+
+            assert_eq!(shares, Nat::from(75u64));
+        }
+
+        #[test]
+        fn falls_back_to_weights() {
+            let allocations = vec![allocation(2, 2_000), allocation(3, 8_000)];
+
+            let shares = calculate_unwound_shares(&Nat::from(100u64), &allocations, None, 1);
+
+            assert_eq!(shares, Nat::from(20u64));
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use std::collections::HashMap;
-use candid::{Nat, Principal};
+use candid::Nat;
+use icrc_ledger_types::icrc1::account::Account;
 
 use types::CanisterId;
-use types::strategies::StrategyId;
+use types::strategies::{StrategyId, StrategyProfile, StrategyParamsOverrides, StrategyFees, StrategyAllocation};
 
 use ::types::strategies::Pool;
 
@@ -18,20 +19,30 @@ pub trait BasicStrategy {
     fn set_total_balance(&mut self, total_balance: Nat);
     fn get_current_pool(&self) -> Option<Pool>;
     fn set_current_pool(&mut self, pool: Option<Pool>);
-    fn get_user_shares(&self) -> HashMap<Principal, Nat>;
-    fn set_user_shares(&mut self, user_shares: HashMap<Principal, Nat>);
-    fn get_initial_deposit(&self) -> HashMap<Principal, Nat>;
-    fn set_initial_deposit(&mut self, map: HashMap<Principal, Nat>);
+    fn get_user_shares(&self) -> HashMap<Account, Nat>;
+    fn set_user_shares(&mut self, user_shares: HashMap<Account, Nat>);
+    fn get_initial_deposit(&self) -> HashMap<Account, Nat>;
+    fn set_initial_deposit(&mut self, map: HashMap<Account, Nat>);
     fn get_users_count(&self) -> u32;
-    fn get_user_shares_by_principal(&self, principal: Principal) -> Nat;
+    fn get_user_shares_by_account(&self, account: Account) -> Nat;
     fn get_position_id(&self) -> Option<u64>;
     fn set_position_id(&mut self, position_id: Option<u64>);
+    fn get_allocations(&self) -> Vec<StrategyAllocation>;
+    fn set_allocations(&mut self, allocations: Vec<StrategyAllocation>);
+    fn get_uninvested_balance(&self) -> Nat;
+    fn set_uninvested_balance(&mut self, uninvested_balance: Nat);
     fn get_current_liquidity(&self) -> Option<Nat>;
     fn set_current_liquidity(&mut self, current_liquidity: Option<Nat>);
     fn get_current_liquidity_updated_at(&self) -> Option<u64>;
     fn set_current_liquidity_updated_at(&mut self, current_liquidity_updated_at: Option<u64>);
     fn get_enabled(&self) -> bool;
     fn set_enabled(&mut self, enabled: bool);
+    fn get_profile(&self) -> StrategyProfile;
+    fn set_profile(&mut self, profile: StrategyProfile);
+    fn get_params_overrides(&self) -> StrategyParamsOverrides;
+    fn set_params_overrides(&mut self, params_overrides: StrategyParamsOverrides);
+    fn get_fees(&self) -> StrategyFees;
+    fn set_fees(&mut self, fees: StrategyFees);
 }
 
 #[macro_export]
@@ -40,7 +51,7 @@ macro_rules! impl_strategy_methods {
         #[async_trait]
         impl BasicStrategy for $type {
             fn get_name(&self) -> String {
-                STRATEGY_MAP.get(&self.id).unwrap().name.clone()
+                $crate::repository::strategy_definitions_repo::get_strategy_definition(self.id).unwrap().name
             }
 
             fn get_id(&self) -> StrategyId {
@@ -48,15 +59,15 @@ macro_rules! impl_strategy_methods {
             }
 
             fn get_description(&self) -> String {
-                STRATEGY_MAP.get(&self.id).unwrap().description.clone()
+                $crate::repository::strategy_definitions_repo::get_strategy_definition(self.id).unwrap().description
             }
 
             fn get_base_token(&self) -> CanisterId {
-                STRATEGY_MAP.get(&self.id).unwrap().base_token
+                $crate::repository::strategy_definitions_repo::get_strategy_definition(self.id).unwrap().base_token
             }
 
             fn get_pools(&self) -> Vec<Pool> {
-                STRATEGY_MAP.get(&self.id).unwrap().pools.clone()
+                $crate::repository::strategy_definitions_repo::get_strategy_definition(self.id).unwrap().pools
             }
 
             fn get_total_shares(&self) -> Nat {
@@ -83,28 +94,41 @@ macro_rules! impl_strategy_methods {
                 self.current_pool = pool;
             }
 
-            fn get_user_shares(&self) -> HashMap<Principal, Nat> {
-                self.user_shares.clone()
+            fn get_user_shares(&self) -> HashMap<Account, Nat> {
+                match &self.account_shares {
+                    Some(account_shares) => account_shares.clone(),
+                    // Strategies saved before the positions were kept per account hold them per principal
+                    None => self.user_shares.iter()
+                        .map(|(user, shares)| (Account::from(*user), shares.clone()))
+                        .collect(),
+                }
             }
 
-            fn set_user_shares(&mut self, user_shares: HashMap<Principal, Nat>) {
-                self.user_shares = user_shares;
+            fn set_user_shares(&mut self, user_shares: HashMap<Account, Nat>) {
+                self.user_shares = HashMap::new();
+                self.account_shares = Some(user_shares);
             }
 
-            fn get_initial_deposit(&self) -> HashMap<Principal, Nat> {
-                self.initial_deposit.clone()
+            fn get_initial_deposit(&self) -> HashMap<Account, Nat> {
+                match &self.account_initial_deposit {
+                    Some(account_initial_deposit) => account_initial_deposit.clone(),
+                    None => self.initial_deposit.iter()
+                        .map(|(user, initial_deposit)| (Account::from(*user), initial_deposit.clone()))
+                        .collect(),
+                }
             }
 
-            fn set_initial_deposit(&mut self, map: HashMap<Principal, Nat>) {
-                self.initial_deposit = map;
+            fn set_initial_deposit(&mut self, map: HashMap<Account, Nat>) {
+                self.initial_deposit = HashMap::new();
+                self.account_initial_deposit = Some(map);
             }
 
             fn get_users_count(&self) -> u32 {
-                self.user_shares.len() as u32
+                self.get_user_shares().len() as u32
             }
 
-            fn get_user_shares_by_principal(&self, principal: Principal) -> Nat {
-                self.user_shares.get(&principal).unwrap().clone()
+            fn get_user_shares_by_account(&self, account: Account) -> Nat {
+                self.get_user_shares().get(&account).cloned().unwrap_or(Nat::from(0u64))
             }
 
             fn get_position_id(&self) -> Option<u64> {
@@ -115,6 +139,39 @@ macro_rules! impl_strategy_methods {
                 self.position_id = position_id;
             }
 
+            fn get_allocations(&self) -> Vec<StrategyAllocation> {
+                match &self.allocations {
+                    Some(allocations) => allocations.clone(),
+                    // Strategies saved before the allocations were introduced hold a single position
+                    None => self.current_pool.clone()
+                        .map(|pool| vec![StrategyAllocation {
+                            pool,
+                            position_id: self.position_id,
+                            target_weight_bps: $crate::strategies::allocation_service::TOTAL_WEIGHT_BPS,
+                            lp_amount: None,
+                        }])
+                        .unwrap_or_default(),
+                }
+            }
+
+            fn set_allocations(&mut self, allocations: Vec<StrategyAllocation>) {
+                // The current pool and position id mirror the allocation with the largest weight
+                let primary_allocation = $crate::strategies::allocation_service::get_primary_allocation(&allocations)
+                    .cloned();
+
+                self.current_pool = primary_allocation.as_ref().map(|allocation| allocation.pool.clone());
+                self.position_id = primary_allocation.and_then(|allocation| allocation.position_id);
+                self.allocations = Some(allocations);
+            }
+
+            fn get_uninvested_balance(&self) -> Nat {
+                self.uninvested_balance.clone().unwrap_or(Nat::from(0u64))
+            }
+
+            fn set_uninvested_balance(&mut self, uninvested_balance: Nat) {
+                self.uninvested_balance = Some(uninvested_balance);
+            }
+
             fn get_current_liquidity(&self) -> Option<Nat> {
                 self.current_liquidity.clone()
             }
@@ -138,6 +195,30 @@ macro_rules! impl_strategy_methods {
             fn set_enabled(&mut self, enabled: bool) {
                 self.enabled = enabled;
             }
+
+            fn get_profile(&self) -> StrategyProfile {
+                self.profile.unwrap_or_default()
+            }
+
+            fn set_profile(&mut self, profile: StrategyProfile) {
+                self.profile = Some(profile);
+            }
+
+            fn get_params_overrides(&self) -> StrategyParamsOverrides {
+                self.params_overrides.clone().unwrap_or_default()
+            }
+
+            fn set_params_overrides(&mut self, params_overrides: StrategyParamsOverrides) {
+                self.params_overrides = Some(params_overrides);
+            }
+
+            fn get_fees(&self) -> StrategyFees {
+                self.fees.clone().unwrap_or_default()
+            }
+
+            fn set_fees(&mut self, fees: StrategyFees) {
+                self.fees = Some(fees);
+            }
         }
     };
 }
\ No newline at end of file
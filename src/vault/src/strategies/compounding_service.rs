@@ -0,0 +1,591 @@
+use std::cell::RefCell;
+use std::time::Duration;
+use candid::Nat;
+use ic_cdk_timers::TimerId;
+
+use types::context::Context;
+use types::exchange_id::ExchangeId;
+use types::pool::PoolTrait;
+use types::strategies::{Pool, StrategyId};
+use utils::util::{current_timestamp_secs, nat_to_f64};
+use liquidity::liquidity_router::get_liquidity_client;
+use swap::swap_service;
+use smart_rebalance::types::Params;
+use errors::internal_error::error::{InternalError, InternalErrorKind};
+use errors::internal_error::error_codes::module::areas::{
+    canisters as canister_area,
+    canisters::domains::vault as vault_domain,
+    canisters::domains::vault::components as vault_domain_components,
+};
+
+use crate::event_records::event_record::Event;
+use crate::event_records::event_record_service;
+use crate::repository::strategies_repo;
+use crate::repository::claimed_fees_repo::{self, ClaimedFees};
+use crate::strategies::emergency_service;
+use crate::strategies::strategy::IStrategy;
+use crate::strategies::stats::strategy_stats_service;
+use crate::strategies::strategy_lock_service::{self, StrategyOperation};
+use crate::types::types::{PoolCompoundResponse, StrategyCompoundResponse};
+use crate::utils::service_resolver::get_service_resolver;
+
+// Module code: "03-01-07"
+errors::define_error_code_builder_fn!(
+    build_error_code,
+    canister_area::AREA_CODE,            // Area code: "03"
+    vault_domain::DOMAIN_CODE,           // Domain code: "01"
+    vault_domain_components::COMPOUNDING // Component code: "07"
+);
+
+const BPS_SCALE_FACTOR: u64 = 10_000;
+
+// Ledger fees paid per token: withdrawal of the claimed fees, approval and deposit back to the pool
+const TRANSFERS_PER_TOKEN: u64 = 3;
+
+/// Swap bringing the fees to the 50/50 value split of a full range position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BalancingSwap {
+    pub zero_for_one: bool,
+    pub amount_in: Nat,
+    /// Value of `amount_in` in token0
+    pub value: Nat,
+}
+
+thread_local! {
+    static COMPOUNDING_TIMER_ID: RefCell<Option<TimerId>> = RefCell::new(None);
+}
+
+fn set_timer_interval(
+    interval: Duration,
+    func: impl FnMut() + 'static,
+) -> TimerId {
+    ic_cdk_timers::set_timer_interval(interval, func)
+}
+
+/// Starts the timer that compounds the trading fees of every enabled strategy.
+pub fn start_compounding_timer(interval: u64) {
+    let timer_id = set_timer_interval(Duration::from_secs(interval), || {
+        ic_cdk::spawn(async {
+            run_compounding().await;
+        });
+    });
+
+    COMPOUNDING_TIMER_ID.with(|cell| {
+        cell.replace(Some(timer_id));
+    });
+}
+
+pub fn stop_compounding_timer() {
+    COMPOUNDING_TIMER_ID.with(|timer_id| {
+        if let Some(timer_id) = timer_id.borrow_mut().take() {
+            ic_cdk_timers::clear_timer(timer_id);
+        }
+    });
+}
+
+pub async fn run_compounding() {
+    let strategy_ids = strategies_repo::get_enabled_strategies()
+        .into_iter()
+        .filter(|strategy| !get_compoundable_allocations(strategy.as_ref()).is_empty())
+        .map(|strategy| strategy.get_id())
+        .collect::<Vec<_>>();
+
+    for strategy_id in strategy_ids {
+        let _ = compound_strategy_fees(strategy_id).await;
+    }
+}
+
+/// Claims the trading fees of the strategy positions and adds them back to the positions.
+///
+/// Positions whose fees don't cover the cost of compounding them are skipped.
+pub async fn compound_strategy_fees(
+    strategy_id: StrategyId
+) -> Result<StrategyCompoundResponse, InternalError> {
+    emergency_service::check_operation_allowed(strategy_id, StrategyOperation::Compounding)?;
+
+    let _lock = strategy_lock_service::lock_strategy(
+        strategy_id,
+        None,
+        StrategyOperation::Compounding,
+    )?;
+
+    let strategy = strategies_repo::get_strategy_by_id(strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(InternalErrorKind::NotFound, 1), // Error code: "03-01-07 01 01"
+                "compounding_service::compound_strategy_fees".to_string(),
+                "Strategy not found".to_string(),
+                errors::error_extra! {
+                    "strategy_id" => strategy_id,
+                },
+            )
+        })?;
+
+    // Fees of a position held by another strategy belong to that strategy
+    strategy.check_position_ownership(&strategy.get_allocations())?;
+
+    let context = Context::generate(None, Some(strategy_id));
+    let params = strategy.get_rebalance_params();
+    let mut pools = Vec::new();
+
+    for (pool, position_id) in get_compoundable_allocations(strategy.as_ref()) {
+        let response = compound_position(context.clone(), &pool, position_id, &params).await
+            .map_err(|error| {
+                // Event: Strategy compound failed
+                event_record_service::create_event_record(
+                    Event::strategy_compound_failed(
+                        strategy_id.to_string(),
+                        pool.get_id(),
+                        error.clone(),
+                    ),
+                    context.correlation_id.clone(),
+                    None,
+                    context.strategy_id,
+                );
+
+                error
+            })?;
+
+        pools.push(response);
+    }
+
+    if pools.iter().any(|pool| pool.is_compounded) {
+        // Update strategy current liquidity
+        strategy_stats_service::spawn_update_strategy_liquidity(strategy);
+    }
+
+    Ok(StrategyCompoundResponse { strategy_id, pools })
+}
+
+/// Invested allocations whose pool keeps the trading fees apart from the position.
+fn get_compoundable_allocations(strategy: &dyn IStrategy) -> Vec<(Pool, u64)> {
+    strategy.get_allocations()
+        .into_iter()
+        // KongSwap adds the fees to the pool reserves, so they are already compounded
+        .filter(|allocation| allocation.pool.provider == ExchangeId::ICPSwap)
+        .filter_map(|allocation| allocation.position_id.map(|position_id| (allocation.pool, position_id)))
+        .collect()
+}
+
+async fn compound_position(
+    context: Context,
+    pool: &Pool,
+    position_id: u64,
+    params: &Params,
+) -> Result<PoolCompoundResponse, InternalError> {
+    let strategy_id = context.strategy_id.unwrap().to_string();
+    let service_resolver = get_service_resolver();
+    let provider_impls = service_resolver.provider_impls();
+    let icrc_ledger_client = service_resolver.icrc_ledger_client();
+
+    let liquidity_client = get_liquidity_client(
+        provider_impls.clone(),
+        icrc_ledger_client.clone(),
+        pool.token0,
+        pool.token1,
+        pool.provider,
+    ).await;
+
+    let fees = liquidity_client.get_position_fees(position_id).await?;
+
+    // Fees claimed by a run that failed before adding them to the position are compounded again
+    let claimed_fees = claimed_fees_repo::get_claimed_fees(context.strategy_id.unwrap(), position_id);
+    let (claimed_token_0_amount, claimed_token_1_amount) = claimed_fees
+        .map_or((Nat::from(0u64), Nat::from(0u64)), |claimed| (claimed.token_0_amount, claimed.token_1_amount));
+    let fees_token_0_amount = fees.token_0_amount.clone() + claimed_token_0_amount.clone();
+    let fees_token_1_amount = fees.token_1_amount.clone() + claimed_token_1_amount.clone();
+
+    let token_0_fee = icrc_ledger_client.icrc1_fee(pool.token0).await?;
+    let token_1_fee = icrc_ledger_client.icrc1_fee(pool.token1).await?;
+
+    // Token1 is valued in token0 at the pool price, the ledger fee is quoted if there are no token1 fees
+    let quote_amount = fees_token_1_amount.clone().max(token_1_fee.clone());
+    let quote_amount_out = if quote_amount > Nat::from(0u64) {
+        let quote = swap_service::quote_swap_icrc2(
+            provider_impls.clone(),
+            icrc_ledger_client.clone(),
+            pool.token1,
+            pool.token0,
+            quote_amount.clone(),
+            pool.provider,
+        ).await?;
+
+        Nat::from(quote.amount_out)
+    } else {
+        Nat::from(0u64)
+    };
+
+    let to_token_0_value = |amount: &Nat| {
+        if quote_amount == 0u64 {
+            Nat::from(0u64)
+        } else {
+            amount.clone() * quote_amount_out.clone() / quote_amount.clone()
+        }
+    };
+
+    let token_1_value = to_token_0_value(&fees_token_1_amount);
+    let fees_value = fees_token_0_amount.clone() + token_1_value.clone();
+
+    let swap_value = calculate_balancing_swap(&fees_token_0_amount, &fees_token_1_amount, &token_1_value)
+        .map_or(Nat::from(0u64), |swap| swap.value);
+    let cost = estimate_compound_cost(
+        &token_0_fee,
+        &to_token_0_value(&token_1_fee),
+        &swap_value,
+        params.dex_fee_percent_bps,
+    );
+
+    if !is_worth_compounding(&fees_value, &cost, params.gain_cost_multiplier) {
+        // Event: Strategy compound skipped
+        event_record_service::create_event_record(
+            Event::strategy_compound_skipped(
+                strategy_id,
+                pool.get_id(),
+                fees_value.clone(),
+                cost.clone(),
+            ),
+            context.correlation_id,
+            None,
+            context.strategy_id,
+        );
+
+        return Ok(PoolCompoundResponse {
+            pool: pool.clone(),
+            is_compounded: false,
+            fees_value,
+            cost,
+            token_0_amount: Nat::from(0u64),
+            token_1_amount: Nat::from(0u64),
+        });
+    }
+
+    // Event: Strategy compound started
+    event_record_service::create_event_record(
+        Event::strategy_compound_started(
+            strategy_id.clone(),
+            pool.get_id(),
+            fees_token_0_amount,
+            fees_token_1_amount,
+        ),
+        context.correlation_id.clone(),
+        None,
+        context.strategy_id,
+    );
+
+    let mut claimed_token_0_amount = claimed_token_0_amount;
+    let mut claimed_token_1_amount = claimed_token_1_amount;
+
+    if fees.token_0_amount > 0u64 || fees.token_1_amount > 0u64 {
+        let claimed = liquidity_client.claim_fees(position_id).await?;
+
+        claimed_token_0_amount += claimed.token_0_amount;
+        claimed_token_1_amount += claimed.token_1_amount;
+    }
+
+    // The claimed fees are held by the vault until they are added to the position
+    save_claimed_fees(&context, position_id, &claimed_token_0_amount, &claimed_token_1_amount);
+
+    let mut token_0_amount = claimed_token_0_amount.clone();
+    let mut token_1_amount = claimed_token_1_amount.clone();
+
+    // Swap the excess token into the other one, the claimed amounts can differ from the estimated fees
+    if let Some(swap) = calculate_balancing_swap(
+        &claimed_token_0_amount,
+        &claimed_token_1_amount,
+        &to_token_0_value(&claimed_token_1_amount),
+    ) {
+        let (input_token, output_token) = if swap.zero_for_one {
+            (pool.token0, pool.token1)
+        } else {
+            (pool.token1, pool.token0)
+        };
+
+        // The output is expected at the pool price of the token1 quote
+        let expected_amount_out = calculate_expected_amount_out(&swap, &quote_amount, &quote_amount_out);
+        let min_amount_out = calculate_min_amount_out(&expected_amount_out, params.max_slippage_bps);
+
+        let swap_response = swap_service::swap_icrc2_optimal(
+            provider_impls,
+            icrc_ledger_client,
+            input_token,
+            output_token,
+            swap.amount_in.clone(),
+        ).await?;
+
+        let amount_out = Nat::from(swap_response.amount_out);
+
+        if swap.zero_for_one {
+            token_0_amount -= swap.amount_in.clone();
+            token_1_amount += amount_out.clone();
+        } else {
+            token_1_amount -= swap.amount_in.clone();
+            token_0_amount += amount_out.clone();
+        }
+
+        save_claimed_fees(&context, position_id, &token_0_amount, &token_1_amount);
+
+        emergency_service::check_swap_slippage(context.strategy_id.unwrap(), &expected_amount_out, &amount_out);
+
+        // The swapped fees stay claimed and are compounded by the next run
+        if amount_out < min_amount_out {
+            return Err(InternalError::business_logic(
+                build_error_code(InternalErrorKind::BusinessLogic, 2), // Error code: "03-01-07 03 02"
+                "compounding_service::compound_position".to_string(),
+                "Swapped amount is below the minimum amount out".to_string(),
+                errors::error_extra! {
+                    "context" => context,
+                    "position_id" => position_id,
+                    "token_in" => input_token,
+                    "token_out" => output_token,
+                    "amount_in" => swap.amount_in,
+                    "amount_out" => amount_out,
+                    "min_amount_out" => min_amount_out,
+                },
+            ));
+        }
+    }
+
+    let increase_response = liquidity_client.increase_position_liquidity(
+        position_id,
+        token_0_amount,
+        token_1_amount,
+    ).await?;
+
+    claimed_fees_repo::remove_claimed_fees(context.strategy_id.unwrap(), position_id);
+
+    // Event: Strategy compound completed
+    event_record_service::create_event_record(
+        Event::strategy_compound_completed(
+            strategy_id,
+            pool.get_id(),
+            claimed_token_0_amount,
+            claimed_token_1_amount,
+            increase_response.token_0_amount.clone(),
+            increase_response.token_1_amount.clone(),
+        ),
+        context.correlation_id,
+        None,
+        context.strategy_id,
+    );
+
+    Ok(PoolCompoundResponse {
+        pool: pool.clone(),
+        is_compounded: true,
+        fees_value,
+        cost,
+        token_0_amount: increase_response.token_0_amount,
+        token_1_amount: increase_response.token_1_amount,
+    })
+}
+
+fn save_claimed_fees(context: &Context, position_id: u64, token_0_amount: &Nat, token_1_amount: &Nat) {
+    claimed_fees_repo::save_claimed_fees(
+        context.strategy_id.unwrap(),
+        position_id,
+        ClaimedFees {
+            token_0_amount: token_0_amount.clone(),
+            token_1_amount: token_1_amount.clone(),
+            updated_at: current_timestamp_secs(),
+        },
+    );
+}
+
+/// Returns the swap that splits the value of both amounts equally,
+/// `None` if they are already balanced.
+pub fn calculate_balancing_swap(
+    token_0_amount: &Nat,
+    token_1_amount: &Nat,
+    token_1_value: &Nat,
+) -> Option<BalancingSwap> {
+    let swap = if token_0_amount > token_1_value {
+        let value = (token_0_amount.clone() - token_1_value.clone()) / Nat::from(2u64);
+
+        BalancingSwap {
+            zero_for_one: true,
+            amount_in: value.clone(),
+            value,
+        }
+    } else if token_1_value > token_0_amount {
+        let value = (token_1_value.clone() - token_0_amount.clone()) / Nat::from(2u64);
+
+        BalancingSwap {
+            zero_for_one: false,
+            amount_in: token_1_amount.clone() * value.clone() / token_1_value.clone(),
+            value,
+        }
+    } else {
+        return None;
+    };
+
+    if swap.amount_in == 0u64 {
+        return None;
+    }
+
+    Some(swap)
+}
+
+/// Output of the balancing swap at the price of the token1 quote of `quote_amount`.
+pub fn calculate_expected_amount_out(swap: &BalancingSwap, quote_amount: &Nat, quote_amount_out: &Nat) -> Nat {
+    if !swap.zero_for_one {
+        return swap.value.clone();
+    }
+
+    if *quote_amount_out == 0u64 {
+        return Nat::from(0u64);
+    }
+
+    swap.value.clone() * quote_amount.clone() / quote_amount_out.clone()
+}
+
+/// Lowest swap output accepted with the max slippage of the strategy profile.
+pub fn calculate_min_amount_out(expected_amount_out: &Nat, max_slippage_bps: u32) -> Nat {
+    let max_slippage_bps = Nat::from(max_slippage_bps).min(Nat::from(BPS_SCALE_FACTOR));
+
+    expected_amount_out.clone() * (Nat::from(BPS_SCALE_FACTOR) - max_slippage_bps) / Nat::from(BPS_SCALE_FACTOR)
+}
+
+/// Cost of compounding the fees in token0: the ledger fees of both tokens and the DEX fee of the swap.
+pub fn estimate_compound_cost(
+    token_0_fee: &Nat,
+    token_1_fee_value: &Nat,
+    swap_value: &Nat,
+    dex_fee_percent_bps: u32,
+) -> Nat {
+    let transfer_cost = (token_0_fee.clone() + token_1_fee_value.clone()) * Nat::from(TRANSFERS_PER_TOKEN);
+    let swap_cost = swap_value.clone() * Nat::from(dex_fee_percent_bps) / Nat::from(BPS_SCALE_FACTOR);
+
+    transfer_cost + swap_cost
+}
+
+/// Fees are compounded only if their value exceeds the cost with the margin of the strategy profile.
+pub fn is_worth_compounding(fees_value: &Nat, cost: &Nat, gain_cost_multiplier: f64) -> bool {
+    *fees_value > 0u64 && nat_to_f64(fees_value) >= nat_to_f64(cost) * gain_cost_multiplier
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod calculate_balancing_swap {
+        use super::*;
+
+        #[test]
+        fn swaps_excess_token_0() {
+            let swap = calculate_balancing_swap(
+                &Nat::from(1_000u64),
+                &Nat::from(50u64),
+                &Nat::from(200u64),
+            );
+
+            assert_eq!(swap, Some(BalancingSwap {
+                zero_for_one: true,
+                amount_in: Nat::from(400u64),
+                value: Nat::from(400u64),
+            }));
+        }
+
+        #[test]
+        fn swaps_excess_token_1() {
+            // 100 token1 are worth 1_000 token0
+            let swap = calculate_balancing_swap(
+                &Nat::from(0u64),
+                &Nat::from(100u64),
+                &Nat::from(1_000u64),
+            );
+
+            assert_eq!(swap, Some(BalancingSwap {
+                zero_for_one: false,
+                amount_in: Nat::from(50u64),
+                value: Nat::from(500u64),
+            }));
+        }
+
+        #[test]
+        fn skips_balanced_amounts() {
+            let swap = calculate_balancing_swap(
+                &Nat::from(500u64),
+                &Nat::from(20u64),
+                &Nat::from(500u64),
+            );
+
+            assert_eq!(swap, None);
+        }
+    }
+
+    mod calculate_expected_amount_out {
+        use super::*;
+
+        #[test]
+        fn values_token_1_output_at_quote_price() {
+            // 10 token1 are worth 100 token0
+            let swap = BalancingSwap {
+                zero_for_one: true,
+                amount_in: Nat::from(400u64),
+                value: Nat::from(400u64),
+            };
+
+            let amount_out = calculate_expected_amount_out(&swap, &Nat::from(10u64), &Nat::from(100u64));
+
+            assert_eq!(amount_out, Nat::from(40u64));
+        }
+
+        #[test]
+        fn uses_swap_value_for_token_0_output() {
+            let swap = BalancingSwap {
+                zero_for_one: false,
+                amount_in: Nat::from(50u64),
+                value: Nat::from(500u64),
+            };
+
+            let amount_out = calculate_expected_amount_out(&swap, &Nat::from(10u64), &Nat::from(100u64));
+
+            assert_eq!(amount_out, Nat::from(500u64));
+        }
+    }
+
+    mod calculate_min_amount_out {
+        use super::*;
+
+        #[test]
+        fn deducts_max_slippage() {
+            assert_eq!(calculate_min_amount_out(&Nat::from(1_000u64), 100), Nat::from(990u64));
+        }
+
+        #[test]
+        fn caps_slippage_at_100_percent() {
+            assert_eq!(calculate_min_amount_out(&Nat::from(1_000u64), 20_000), Nat::from(0u64));
+        }
+    }
+
+    mod estimate_compound_cost {
+        use super::*;
+
+        #[test]
+        fn adds_transfer_and_swap_fees() {
+            // (10 + 20) * 3 transfers + 1% of 1_000
+            let cost = estimate_compound_cost(
+                &Nat::from(10u64),
+                &Nat::from(20u64),
+                &Nat::from(1_000u64),
+                100,
+            );
+
+            assert_eq!(cost, Nat::from(100u64));
+        }
+    }
+
+    mod is_worth_compounding {
+        use super::*;
+
+        #[test]
+        fn requires_fees_above_cost_margin() {
+            assert!(is_worth_compounding(&Nat::from(200u64), &Nat::from(100u64), 2.0));
+            assert!(!is_worth_compounding(&Nat::from(199u64), &Nat::from(100u64), 2.0));
+        }
+
+        #[test]
+        fn skips_without_fees() {
+            assert!(!is_worth_compounding(&Nat::from(0u64), &Nat::from(0u64), 1.0));
+        }
+    }
+}
@@ -0,0 +1,452 @@
+use candid::Nat;
+
+use types::context::Context;
+use types::strategies::StrategyId;
+use utils::util::current_timestamp_secs;
+use errors::internal_error::error::{InternalError, InternalErrorKind};
+use errors::internal_error::error_codes::module::areas::{
+    canisters as canister_area,
+    canisters::domains::vault as vault_domain,
+    canisters::domains::vault::components as vault_domain_components,
+};
+
+use crate::repository::strategies_repo;
+use crate::repository::emergency_repo::{self, PauseState, CircuitBreakerConfig};
+use crate::repository::unwinds_repo;
+use crate::strategies::fee_service;
+use crate::strategies::preview_service;
+use crate::strategies::strategy_lock_service::StrategyOperation;
+use crate::event_records::event_record::Event;
+use crate::event_records::event_record_service;
+use crate::event_records::events::emergency_events::CircuitBreaker;
+use crate::types::types::{EmergencyStateResponse, PauseOperationsArgs, StrategyPauseResponse};
+
+// Module code: "03-01-09"
+errors::define_error_code_builder_fn!(
+    build_error_code,
+    canister_area::AREA_CODE,          // Area code: "03"
+    vault_domain::DOMAIN_CODE,         // Domain code: "01"
+    vault_domain_components::EMERGENCY // Component code: "09"
+);
+
+const MAX_BOUND_BPS: u64 = 10_000;
+
+/// Fails if the operation is paused for every strategy or for this one.
+///
+/// Only deposits, withdrawals and rebalances can be paused, compounding is paused with the rebalances
+/// since it moves the liquidity of the positions too.
+/// They are not allowed anymore once the strategy is unwound, its shares can only be redeemed.
+pub fn check_operation_allowed(
+    strategy_id: StrategyId,
+    operation: StrategyOperation,
+) -> Result<(), InternalError> {
+    let is_pausable = matches!(
+        operation,
+        StrategyOperation::Deposit
+            | StrategyOperation::Withdraw
+            | StrategyOperation::Rebalance
+            | StrategyOperation::Compounding
+    );
+
+    if is_pausable && unwinds_repo::get_strategy_unwind(strategy_id).is_some() {
+        return Err(InternalError::business_logic(
+            build_error_code(InternalErrorKind::BusinessLogic, 5), // Error code: "03-01-09 03 05"
+            "emergency_service::check_operation_allowed".to_string(),
+            format!("{operation:?} is not allowed, the strategy is unwound"),
+            errors::error_extra! {
+                "strategy_id" => strategy_id,
+                "operation" => operation,
+            },
+        ));
+    }
+
+    let global_pause = emergency_repo::get_global_pause();
+    let strategy_pause = emergency_repo::get_strategy_pause(strategy_id);
+
+    let paused_by = if is_operation_paused(&global_pause, operation) {
+        Some(global_pause)
+    } else if is_operation_paused(&strategy_pause, operation) {
+        Some(strategy_pause)
+    } else {
+        None
+    };
+
+    match paused_by {
+        Some(pause) => Err(InternalError::business_logic(
+            build_error_code(InternalErrorKind::BusinessLogic, 1), // Error code: "03-01-09 03 01"
+            "emergency_service::check_operation_allowed".to_string(),
+            format!("{operation:?} is paused"),
+            errors::error_extra! {
+                "strategy_id" => strategy_id,
+                "operation" => operation,
+                "reason" => pause.reason,
+            },
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Pauses the selected operations, the other ones keep their state.
+pub fn pause_operations(
+    context: Context,
+    args: PauseOperationsArgs,
+    reason: Option<String>,
+) -> Result<PauseState, InternalError> {
+    validate_pause_args(&args, "emergency_service::pause_operations")?;
+
+    let mut pause = get_pause(args.strategy_id);
+    set_paused_operations(&mut pause, &args, true);
+    pause.reason = reason.clone();
+    pause.updated_at = Some(current_timestamp_secs());
+    save_pause(args.strategy_id, pause.clone());
+
+    // Event: Operations paused
+    event_record_service::create_event_record(
+        Event::operations_paused(
+            args.strategy_id.map(|strategy_id| strategy_id.to_string()),
+            args.deposit,
+            args.withdraw,
+            args.rebalance,
+            reason,
+        ),
+        context.correlation_id,
+        context.user,
+        args.strategy_id,
+    );
+
+    Ok(pause)
+}
+
+/// Resumes the selected operations, the reason is cleared once nothing is paused.
+pub fn unpause_operations(
+    context: Context,
+    args: PauseOperationsArgs,
+) -> Result<PauseState, InternalError> {
+    validate_pause_args(&args, "emergency_service::unpause_operations")?;
+
+    let mut pause = get_pause(args.strategy_id);
+    set_paused_operations(&mut pause, &args, false);
+    if !pause.deposit && !pause.withdraw && !pause.rebalance {
+        pause.reason = None;
+    }
+    pause.updated_at = Some(current_timestamp_secs());
+    save_pause(args.strategy_id, pause.clone());
+
+    // NAV drops are measured again from the share price after the incident
+    if let Some(strategy_id) = args.strategy_id {
+        emergency_repo::save_last_share_price(strategy_id, None);
+    }
+
+    // Event: Operations unpaused
+    event_record_service::create_event_record(
+        Event::operations_unpaused(
+            args.strategy_id.map(|strategy_id| strategy_id.to_string()),
+            args.deposit,
+            args.withdraw,
+            args.rebalance,
+        ),
+        context.correlation_id,
+        context.user,
+        args.strategy_id,
+    );
+
+    Ok(pause)
+}
+
+pub fn update_circuit_breaker_config(
+    config: CircuitBreakerConfig,
+) -> Result<CircuitBreakerConfig, InternalError> {
+    let bounds = [config.max_slippage_bps, config.max_nav_drop_bps];
+
+    if bounds.iter().flatten().any(|bound| *bound == 0 || *bound > MAX_BOUND_BPS) {
+        return Err(InternalError::validation(
+            build_error_code(InternalErrorKind::Validation, 2), // Error code: "03-01-09 02 02"
+            "emergency_service::update_circuit_breaker_config".to_string(),
+            format!("Circuit breaker bounds must be between 1 and {} bps", MAX_BOUND_BPS),
+            errors::error_extra! {
+                "config" => config,
+            },
+        ));
+    }
+
+    emergency_repo::set_circuit_breaker_config(config.clone());
+
+    Ok(config)
+}
+
+pub fn get_emergency_state() -> EmergencyStateResponse {
+    EmergencyStateResponse {
+        global_pause: emergency_repo::get_global_pause(),
+        paused_strategies: emergency_repo::get_paused_strategies()
+            .into_iter()
+            .map(|(strategy_id, pause)| StrategyPauseResponse { strategy_id, pause })
+            .collect(),
+        circuit_breaker: emergency_repo::get_circuit_breaker_config(),
+    }
+}
+
+/// Trips the slippage breaker of the strategy if a swap returned too little compared to its quote.
+pub fn check_swap_slippage(strategy_id: StrategyId, quoted_amount_out: &Nat, amount_out: &Nat) {
+    let max_slippage_bps = match emergency_repo::get_circuit_breaker_config().max_slippage_bps {
+        Some(max_slippage_bps) => max_slippage_bps,
+        None => return,
+    };
+
+    let slippage_bps = preview_service::calculate_price_impact_bps(quoted_amount_out, amount_out);
+
+    if slippage_bps > max_slippage_bps {
+        trip_circuit_breaker(strategy_id, CircuitBreaker::Slippage, slippage_bps, max_slippage_bps);
+    }
+}
+
+/// Trips the NAV drop breaker of the strategy if its share price dropped too much since the last check.
+pub fn check_nav_drop(strategy_id: StrategyId, nav: &Nat, total_shares: &Nat) {
+    // Nothing is invested, the share price starts over with the next deposit
+    if *total_shares == 0u64 || *nav == 0u64 {
+        emergency_repo::save_last_share_price(strategy_id, None);
+        return;
+    }
+
+    let share_price = fee_service::calculate_share_price(nav, total_shares);
+    let last_share_price = emergency_repo::get_last_share_price(strategy_id);
+    emergency_repo::save_last_share_price(strategy_id, Some(share_price.clone()));
+
+    let (last_share_price, max_nav_drop_bps) = match (
+        last_share_price,
+        emergency_repo::get_circuit_breaker_config().max_nav_drop_bps,
+    ) {
+        (Some(last_share_price), Some(max_nav_drop_bps)) => (last_share_price, max_nav_drop_bps),
+        _ => return,
+    };
+
+    let nav_drop_bps = preview_service::calculate_price_impact_bps(&last_share_price, &share_price);
+
+    if nav_drop_bps > max_nav_drop_bps {
+        trip_circuit_breaker(strategy_id, CircuitBreaker::NavDrop, nav_drop_bps, max_nav_drop_bps);
+    }
+}
+
+/// Pauses the deposits and rebalances of the strategy, withdrawals stay open.
+fn trip_circuit_breaker(
+    strategy_id: StrategyId,
+    breaker: CircuitBreaker,
+    value_bps: u64,
+    bound_bps: u64,
+) {
+    let mut pause = emergency_repo::get_strategy_pause(strategy_id);
+    pause.deposit = true;
+    pause.rebalance = true;
+    pause.reason = Some(format!("{breaker:?} circuit breaker: {value_bps} bps above the {bound_bps} bps bound"));
+    pause.updated_at = Some(current_timestamp_secs());
+    emergency_repo::save_strategy_pause(strategy_id, pause);
+
+    let context = Context::generate(None, Some(strategy_id));
+
+    // Event: Circuit breaker tripped
+    event_record_service::create_event_record(
+        Event::circuit_breaker_tripped(
+            strategy_id.to_string(),
+            breaker,
+            value_bps,
+            bound_bps,
+        ),
+        context.correlation_id,
+        None,
+        context.strategy_id,
+    );
+}
+
+fn validate_pause_args(args: &PauseOperationsArgs, error_context: &str) -> Result<(), InternalError> {
+    if !args.deposit && !args.withdraw && !args.rebalance {
+        return Err(InternalError::validation(
+            build_error_code(InternalErrorKind::Validation, 3), // Error code: "03-01-09 02 03"
+            error_context.to_string(),
+            "No operation selected".to_string(),
+            errors::error_extra! {
+                "args" => args,
+            },
+        ));
+    }
+
+    if let Some(strategy_id) = args.strategy_id {
+        if strategies_repo::get_strategy_by_id(strategy_id).is_none() {
+            return Err(InternalError::not_found(
+                build_error_code(InternalErrorKind::NotFound, 4), // Error code: "03-01-09 01 04"
+                error_context.to_string(),
+                "Strategy not found".to_string(),
+                errors::error_extra! {
+                    "args" => args,
+                },
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn get_pause(strategy_id: Option<StrategyId>) -> PauseState {
+    match strategy_id {
+        Some(strategy_id) => emergency_repo::get_strategy_pause(strategy_id),
+        None => emergency_repo::get_global_pause(),
+    }
+}
+
+fn save_pause(strategy_id: Option<StrategyId>, pause: PauseState) {
+    match strategy_id {
+        Some(strategy_id) => emergency_repo::save_strategy_pause(strategy_id, pause),
+        None => emergency_repo::set_global_pause(pause),
+    }
+}
+
+fn set_paused_operations(pause: &mut PauseState, args: &PauseOperationsArgs, paused: bool) {
+    if args.deposit {
+        pause.deposit = paused;
+    }
+    if args.withdraw {
+        pause.withdraw = paused;
+    }
+    if args.rebalance {
+        pause.rebalance = paused;
+    }
+}
+
+fn is_operation_paused(pause: &PauseState, operation: StrategyOperation) -> bool {
+    match operation {
+        StrategyOperation::Deposit => pause.deposit,
+        StrategyOperation::Withdraw => pause.withdraw,
+        StrategyOperation::Rebalance | StrategyOperation::Compounding => pause.rebalance,
+        StrategyOperation::FeeAccrual
+        | StrategyOperation::Unwind
+        | StrategyOperation::Redemption => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(deposit: bool, withdraw: bool, rebalance: bool) -> PauseOperationsArgs {
+        PauseOperationsArgs {
+            strategy_id: None,
+            deposit,
+            withdraw,
+            rebalance,
+        }
+    }
+
+    mod set_paused_operations {
+        use super::*;
+
+        #[test]
+        fn changes_only_selected_operations() {
+            let mut pause = PauseState { withdraw: true, ..PauseState::default() };
+
+            set_paused_operations(&mut pause, &args(true, false, false), true);
+
+            assert!(pause.deposit);
+            assert!(pause.withdraw);
+            assert!(!pause.rebalance);
+
+            set_paused_operations(&mut pause, &args(false, true, false), false);
+
+            assert!(pause.deposit);
+            assert!(!pause.withdraw);
+        }
+    }
+
+    mod is_operation_paused {
+        use super::*;
+
+        #[test]
+        fn maps_operations_to_flags() {
+            let pause = PauseState { rebalance: true, ..PauseState::default() };
+
+            assert!(is_operation_paused(&pause, StrategyOperation::Rebalance));
+            assert!(is_operation_paused(&pause, StrategyOperation::Compounding));
+            assert!(!is_operation_paused(&pause, StrategyOperation::Deposit));
+            assert!(!is_operation_paused(&pause, StrategyOperation::Withdraw));
+        }
+
+        #[test]
+        fn never_pauses_maintenance_operations() {
+            let pause = PauseState {
+                deposit: true,
+                withdraw: true,
+                rebalance: true,
+                ..PauseState::default()
+            };
+
+            assert!(!is_operation_paused(&pause, StrategyOperation::FeeAccrual));
+            assert!(!is_operation_paused(&pause, StrategyOperation::Redemption));
+        }
+    }
+
+    mod check_operation_allowed {
+        use super::*;
+
+        #[test]
+        fn fails_when_paused_globally() {
+            emergency_repo::set_global_pause(PauseState { withdraw: true, ..PauseState::default() });
+
+            let result = check_operation_allowed(1, StrategyOperation::Withdraw);
+
+            emergency_repo::set_global_pause(PauseState::default());
+
+            assert!(matches!(result.unwrap_err().kind, InternalErrorKind::BusinessLogic));
+            assert!(check_operation_allowed(1, StrategyOperation::Withdraw).is_ok());
+        }
+
+        #[test]
+        fn fails_when_strategy_is_unwound() {
+            unwinds_repo::save_strategy_unwind(unwinds_repo::StrategyUnwind {
+                strategy_id: 50,
+                token: candid::Principal::anonymous(),
+                balance: Nat::from(0u64),
+                shares: Nat::from(0u64),
+                completed: false,
+                started_at: 0,
+                completed_at: None,
+            });
+
+            assert!(check_operation_allowed(50, StrategyOperation::Deposit).is_err());
+            assert!(check_operation_allowed(50, StrategyOperation::Rebalance).is_err());
+            assert!(check_operation_allowed(50, StrategyOperation::Compounding).is_err());
+            assert!(check_operation_allowed(50, StrategyOperation::Redemption).is_ok());
+        }
+    }
+
+    mod update_circuit_breaker_config {
+        use super::*;
+
+        #[test]
+        fn rejects_out_of_range_bounds() {
+            let config = CircuitBreakerConfig {
+                max_slippage_bps: Some(MAX_BOUND_BPS + 1),
+                max_nav_drop_bps: None,
+            };
+
+            assert!(update_circuit_breaker_config(config).is_err());
+
+            let config = CircuitBreakerConfig {
+                max_slippage_bps: None,
+                max_nav_drop_bps: Some(0),
+            };
+
+            assert!(update_circuit_breaker_config(config).is_err());
+        }
+
+        #[test]
+        fn saves_valid_bounds() {
+            let config = CircuitBreakerConfig {
+                max_slippage_bps: Some(300),
+                max_nav_drop_bps: None,
+            };
+
+            update_circuit_breaker_config(config.clone()).unwrap();
+
+            assert_eq!(emergency_repo::get_circuit_breaker_config(), config);
+
+            emergency_repo::set_circuit_breaker_config(CircuitBreakerConfig::default());
+        }
+    }
+}
@@ -0,0 +1,381 @@
+use candid::Nat;
+
+use types::context::Context;
+use types::strategies::{StrategyId, StrategyFees, SHARE_PRICE_SCALE};
+use errors::internal_error::error::{InternalError, InternalErrorKind};
+use errors::internal_error::error_codes::module::areas::{
+    canisters as canister_area,
+    canisters::domains::vault as vault_domain,
+    canisters::domains::vault::components as vault_domain_components,
+};
+
+use crate::repository::strategies_repo;
+use crate::repository::unwinds_repo;
+use crate::repository::share_prices_repo::SharePriceSource;
+use crate::strategies::strategy_lock_service::{self, StrategyOperation};
+use crate::strategies::emergency_service;
+use crate::strategies::share_price_service;
+use crate::types::types::UpdateStrategyFeesArgs;
+
+// Module code: "03-01-05"
+errors::define_error_code_builder_fn!(
+    build_error_code,
+    canister_area::AREA_CODE,     // Area code: "03"
+    vault_domain::DOMAIN_CODE,    // Domain code: "01"
+    vault_domain_components::FEES // Component code: "05"
+);
+
+pub const MAX_PERFORMANCE_FEE_BPS: u32 = 5_000; // 50%
+pub const MAX_MANAGEMENT_FEE_BPS: u32 = 1_000; // 10% per year
+
+const BPS_SCALE_FACTOR: u64 = 10_000;
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 3600;
+
+/// Fee shares to mint and the fee state after an accrual.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeeAccrual {
+    pub management_fee_shares: Nat,
+    pub performance_fee_shares: Nat,
+    /// Time the management fee was charged for
+    pub period_secs: u64,
+    pub fees: StrategyFees,
+}
+
+/// Accrues the fees of a strategy on its current position value.
+///
+/// An unwound strategy isn't charged, its shares are only redeemed for the parked amount.
+pub async fn accrue_strategy_fees(strategy_id: StrategyId) -> Result<(), InternalError> {
+    if unwinds_repo::get_strategy_unwind(strategy_id).is_some() {
+        return Ok(());
+    }
+
+    let _lock = strategy_lock_service::lock_strategy(
+        strategy_id,
+        None,
+        StrategyOperation::FeeAccrual,
+    )?;
+
+    let mut strategy = strategies_repo::get_strategy_by_id(strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(InternalErrorKind::NotFound, 1), // Error code: "03-01-05 01 01"
+                "fee_service::accrue_strategy_fees".to_string(),
+                "Strategy not found".to_string(),
+                errors::error_extra! {
+                    "strategy_id" => strategy_id,
+                },
+            )
+        })?;
+
+    let nav = strategy.get_nav().await?;
+
+    strategy.reload_state();
+    emergency_service::check_nav_drop(strategy_id, &nav, &strategy.get_total_shares());
+    strategy.accrue_fees(nav.clone(), Context::generate(None, Some(strategy_id)));
+    share_price_service::record_share_price(
+        strategy_id,
+        &nav,
+        &strategy.get_total_shares(),
+        SharePriceSource::StatsUpdate,
+    );
+
+    Ok(())
+}
+
+/// Sets the fee rates of a strategy.
+pub fn update_strategy_fees(args: UpdateStrategyFeesArgs) -> Result<StrategyFees, InternalError> {
+    let mut strategy = strategies_repo::get_strategy_by_id(args.strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(InternalErrorKind::NotFound, 2), // Error code: "03-01-05 01 02"
+                "fee_service::update_strategy_fees".to_string(),
+                "Strategy not found".to_string(),
+                errors::error_extra! {
+                    "args" => args,
+                },
+            )
+        })?;
+
+    validate_fee_rates(args.performance_fee_bps, args.management_fee_bps).map_err(|message| {
+        InternalError::validation(
+            build_error_code(InternalErrorKind::Validation, 3), // Error code: "03-01-05 02 03"
+            "fee_service::update_strategy_fees".to_string(),
+            message,
+            errors::error_extra! {
+                "args" => args,
+            },
+        )
+    })?;
+
+    let mut fees = strategy.get_fees();
+    fees.performance_fee_bps = args.performance_fee_bps;
+    fees.management_fee_bps = args.management_fee_bps;
+
+    strategy.set_fees(fees.clone());
+    strategies_repo::save_strategy(strategy);
+
+    Ok(fees)
+}
+
+/// Calculates the fee shares for the current position value.
+///
+/// The management fee is charged for the time since the last accrual.
+/// The performance fee is charged on the share price gain above the high-water mark,
+/// after which the high-water mark moves to the share price net of fees.
+/// If `charge_fees` is false, only the accrual checkpoints are moved.
+pub fn calculate_fee_accrual(
+    fees: &StrategyFees,
+    nav: &Nat,
+    total_shares: &Nat,
+    now: u64,
+    charge_fees: bool,
+) -> FeeAccrual {
+    let zero = Nat::from(0u64);
+
+    let mut updated_fees = fees.clone();
+    let mut management_fee_shares = zero.clone();
+    let mut performance_fee_shares = zero.clone();
+
+    let period_secs = fees.last_management_fee_at
+        .map_or(0, |last_management_fee_at| now.saturating_sub(last_management_fee_at));
+    updated_fees.last_management_fee_at = Some(now);
+
+    if *total_shares == zero || *nav == zero {
+        // Nothing is invested, the share price starts over with the next deposit
+        updated_fees.high_water_mark = None;
+
+        return FeeAccrual {
+            management_fee_shares,
+            performance_fee_shares,
+            period_secs,
+            fees: updated_fees,
+        };
+    }
+
+    let mut shares = total_shares.clone();
+
+    if charge_fees && fees.management_fee_bps > 0 && period_secs > 0 {
+        let fee_value = nav.clone()
+            * Nat::from(fees.management_fee_bps)
+            * Nat::from(period_secs)
+            / Nat::from(BPS_SCALE_FACTOR * SECONDS_PER_YEAR);
+
+        management_fee_shares = calculate_shares_for_fee(&fee_value, nav, &shares);
+        shares += management_fee_shares.clone();
+    }
+
+    let share_price = calculate_share_price(nav, &shares);
+
+    match fees.high_water_mark.clone() {
+        Some(high_water_mark) if share_price > high_water_mark => {
+            if charge_fees && fees.performance_fee_bps > 0 {
+                let gain = (share_price - high_water_mark) * shares.clone()
+                    / Nat::from(SHARE_PRICE_SCALE);
+                let fee_value = gain * Nat::from(fees.performance_fee_bps)
+                    / Nat::from(BPS_SCALE_FACTOR);
+
+                performance_fee_shares = calculate_shares_for_fee(&fee_value, nav, &shares);
+                shares += performance_fee_shares.clone();
+            }
+
+            updated_fees.high_water_mark = Some(calculate_share_price(nav, &shares));
+        }
+        Some(_) => {}
+        None => updated_fees.high_water_mark = Some(share_price),
+    }
+
+    updated_fees.total_management_fee_shares += management_fee_shares.clone();
+    updated_fees.total_performance_fee_shares += performance_fee_shares.clone();
+
+    FeeAccrual {
+        management_fee_shares,
+        performance_fee_shares,
+        period_secs,
+        fees: updated_fees,
+    }
+}
+
+/// Value of a share scaled by 10^12.
+pub fn calculate_share_price(nav: &Nat, shares: &Nat) -> Nat {
+    nav.clone() * Nat::from(SHARE_PRICE_SCALE) / shares.clone()
+}
+
+/// Shares whose value equals `fee_value` once they are minted.
+fn calculate_shares_for_fee(fee_value: &Nat, nav: &Nat, shares: &Nat) -> Nat {
+    if *fee_value == 0u64 || fee_value >= nav {
+        return Nat::from(0u64);
+    }
+
+    fee_value.clone() * shares.clone() / (nav.clone() - fee_value.clone())
+}
+
+fn validate_fee_rates(performance_fee_bps: u32, management_fee_bps: u32) -> Result<(), String> {
+    if performance_fee_bps > MAX_PERFORMANCE_FEE_BPS {
+        return Err(format!("performance_fee_bps must not exceed {}", MAX_PERFORMANCE_FEE_BPS));
+    }
+
+    if management_fee_bps > MAX_MANAGEMENT_FEE_BPS {
+        return Err(format!("management_fee_bps must not exceed {}", MAX_MANAGEMENT_FEE_BPS));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fees(performance_fee_bps: u32, management_fee_bps: u32) -> StrategyFees {
+        StrategyFees {
+            performance_fee_bps,
+            management_fee_bps,
+            ..StrategyFees::default()
+        }
+    }
+
+    fn price(value: u64) -> Nat {
+        Nat::from(value) * Nat::from(SHARE_PRICE_SCALE)
+    }
+
+    mod calculate_fee_accrual {
+        use super::*;
+
+        #[test]
+        fn sets_high_water_mark_on_first_accrual() {
+            let accrual = calculate_fee_accrual(
+                &fees(1_000, 200),
+                &Nat::from(2_000u64),
+                &Nat::from(1_000u64),
+                1_000,
+                true,
+            );
+
+            assert_eq!(accrual.performance_fee_shares, Nat::from(0u64));
+            assert_eq!(accrual.management_fee_shares, Nat::from(0u64));
+            assert_eq!(accrual.fees.high_water_mark, Some(price(2)));
+            assert_eq!(accrual.fees.last_management_fee_at, Some(1_000));
+        }
+
+        #[test]
+        fn charges_performance_fee_above_high_water_mark() {
+            let fees = StrategyFees {
+                high_water_mark: Some(price(1)),
+                ..fees(1_000, 0)
+            };
+
+            // Share price doubled: 1_000 gain, 100 fee
+            let accrual = calculate_fee_accrual(
+                &fees,
+                &Nat::from(2_000u64),
+                &Nat::from(1_000u64),
+                1_000,
+                true,
+            );
+
+            // 100 * 1_000 / (2_000 - 100)
+            assert_eq!(accrual.performance_fee_shares, Nat::from(52u64));
+            assert_eq!(accrual.fees.total_performance_fee_shares, Nat::from(52u64));
+            assert_eq!(
+                accrual.fees.high_water_mark,
+                Some(Nat::from(2_000u64) * Nat::from(SHARE_PRICE_SCALE) / Nat::from(1_052u64))
+            );
+        }
+
+        #[test]
+        fn skips_performance_fee_below_high_water_mark() {
+            let fees = StrategyFees {
+                high_water_mark: Some(price(3)),
+                ..fees(1_000, 0)
+            };
+
+            let accrual = calculate_fee_accrual(
+                &fees,
+                &Nat::from(2_000u64),
+                &Nat::from(1_000u64),
+                1_000,
+                true,
+            );
+
+            assert_eq!(accrual.performance_fee_shares, Nat::from(0u64));
+            assert_eq!(accrual.fees.high_water_mark, Some(price(3)));
+        }
+
+        #[test]
+        fn charges_management_fee_for_elapsed_time() {
+            let fees = StrategyFees {
+                last_management_fee_at: Some(0),
+                high_water_mark: Some(price(1)),
+                ..fees(0, 1_000)
+            };
+
+            // One year at 10%: 100 fee
+            let accrual = calculate_fee_accrual(
+                &fees,
+                &Nat::from(1_000u64),
+                &Nat::from(1_000u64),
+                SECONDS_PER_YEAR,
+                true,
+            );
+
+            // 100 * 1_000 / (1_000 - 100)
+            assert_eq!(accrual.management_fee_shares, Nat::from(111u64));
+            assert_eq!(accrual.period_secs, SECONDS_PER_YEAR);
+            assert_eq!(accrual.fees.last_management_fee_at, Some(SECONDS_PER_YEAR));
+        }
+
+        #[test]
+        fn moves_checkpoints_without_charging() {
+            let fees = StrategyFees {
+                last_management_fee_at: Some(0),
+                high_water_mark: Some(price(1)),
+                ..fees(1_000, 1_000)
+            };
+
+            let accrual = calculate_fee_accrual(
+                &fees,
+                &Nat::from(2_000u64),
+                &Nat::from(1_000u64),
+                SECONDS_PER_YEAR,
+                false,
+            );
+
+            assert_eq!(accrual.management_fee_shares, Nat::from(0u64));
+            assert_eq!(accrual.performance_fee_shares, Nat::from(0u64));
+            assert_eq!(accrual.fees.high_water_mark, Some(price(2)));
+            assert_eq!(accrual.fees.last_management_fee_at, Some(SECONDS_PER_YEAR));
+        }
+
+        #[test]
+        fn resets_high_water_mark_without_shares() {
+            let fees = StrategyFees {
+                high_water_mark: Some(price(2)),
+                ..fees(1_000, 1_000)
+            };
+
+            let accrual = calculate_fee_accrual(
+                &fees,
+                &Nat::from(0u64),
+                &Nat::from(0u64),
+                1_000,
+                true,
+            );
+
+            assert_eq!(accrual.fees.high_water_mark, None);
+        }
+    }
+
+    mod validate_fee_rates {
+        use super::*;
+
+        #[test]
+        fn accepts_max_rates() {
+            assert!(validate_fee_rates(MAX_PERFORMANCE_FEE_BPS, MAX_MANAGEMENT_FEE_BPS).is_ok());
+        }
+
+        #[test]
+        fn rejects_rates_above_max() {
+            assert!(validate_fee_rates(MAX_PERFORMANCE_FEE_BPS + 1, 0).is_err());
+            assert!(validate_fee_rates(0, MAX_MANAGEMENT_FEE_BPS + 1).is_err());
+        }
+    }
+}
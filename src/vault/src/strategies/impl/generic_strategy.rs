@@ -1,60 +1,73 @@
 use async_trait::async_trait;
 use candid::{CandidType, Deserialize, Nat, Principal};
+use icrc_ledger_types::icrc1::account::Account;
 use serde::Serialize;
 use std::collections::HashMap;
 
 use types::CanisterId;
-use types::strategies::{StrategyId, Pool};
-use utils::constants::ICP_TOKEN_CANISTER_ID;
+use types::strategies::{StrategyId, Pool, StrategyProfile, StrategyParamsOverrides, StrategyFees, StrategyAllocation};
 
 use crate::impl_strategy_methods;
 use crate::strategies::basic_strategy::BasicStrategy;
 use crate::strategies::strategy::IStrategy;
 use crate::strategies::strategy_candid::StrategyCandid;
-use crate::strategies::r#impl::description::STRATEGY_MAP;
 
-impl_strategy_methods!(ICPStrategy);
+/// Strategy parameterized by its definition in the strategy catalogue.
 #[derive(Clone, Debug, CandidType, Serialize, Deserialize)]
-pub struct ICPStrategy {
+pub struct GenericStrategy {
     id: StrategyId,
-    base_token: CanisterId,
     current_pool: Option<Pool>,
     position_id: Option<u64>,
     total_balance: Nat,
     total_shares: Nat,
+    /// Positions per principal of the strategies saved before the positions were kept per account
     user_shares: HashMap<Principal, Nat>,
     initial_deposit: HashMap<Principal, Nat>,
+    account_shares: Option<HashMap<Account, Nat>>,
+    account_initial_deposit: Option<HashMap<Account, Nat>>,
     current_liquidity: Option<Nat>,
     current_liquidity_updated_at: Option<u64>,
     enabled: bool,
+    profile: Option<StrategyProfile>,
+    params_overrides: Option<StrategyParamsOverrides>,
+    fees: Option<StrategyFees>,
+    allocations: Option<Vec<StrategyAllocation>>,
+    /// Base token held by the vault for the strategy outside of its positions
+    uninvested_balance: Option<Nat>,
 }
 
-impl ICPStrategy {
-    pub fn new() -> Self {
-        //TODO move to config
-        ICPStrategy {
-            id: 2,
-            base_token: *ICP_TOKEN_CANISTER_ID,
+impl_strategy_methods!(GenericStrategy);
+
+impl GenericStrategy {
+    pub fn new(id: StrategyId) -> Self {
+        GenericStrategy {
+            id,
             current_pool: None,
             position_id: None,
             total_balance: Nat::from(0u64),
             total_shares: Nat::from(0u64),
             user_shares: HashMap::new(),
             initial_deposit: HashMap::new(),
+            account_shares: None,
+            account_initial_deposit: None,
             current_liquidity: None,
             current_liquidity_updated_at: None,
             enabled: false,
+            profile: None,
+            params_overrides: None,
+            fees: None,
+            allocations: None,
+            uninvested_balance: None,
         }
     }
 }
 
 #[async_trait]
-impl IStrategy for ICPStrategy {
+impl IStrategy for GenericStrategy {
     fn to_candid(&self) -> StrategyCandid {
-        StrategyCandid::ICPStrategyV(self.clone())
+        StrategyCandid::GenericStrategyV(self.clone())
     }
 
-
     fn clone_self(&self) -> Box<dyn IStrategy> {
         Box::new(self.clone())
     }
@@ -1,11 +1,2 @@
-pub mod ck_btc_strategy;
-pub mod icp_strategy;
 pub mod description;
-pub mod panda_icp_stategy;
-pub mod ics_icp_strategy;
-pub mod icp_ck_usdt_strategy;
-pub mod ck_btc_ck_usdt_strategy;
-pub mod ck_btc_icp_strategy;
-pub mod icp_ck_eth_strategy;
-pub mod gldt_ck_usdt_strategy;
-pub mod ck_link_icp_strategy;
+pub mod generic_strategy;
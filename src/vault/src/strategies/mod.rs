@@ -1,8 +1,19 @@
 pub mod strategy;
-mod r#impl;
+pub mod r#impl;
 pub mod strategy_service;
 pub mod strategy_candid;
 pub mod basic_strategy;
 pub mod test;
 pub mod stats;
 pub mod smart_rebalance_service;
+pub mod rebalance_scheduler_service;
+pub mod strategy_lock_service;
+pub mod fee_service;
+pub mod allocation_service;
+pub mod compounding_service;
+pub mod preview_service;
+pub mod strategy_definitions_service;
+pub mod emergency_service;
+pub mod unwind_service;
+pub mod share_price_service;
+pub mod share_token_service;
@@ -0,0 +1,356 @@
+use candid::Nat;
+use icrc_ledger_types::icrc1::account::Account;
+
+use types::CanisterId;
+use types::strategies::{Pool, StrategyAllocation};
+use utils::util::nat_to_u64;
+use liquidity::liquidity_router;
+use swap::swap_service;
+use errors::internal_error::error::{InternalError, InternalErrorKind};
+use errors::internal_error::error_codes::module::areas::{
+    canisters as canister_area,
+    canisters::domains::vault as vault_domain,
+    canisters::domains::vault::components as vault_domain_components,
+};
+
+use crate::strategies::strategy::IStrategy;
+use crate::strategies::allocation_service;
+use crate::types::types::{StrategyDepositPreview, StrategyWithdrawPreview};
+use crate::user::zap_service;
+use crate::utils::service_resolver::get_service_resolver;
+
+// Module code: "03-01-08"
+errors::define_error_code_builder_fn!(
+    build_error_code,
+    canister_area::AREA_CODE,        // Area code: "03"
+    vault_domain::DOMAIN_CODE,       // Domain code: "01"
+    vault_domain_components::PREVIEW // Component code: "08"
+);
+
+const BPS: u64 = 10_000;
+/// Share of the amount quoted to get the price before impact
+const REFERENCE_AMOUNT_DIVISOR: u64 = 100;
+
+/// Quote of a swap with the amount it would return at the price before impact.
+struct PriceQuote {
+    amount_out: Nat,
+    ideal_amount_out: Nat,
+}
+
+/// Estimates the shares a deposit would get at the current NAV.
+///
+/// A deposit in another token is quoted to the base token after the swap fees,
+/// then every pool is charged the impact of swapping half of its part to token1.
+/// The shares are minted like the deposit does, on the token0 equivalent of what is added to the pools:
+/// the token0 kept plus the token1 received, valued at the price before impact.
+pub async fn preview_deposit(
+    strategy: &dyn IStrategy,
+    ledger: CanisterId,
+    amount: Nat,
+) -> Result<StrategyDepositPreview, InternalError> {
+    let base_token = strategy.get_base_token();
+
+    let mut allocations = strategy.get_allocations();
+
+    if allocations.is_empty() {
+        let pool = strategy.get_best_apy_pool().await
+            .ok_or_else(|| {
+                InternalError::not_found(
+                    build_error_code(InternalErrorKind::NotFound, 1), // Error code: "03-01-08 01 01"
+                    "preview_service::preview_deposit".to_string(),
+                    "No pool found to deposit".to_string(),
+                    errors::error_extra! {
+                        "strategy_id" => strategy.get_id(),
+                        "amount" => amount,
+                    },
+                )
+            })?;
+
+        allocations = vec![StrategyAllocation {
+            pool,
+            position_id: None,
+            target_weight_bps: allocation_service::TOTAL_WEIGHT_BPS,
+            lp_amount: None,
+        }];
+    }
+
+    let mut base_amount = amount.clone();
+    let mut ideal_amount = amount.clone();
+    let mut fees = Nat::from(0u64);
+
+    if ledger != base_token {
+        let ledger_fee = get_service_resolver().icrc_ledger_client()
+            .icrc1_fee(ledger)
+            .await?;
+
+        let amount_in = zap_service::calculate_zap_amount_in(&amount, &ledger_fee)
+            .ok_or_else(|| {
+                InternalError::validation(
+                    build_error_code(InternalErrorKind::Validation, 2), // Error code: "03-01-08 02 02"
+                    "preview_service::preview_deposit".to_string(),
+                    "Amount doesn't cover the swap fees".to_string(),
+                    errors::error_extra! {
+                        "strategy_id" => strategy.get_id(),
+                        "ledger" => ledger,
+                        "amount" => amount,
+                        "ledger_fee" => ledger_fee,
+                    },
+                )
+            })?;
+
+        let zap_quote = quote_with_price_impact(ledger, base_token, amount_in.clone()).await?;
+
+        // Fees are valued in base token at the price before impact
+        fees = convert_at_ideal_price(&(amount.clone() - amount_in.clone()), &amount_in, &zap_quote.ideal_amount_out);
+        ideal_amount = zap_quote.ideal_amount_out.clone() + fees.clone();
+        base_amount = zap_quote.amount_out;
+    }
+
+    let allocation_amounts = allocation_service::split_amount(&base_amount, &allocations);
+    let mut token0_equivalent_total = Nat::from(0u64);
+
+    for (allocation, allocation_amount) in allocations.iter().zip(allocation_amounts) {
+        let token0_for_swap = allocation_amount.clone() / Nat::from(2u64);
+
+        if token0_for_swap == Nat::from(0u64) {
+            token0_equivalent_total += allocation_amount;
+            continue;
+        }
+
+        let quote = quote_with_price_impact(
+            allocation.pool.token0,
+            allocation.pool.token1,
+            token0_for_swap.clone(),
+        ).await?;
+
+        // The token1 received is valued at the price before impact
+        let token1_value = convert_at_ideal_price(&quote.amount_out, &quote.ideal_amount_out, &token0_for_swap);
+
+        token0_equivalent_total += allocation_amount - token0_for_swap + token1_value;
+    }
+
+    let nav = strategy.get_nav().await?;
+    let shares = strategy.calculate_deposit_shares(token0_equivalent_total.clone(), nav);
+
+    Ok(StrategyDepositPreview {
+        shares,
+        amount: token0_equivalent_total.clone(),
+        fees: fees.clone(),
+        price_impact_bps: calculate_price_impact_bps(&ideal_amount, &(token0_equivalent_total + fees)),
+        pool: get_primary_pool(&allocations),
+    })
+}
+
+/// Estimates the base token amount a withdrawal of a percentage of the shares of a user account would return.
+///
+/// The token1 part of every position is quoted to the base token after the fees reserved for the swap.
+pub async fn preview_withdraw(
+    strategy: &dyn IStrategy,
+    account: Account,
+    percentage: Nat,
+) -> Result<StrategyWithdrawPreview, InternalError> {
+    let user_shares = strategy.get_user_shares_by_account(account);
+    let shares = user_shares.clone() * percentage.clone() / Nat::from(100u64);
+    let total_shares = strategy.get_total_shares();
+
+    if shares == Nat::from(0u64) || shares > user_shares {
+        return Err(InternalError::business_logic(
+            build_error_code(InternalErrorKind::BusinessLogic, 3), // Error code: "03-01-08 03 03"
+            "preview_service::preview_withdraw".to_string(),
+            "Not sufficient shares for user".to_string(),
+            errors::error_extra! {
+                "strategy_id" => strategy.get_id(),
+                "account" => account,
+                "percentage" => percentage,
+                "user_shares" => user_shares,
+            },
+        ));
+    }
+
+    let allocations: Vec<StrategyAllocation> = strategy.get_allocations()
+        .into_iter()
+        .filter(|allocation| allocation.position_id.is_some())
+        .collect();
+
+    if allocations.is_empty() {
+        return Err(InternalError::not_found(
+            build_error_code(InternalErrorKind::NotFound, 4), // Error code: "03-01-08 01 04"
+            "preview_service::preview_withdraw".to_string(),
+            "No invested allocation found in strategy".to_string(),
+            errors::error_extra! {
+                "strategy_id" => strategy.get_id(),
+            },
+        ));
+    }
+
+    let service_resolver = get_service_resolver();
+    let mut fees = Nat::from(0u64);
+
+    // Base token held outside of the positions is paid out without a swap
+    let uninvested_amount = strategy.get_uninvested_balance() * shares.clone() / total_shares.clone();
+    let mut expected_amount = uninvested_amount.clone();
+    let mut ideal_amount = uninvested_amount;
+
+    for allocation in allocations.iter() {
+        let pool = allocation.pool.clone();
+
+        let liquidity_client = liquidity_router::get_liquidity_client(
+            service_resolver.provider_impls(),
+            service_resolver.icrc_ledger_client(),
+            pool.token0,
+            pool.token1,
+            pool.provider,
+        ).await;
+
+        let position = liquidity_client.get_position(allocation.liquidity_position()).await?;
+
+        let token0_amount = position.token_0_amount * shares.clone() / total_shares.clone();
+        let token1_amount = position.token_1_amount * shares.clone() / total_shares.clone();
+
+        expected_amount += token0_amount.clone();
+        ideal_amount += token0_amount;
+
+        // Same reserve as the withdrawal keeps for the swap fees
+        let token1_fee = service_resolver.icrc_ledger_client()
+            .icrc1_fee(pool.token1)
+            .await?;
+        let token1_fees = token1_fee * Nat::from(2u64);
+
+        if token1_amount <= token1_fees {
+            continue;
+        }
+
+        let token1_for_swap = token1_amount.clone() - token1_fees.clone();
+        let quote = quote_with_price_impact(pool.token1, pool.token0, token1_for_swap.clone()).await?;
+
+        fees += convert_at_ideal_price(&token1_fees, &token1_for_swap, &quote.ideal_amount_out);
+        expected_amount += quote.amount_out;
+        ideal_amount += quote.ideal_amount_out;
+    }
+
+    Ok(StrategyWithdrawPreview {
+        shares,
+        amount: expected_amount.clone(),
+        fees: fees.clone(),
+        price_impact_bps: calculate_price_impact_bps(&(ideal_amount + fees.clone()), &(expected_amount + fees)),
+        pool: get_primary_pool(&allocations),
+    })
+}
+
+/// Quotes the amount and a small reference amount, to compare the quote to the price before impact.
+async fn quote_with_price_impact(
+    token_in: CanisterId,
+    token_out: CanisterId,
+    amount_in: Nat,
+) -> Result<PriceQuote, InternalError> {
+    let service_resolver = get_service_resolver();
+
+    let quote = swap_service::quote_swap_icrc2_optimal(
+        service_resolver.provider_impls(),
+        service_resolver.icrc_ledger_client(),
+        token_in,
+        token_out,
+        amount_in.clone(),
+    ).await?;
+    let amount_out = Nat::from(quote.amount_out);
+
+    let reference_amount_in = amount_in.clone() / Nat::from(REFERENCE_AMOUNT_DIVISOR);
+
+    // Too small to have a reference, the quote is taken as the price
+    if reference_amount_in == Nat::from(0u64) {
+        return Ok(PriceQuote { ideal_amount_out: amount_out.clone(), amount_out });
+    }
+
+    let reference_quote = swap_service::quote_swap_icrc2(
+        service_resolver.provider_impls(),
+        service_resolver.icrc_ledger_client(),
+        token_in,
+        token_out,
+        reference_amount_in.clone(),
+        quote.provider,
+    ).await?;
+
+    let ideal_amount_out = convert_at_ideal_price(
+        &amount_in,
+        &reference_amount_in,
+        &Nat::from(reference_quote.amount_out),
+    ).max(amount_out.clone());
+
+    Ok(PriceQuote { amount_out, ideal_amount_out })
+}
+
+fn get_primary_pool(allocations: &[StrategyAllocation]) -> Pool {
+    allocation_service::get_primary_allocation(allocations)
+        .map(|allocation| allocation.pool.clone())
+        .unwrap()
+}
+
+/// Converts an amount with the price given by a pair of amounts.
+fn convert_at_ideal_price(amount: &Nat, price_amount_in: &Nat, price_amount_out: &Nat) -> Nat {
+    if *price_amount_in == Nat::from(0u64) {
+        return Nat::from(0u64);
+    }
+
+    amount.clone() * price_amount_out.clone() / price_amount_in.clone()
+}
+
+/// Share of the value lost to the price impact, in basis points.
+pub fn calculate_price_impact_bps(ideal_amount: &Nat, expected_amount: &Nat) -> u64 {
+    if *ideal_amount == Nat::from(0u64) || expected_amount >= ideal_amount {
+        return 0;
+    }
+
+    let lost_amount = ideal_amount.clone() - expected_amount.clone();
+    let impact_bps = lost_amount * Nat::from(BPS) / ideal_amount.clone();
+
+    nat_to_u64(&impact_bps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod calculate_price_impact_bps {
+        use super::*;
+
+        #[test]
+        fn returns_lost_share_in_bps() {
+            let impact = calculate_price_impact_bps(&Nat::from(1_000_000u64), &Nat::from(985_000u64));
+
+            assert_eq!(impact, 150);
+        }
+
+        #[test]
+        fn returns_zero_when_nothing_is_lost() {
+            assert_eq!(calculate_price_impact_bps(&Nat::from(1_000u64), &Nat::from(1_000u64)), 0);
+            assert_eq!(calculate_price_impact_bps(&Nat::from(1_000u64), &Nat::from(1_200u64)), 0);
+        }
+
+        #[test]
+        fn returns_zero_for_zero_ideal_amount() {
+            assert_eq!(calculate_price_impact_bps(&Nat::from(0u64), &Nat::from(0u64)), 0);
+        }
+    }
+
+    mod convert_at_ideal_price {
+        use super::*;
+
+        #[test]
+        fn converts_with_the_price_of_the_pair() {
+            let converted = convert_at_ideal_price(
+                &Nat::from(500u64),
+                &Nat::from(100u64),
+                &Nat::from(250u64),
+            );
+
+            assert_eq!(converted, Nat::from(1_250u64));
+        }
+
+        #[test]
+        fn returns_zero_without_price() {
+            let converted = convert_at_ideal_price(&Nat::from(500u64), &Nat::from(0u64), &Nat::from(250u64));
+
+            assert_eq!(converted, Nat::from(0u64));
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::cell::RefCell;
+use std::time::Duration;
+use ic_cdk_timers::TimerId;
+
+use types::strategies::StrategyId;
+use utils::util::current_timestamp_secs;
+use errors::internal_error::error::{InternalError, InternalErrorKind};
+use errors::internal_error::error_codes::module::areas::{
+    canisters as canister_area,
+    canisters::domains::vault as vault_domain,
+    canisters::domains::vault::components as vault_domain_components,
+};
+
+use crate::repository::strategies_repo;
+use crate::repository::rebalance_schedules_repo::{self, RebalanceSchedule};
+use crate::strategies::strategy::IStrategy;
+use crate::strategies::strategy_lock_service::{self, StrategyOperation};
+use crate::strategies::emergency_service;
+use crate::types::types::{
+    RebalanceScheduleResponse,
+    StrategyRebalanceResponse,
+    UpdateRebalanceScheduleArgs,
+};
+
+// Module code: "03-01-03"
+errors::define_error_code_builder_fn!(
+    build_error_code,
+    canister_area::AREA_CODE,          // Area code: "03"
+    vault_domain::DOMAIN_CODE,         // Domain code: "01"
+    vault_domain_components::REBALANCE // Component code: "03"
+);
+
+thread_local! {
+    static REBALANCE_SCHEDULER_TIMER_ID: RefCell<Option<TimerId>> = RefCell::new(None);
+}
+
+fn set_timer_interval(
+    interval: Duration,
+    func: impl FnMut() + 'static,
+) -> TimerId {
+    ic_cdk_timers::set_timer_interval(interval, func)
+}
+
+/// Starts the timer that rebalances every strategy whose schedule is due.
+///
+/// The interval only defines how often schedules are checked,
+/// the cadence of each strategy is defined by its cooldown.
+pub fn start_rebalance_scheduler_timer(interval: u64) {
+    let timer_id = set_timer_interval(Duration::from_secs(interval), || {
+        ic_cdk::spawn(async {
+            run_due_rebalances().await;
+        });
+    });
+
+    REBALANCE_SCHEDULER_TIMER_ID.with(|cell| {
+        cell.replace(Some(timer_id));
+    });
+}
+
+pub fn stop_rebalance_scheduler_timer() {
+    REBALANCE_SCHEDULER_TIMER_ID.with(|timer_id| {
+        if let Some(timer_id) = timer_id.borrow_mut().take() {
+            ic_cdk_timers::clear_timer(timer_id);
+        }
+    });
+}
+
+pub async fn run_due_rebalances() {
+    let now = current_timestamp_secs();
+
+    let strategy_ids = strategies_repo::get_enabled_strategies()
+        .into_iter()
+        // Strategies without a current pool have nothing to rebalance yet
+        .filter(|strategy| strategy.get_current_pool().is_some())
+        .filter(|strategy| {
+            let schedule = rebalance_schedules_repo::get_schedule(strategy.get_id());
+            schedule.enabled && is_due(&schedule, strategy.get_rebalance_params().cooldown_secs, now)
+        })
+        .map(|strategy| strategy.get_id())
+        .collect::<Vec<_>>();
+
+    for strategy_id in strategy_ids {
+        let _ = rebalance_strategy(strategy_id).await;
+    }
+}
+
+/// Rebalances the strategy unless its rebalances are paused
+/// or another operation of the same strategy is already running.
+/// The result is recorded in the strategy schedule.
+pub async fn rebalance_strategy(
+    strategy_id: StrategyId
+) -> Result<StrategyRebalanceResponse, InternalError> {
+    emergency_service::check_operation_allowed(strategy_id, StrategyOperation::Rebalance)?;
+
+    let _lock = strategy_lock_service::lock_strategy(
+        strategy_id,
+        None,
+        StrategyOperation::Rebalance,
+    )?;
+
+    // Read under the lock, so the strategy can't be changed by a deposit or withdraw during the rebalance
+    let mut strategy = strategies_repo::get_strategy_by_id(strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(InternalErrorKind::NotFound, 1), // Error code: "03-01-03 01 01"
+                "rebalance_scheduler_service::rebalance_strategy".to_string(),
+                "Strategy not found".to_string(),
+                errors::error_extra! {
+                    "strategy_id" => strategy_id,
+                },
+            )
+        })?;
+
+    let mut schedule = rebalance_schedules_repo::get_schedule(strategy_id);
+    schedule.last_run_at = Some(current_timestamp_secs());
+    rebalance_schedules_repo::save_schedule(strategy_id, schedule);
+
+    let result = strategy.rebalance().await;
+
+    // Re-read the schedule since it could be changed by an admin during the rebalance
+    let mut schedule = rebalance_schedules_repo::get_schedule(strategy_id);
+    schedule.last_error = result.as_ref().err().map(|error| error.message.clone());
+    rebalance_schedules_repo::save_schedule(strategy_id, schedule);
+
+    result
+}
+
+pub fn get_rebalance_schedules() -> Vec<RebalanceScheduleResponse> {
+    strategies_repo::get_all_strategies()
+        .into_iter()
+        .map(|strategy| build_schedule_response(strategy.as_ref()))
+        .collect()
+}
+
+pub fn update_rebalance_schedule(
+    args: UpdateRebalanceScheduleArgs
+) -> Result<RebalanceScheduleResponse, InternalError> {
+    let strategy_id = args.strategy_id;
+
+    let strategy = strategies_repo::get_strategy_by_id(strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(InternalErrorKind::NotFound, 3), // Error code: "03-01-03 01 03"
+                "rebalance_scheduler_service::update_rebalance_schedule".to_string(),
+                "Strategy not found".to_string(),
+                errors::error_extra! {
+                    "args" => args,
+                },
+            )
+        })?;
+
+    let mut schedule = rebalance_schedules_repo::get_schedule(strategy_id);
+    schedule.enabled = args.enabled;
+    rebalance_schedules_repo::save_schedule(strategy_id, schedule);
+
+    Ok(build_schedule_response(strategy.as_ref()))
+}
+
+fn build_schedule_response(strategy: &dyn IStrategy) -> RebalanceScheduleResponse {
+    let strategy_id = strategy.get_id();
+    let schedule = rebalance_schedules_repo::get_schedule(strategy_id);
+    // The cadence of the schedule is the cooldown of the strategy profile
+    let cooldown_secs = strategy.get_rebalance_params().cooldown_secs;
+
+    RebalanceScheduleResponse {
+        strategy_id,
+        enabled: schedule.enabled,
+        cooldown_secs,
+        last_run_at: schedule.last_run_at,
+        next_run_at: get_next_run_at(&schedule, cooldown_secs),
+        last_error: schedule.last_error,
+        in_progress: strategy_lock_service::get_locked_operation(strategy_id)
+            == Some(StrategyOperation::Rebalance),
+    }
+}
+
+/// Returns `None` if the schedule has never run, i.e. it is due immediately.
+fn get_next_run_at(schedule: &RebalanceSchedule, cooldown_secs: u64) -> Option<u64> {
+    schedule.last_run_at.map(|last_run_at| last_run_at.saturating_add(cooldown_secs))
+}
+
+fn is_due(schedule: &RebalanceSchedule, cooldown_secs: u64, now: u64) -> bool {
+    get_next_run_at(schedule, cooldown_secs)
+        .map_or(true, |next_run_at| now >= next_run_at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule_with_last_run(last_run_at: Option<u64>) -> RebalanceSchedule {
+        RebalanceSchedule {
+            last_run_at,
+            ..RebalanceSchedule::default()
+        }
+    }
+
+    mod is_due {
+        use super::*;
+
+        #[test]
+        fn is_due_if_never_run() {
+            assert!(is_due(&schedule_with_last_run(None), 3600, 0));
+        }
+
+        #[test]
+        fn is_not_due_during_cooldown() {
+            assert!(!is_due(&schedule_with_last_run(Some(1_000)), 3600, 4_599));
+        }
+
+        #[test]
+        fn is_due_after_cooldown() {
+            assert!(is_due(&schedule_with_last_run(Some(1_000)), 3600, 4_600));
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::ops::Range;
+use candid::Nat;
+
+use types::strategies::StrategyId;
+use utils::util::current_timestamp_secs;
+
+use crate::repository::share_prices_repo::{self, SharePricePoint, SharePriceSource};
+use crate::strategies::fee_service;
+use crate::types::types::{ListItemsPaginationRequest, ListItemsPaginationResponse, SortOrder};
+
+pub const MAX_SHARE_PRICE_HISTORY_PAGE_SIZE: u64 = 1_000;
+
+/// Records the value of a share of the strategy, nothing is recorded while the strategy has no shares.
+pub fn record_share_price(
+    strategy_id: StrategyId,
+    nav: &Nat,
+    total_shares: &Nat,
+    source: SharePriceSource,
+) {
+    if *total_shares == Nat::from(0u64) {
+        return;
+    }
+
+    share_prices_repo::add_share_price_point(strategy_id, SharePricePoint {
+        timestamp: current_timestamp_secs(),
+        share_price: fee_service::calculate_share_price(nav, total_shares),
+        nav: nav.clone(),
+        total_shares: total_shares.clone(),
+        source,
+    });
+}
+
+/// Returns a page of the share price series of a strategy, with the last point of every interval.
+///
+/// A zero interval returns every recorded point, only the points of the page are read then.
+pub fn get_share_price_history(
+    strategy_id: StrategyId,
+    from_timestamp: u64,
+    to_timestamp: u64,
+    interval_secs: u64,
+    pagination: ListItemsPaginationRequest,
+) -> ListItemsPaginationResponse<SharePricePoint> {
+    let sequences = share_prices_repo::get_share_price_sequences(strategy_id, from_timestamp, to_timestamp);
+
+    let (mut items, total) = if interval_secs == 0 {
+        let total = sequences.end - sequences.start;
+        let page = page_range(total, &pagination);
+        let points = share_prices_repo::get_share_price_points(
+            strategy_id,
+            sequences.start + page.start..sequences.start + page.end,
+        );
+
+        (points, total)
+    } else {
+        let points = downsample(share_prices_repo::get_share_price_points(strategy_id, sequences), interval_secs);
+        let total = points.len() as u64;
+        let page = page_range(total, &pagination);
+
+        (points[page.start as usize..page.end as usize].to_vec(), total)
+    };
+
+    if let SortOrder::Desc = pagination.sort_order {
+        items.reverse();
+    }
+
+    ListItemsPaginationResponse {
+        items,
+        total,
+        page: pagination.page,
+        page_size: pagination.page_size,
+    }
+}
+
+// Positions of the page items within the chronological series
+fn page_range(total: u64, pagination: &ListItemsPaginationRequest) -> Range<u64> {
+    let offset = pagination.page.saturating_sub(1).saturating_mul(pagination.page_size);
+
+    match pagination.sort_order {
+        SortOrder::Asc => {
+            let start = offset.min(total);
+
+            start..start.saturating_add(pagination.page_size).min(total)
+        }
+        SortOrder::Desc => {
+            let end = total.saturating_sub(offset);
+
+            end.saturating_sub(pagination.page_size)..end
+        }
+    }
+}
+
+fn downsample(points: Vec<SharePricePoint>, interval_secs: u64) -> Vec<SharePricePoint> {
+    if interval_secs == 0 {
+        return points;
+    }
+
+    let mut sampled: Vec<SharePricePoint> = Vec::new();
+
+    for point in points {
+        let is_same_interval = sampled.last()
+            .map_or(false, |last| last.timestamp / interval_secs == point.timestamp / interval_secs);
+
+        if is_same_interval {
+            sampled.pop();
+        }
+
+        sampled.push(point);
+    }
+
+    sampled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(timestamp: u64) -> SharePricePoint {
+        SharePricePoint {
+            timestamp,
+            share_price: Nat::from(timestamp),
+            nav: Nat::from(1_000u64),
+            total_shares: Nat::from(1_000u64),
+            source: SharePriceSource::Deposit,
+        }
+    }
+
+    mod page_range {
+        use super::*;
+
+        fn pagination(page: u64, page_size: u64, sort_order: SortOrder) -> ListItemsPaginationRequest {
+            ListItemsPaginationRequest {
+                page,
+                page_size,
+                sort_order,
+                search: None,
+            }
+        }
+
+        #[test]
+        fn returns_pages_from_oldest_point() {
+            assert_eq!(page_range(5, &pagination(1, 2, SortOrder::Asc)), 0..2);
+            assert_eq!(page_range(5, &pagination(3, 2, SortOrder::Asc)), 4..5);
+            assert_eq!(page_range(5, &pagination(4, 2, SortOrder::Asc)), 5..5);
+        }
+
+        #[test]
+        fn returns_pages_from_latest_point() {
+            assert_eq!(page_range(5, &pagination(1, 2, SortOrder::Desc)), 3..5);
+            assert_eq!(page_range(5, &pagination(3, 2, SortOrder::Desc)), 0..1);
+            assert_eq!(page_range(5, &pagination(4, 2, SortOrder::Desc)), 0..0);
+        }
+
+        #[test]
+        fn saturates_offset_of_large_page() {
+            assert_eq!(page_range(5, &pagination(u64::MAX, 2, SortOrder::Asc)), 5..5);
+            assert_eq!(page_range(5, &pagination(u64::MAX, 2, SortOrder::Desc)), 0..0);
+        }
+    }
+
+    mod downsample {
+        use super::*;
+
+        #[test]
+        fn keeps_last_point_of_every_interval() {
+            let points = vec![point(0), point(30), point(59), point(60), point(150)];
+
+            let timestamps: Vec<u64> = downsample(points, 60)
+                .iter()
+                .map(|point| point.timestamp)
+                .collect();
+
+            assert_eq!(timestamps, vec![59, 60, 150]);
+        }
+
+        #[test]
+        fn keeps_every_point_without_interval() {
+            let points = vec![point(0), point(1), point(2)];
+
+            assert_eq!(downsample(points.clone(), 0), points);
+        }
+    }
+}
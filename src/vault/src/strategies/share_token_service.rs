@@ -0,0 +1,705 @@
+use candid::{Nat, Principal};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::icrc1::transfer::{Memo, TransferArg, TransferError};
+use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
+use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
+use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
+
+use types::context::{Context, DEFAULT_SUBACCOUNT};
+use types::strategies::StrategyId;
+
+use crate::repository::strategies_repo;
+use crate::repository::token_prices_repo;
+use crate::repository::share_token_repo::{
+    self,
+    ShareAllowance,
+    ShareAllowanceKey,
+    ShareTokenOperation,
+    ShareTokenTransaction,
+};
+use crate::strategies::strategy::IStrategy;
+use crate::strategies::strategy_lock_service;
+use crate::types::types::StrategyShareToken;
+use crate::user::user_service;
+
+// Prefix of the symbol of the share token of every strategy
+const SHARE_TOKEN_SYMBOL: &str = "VSHARES";
+
+// The share subaccount of a position is the prefix followed by the strategy ID, zeros and
+// the index of the position subaccount in its last 8 bytes, zero for the default account
+const SHARE_SUBACCOUNT_PREFIX: &[u8] = b"vault-shares";
+const POSITION_INDEX_OFFSET: usize = 24;
+const TRANSACTION_WINDOW_NANOS: u64 = 24 * 3600 * 1_000_000_000; // 1 day
+const PERMITTED_DRIFT_NANOS: u64 = 60 * 1_000_000_000; // 1 minute
+const MAX_MEMO_LENGTH: usize = 32;
+
+// Codes of the generic errors
+const INVALID_SHARE_ACCOUNT_ERROR: u64 = 1;
+const DIFFERENT_STRATEGIES_ERROR: u64 = 2;
+const STRATEGY_NOT_FOUND_ERROR: u64 = 3;
+const MEMO_TOO_LONG_ERROR: u64 = 4;
+const SELF_APPROVAL_ERROR: u64 = 5;
+
+/// Errors of the share token ledger, converted to the error of the called ICRC method.
+#[derive(Clone, Debug, PartialEq)]
+enum ShareTokenError {
+    BadFee,
+    InsufficientFunds { balance: Nat },
+    InsufficientAllowance { allowance: Nat },
+    AllowanceChanged { current_allowance: Nat },
+    Expired { ledger_time: u64 },
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    Duplicate { duplicate_of: Nat },
+    TemporarilyUnavailable,
+    Generic { error_code: u64, message: String },
+}
+
+impl ShareTokenError {
+    fn generic(error_code: u64, message: &str) -> Self {
+        ShareTokenError::Generic {
+            error_code,
+            message: message.to_string(),
+        }
+    }
+
+    /// Generic error for the errors the called method doesn't define.
+    fn to_generic(&self) -> (Nat, String) {
+        match self {
+            ShareTokenError::Generic { error_code, message } => (Nat::from(*error_code), message.clone()),
+            error => (Nat::from(0u64), format!("{:?}", error)),
+        }
+    }
+}
+
+impl From<ShareTokenError> for TransferError {
+    fn from(error: ShareTokenError) -> Self {
+        match error {
+            ShareTokenError::BadFee => TransferError::BadFee { expected_fee: Nat::from(0u64) },
+            ShareTokenError::InsufficientFunds { balance } => TransferError::InsufficientFunds { balance },
+            ShareTokenError::TooOld => TransferError::TooOld,
+            ShareTokenError::CreatedInFuture { ledger_time } => TransferError::CreatedInFuture { ledger_time },
+            ShareTokenError::Duplicate { duplicate_of } => TransferError::Duplicate { duplicate_of },
+            ShareTokenError::TemporarilyUnavailable => TransferError::TemporarilyUnavailable,
+            error => {
+                let (error_code, message) = error.to_generic();
+                TransferError::GenericError { error_code, message }
+            }
+        }
+    }
+}
+
+impl From<ShareTokenError> for TransferFromError {
+    fn from(error: ShareTokenError) -> Self {
+        match error {
+            ShareTokenError::BadFee => TransferFromError::BadFee { expected_fee: Nat::from(0u64) },
+            ShareTokenError::InsufficientFunds { balance } => TransferFromError::InsufficientFunds { balance },
+            ShareTokenError::InsufficientAllowance { allowance } => TransferFromError::InsufficientAllowance { allowance },
+            ShareTokenError::TooOld => TransferFromError::TooOld,
+            ShareTokenError::CreatedInFuture { ledger_time } => TransferFromError::CreatedInFuture { ledger_time },
+            ShareTokenError::Duplicate { duplicate_of } => TransferFromError::Duplicate { duplicate_of },
+            ShareTokenError::TemporarilyUnavailable => TransferFromError::TemporarilyUnavailable,
+            error => {
+                let (error_code, message) = error.to_generic();
+                TransferFromError::GenericError { error_code, message }
+            }
+        }
+    }
+}
+
+impl From<ShareTokenError> for ApproveError {
+    fn from(error: ShareTokenError) -> Self {
+        match error {
+            ShareTokenError::BadFee => ApproveError::BadFee { expected_fee: Nat::from(0u64) },
+            ShareTokenError::AllowanceChanged { current_allowance } => ApproveError::AllowanceChanged { current_allowance },
+            ShareTokenError::Expired { ledger_time } => ApproveError::Expired { ledger_time },
+            ShareTokenError::TooOld => ApproveError::TooOld,
+            ShareTokenError::CreatedInFuture { ledger_time } => ApproveError::CreatedInFuture { ledger_time },
+            ShareTokenError::Duplicate { duplicate_of } => ApproveError::Duplicate { duplicate_of },
+            ShareTokenError::TemporarilyUnavailable => ApproveError::TemporarilyUnavailable,
+            error => {
+                let (error_code, message) = error.to_generic();
+                ApproveError::GenericError { error_code, message }
+            }
+        }
+    }
+}
+
+/// Movement of shares between two share accounts of a strategy.
+struct ShareTransfer {
+    from: Account,
+    to: Account,
+    spender: Option<Account>,
+    amount: Nat,
+    fee: Option<Nat>,
+    memo: Option<Memo>,
+    created_at_time: Option<u64>,
+}
+
+/// Subaccount holding the shares of a strategy deposited from the default account of the shareholder,
+/// the owner of the account is the shareholder.
+pub fn strategy_subaccount(strategy_id: StrategyId) -> Subaccount {
+    share_subaccount(strategy_id, 0)
+}
+
+/// Returns the strategy of a share subaccount, none for any other subaccount.
+pub fn get_subaccount_strategy_id(subaccount: Option<&Subaccount>) -> Option<StrategyId> {
+    let subaccount = subaccount?;
+    let prefix_length = SHARE_SUBACCOUNT_PREFIX.len();
+
+    let is_share_subaccount = &subaccount[..prefix_length] == SHARE_SUBACCOUNT_PREFIX
+        && subaccount[prefix_length + 2..POSITION_INDEX_OFFSET].iter().all(|byte| *byte == 0);
+
+    if !is_share_subaccount {
+        return None;
+    }
+
+    Some(StrategyId::from_be_bytes([subaccount[prefix_length], subaccount[prefix_length + 1]]))
+}
+
+/// Share account holding the shares of a position,
+/// none if the subaccount of the position was not registered yet.
+pub fn get_share_account(strategy_id: StrategyId, position: Account) -> Option<Account> {
+    let position_index = match position.subaccount.filter(|subaccount| *subaccount != DEFAULT_SUBACCOUNT) {
+        Some(subaccount) => share_token_repo::get_position_subaccount_index(&subaccount)?,
+        None => 0,
+    };
+
+    Some(Account {
+        owner: position.owner,
+        subaccount: Some(share_subaccount(strategy_id, position_index)),
+    })
+}
+
+/// Gives the subaccount of a position a share subaccount, so its shares can be sent and received.
+pub fn register_share_account(strategy_id: StrategyId, position: Account) -> Account {
+    if let Some(subaccount) = position.subaccount.filter(|subaccount| *subaccount != DEFAULT_SUBACCOUNT) {
+        share_token_repo::register_position_subaccount(subaccount);
+    }
+
+    get_share_account(strategy_id, position).unwrap()
+}
+
+/// Registers the subaccounts of the positions opened before they had share subaccounts.
+pub fn register_position_subaccounts() {
+    for strategy in strategies_repo::get_all_strategies() {
+        for position in strategy.get_user_shares().keys() {
+            register_share_account(strategy.get_id(), *position);
+        }
+    }
+}
+
+pub fn balance_of(account: Account) -> Nat {
+    let position = match position_account(&account) {
+        Some(position) => position,
+        None => return Nat::from(0u64),
+    };
+
+    get_subaccount_strategy_id(account.subaccount.as_ref())
+        .and_then(strategies_repo::get_strategy_by_id)
+        .map(|strategy| strategy.get_user_shares_by_account(position))
+        .unwrap_or(Nat::from(0u64))
+}
+
+/// Share token of a strategy with its own decimals and supply.
+///
+/// Shares of strategies with different base tokens are not summed, so there is no supply of all strategies.
+pub fn get_share_token(strategy_id: StrategyId) -> Option<StrategyShareToken> {
+    let strategy = strategies_repo::get_strategy_by_id(strategy_id)?;
+
+    Some(StrategyShareToken {
+        strategy_id,
+        name: strategy_name(strategy.as_ref()),
+        symbol: strategy_symbol(strategy_id),
+        decimals: strategy_decimals(strategy.as_ref()),
+        total_supply: strategy.get_total_shares(),
+        subaccount: strategy_subaccount(strategy_id),
+    })
+}
+
+/// Decimals of the shares of a strategy, those of its base token once its price was cached.
+pub fn strategy_decimals(strategy: &dyn IStrategy) -> Option<u8> {
+    token_prices_repo::get_token_price(strategy.get_base_token())
+        .map(|price| price.decimals)
+}
+
+pub fn allowance(args: AllowanceArgs) -> Allowance {
+    let (strategy_id, position) = match (
+        get_subaccount_strategy_id(args.account.subaccount.as_ref()),
+        position_account(&args.account),
+    ) {
+        (Some(strategy_id), Some(position)) => (strategy_id, position),
+        _ => return Allowance { allowance: Nat::from(0u64), expires_at: None },
+    };
+
+    let key = allowance_key(strategy_id, position, args.spender);
+
+    match get_active_allowance(&key, ic_cdk::api::time()) {
+        Some(allowance) => Allowance {
+            allowance: allowance.allowance,
+            expires_at: allowance.expires_at,
+        },
+        None => Allowance { allowance: Nat::from(0u64), expires_at: None },
+    }
+}
+
+/// Transfers shares from a share account of the caller to a share account of the same strategy.
+///
+/// The cost basis of the shares is moved with them.
+pub fn transfer(caller: Principal, args: TransferArg) -> Result<Nat, TransferError> {
+    let transfer = ShareTransfer {
+        from: Account { owner: caller, subaccount: args.from_subaccount },
+        to: args.to,
+        spender: None,
+        amount: args.amount,
+        fee: args.fee,
+        memo: args.memo,
+        created_at_time: args.created_at_time,
+    };
+
+    execute_transfer(transfer, ic_cdk::api::time()).map_err(TransferError::from)
+}
+
+/// Transfers shares of another holder within the allowance given to the caller.
+pub fn transfer_from(caller: Principal, args: TransferFromArgs) -> Result<Nat, TransferFromError> {
+    let spender = Account { owner: caller, subaccount: args.spender_subaccount };
+
+    let transfer = ShareTransfer {
+        // The holder moving its own shares doesn't need an allowance
+        spender: Some(spender).filter(|spender| *spender != args.from),
+        from: args.from,
+        to: args.to,
+        amount: args.amount,
+        fee: args.fee,
+        memo: args.memo,
+        created_at_time: args.created_at_time,
+    };
+
+    execute_transfer(transfer, ic_cdk::api::time()).map_err(TransferFromError::from)
+}
+
+/// Allows a spender to transfer up to an amount of the shares of a share account of the caller.
+pub fn approve(caller: Principal, args: ApproveArgs) -> Result<Nat, ApproveError> {
+    let now = ic_cdk::api::time();
+    let from = Account { owner: caller, subaccount: args.from_subaccount };
+
+    let strategy_id = get_subaccount_strategy_id(from.subaccount.as_ref())
+        .ok_or_else(|| ShareTokenError::generic(INVALID_SHARE_ACCOUNT_ERROR, "Account is not a share account"))?;
+    let from_position = position_account(&from)
+        .ok_or_else(|| ShareTokenError::generic(INVALID_SHARE_ACCOUNT_ERROR, "Account is not a share account"))?;
+
+    if args.spender.owner == caller {
+        return Err(ShareTokenError::generic(SELF_APPROVAL_ERROR, "Spender is the owner of the shares").into());
+    }
+
+    check_request(&args.fee, &args.memo, args.created_at_time, now)?;
+
+    if let Some(expires_at) = args.expires_at {
+        if expires_at <= now {
+            return Err(ShareTokenError::Expired { ledger_time: now }.into());
+        }
+    }
+
+    if strategies_repo::get_strategy_by_id(strategy_id).is_none() {
+        return Err(ShareTokenError::generic(STRATEGY_NOT_FOUND_ERROR, "Strategy not found").into());
+    }
+
+    let transaction = ShareTokenTransaction {
+        operation: ShareTokenOperation::Approve,
+        strategy_id,
+        from,
+        to: None,
+        spender: Some(args.spender),
+        amount: args.amount.clone(),
+        cost_basis: None,
+        expires_at: args.expires_at,
+        memo: args.memo,
+        created_at_time: args.created_at_time,
+        timestamp: now,
+    };
+
+    check_duplicate(&transaction, now)?;
+
+    let key = allowance_key(strategy_id, from_position, args.spender);
+
+    let current_allowance = get_active_allowance(&key, now)
+        .map_or(Nat::from(0u64), |allowance| allowance.allowance);
+
+    if let Some(expected_allowance) = args.expected_allowance {
+        if expected_allowance != current_allowance {
+            return Err(ShareTokenError::AllowanceChanged { current_allowance }.into());
+        }
+    }
+
+    share_token_repo::save_allowance(key, ShareAllowance {
+        allowance: args.amount,
+        expires_at: args.expires_at,
+    });
+
+    Ok(Nat::from(share_token_repo::add_transaction(transaction)))
+}
+
+// =============== Private methods ===============
+
+fn execute_transfer(transfer: ShareTransfer, now: u64) -> Result<Nat, ShareTokenError> {
+    let strategy_id = get_subaccount_strategy_id(transfer.from.subaccount.as_ref())
+        .ok_or_else(|| ShareTokenError::generic(INVALID_SHARE_ACCOUNT_ERROR, "Account is not a share account"))?;
+
+    match get_subaccount_strategy_id(transfer.to.subaccount.as_ref()) {
+        Some(to_strategy_id) if to_strategy_id == strategy_id => {}
+        Some(_) => return Err(ShareTokenError::generic(DIFFERENT_STRATEGIES_ERROR, "Accounts hold shares of different strategies")),
+        None => return Err(ShareTokenError::generic(INVALID_SHARE_ACCOUNT_ERROR, "Account is not a share account")),
+    }
+
+    let (from_position, to_position) = match (position_account(&transfer.from), position_account(&transfer.to)) {
+        (Some(from_position), Some(to_position)) => (from_position, to_position),
+        _ => return Err(ShareTokenError::generic(INVALID_SHARE_ACCOUNT_ERROR, "Account is not a share account")),
+    };
+
+    check_request(&transfer.fee, &transfer.memo, transfer.created_at_time, now)?;
+
+    let mut strategy = strategies_repo::get_strategy_by_id(strategy_id)
+        .ok_or_else(|| ShareTokenError::generic(STRATEGY_NOT_FOUND_ERROR, "Strategy not found"))?;
+
+    // The shares can't move while a deposit, withdrawal or another operation is using them
+    if strategy_lock_service::get_locked_operation(strategy_id).is_some() {
+        return Err(ShareTokenError::TemporarilyUnavailable);
+    }
+
+    let mut transaction = ShareTokenTransaction {
+        operation: ShareTokenOperation::Transfer,
+        strategy_id,
+        from: transfer.from,
+        to: Some(transfer.to),
+        spender: transfer.spender,
+        amount: transfer.amount.clone(),
+        cost_basis: None,
+        expires_at: None,
+        memo: transfer.memo,
+        created_at_time: transfer.created_at_time,
+        timestamp: now,
+    };
+
+    check_duplicate(&transaction, now)?;
+
+    let allowance = match transfer.spender {
+        Some(spender) => {
+            let key = allowance_key(strategy_id, from_position, spender);
+            let allowance = get_active_allowance(&key, now)
+                .unwrap_or(ShareAllowance { allowance: Nat::from(0u64), expires_at: None });
+
+            if allowance.allowance < transfer.amount {
+                return Err(ShareTokenError::InsufficientAllowance { allowance: allowance.allowance });
+            }
+
+            Some((key, allowance))
+        }
+        None => None,
+    };
+
+    let balance = strategy.get_user_shares_by_account(from_position);
+
+    if balance < transfer.amount {
+        return Err(ShareTokenError::InsufficientFunds { balance });
+    }
+
+    let cost_basis = move_shares(strategy.as_mut(), from_position, to_position, &transfer.amount);
+    strategies_repo::save_strategy(strategy);
+
+    if let Some((key, allowance)) = allowance {
+        share_token_repo::save_allowance(key, ShareAllowance {
+            allowance: allowance.allowance - transfer.amount.clone(),
+            expires_at: allowance.expires_at,
+        });
+    }
+
+    transaction.cost_basis = Some(cost_basis.clone());
+    let block_index = Nat::from(share_token_repo::add_transaction(transaction));
+
+    // Transfers between positions of the same owner move the cost basis too
+    if from_position != to_position {
+        user_service::record_shares_transferred(
+            from_position,
+            to_position,
+            &Context::generate(Some(from_position.owner), Some(strategy_id)),
+            transfer.amount,
+            cost_basis,
+            block_index.clone(),
+        );
+    }
+
+    Ok(block_index)
+}
+
+fn share_subaccount(strategy_id: StrategyId, position_index: u64) -> Subaccount {
+    let mut subaccount = [0u8; 32];
+    let prefix_length = SHARE_SUBACCOUNT_PREFIX.len();
+
+    subaccount[..prefix_length].copy_from_slice(SHARE_SUBACCOUNT_PREFIX);
+    subaccount[prefix_length..prefix_length + 2].copy_from_slice(&strategy_id.to_be_bytes());
+    subaccount[POSITION_INDEX_OFFSET..].copy_from_slice(&position_index.to_be_bytes());
+
+    subaccount
+}
+
+/// Position holding the shares of a share account, none for an unknown position subaccount.
+///
+/// The index of the share subaccount gives the subaccount of the position,
+/// zero is the default account of the shareholder.
+fn position_account(share_account: &Account) -> Option<Account> {
+    let share_subaccount = share_account.subaccount?;
+    let mut position_index = [0u8; 8];
+    position_index.copy_from_slice(&share_subaccount[POSITION_INDEX_OFFSET..]);
+
+    let subaccount = match u64::from_be_bytes(position_index) {
+        0 => None,
+        index => Some(share_token_repo::get_position_subaccount(index)?),
+    };
+
+    Some(Account { owner: share_account.owner, subaccount })
+}
+
+fn allowance_key(strategy_id: StrategyId, owner: Account, spender: Account) -> ShareAllowanceKey {
+    ShareAllowanceKey {
+        strategy_id,
+        owner: owner.owner,
+        spender,
+        subaccount: owner.subaccount,
+    }
+}
+
+/// Moves shares between two positions with their part of the cost basis of the sender, returns the moved cost basis.
+fn move_shares(strategy: &mut dyn IStrategy, from: Account, to: Account, shares: &Nat) -> Nat {
+    let from_shares = strategy.get_user_shares().get(&from).cloned().unwrap_or(Nat::from(0u64));
+    let from_cost_basis = strategy.get_initial_deposit().get(&from).cloned().unwrap_or(Nat::from(0u64));
+
+    let cost_basis = if from_shares == Nat::from(0u64) {
+        Nat::from(0u64)
+    } else {
+        from_cost_basis * shares.clone() / from_shares
+    };
+
+    strategy.decrease_user_shares(from, shares.clone());
+    strategy.decrease_initial_deposit(from, cost_basis.clone());
+    strategy.increase_user_shares(to, shares.clone());
+    strategy.increase_initial_deposit(to, cost_basis.clone());
+
+    cost_basis
+}
+
+/// Checks the fee, the memo and the creation time of a request.
+fn check_request(
+    fee: &Option<Nat>,
+    memo: &Option<Memo>,
+    created_at_time: Option<u64>,
+    now: u64,
+) -> Result<(), ShareTokenError> {
+    if fee.as_ref().map_or(false, |fee| *fee != Nat::from(0u64)) {
+        return Err(ShareTokenError::BadFee);
+    }
+
+    if memo.as_ref().map_or(false, |memo| memo.0.len() > MAX_MEMO_LENGTH) {
+        return Err(ShareTokenError::generic(MEMO_TOO_LONG_ERROR, "Memo is longer than 32 bytes"));
+    }
+
+    if let Some(created_at_time) = created_at_time {
+        if created_at_time.saturating_add(TRANSACTION_WINDOW_NANOS + PERMITTED_DRIFT_NANOS) < now {
+            return Err(ShareTokenError::TooOld);
+        }
+
+        if created_at_time > now.saturating_add(PERMITTED_DRIFT_NANOS) {
+            return Err(ShareTokenError::CreatedInFuture { ledger_time: now });
+        }
+    }
+
+    Ok(())
+}
+
+/// Rejects a request with a creation time already applied in the deduplication window.
+fn check_duplicate(transaction: &ShareTokenTransaction, now: u64) -> Result<(), ShareTokenError> {
+    if transaction.created_at_time.is_none() {
+        return Ok(());
+    }
+
+    let since_timestamp = now.saturating_sub(TRANSACTION_WINDOW_NANOS + PERMITTED_DRIFT_NANOS);
+
+    let duplicate = share_token_repo::find_transaction_since(since_timestamp, |recorded| {
+        is_same_request(recorded, transaction)
+    });
+
+    match duplicate {
+        Some(duplicate_of) => Err(ShareTokenError::Duplicate { duplicate_of: Nat::from(duplicate_of) }),
+        None => Ok(()),
+    }
+}
+
+fn is_same_request(recorded: &ShareTokenTransaction, transaction: &ShareTokenTransaction) -> bool {
+    recorded.operation == transaction.operation
+        && recorded.strategy_id == transaction.strategy_id
+        && recorded.from == transaction.from
+        && recorded.to == transaction.to
+        && recorded.spender == transaction.spender
+        && recorded.amount == transaction.amount
+        && recorded.expires_at == transaction.expires_at
+        && recorded.memo == transaction.memo
+        && recorded.created_at_time == transaction.created_at_time
+}
+
+fn get_active_allowance(key: &ShareAllowanceKey, now: u64) -> Option<ShareAllowance> {
+    share_token_repo::get_allowance(key)
+        .filter(|allowance| allowance.expires_at.map_or(true, |expires_at| expires_at > now))
+}
+
+pub fn strategy_symbol(strategy_id: StrategyId) -> String {
+    format!("{}-{}", SHARE_TOKEN_SYMBOL, strategy_id)
+}
+
+fn strategy_name(strategy: &dyn IStrategy) -> String {
+    format!("{} Shares", strategy.get_name())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::strategies::basic_strategy::BasicStrategy;
+    use crate::strategies::r#impl::generic_strategy::GenericStrategy;
+
+    fn fake_principal(id: u8) -> Principal {
+        Principal::from_slice(&[id; 29])
+    }
+
+    mod get_subaccount_strategy_id {
+        use super::*;
+
+        #[test]
+        fn reads_strategy_of_share_subaccount() {
+            let subaccount = strategy_subaccount(258);
+
+            assert_eq!(get_subaccount_strategy_id(Some(&subaccount)), Some(258));
+        }
+
+        #[test]
+        fn reads_strategy_of_position_share_subaccount() {
+            let subaccount = share_subaccount(258, 7);
+
+            assert_eq!(get_subaccount_strategy_id(Some(&subaccount)), Some(258));
+        }
+
+        #[test]
+        fn rejects_other_subaccounts() {
+            let mut subaccount = strategy_subaccount(1);
+            subaccount[20] = 1;
+
+            assert_eq!(get_subaccount_strategy_id(None), None);
+            assert_eq!(get_subaccount_strategy_id(Some(&[0u8; 32])), None);
+            assert_eq!(get_subaccount_strategy_id(Some(&subaccount)), None);
+        }
+    }
+
+    mod position_account {
+        use super::*;
+
+        #[test]
+        fn maps_share_account_to_position_subaccount() {
+            let owner = fake_principal(1);
+            let position = Account { owner, subaccount: Some([7; 32]) };
+
+            let share_account = register_share_account(3, position);
+
+            assert_eq!(get_subaccount_strategy_id(share_account.subaccount.as_ref()), Some(3));
+            assert_eq!(position_account(&share_account), Some(position));
+            assert_eq!(get_share_account(3, position), Some(share_account));
+        }
+
+        #[test]
+        fn maps_default_share_account_to_default_position() {
+            let owner = fake_principal(1);
+            let share_account = Account { owner, subaccount: Some(strategy_subaccount(3)) };
+
+            assert_eq!(position_account(&share_account), Some(Account::from(owner)));
+            assert_eq!(get_share_account(3, Account { owner, subaccount: Some([0; 32]) }), Some(share_account));
+        }
+
+        #[test]
+        fn rejects_unknown_position_index() {
+            let share_account = Account {
+                owner: fake_principal(1),
+                subaccount: Some(share_subaccount(3, u64::MAX)),
+            };
+
+            assert_eq!(position_account(&share_account), None);
+            assert_eq!(get_share_account(3, Account { owner: fake_principal(1), subaccount: Some([9; 32]) }), None);
+        }
+    }
+
+    mod move_shares {
+        use super::*;
+
+        #[test]
+        fn moves_cost_basis_proportionally() {
+            let from = Account::from(fake_principal(1));
+            let to = Account::from(fake_principal(2));
+
+            let mut strategy = GenericStrategy::new(1);
+            strategy.set_user_shares(HashMap::from([(from, Nat::from(400u64))]));
+            strategy.set_initial_deposit(HashMap::from([(from, Nat::from(1_000u64))]));
+
+            let cost_basis = move_shares(&mut strategy, from, to, &Nat::from(100u64));
+
+            assert_eq!(cost_basis, Nat::from(250u64));
+            assert_eq!(strategy.get_user_shares().get(&from), Some(&Nat::from(300u64)));
+            assert_eq!(strategy.get_user_shares().get(&to), Some(&Nat::from(100u64)));
+            assert_eq!(strategy.get_initial_deposit().get(&from), Some(&Nat::from(750u64)));
+            assert_eq!(strategy.get_initial_deposit().get(&to), Some(&Nat::from(250u64)));
+        }
+
+        #[test]
+        fn removes_holder_sending_every_share() {
+            let from = Account::from(fake_principal(1));
+            let to = Account::from(fake_principal(2));
+
+            let mut strategy = GenericStrategy::new(1);
+            strategy.set_user_shares(HashMap::from([(from, Nat::from(400u64))]));
+            strategy.set_initial_deposit(HashMap::from([(from, Nat::from(1_000u64))]));
+
+            move_shares(&mut strategy, from, to, &Nat::from(400u64));
+
+            assert_eq!(strategy.get_users_count(), 1);
+            assert_eq!(strategy.get_initial_deposit().get(&from), None);
+            assert_eq!(strategy.get_initial_deposit().get(&to), Some(&Nat::from(1_000u64)));
+        }
+    }
+
+    mod check_request {
+        use super::*;
+
+        const NOW: u64 = 10 * TRANSACTION_WINDOW_NANOS;
+
+        #[test]
+        fn rejects_fee_and_long_memo() {
+            let long_memo = Memo(serde_bytes::ByteBuf::from(vec![0u8; 33]));
+
+            assert_eq!(check_request(&Some(Nat::from(1u64)), &None, None, NOW), Err(ShareTokenError::BadFee));
+            assert!(check_request(&Some(Nat::from(0u64)), &None, None, NOW).is_ok());
+            assert!(matches!(
+                check_request(&None, &Some(long_memo), None, NOW),
+                Err(ShareTokenError::Generic { error_code: MEMO_TOO_LONG_ERROR, .. })
+            ));
+        }
+
+        #[test]
+        fn checks_creation_time_window() {
+            let too_old = NOW - TRANSACTION_WINDOW_NANOS - PERMITTED_DRIFT_NANOS - 1;
+            let in_future = NOW + PERMITTED_DRIFT_NANOS + 1;
+
+            assert_eq!(check_request(&None, &None, Some(too_old), NOW), Err(ShareTokenError::TooOld));
+            assert_eq!(
+                check_request(&None, &None, Some(in_future), NOW),
+                Err(ShareTokenError::CreatedInFuture { ledger_time: NOW })
+            );
+            assert!(check_request(&None, &None, Some(NOW - 1), NOW).is_ok());
+        }
+    }
+}
@@ -1,16 +1,37 @@
 use smart_rebalance::{self, types::*};
 use utils::util::current_timestamp_secs;
+use errors::internal_error::error::{InternalError, InternalErrorKind};
+use errors::internal_error::error_codes::module::areas::{
+    canisters as canister_area,
+    canisters::domains::vault as vault_domain,
+    canisters::domains::vault::components as vault_domain_components,
+};
 
 use ::types::strategies::Pool;
 
 use crate::pool_stats::pool_stats_service;
+use crate::repository::strategies_repo;
+use crate::strategies::strategy::IStrategy;
+use crate::types::types::{
+    StrategyRebalanceParamsResponse,
+    UpdateStrategyRebalanceParamsArgs,
+};
+
+// Module code: "03-01-03"
+errors::define_error_code_builder_fn!(
+    build_error_code,
+    canister_area::AREA_CODE,          // Area code: "03"
+    vault_domain::DOMAIN_CODE,         // Domain code: "01"
+    vault_domain_components::REBALANCE // Component code: "03"
+);
 
 pub const BPS_SCALE_FACTOR: u32 = 10_000;
+pub const MAX_COOLDOWN_SECS: u64 = 30 * 24 * 3600; // 30 days
 
 pub struct RebalanceInputs {
     pub current_pool: Pool,
     pub pools: Vec<Pool>,
-    pub profile: StrategyProfile,
+    pub params: Params,
     pub last_rebalance_at: Option<u64>,
     pub position_value_usd: f64,
 }
@@ -20,7 +41,7 @@ pub async fn decide_rebalance(inputs: RebalanceInputs) -> RebalanceDecision {
     let actor = pool_stats_service::get_pool_stats_actor().await.unwrap();
     let pool_metrics_map = actor.get_pool_metrics(pool_ids).await;
 
-    let params = smart_rebalance::profiles::default_params_for_profile(inputs.profile);
+    let params = inputs.params.clone();
     let fee_percent = (params.dex_fee_percent_bps as f64) / BPS_SCALE_FACTOR as f64;
     let gas_cost_usd = 0.0; // TODO: wire from config or estimation
 
@@ -64,3 +85,202 @@ pub async fn decide_rebalance(inputs: RebalanceInputs) -> RebalanceDecision {
         current_score.components.sma_apy_usd,
     )
 }
+
+/// Sets the profile and the params overrides of a strategy.
+pub fn update_strategy_rebalance_params(
+    args: UpdateStrategyRebalanceParamsArgs
+) -> Result<StrategyRebalanceParamsResponse, InternalError> {
+    let mut strategy = strategies_repo::get_strategy_by_id(args.strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(InternalErrorKind::NotFound, 5), // Error code: "03-01-03 01 05"
+                "smart_rebalance_service::update_strategy_rebalance_params".to_string(),
+                "Strategy not found".to_string(),
+                errors::error_extra! {
+                    "args" => args,
+                },
+            )
+        })?;
+
+    validate_params_overrides(&args.params_overrides).map_err(|message| {
+        InternalError::validation(
+            build_error_code(InternalErrorKind::Validation, 6), // Error code: "03-01-03 02 06"
+            "smart_rebalance_service::update_strategy_rebalance_params".to_string(),
+            message,
+            errors::error_extra! {
+                "args" => args,
+            },
+        )
+    })?;
+
+    strategy.set_profile(args.profile);
+    strategy.set_params_overrides(args.params_overrides);
+    strategies_repo::save_strategy(strategy.clone());
+
+    Ok(build_rebalance_params_response(strategy.as_ref()))
+}
+
+fn build_rebalance_params_response(strategy: &dyn IStrategy) -> StrategyRebalanceParamsResponse {
+    StrategyRebalanceParamsResponse {
+        strategy_id: strategy.get_id(),
+        profile: strategy.get_profile(),
+        params_overrides: strategy.get_params_overrides(),
+        params: strategy.get_rebalance_params(),
+    }
+}
+
+fn validate_params_overrides(overrides: &StrategyParamsOverrides) -> Result<(), String> {
+    if let Some(cooldown_secs) = overrides.cooldown_secs {
+        if cooldown_secs == 0 || cooldown_secs > MAX_COOLDOWN_SECS {
+            return Err(format!("cooldown_secs must be between 1 and {}", MAX_COOLDOWN_SECS));
+        }
+    }
+
+    if let Some(dex_fee_percent_bps) = overrides.dex_fee_percent_bps {
+        if dex_fee_percent_bps > BPS_SCALE_FACTOR {
+            return Err(format!("dex_fee_percent_bps must not exceed {}", BPS_SCALE_FACTOR));
+        }
+    }
+
+    if let Some(max_weight_shift_bps) = overrides.max_weight_shift_bps {
+        if max_weight_shift_bps == 0 || max_weight_shift_bps > BPS_SCALE_FACTOR {
+            return Err(format!("max_weight_shift_bps must be between 1 and {}", BPS_SCALE_FACTOR));
+        }
+    }
+
+    if let Some(max_slippage_bps) = overrides.max_slippage_bps {
+        if max_slippage_bps >= BPS_SCALE_FACTOR {
+            return Err(format!("max_slippage_bps must be below {}", BPS_SCALE_FACTOR));
+        }
+    }
+
+    let mut non_negative_values = vec![
+        ("score_threshold", overrides.score_threshold),
+        ("gain_cost_multiplier", overrides.gain_cost_multiplier),
+    ];
+
+    if let Some(weights) = overrides.weights {
+        non_negative_values.extend([
+            ("weights.w1_usd_apy_sma", Some(weights.w1_usd_apy_sma)),
+            ("weights.w2_token_apy_sma", Some(weights.w2_token_apy_sma)),
+            ("weights.w3_log_tvl", Some(weights.w3_log_tvl)),
+            ("weights.w4_capital_efficiency", Some(weights.w4_capital_efficiency)),
+            ("weights.w5_apy_volatility", Some(weights.w5_apy_volatility)),
+            ("weights.w6_rebalance_cost", Some(weights.w6_rebalance_cost)),
+            ("weights.w7_token_price_volatility", Some(weights.w7_token_price_volatility)),
+        ]);
+    }
+
+    for (field, value) in non_negative_values {
+        if let Some(value) = value {
+            if !value.is_finite() || value < 0.0 {
+                return Err(format!("{} must be a finite non-negative number", field));
+            }
+        }
+    }
+
+    if let Some(long_term_apy_usd_min) = overrides.long_term_apy_usd_min {
+        if !long_term_apy_usd_min.is_finite() {
+            return Err("long_term_apy_usd_min must be a finite number".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod validate_params_overrides {
+        use super::*;
+
+        #[test]
+        fn accepts_empty_overrides() {
+            assert!(validate_params_overrides(&StrategyParamsOverrides::default()).is_ok());
+        }
+
+        #[test]
+        fn accepts_valid_overrides() {
+            let overrides = StrategyParamsOverrides {
+                weights: Some(smart_rebalance::profiles::default_params_for_profile(
+                    StrategyProfile::Aggressive
+                ).weights),
+                cooldown_secs: Some(3600),
+                score_threshold: Some(2.5),
+                gain_cost_multiplier: Some(1.0),
+                dex_fee_percent_bps: Some(30),
+                long_term_apy_usd_min: Some(-1.0),
+                max_weight_shift_bps: Some(2_500),
+                max_slippage_bps: Some(50),
+            };
+
+            assert!(validate_params_overrides(&overrides).is_ok());
+        }
+
+        #[test]
+        fn rejects_zero_cooldown() {
+            let overrides = StrategyParamsOverrides {
+                cooldown_secs: Some(0),
+                ..StrategyParamsOverrides::default()
+            };
+
+            assert!(validate_params_overrides(&overrides).is_err());
+        }
+
+        #[test]
+        fn rejects_fee_above_100_percent() {
+            let overrides = StrategyParamsOverrides {
+                dex_fee_percent_bps: Some(BPS_SCALE_FACTOR + 1),
+                ..StrategyParamsOverrides::default()
+            };
+
+            assert!(validate_params_overrides(&overrides).is_err());
+        }
+
+        #[test]
+        fn rejects_zero_or_above_100_percent_weight_shift() {
+            let zero = StrategyParamsOverrides {
+                max_weight_shift_bps: Some(0),
+                ..StrategyParamsOverrides::default()
+            };
+            let above = StrategyParamsOverrides {
+                max_weight_shift_bps: Some(BPS_SCALE_FACTOR + 1),
+                ..StrategyParamsOverrides::default()
+            };
+
+            assert!(validate_params_overrides(&zero).is_err());
+            assert!(validate_params_overrides(&above).is_err());
+        }
+
+        #[test]
+        fn rejects_slippage_of_100_percent() {
+            let overrides = StrategyParamsOverrides {
+                max_slippage_bps: Some(BPS_SCALE_FACTOR),
+                ..StrategyParamsOverrides::default()
+            };
+
+            assert!(validate_params_overrides(&overrides).is_err());
+        }
+
+        #[test]
+        fn rejects_negative_or_nan_weights() {
+            let mut weights = smart_rebalance::profiles::default_params_for_profile(
+                StrategyProfile::Balanced
+            ).weights;
+            weights.w3_log_tvl = -0.1;
+
+            let negative = StrategyParamsOverrides {
+                weights: Some(weights),
+                ..StrategyParamsOverrides::default()
+            };
+            let nan = StrategyParamsOverrides {
+                score_threshold: Some(f64::NAN),
+                ..StrategyParamsOverrides::default()
+            };
+
+            assert!(validate_params_overrides(&negative).is_err());
+            assert!(validate_params_overrides(&nan).is_err());
+        }
+    }
+}
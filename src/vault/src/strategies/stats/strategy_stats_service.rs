@@ -3,7 +3,9 @@ use ic_cdk_timers::TimerId;
 use candid::Nat;
 use std::cell::RefCell;
 
+use types::CanisterId;
 use types::exchange_id::ExchangeId;
+use types::strategies::{StrategyId, StrategyAllocation};
 use liquidity::liquidity_router;
 use swap::swap_service;
 use utils::util::current_timestamp_secs;
@@ -16,7 +18,9 @@ use errors::internal_error::error_codes::module::areas::{
 };
 
 use crate::repository::strategies_repo;
+use crate::repository::token_prices_repo::{self, TokenPrice};
 use crate::strategies::strategy::IStrategy;
+use crate::strategies::fee_service;
 use crate::utils::service_resolver::get_service_resolver;
 
 // Module code: "03-01-01"
@@ -61,19 +65,69 @@ pub fn stop_strategy_stats_update_timer() {
 pub async fn update_all_strategy_liquidity() {
     let strategies = strategies_repo::get_all_strategies()
         .into_iter()
-        .filter(|strategy| strategy.get_position_id().is_some())
+        .filter(|strategy| strategy.get_allocations().iter().any(|allocation| allocation.position_id.is_some()))
         .collect::<Vec<_>>();
 
+    let mut base_tokens = Vec::new();
+
     for strategy in strategies {
+        let strategy_id = strategy.get_id();
+
+        if !base_tokens.contains(&strategy.get_base_token()) {
+            base_tokens.push(strategy.get_base_token());
+        }
+
         let _ = update_strategy_liquidity(strategy).await;
+        let _ = fee_service::accrue_strategy_fees(strategy_id).await;
     }
+
+    for base_token in base_tokens {
+        let _ = update_token_usd_price(base_token).await;
+    }
+}
+
+/// Caches the USD price of a token, so the positions can be valued without calls in queries.
+pub async fn update_token_usd_price(token: CanisterId) -> Result<TokenPrice, InternalError> {
+    let service_resolver = get_service_resolver();
+    let icrc_ledger_client = service_resolver.icrc_ledger_client();
+
+    let decimals = icrc_ledger_client.icrc1_decimals(token).await?;
+
+    let usd_price = if token == *CKUSDT_TOKEN_CANISTER_ID {
+        1.0
+    } else {
+        // Quote one whole token to ckUSDT to approximate its USD price
+        let quote_to_usdt = swap_service::quote_swap_icrc2_optimal(
+            service_resolver.provider_impls(),
+            icrc_ledger_client.clone(),
+            token,
+            *CKUSDT_TOKEN_CANISTER_ID,
+            Nat::from(10u128.pow(decimals as u32)),
+        ).await?;
+
+        let usdt_decimals = icrc_ledger_client.icrc1_decimals(*CKUSDT_TOKEN_CANISTER_ID).await?;
+
+        (quote_to_usdt.amount_out as f64) / 10f64.powi(usdt_decimals as i32)
+    };
+
+    let price = TokenPrice {
+        usd_price,
+        decimals,
+        updated_at: current_timestamp_secs(),
+    };
+
+    token_prices_repo::save_token_price(token, price.clone());
+
+    Ok(price)
 }
 
 pub async fn update_strategy_liquidity(
     mut strategy: Box<dyn IStrategy>
 ) -> Result<(), InternalError> {
     let liquidity_amount = get_strategy_current_liquidity(strategy.as_ref()).await?;
-    
+
+    // Only the liquidity is updated, the rest of the state could be changed during the await
+    strategy.reload_state();
     strategy.set_current_liquidity(Some(liquidity_amount));
     strategy.set_current_liquidity_updated_at(Some(current_timestamp_secs()));
 
@@ -91,21 +145,47 @@ pub fn spawn_update_strategy_liquidity(strategy: Box<dyn IStrategy>) -> () {
 pub async fn get_strategy_current_liquidity(
     strategy: &dyn IStrategy
 ) -> Result<Nat, InternalError> {
+    let allocation_liquidities = get_allocation_liquidities(strategy).await?;
+
+    Ok(allocation_liquidities.into_iter().fold(Nat::from(0u64), |sum, liquidity| sum + liquidity))
+}
+
+/// Values of the invested allocations of the strategy in base token,
+/// in the order of the allocations.
+pub async fn get_allocation_liquidities(
+    strategy: &dyn IStrategy
+) -> Result<Vec<Nat>, InternalError> {
     let strategy_id = strategy.get_id();
-    let current_pool = strategy.get_current_pool();
+    let allocations: Vec<StrategyAllocation> = strategy.get_allocations()
+        .into_iter()
+        .filter(|allocation| allocation.position_id.is_some())
+        .collect();
 
-    if current_pool.is_none() {
+    if allocations.is_empty() {
         return Err(InternalError::business_logic(
                             build_error_code(InternalErrorKind::BusinessLogic, 5), // Error code: "03-01-01 03 05"
-            "strategy_stats_service::get_strategy_current_liquidity".to_string(),
-            "Strategy has no current pool".to_string(),
+            "strategy_stats_service::get_allocation_liquidities".to_string(),
+            "Strategy has no invested allocation".to_string(),
             errors::error_extra! {
                 "strategy_id" => strategy_id,
             },
         ));
     }
 
-    let pool = current_pool.unwrap();
+    let mut allocation_liquidities = Vec::with_capacity(allocations.len());
+
+    for allocation in allocations {
+        allocation_liquidities.push(get_allocation_liquidity(strategy_id, &allocation).await?);
+    }
+
+    Ok(allocation_liquidities)
+}
+
+async fn get_allocation_liquidity(
+    strategy_id: StrategyId,
+    allocation: &StrategyAllocation,
+) -> Result<Nat, InternalError> {
+    let pool = allocation.pool.clone();
 
     let service_resolver = get_service_resolver();
 
@@ -117,20 +197,22 @@ pub async fn get_strategy_current_liquidity(
         pool.provider
     ).await;
 
-    let position_id = strategy.get_position_id()
+    // Only invested allocations have a position in the pool
+    allocation.position_id
         .ok_or_else(|| {
             InternalError::business_logic(
                 build_error_code(InternalErrorKind::BusinessLogic, 6), // Error code: "03-01-01 03 06"
-                "strategy_stats_service::get_strategy_current_liquidity".to_string(),
-                "Strategy has no position id".to_string(),
+                "strategy_stats_service::get_allocation_liquidity".to_string(),
+                "Allocation has no position id".to_string(),
                 errors::error_extra! {
                     "strategy_id" => strategy_id,
+                    "pool_id" => pool.id,
                 },
             )
         })?;
 
     let position_response = liquidity_client
-        .get_position_by_id(position_id)
+        .get_position(allocation.liquidity_position())
         .await?;
 
     let quote_response = swap_service::quote_swap_icrc2(
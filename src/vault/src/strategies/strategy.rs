@@ -1,6 +1,6 @@
 use async_trait::async_trait;
-use candid::{Nat, Principal};
-use std::cell::RefMut;
+use candid::Nat;
+use icrc_ledger_types::icrc1::account::Account;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 
@@ -8,24 +8,37 @@ use liquidity::liquidity_calculator::LiquidityCalculator;
 use types::exchange_id::ExchangeId;
 use types::pool::PoolTrait;
 use types::context::Context;
-use utils::token_transfer::icrc1_transfer_to_user;
-use ::types::strategies::{StrategyResponse, Pool};
+use types::liquidity::AddLiquidityResponse;
+use types::CanisterId;
+use utils::util::current_timestamp_secs;
+use smart_rebalance::types::Params;
+use ::types::strategies::{StrategyResponse, Pool, StrategyAllocation, StrategyId};
 use errors::internal_error::error::{InternalError, InternalErrorKind};
+use errors::response_error::error::ResponseError;
 use errors::internal_error::error_codes::module::areas::{
     canisters as canister_area,
     canisters::domains::vault as vault_domain,
     canisters::domains::vault::components as vault_domain_components,
 };
 
-use crate::repository::runtime_config_repo;
 use crate::event_records::event_record::Event;
 use crate::event_records::event_record_service;
 use crate::repository::strategies_repo;
+use crate::repository::config_repo;
+use crate::repository::rebalance_history_repo::{self, RebalanceRecord};
+use crate::repository::share_prices_repo::{self, SharePriceSource};
+use crate::repository::pending_withdrawals_repo::{PendingWithdrawalKind, PendingWithdrawalStatus};
+use crate::user::pending_withdrawals_service;
+use crate::user::user_service;
+use crate::user::zap_service::{self, ZapFailure};
 use crate::strategies::basic_strategy::BasicStrategy;
 use crate::strategies::strategy_candid::StrategyCandid;
 use crate::liquidity::liquidity_service;
 use crate::strategies::stats::strategy_stats_service;
 use crate::strategies::smart_rebalance_service;
+use crate::strategies::fee_service;
+use crate::strategies::allocation_service;
+use crate::strategies::share_price_service;
 use crate::types::types::{
     StrategyDepositResponse,
     StrategyRebalanceResponse,
@@ -46,7 +59,7 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
     ///
     /// # Arguments
     ///
-    /// * `investor` - The Principal ID of the investor who is depositing tokens
+    /// * `context` - The context of the deposit, its user account holds the position
     /// * `amount` - The amount of tokens to deposit
     ///
     /// # Returns
@@ -55,18 +68,20 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
     /// - `amount`: The amount of tokens deposited
     /// - `shares`: The number of shares received
     /// - `tx_id`: The transaction ID (always 0 for this implementation)
-    /// - `position_id`: The request ID from the deposit call
+    /// - `position_id`: The position ID of the allocation with the largest weight
     ///
     /// # Details
     ///
     /// This function:
-    /// 1. Retrieves the current pool from the strategy
-    /// 2. Calculates the new shares for the investor's deposit
-    /// 3. Updates the total balance and total shares
-    /// 4. Updates the user shares mapping
-    /// 5. Updates the initial deposit mapping
-    /// 6. Adds liquidity to the pool
-    /// 7. Saves the updated strategy state
+    /// 1. Retrieves the allocations of the strategy, the first one is the best APY pool
+    /// 2. Gets the current value of the strategy position (NAV)
+    /// 3. Splits the amount by the target weights and adds liquidity to every pool
+    /// 4. Calculates the new shares for the investor's deposit priced against the NAV
+    /// 5. Updates the total balance and total shares
+    /// 6. Updates the user shares mapping
+    /// 7. Updates the initial deposit mapping
+    /// 8. Saves the updated strategy state
+    /// 9. Refunds the part of the amount that could not be added to its pool
     ///
     async fn deposit(
         &mut self,
@@ -75,6 +90,7 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
     ) -> Result<StrategyDepositResponse, InternalError> {
         let strategy_id = self.get_id().to_string();
         let investor = context.user.unwrap();
+        let investor_account = context.account().unwrap();
 
         // Event: Strategy deposit started
         event_record_service::create_event_record(
@@ -84,10 +100,10 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
             context.strategy_id,
         );
 
-        let mut current_pool = self.get_current_pool();
+        let mut allocations = self.get_allocations();
 
-        // Set current pool to the best APY pool if not set
-        if current_pool.is_none() {
+        // Allocate everything to the best APY pool if there are no allocations yet
+        if allocations.is_empty() {
             // Find the best APY pool
             let best_apy_pool = self.get_best_apy_pool().await;
 
@@ -119,44 +135,155 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
                 return Err(error);
             }
 
-            current_pool = best_apy_pool;
+            allocations = vec![StrategyAllocation {
+                pool: best_apy_pool.unwrap(),
+                position_id: None,
+                target_weight_bps: allocation_service::TOTAL_WEIGHT_BPS,
+                lp_amount: None,
+            }];
         }
 
-        let current_pool = current_pool.unwrap();
+        let primary_pool_id = allocation_service::get_primary_allocation(&allocations)
+            .map(|allocation| allocation.pool.get_id());
 
-        // Add liquidity to pool
-        let add_liquidity_response = liquidity_service::add_liquidity_to_pool(
-            context.clone(),
-            amount.clone(),
-            current_pool.clone()
-        ).await?;
+        // Liquidity is added only to positions held by this strategy
+        if let Err(error) = self.check_position_ownership(&allocations) {
+            // Event: Strategy deposit failed
+            event_record_service::create_event_record(
+                Event::strategy_deposit_failed(
+                    strategy_id.clone(),
+                    primary_pool_id.clone(),
+                    Some(amount.clone()),
+                    error.clone(),
+                ),
+                context.correlation_id.clone(),
+                Some(investor),
+                context.strategy_id,
+            );
 
-        let token0_equivalent_total = add_liquidity_response.token0_equivalent_total.clone();
+            return Err(error);
+        }
+
+        // Position value before the deposit, new shares are priced against it
+        let nav = self.get_nav().await
+            .map_err(|error| {
+                // Event: Strategy deposit failed
+                event_record_service::create_event_record(
+                    Event::strategy_deposit_failed(
+                        strategy_id.clone(),
+                        primary_pool_id.clone(),
+                        Some(amount.clone()),
+                        error.clone(),
+                    ),
+                    context.correlation_id.clone(),
+                    Some(investor),
+                    context.strategy_id,
+                );
+
+                error
+            })?;
+
+        // Add liquidity to the pools according to their target weights
+        let allocation_amounts = allocation_service::split_amount(&amount, &allocations);
+        let mut deposited_amount = Nat::from(0u64);
+        let mut token0_equivalent_total = Nat::from(0u64);
+        let mut added_liquidity = Vec::new();
+        let mut add_liquidity_error = None;
+
+        for (allocation, allocation_amount) in allocations.iter().zip(allocation_amounts) {
+            if allocation_amount == Nat::from(0u64) {
+                continue;
+            }
+
+            match liquidity_service::add_liquidity_to_pool(
+                context.clone(),
+                allocation_amount.clone(),
+                allocation.pool.clone(),
+                allocation.position_id,
+            ).await {
+                Ok(add_liquidity_response) => {
+                    added_liquidity.push((
+                        allocation.clone(),
+                        add_liquidity_response.position_id,
+                        add_liquidity_response.lp_amount,
+                    ));
+                    deposited_amount += allocation_amount;
+                    token0_equivalent_total += add_liquidity_response.token0_equivalent_total;
+                }
+                Err(error) => {
+                    add_liquidity_error = Some(error);
+                    break;
+                }
+            }
+        }
+
+        // Nothing was added, the whole amount is refunded by the caller
+        if let Some(error) = add_liquidity_error.clone() {
+            if deposited_amount == Nat::from(0u64) {
+                return Err(error);
+            }
+        }
 
-        self.update_strategy_state_after_deposit(
-            investor,
+        let minted_shares = self.update_strategy_state_after_deposit(
+            investor_account,
             token0_equivalent_total.clone(),
-            current_pool.clone(),
-            add_liquidity_response.position_id,
+            added_liquidity,
+            nav.clone(),
+        );
+
+        user_service::record_shares_minted(
+            investor_account,
+            &context,
+            minted_shares,
+            Some(token0_equivalent_total.clone()),
+            nav.clone(),
         );
 
+        share_price_service::record_share_price(
+            self.get_id(),
+            &(nav.clone() + token0_equivalent_total.clone()),
+            &self.get_total_shares(),
+            SharePriceSource::Deposit,
+        );
+
+        if add_liquidity_error.is_some() {
+            // The part of the pools that failed is held by the vault, so it is refunded to the user
+            let refund = pending_withdrawals_service::create_pending_withdrawal(
+                PendingWithdrawalKind::DepositRefund,
+                PendingWithdrawalStatus::Claimable,
+                &context,
+                investor_account,
+                self.get_base_token(),
+                Nat::from(0u64),
+                Some(amount.clone() - deposited_amount),
+            );
+
+            // Refund stays claimable if the transfer fails
+            let _ = pending_withdrawals_service::transfer_pending_withdrawal(refund.id).await;
+        }
+
         // Event: Strategy deposit completed
         event_record_service::create_event_record(
             Event::strategy_deposit_completed(
                 strategy_id, 
-                Some(current_pool.get_id()), 
-                Some(token0_equivalent_total.clone())
+                primary_pool_id,
+                Some(token0_equivalent_total.clone()),
+                Some(nav),
             ),
             context.correlation_id,
             Some(investor),
             context.strategy_id,
         );
 
+        let position_id = allocation_service::get_primary_allocation(&self.get_allocations())
+            .and_then(|allocation| allocation.position_id)
+            .unwrap_or_default();
+
         Ok(StrategyDepositResponse {
             amount: token0_equivalent_total,
-            shares: self.get_user_shares().get(&investor).unwrap().clone(),
+            shares: self.get_user_shares_by_account(investor_account),
             tx_id: 0,
-            position_id: add_liquidity_response.position_id,
+            position_id,
         })
     }
 
@@ -165,31 +292,47 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
     /// # Arguments
     ///
     /// * `shares` - The number of shares to withdraw
+    /// * `output_token` - Token to receive instead of the base token
+    /// * `min_amount_out` - Minimum output token amount, required with `output_token`
+    /// * `destination` - Account the withdrawn tokens are transferred to
     ///
     /// # Returns
     ///
-    /// * `StrategyWithdrawResponse` - Contains the amount of tokens withdrawn and remaining shares
+    /// * `StrategyWithdrawResponse` - Contains the amount and token withdrawn and remaining shares
     ///
     /// # Details
     ///
     /// This function:
-    /// 1. Verifies the caller has sufficient shares
-    /// 2. Gets the current pool and token information
-    /// 3. Removes liquidity from the pool proportional to shares
-    /// 4. Swaps secondary token to base token
-    /// 5. Transfers total tokens to caller
-    /// 6. Updates total shares, user shares and initial deposit
-    /// 7. Saves updated strategy state
+    /// 1. Verifies the position of the context account has sufficient shares
+    /// 2. Gets the invested allocations and their current values (NAV)
+    /// 3. Creates a pending withdrawal record
+    /// 4. Removes liquidity from every pool proportional to shares
+    /// 5. Swaps secondary tokens to base token
+    /// 6. Updates total shares, user shares and initial deposit and saves the strategy state
+    /// 7. Swaps the withdrawn amount to the output token, if any
+    /// 8. Transfers total tokens to the destination, if the transfer fails the amount stays claimable
+    ///
+    /// If a pool fails after other pools were unwound, only the shares of the unwound pools are burned.
+    ///
+    /// A withdrawal to another token is rejected before unwinding if the quote of the estimated amount
+    /// is below `min_amount_out`. If the quote of the withdrawn amount is then below it or the swap fails,
+    /// the amount is paid in the base token and the response carries the swap error.
     ///
     /// TODO: Rename `shares` to `percentage`
     async fn withdraw(
         &mut self,
         context: Context,
-        percentage: Nat
+        percentage: Nat,
+        output_token: Option<CanisterId>,
+        min_amount_out: Option<Nat>,
+        destination: Account,
     ) -> Result<StrategyWithdrawResponse, InternalError> {
         let strategy_id = self.get_id().to_string();
         let investor = context.user.unwrap();
-        let user_shares = self.get_user_shares_by_principal(investor.clone());
+        let investor_account = context.account().unwrap();
+        let base_token = self.get_base_token();
+        let output_token = output_token.filter(|output_token| *output_token != base_token);
+        let user_shares = self.get_user_shares_by_account(investor_account);
         let shares = user_shares.clone() * percentage.clone() / Nat::from(100u64); // TODO: Check this operation
 
         // Event: Strategy withdraw started
@@ -200,8 +343,12 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
             context.strategy_id,
         );
 
-        let current_pool = self.get_current_pool().clone();
-        let current_pool_id = current_pool.clone().unwrap().get_id();
+        let allocations: Vec<StrategyAllocation> = self.get_allocations()
+            .into_iter()
+            .filter(|allocation| allocation.position_id.is_some())
+            .collect();
+        let current_pool_id = allocation_service::get_primary_allocation(&allocations)
+            .map(|allocation| allocation.pool.get_id());
 
         if user_shares == Nat::from(0u8) {
             let error = InternalError::business_logic(
@@ -221,7 +368,7 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
             event_record_service::create_event_record(
                 Event::strategy_withdraw_failed(
                     strategy_id,
-                    Some(current_pool_id),
+                    current_pool_id,
                     Some(shares.clone()),
                     error.clone(),
                 ),
@@ -252,7 +399,7 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
             event_record_service::create_event_record(
                 Event::strategy_withdraw_failed(
                     strategy_id,
-                    Some(current_pool_id),
+                    current_pool_id,
                     Some(shares.clone()),
                     error.clone(),
                 ),
@@ -264,11 +411,11 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
             return Err(error);
         }
 
-        if current_pool.is_none() {
+        if allocations.is_empty() {
             let error = InternalError::not_found(
                 build_error_code(InternalErrorKind::NotFound, 5), // Error code: "03-01-02 01 05"
                 "Strategy::withdraw".to_string(),
-                "No current pool found in strategy".to_string(),
+                "No invested allocation found in strategy".to_string(),
                 errors::error_extra! {
                     "context" => context,
                     "strategy_id" => strategy_id,
@@ -294,32 +441,201 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
             return Err(error);
         }
 
-        let current_pool = current_pool.unwrap();
+        // Liquidity is withdrawn only from positions held by this strategy
+        if let Err(error) = self.check_position_ownership(&allocations) {
+            // Event: Strategy withdraw failed
+            event_record_service::create_event_record(
+                Event::strategy_withdraw_failed(
+                    strategy_id,
+                    current_pool_id,
+                    Some(shares.clone()),
+                    error.clone(),
+                ),
+                context.correlation_id,
+                Some(investor),
+                context.strategy_id,
+            );
 
-        // Withdraw liquidity from pool and swap token_1 to token_0 (base token)
-        let amount_0_to_withdraw = liquidity_service::withdraw_liquidity_from_pool_and_swap(
-            context.clone(),
-            self.get_total_shares(),
+            return Err(error);
+        }
+
+        // Withdrawn liquidity is proportional to the shares, so the values are only used to record the NAV
+        // and to split the shares if a pool fails, the withdrawal is not blocked if they are not available
+        let allocation_values = strategy_stats_service::get_allocation_liquidities(self.clone_self().as_ref())
+            .await
+            .ok();
+        let nav = allocation_values.as_ref()
+            .map(|values| values.iter().fold(self.get_uninvested_balance(), |sum, value| sum + value.clone()));
+        let total_shares = self.get_total_shares();
+
+        if let Some(output_token) = output_token {
+            let quote_result = match nav.clone() {
+                Some(nav) if total_shares > Nat::from(0u64) => {
+                    let estimated_amount = nav * shares.clone() / total_shares.clone();
+
+                    zap_service::quote_zap(
+                        context.clone(),
+                        base_token,
+                        output_token,
+                        estimated_amount,
+                        min_amount_out.clone(),
+                    ).await.map(|_| ())
+                }
+                _ => Err(InternalError::business_logic(
+                    build_error_code(InternalErrorKind::BusinessLogic, 8), // Error code: "03-01-02 03 08"
+                    "Strategy::withdraw".to_string(),
+                    "Withdrawn amount can't be estimated to quote the output token".to_string(),
+                    errors::error_extra! {
+                        "context" => context,
+                        "strategy_id" => strategy_id,
+                        "output_token" => output_token,
+                        "shares" => shares,
+                        "total_shares" => total_shares,
+                    },
+                )),
+            };
+
+            if let Err(error) = quote_result {
+                // Event: Strategy withdraw failed
+                event_record_service::create_event_record(
+                    Event::strategy_withdraw_failed(
+                        strategy_id,
+                        current_pool_id,
+                        Some(shares.clone()),
+                        error.clone(),
+                    ),
+                    context.correlation_id,
+                    Some(investor),
+                    context.strategy_id,
+                );
+
+                return Err(error);
+            }
+        }
+
+        // Persisted before any external call, so the withdrawn tokens always stay traceable to the user
+        let pending_withdrawal = pending_withdrawals_service::create_pending_withdrawal(
+            PendingWithdrawalKind::Withdraw,
+            PendingWithdrawalStatus::Started,
+            &context,
+            destination,
+            base_token,
             shares.clone(),
-            current_pool.clone(),
-        ).await?;
+            None,
+        );
 
-        let environment = runtime_config_repo::get_current_env();
+        // Withdraw liquidity from every pool and swap token_1 to token_0 (base token)
+        let mut amount_0_to_withdraw = Nat::from(0u64);
+        let mut unwound_allocations = Vec::new();
+        let mut withdraw_error = None;
+
+        for allocation in allocations.iter() {
+            match liquidity_service::withdraw_liquidity_from_pool_and_swap(
+                context.clone(),
+                total_shares.clone(),
+                shares.clone(),
+                allocation.pool.clone(),
+                allocation.liquidity_position(),
+            ).await {
+                Ok(withdraw_response) => {
+                    amount_0_to_withdraw += withdraw_response.amount;
+                    unwound_allocations.push((allocation.pool.clone(), withdraw_response.lp_amount));
+                }
+                Err(error) => {
+                    withdraw_error = Some(error);
+                    break;
+                }
+            }
+        }
+
+        let burned_shares = match withdraw_error {
+            None => shares.clone(),
+            Some(error) if unwound_allocations.is_empty() => {
+                pending_withdrawals_service::fail_pending_withdrawal(pending_withdrawal.id, &error);
+                return Err(error);
+            }
+            // The user keeps the shares of the pools that were not unwound
+            Some(_) => allocation_service::calculate_unwound_shares(
+                &shares,
+                &allocations,
+                allocation_values.as_deref(),
+                unwound_allocations.len(),
+            ),
+        };
+
+        // The burned shares also own their part of the base token held outside of the positions
+        let uninvested_amount = if total_shares > Nat::from(0u64) {
+            self.get_uninvested_balance() * burned_shares.clone() / total_shares.clone()
+        } else {
+            Nat::from(0u64)
+        };
+        amount_0_to_withdraw += uninvested_amount.clone();
+
+        // Shares are burned before the transfer, from now on the withdrawn amount is claimable by the user
+        let new_user_shares = self.update_strategy_state_after_withdraw(
+            investor_account,
+            burned_shares.clone(),
+            unwound_allocations,
+            uninvested_amount,
+        );
 
-        // Transfer amount of token_0 (base token) to user
-        icrc1_transfer_to_user(
-            &environment,
-            investor,
-            current_pool.token0,
-            amount_0_to_withdraw.clone(),
-        ).await
+        user_service::record_withdraw(&context, base_token, amount_0_to_withdraw.clone(), nav.clone());
+        user_service::record_shares_burned(&context, burned_shares.clone(), nav.clone());
+
+        // The remaining value is only known if the position values were available
+        if let Some(nav) = nav.as_ref() {
+            let remaining_nav = if *nav > amount_0_to_withdraw {
+                nav.clone() - amount_0_to_withdraw.clone()
+            } else {
+                Nat::from(0u64)
+            };
+
+            share_price_service::record_share_price(
+                self.get_id(),
+                &remaining_nav,
+                &self.get_total_shares(),
+                SharePriceSource::Withdraw,
+            );
+        }
+
+        // Quoted again with the withdrawn amount, the withdrawal isn't claimable until the swap is done
+        let (payout_token, payout_amount, zap_error) = match output_token {
+            Some(output_token) => {
+                let zap_result = match zap_service::quote_zap(
+                    context.clone(),
+                    base_token,
+                    output_token,
+                    amount_0_to_withdraw.clone(),
+                    min_amount_out,
+                ).await {
+                    Ok(quote) => zap_service::zap(context.clone(), quote).await,
+                    Err(error) => Err(ZapFailure {
+                        ledger: base_token,
+                        amount: amount_0_to_withdraw.clone(),
+                        error,
+                    }),
+                };
+
+                zap_service::get_zap_payout(output_token, zap_result)
+            }
+            None => (base_token, amount_0_to_withdraw.clone(), None),
+        };
+
+        pending_withdrawals_service::set_pending_withdrawal_amount(
+            pending_withdrawal.id,
+            payout_token,
+            payout_amount.clone(),
+        );
+
+        // Transfer the withdrawn amount to user
+        pending_withdrawals_service::transfer_pending_withdrawal(pending_withdrawal.id).await
             .map_err(|error| {
                 // Event: Strategy withdraw failed
                 event_record_service::create_event_record(
                     Event::strategy_withdraw_failed(
                         strategy_id.clone(),
-                        Some(current_pool_id.clone()),
-                        Some(shares.clone()),
+                        current_pool_id.clone(),
+                        Some(burned_shares.clone()),
                         error.clone(),
                     ),
                     context.correlation_id.clone(),
@@ -330,27 +646,26 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
                 error
             })?;
 
-        let new_user_shares = self.update_strategy_state_after_withdraw(
-            investor,
-            shares.clone(),
-        );
-
         // Event: Strategy withdraw completed
         event_record_service::create_event_record(
             Event::strategy_withdraw_completed(
                 strategy_id,
-                Some(current_pool_id),
-                Some(shares.clone()),
+                current_pool_id,
+                Some(burned_shares.clone()),
                 Some(amount_0_to_withdraw.clone()),
+                nav,
             ),
             context.correlation_id,
             Some(investor),
             context.strategy_id,
         );
 
+        // The shares are burned and the amount is paid, a failed zap only changes the payout token
         Ok(StrategyWithdrawResponse {
-            amount: amount_0_to_withdraw,
+            amount: payout_amount,
             current_shares: new_user_shares.clone(),
+            token: payout_token,
+            zap_error: zap_error.map(ResponseError::from_internal_error),
         })
     }
 
@@ -359,17 +674,18 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
     /// # Details
     ///
     /// 1. Gets data for all available pools
-    /// 2. Finds the pool with highest APY
-    /// 3. If current pool is different from highest APY pool:
-    ///    - Withdraws liquidity from current pool
+    /// 2. Compares the allocation with the largest weight against the best scored pool
+    /// 3. If the best scored pool is a different pool:
+    ///    - Withdraws up to `max_weight_shift_bps` of the target weights from the allocation pool
     ///    - Swaps token_1 to token_0 (base token)
-    ///    - Adds liquidity to new pool
-    ///    - Updates current pool
+    ///    - Adds liquidity to the best scored pool
+    ///    - Moves the withdrawn weight to the allocation of the best scored pool
     ///
     /// # Returns
     ///
     /// * `StrategyRebalanceResponse` - Contains:
-    ///   * `pool` - The pool being used after rebalancing
+    ///   * `current_pool` - The pool with the largest weight after rebalancing
+    ///   * `allocations` - The allocations after rebalancing
     ///
     async fn rebalance(&mut self) -> Result<StrategyRebalanceResponse, InternalError> {
         let context = Context::generate(
@@ -387,9 +703,10 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
             context.strategy_id,
         );
 
-        let current_pool = self.get_current_pool();
+        let allocations = self.get_allocations();
+        let source_allocation = allocation_service::get_primary_allocation(&allocations).cloned();
 
-        if current_pool.is_none() {
+        if source_allocation.is_none() {
             let error = InternalError::not_found(
                 build_error_code(InternalErrorKind::NotFound, 6), // Error code: "03-01-02 01 06"
                 "Strategy::rebalance".to_string(),
@@ -410,7 +727,22 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
             return Err(error);
         }
 
-        let current_pool = current_pool.unwrap();
+        let source_allocation = source_allocation.unwrap();
+        let current_pool = source_allocation.pool.clone();
+        let params = self.get_rebalance_params();
+
+        // Fees are accrued on the position value before it is moved
+        let nav = self.get_nav().await?;
+
+        // The fee shares are minted on the stored state, which may have changed during the NAV call
+        self.reload_state();
+        self.accrue_fees(nav.clone(), context.clone());
+        share_price_service::record_share_price(
+            self.get_id(),
+            &nav,
+            &self.get_total_shares(),
+            SharePriceSource::Rebalance,
+        );
 
         let position_value_usd = strategy_stats_service::get_strategy_current_liquidity_usd(
             self.clone_self()
@@ -420,8 +752,9 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
             smart_rebalance_service::RebalanceInputs {
                 current_pool: current_pool.clone(),
                 pools: self.get_pools(),
-                profile: smart_rebalance::types::StrategyProfile::Balanced,
-                last_rebalance_at: None, // TODO: track in state
+                params: params.clone(),
+                last_rebalance_at: rebalance_history_repo::get_last_rebalance_record(self.get_id())
+                    .map(|record| record.timestamp),
                 position_value_usd,
             }
         ).await;
@@ -431,10 +764,11 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
                 previous_pool: current_pool.clone(),
                 current_pool: current_pool.clone(),
                 is_rebalanced: false,
+                allocations,
             });
         }
 
-        let target_pool_id = rebalance_decision.target_pool_id.unwrap();
+        let target_pool_id = rebalance_decision.target_pool_id.clone().unwrap();
         let max_apy_pool = self.get_pools()
             .into_iter()
             .find(|p| p.get_id() == target_pool_id)
@@ -457,23 +791,147 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
                 previous_pool: current_pool.clone(),
                 current_pool: current_pool.clone(),
                 is_rebalanced: false,
+                allocations,
             });
         }
 
-        // Withdraw liquidity from current pool and swap token_1 to token_0 (base token)
-        let token_0_to_pool_amount = liquidity_service::withdraw_liquidity_from_pool_and_swap(
+        // The liquidity is added to the position of the strategy in the new pool, if it has one
+        let target_allocation = allocations.iter()
+            .find(|allocation| allocation.pool.is_same_pool(&max_apy_pool))
+            .cloned()
+            .unwrap_or(StrategyAllocation {
+                pool: max_apy_pool.clone(),
+                position_id: None,
+                target_weight_bps: 0,
+                lp_amount: None,
+            });
+
+        // Liquidity is moved only between positions held by this strategy
+        self.check_position_ownership(&[source_allocation.clone(), target_allocation.clone()])
+            .map_err(|error| {
+                // Event: Strategy rebalance failed
+                event_record_service::create_event_record(
+                    Event::strategy_rebalance_failed(
+                        strategy_id.clone(),
+                        Some(current_pool.get_id()),
+                        Some(max_apy_pool.get_id()),
+                        error.clone(),
+                    ),
+                    context.correlation_id.clone(),
+                    context.user,
+                    context.strategy_id,
+                );
+
+                error
+            })?;
+
+        // Weight moved by this rebalance, the whole allocation at most
+        let shifted_weight_bps = params.max_weight_shift_bps.min(source_allocation.target_weight_bps);
+
+        // Withdraw the shifted part of the allocation and swap token_1 to token_0 (base token)
+        let withdraw_response = match liquidity_service::withdraw_liquidity_from_pool_and_swap(
             context.clone(),
-            self.get_total_shares(),
-            self.get_total_shares(),
+            Nat::from(source_allocation.target_weight_bps),
+            Nat::from(shifted_weight_bps),
             current_pool.clone(),
-        ).await?;
+            source_allocation.liquidity_position(),
+        ).await {
+            Ok(response) => response,
+            Err(error) => {
+                // Event: Strategy rebalance failed
+                event_record_service::create_event_record(
+                    Event::strategy_rebalance_failed(
+                        strategy_id,
+                        Some(current_pool.get_id()),
+                        Some(max_apy_pool.get_id()),
+                        error.clone(),
+                    ),
+                    context.correlation_id,
+                    context.user,
+                    context.strategy_id,
+                );
+
+                // Nothing was moved, the liquidity is still in the source position
+                rebalance_history_repo::add_rebalance_record(
+                    self.get_id(),
+                    RebalanceRecord {
+                        timestamp: current_timestamp_secs(),
+                        previous_pool: current_pool,
+                        target_pool: max_apy_pool,
+                        decision: rebalance_decision,
+                        withdrawn_amount: Nat::from(0u64),
+                        token_0_amount: Nat::from(0u64),
+                        token_1_amount: Nat::from(0u64),
+                        position_id: source_allocation.position_id.unwrap_or_default(),
+                        shifted_weight_bps: Some(0),
+                        error: Some(error.clone()),
+                    },
+                );
+
+                return Err(error);
+            }
+        };
+        // Base token left over by a previous failed rebalance is moved together with the withdrawn one
+        let token_0_to_pool_amount = withdraw_response.amount.clone() + self.get_uninvested_balance();
 
         // Add liquidity to new pool
-        let add_liquidity_response = liquidity_service::add_liquidity_to_pool(
+        let add_liquidity_response = match liquidity_service::add_liquidity_to_pool(
             context.clone(),
             token_0_to_pool_amount.clone(),
             max_apy_pool.clone(),
-        ).await?;
+            target_allocation.position_id,
+        ).await {
+            Ok(response) => response,
+            Err(error) => {
+                // The withdrawn liquidity must not be left outside of the strategy
+                let restored_liquidity = self.restore_rebalanced_liquidity(
+                    context.clone(),
+                    &source_allocation,
+                    withdraw_response.lp_amount,
+                    token_0_to_pool_amount.clone(),
+                ).await;
+
+                // Event: Strategy rebalance failed
+                event_record_service::create_event_record(
+                    Event::strategy_rebalance_failed(
+                        strategy_id,
+                        Some(current_pool.get_id()),
+                        Some(max_apy_pool.get_id()),
+                        error.clone(),
+                    ),
+                    context.correlation_id,
+                    context.user,
+                    context.strategy_id,
+                );
+
+                let (token_0_amount, token_1_amount, position_id) = match restored_liquidity {
+                    Some(response) => (response.token_0_amount, response.token_1_amount, response.position_id),
+                    None => (
+                        Nat::from(0u64),
+                        Nat::from(0u64),
+                        source_allocation.position_id.unwrap_or_default(),
+                    ),
+                };
+
+                rebalance_history_repo::add_rebalance_record(
+                    self.get_id(),
+                    RebalanceRecord {
+                        timestamp: current_timestamp_secs(),
+                        previous_pool: current_pool,
+                        target_pool: max_apy_pool,
+                        decision: rebalance_decision,
+                        withdrawn_amount: token_0_to_pool_amount,
+                        token_0_amount,
+                        token_1_amount,
+                        position_id,
+                        shifted_weight_bps: Some(0),
+                        error: Some(error.clone()),
+                    },
+                );
+
+                return Err(error);
+            }
+        };
 
         // Event: Strategy rebalance completed
         event_record_service::create_event_record(
@@ -487,61 +945,284 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
             context.strategy_id,
         );
 
-        // Update current pool
-        self.set_current_pool(Some(max_apy_pool));
+        self.reload_state();
+        self.set_uninvested_balance(Nat::from(0u64));
 
-        // Update position id
-        self.set_position_id(Some(add_liquidity_response.position_id));
+        let mut allocations = self.get_allocations();
+
+        if let Some(source_allocation) = allocations.iter_mut()
+            .find(|allocation| allocation.pool.is_same_pool(&current_pool)) {
+            allocation_service::record_withdrawn_liquidity(source_allocation, withdraw_response.lp_amount);
+        }
+
+        // Move the withdrawn weight to the new pool
+        self.set_allocations(allocation_service::shift_weight(
+            &allocations,
+            &current_pool,
+            &max_apy_pool,
+            shifted_weight_bps,
+            add_liquidity_response.position_id,
+            add_liquidity_response.lp_amount,
+        ));
+
+        strategies_repo::save_strategy(self.clone_self());
+
+        rebalance_history_repo::add_rebalance_record(
+            self.get_id(),
+            RebalanceRecord {
+                timestamp: current_timestamp_secs(),
+                previous_pool: current_pool.clone(),
+                target_pool: max_apy_pool.clone(),
+                decision: rebalance_decision,
+                withdrawn_amount: token_0_to_pool_amount,
+                token_0_amount: add_liquidity_response.token_0_amount,
+                token_1_amount: add_liquidity_response.token_1_amount,
+                position_id: add_liquidity_response.position_id,
+                shifted_weight_bps: Some(shifted_weight_bps),
+                error: None,
+            },
+        );
 
         Ok(StrategyRebalanceResponse {
             previous_pool: current_pool.clone(),
-            current_pool: self.get_current_pool().unwrap(),
+            current_pool: self.get_current_pool().unwrap_or(max_apy_pool),
             is_rebalanced: true,
+            allocations: self.get_allocations(),
         })
     }
 
-    fn update_user_shares(&mut self, user: Principal, shares: Nat) {
+    /// Puts the liquidity withdrawn by a failed rebalance back into the source pool.
+    ///
+    /// If the source pool refuses it too, the base token stays in the vault and
+    /// is booked as uninvested balance of the strategy, so it is still owned by the shares.
+    async fn restore_rebalanced_liquidity(
+        &mut self,
+        context: Context,
+        source_allocation: &StrategyAllocation,
+        withdrawn_lp_amount: Option<Nat>,
+        amount: Nat,
+    ) -> Option<AddLiquidityResponse> {
+        let restored_liquidity = liquidity_service::add_liquidity_to_pool(
+            context,
+            amount.clone(),
+            source_allocation.pool.clone(),
+            source_allocation.position_id,
+        ).await.ok();
+
+        self.reload_state();
+
+        let mut allocations = self.get_allocations();
+
+        if let Some(allocation) = allocations.iter_mut()
+            .find(|allocation| allocation.pool.is_same_pool(&source_allocation.pool)) {
+            allocation_service::record_withdrawn_liquidity(allocation, withdrawn_lp_amount);
+
+            if let Some(response) = restored_liquidity.as_ref() {
+                allocation_service::record_added_liquidity(
+                    allocation,
+                    response.position_id,
+                    response.lp_amount.clone(),
+                );
+            }
+        }
+
+        self.set_allocations(allocations);
+        self.set_uninvested_balance(match restored_liquidity {
+            Some(_) => Nat::from(0u64),
+            None => amount,
+        });
+
+        strategies_repo::save_strategy(self.clone_self());
+
+        restored_liquidity
+    }
+
+    /// Refuses to touch the positions of the allocations if another strategy holds them.
+    ///
+    /// Every strategy owns its own ICPSwap positions and KongSwap LP tokens,
+    /// so the liquidity of one strategy is never withdrawn by another one.
+    fn check_position_ownership(&self, allocations: &[StrategyAllocation]) -> Result<(), InternalError> {
+        let other_strategies: Vec<(StrategyId, Vec<StrategyAllocation>)> = strategies_repo::get_all_strategies()
+            .into_iter()
+            .filter(|strategy| strategy.get_id() != self.get_id())
+            .map(|strategy| (strategy.get_id(), strategy.get_allocations()))
+            .collect();
+
+        for allocation in allocations {
+            if let Some(owner_strategy_id) = allocation_service::find_position_owner(allocation, &other_strategies) {
+                return Err(InternalError::business_logic(
+                    build_error_code(InternalErrorKind::BusinessLogic, 7), // Error code: "03-01-02 03 07"
+                    "Strategy::check_position_ownership".to_string(),
+                    "Position is held by another strategy".to_string(),
+                    errors::error_extra! {
+                        "strategy_id" => self.get_id(),
+                        "owner_strategy_id" => owner_strategy_id,
+                        "pool_id" => allocation.pool.id,
+                        "position_id" => allocation.position_id,
+                    },
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Current value of the strategy positions and uninvested balance in base token, used to price the shares.
+    ///
+    /// The value is zero before the first deposit, so the first shares are minted 1:1.
+    async fn get_nav(&self) -> Result<Nat, InternalError> {
+        if self.get_total_shares() == Nat::from(0u64) {
+            return Ok(Nat::from(0u64));
+        }
+
+        let is_invested = self.get_allocations()
+            .iter()
+            .any(|allocation| allocation.position_id.is_some());
+
+        let positions_value = if is_invested {
+            let strategy = self.clone_self();
+            strategy_stats_service::get_strategy_current_liquidity(strategy.as_ref()).await?
+        } else {
+            Nat::from(0u64)
+        };
+
+        Ok(positions_value + self.get_uninvested_balance())
+    }
+
+    /// Replaces the state of this copy with the state stored in the repository.
+    ///
+    /// Must be called after the awaits of an operation, so the shares and settings
+    /// changed in the meantime are not overwritten by a stale copy.
+    fn reload_state(&mut self) {
+        let stored = match strategies_repo::get_strategy_by_id(self.get_id()) {
+            Some(stored) => stored,
+            None => return,
+        };
+
+        self.set_total_shares(stored.get_total_shares());
+        self.set_total_balance(stored.get_total_balance());
+        self.set_user_shares(stored.get_user_shares());
+        self.set_initial_deposit(stored.get_initial_deposit());
+        self.set_current_pool(stored.get_current_pool());
+        self.set_position_id(stored.get_position_id());
+        self.set_allocations(stored.get_allocations());
+        self.set_uninvested_balance(stored.get_uninvested_balance());
+        self.set_current_liquidity(stored.get_current_liquidity());
+        self.set_current_liquidity_updated_at(stored.get_current_liquidity_updated_at());
+        self.set_enabled(stored.get_enabled());
+        self.set_profile(stored.get_profile());
+        self.set_params_overrides(stored.get_params_overrides());
+        self.set_fees(stored.get_fees());
+    }
+
+    /// Mints the management and performance fee shares to the treasury and saves the strategy.
+    ///
+    /// Without a treasury no fees are charged, only the accrual checkpoints are moved.
+    fn accrue_fees(&mut self, nav: Nat, context: Context) {
+        let strategy_id = self.get_id().to_string();
+        let treasury = config_repo::get_treasury();
+        let previous_high_water_mark = self.get_fees().high_water_mark;
+
+        let accrual = fee_service::calculate_fee_accrual(
+            &self.get_fees(),
+            &nav,
+            &self.get_total_shares(),
+            current_timestamp_secs(),
+            treasury.is_some(),
+        );
+
+        let fee_shares = accrual.management_fee_shares.clone() + accrual.performance_fee_shares.clone();
+
+        let minted_fee_shares = match treasury {
+            Some(treasury) if fee_shares > Nat::from(0u64) => {
+                self.increase_total_shares(fee_shares.clone());
+                self.increase_user_shares(Account::from(treasury), fee_shares.clone());
+                Some((treasury, fee_shares))
+            }
+            _ => None,
+        };
+
+        self.set_fees(accrual.fees.clone());
+        strategies_repo::save_strategy(self.clone_self());
+
+        if let Some((treasury, fee_shares)) = minted_fee_shares {
+            user_service::record_shares_minted(Account::from(treasury), &context, fee_shares, None, nav.clone());
+        }
+
+        if accrual.management_fee_shares > Nat::from(0u64) {
+            // Event: Strategy management fee accrued
+            event_record_service::create_event_record(
+                Event::strategy_management_fee_accrued(
+                    strategy_id.clone(),
+                    nav.clone(),
+                    accrual.management_fee_shares.clone(),
+                    accrual.period_secs,
+                ),
+                context.correlation_id.clone(),
+                treasury,
+                context.strategy_id,
+            );
+        }
+
+        if accrual.performance_fee_shares > Nat::from(0u64) {
+            // Event: Strategy performance fee accrued
+            event_record_service::create_event_record(
+                Event::strategy_performance_fee_accrued(
+                    strategy_id,
+                    nav,
+                    accrual.performance_fee_shares.clone(),
+                    previous_high_water_mark.unwrap_or_default(),
+                    accrual.fees.high_water_mark.unwrap_or_default(),
+                ),
+                context.correlation_id,
+                treasury,
+                context.strategy_id,
+            );
+        }
+    }
+
+    fn update_user_shares(&mut self, account: Account, shares: Nat) {
         let mut user_shares_map = self.get_user_shares();
         if shares == Nat::from(0u64) {
-            user_shares_map.remove(&user);
+            user_shares_map.remove(&account);
         } else {
-            user_shares_map.insert(user, shares);
+            user_shares_map.insert(account, shares);
         }
         self.set_user_shares(user_shares_map);
     }
 
-    fn increase_user_shares(&mut self, user: Principal, shares: Nat) {
-        let user_shares = self.get_user_shares().get(&user).cloned().unwrap_or(Nat::from(0u64));
+    fn increase_user_shares(&mut self, account: Account, shares: Nat) {
+        let user_shares = self.get_user_shares().get(&account).cloned().unwrap_or(Nat::from(0u64));
         let new_user_shares = user_shares + shares.clone();
-        self.update_user_shares(user, new_user_shares);
+        self.update_user_shares(account, new_user_shares);
     }
 
-    fn decrease_user_shares(&mut self, user: Principal, shares: Nat) {
-        let user_shares = self.get_user_shares().get(&user).cloned().unwrap_or(Nat::from(0u64));
+    fn decrease_user_shares(&mut self, account: Account, shares: Nat) {
+        let user_shares = self.get_user_shares().get(&account).cloned().unwrap_or(Nat::from(0u64));
         let new_user_shares = user_shares - shares.clone();
-        self.update_user_shares(user, new_user_shares);
+        self.update_user_shares(account, new_user_shares);
     }
 
-    fn update_initial_deposit(&mut self, user: Principal, initial_deposit: Nat) {
+    fn update_initial_deposit(&mut self, account: Account, initial_deposit: Nat) {
         let mut initial_deposit_map = self.get_initial_deposit();
         if initial_deposit == Nat::from(0u64) {
-            initial_deposit_map.remove(&user);
+            initial_deposit_map.remove(&account);
         } else {
-            initial_deposit_map.insert(user, initial_deposit);
+            initial_deposit_map.insert(account, initial_deposit);
         }
         self.set_initial_deposit(initial_deposit_map);
     }
 
-    fn increase_initial_deposit(&mut self, user: Principal, initial_deposit: Nat) {
-        let user_initial_deposit = self.get_initial_deposit().get(&user).cloned().unwrap_or(Nat::from(0u64));
+    fn increase_initial_deposit(&mut self, account: Account, initial_deposit: Nat) {
+        let user_initial_deposit = self.get_initial_deposit().get(&account).cloned().unwrap_or(Nat::from(0u64));
         let new_initial_deposit = user_initial_deposit + initial_deposit.clone();
-        self.update_initial_deposit(user, new_initial_deposit);
+        self.update_initial_deposit(account, new_initial_deposit);
     }
 
-    fn decrease_initial_deposit(&mut self, user: Principal, initial_deposit: Nat) {
-        let user_initial_deposit = self.get_initial_deposit().get(&user).cloned().unwrap_or(Nat::from(0u64));
+    fn decrease_initial_deposit(&mut self, account: Account, initial_deposit: Nat) {
+        let user_initial_deposit = self.get_initial_deposit().get(&account).cloned().unwrap_or(Nat::from(0u64));
         let new_initial_deposit = user_initial_deposit - initial_deposit.clone();
-        self.update_initial_deposit(user, new_initial_deposit);
+        self.update_initial_deposit(account, new_initial_deposit);
     }
 
     fn increase_total_shares(&mut self, shares: Nat) {
@@ -574,6 +1255,7 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
 
         self.set_current_pool(None);
         self.set_position_id(None);
+        self.set_allocations(vec![]);
 
         self.set_current_liquidity(None);
         self.set_current_liquidity_updated_at(None);
@@ -606,19 +1288,27 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
             .next()
     }
 
+    /// Shares minted for a deposit worth `token0_equivalent_total` of the base token,
+    /// priced against the NAV before the deposit.
+    fn calculate_deposit_shares(&self, token0_equivalent_total: Nat, nav: Nat) -> Nat {
+        LiquidityCalculator::calculate_shares_for_deposit(
+            token0_equivalent_total,
+            nav,
+            self.get_total_shares(),
+        )
+    }
+
     fn update_strategy_state_after_deposit(
         &mut self,
-        investor: Principal,
+        investor: Account,
         amount: Nat,
-        pool: Pool,
-        position_id: u64,
+        added_liquidity: Vec<(StrategyAllocation, u64, Option<Nat>)>,
+        nav: Nat,
     ) -> Nat {
-        // Calculate new shares for investor's deposit
-        let new_user_shares = LiquidityCalculator::calculate_shares_for_deposit(
-            amount.clone(),
-            self.get_total_balance().clone(),
-            self.get_total_shares().clone(),
-        );
+        self.reload_state();
+
+        // Calculate new shares for investor's deposit at the current share price
+        let new_user_shares = self.calculate_deposit_shares(amount.clone(), nav);
 
         // Update strategy state with new shares, initial deposit and total balance
         self.increase_total_shares(new_user_shares.clone());
@@ -626,9 +1316,22 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
         self.increase_initial_deposit(investor, amount.clone());
         self.increase_total_balance(amount.clone());
 
-        // Update current pool and position id
-        self.set_current_pool(Some(pool.clone()));
-        self.set_position_id(Some(position_id));
+        // Add the liquidity to the stored allocations, the pool of a first deposit is allocated here.
+        // The current pool and position id follow the primary allocation
+        let mut allocations = self.get_allocations();
+        for (deposit_allocation, position_id, lp_amount) in added_liquidity {
+            match allocations.iter_mut().find(|allocation| allocation.pool.is_same_pool(&deposit_allocation.pool)) {
+                Some(allocation) => {
+                    allocation_service::record_added_liquidity(allocation, position_id, lp_amount);
+                }
+                None => {
+                    let mut allocation = deposit_allocation;
+                    allocation_service::record_added_liquidity(&mut allocation, position_id, lp_amount);
+                    allocations.push(allocation);
+                }
+            }
+        }
+        self.set_allocations(allocations);
 
         strategies_repo::save_strategy(self.clone_self());
 
@@ -640,16 +1343,36 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
 
     fn update_strategy_state_after_withdraw(
         &mut self,
-        investor: Principal,
+        investor: Account,
         shares: Nat,
+        unwound_allocations: Vec<(Pool, Option<Nat>)>,
+        uninvested_amount: Nat,
     ) -> Nat {
+        self.reload_state();
+
+        let uninvested_balance = self.get_uninvested_balance();
+        self.set_uninvested_balance(if uninvested_balance > uninvested_amount {
+            uninvested_balance - uninvested_amount
+        } else {
+            Nat::from(0u64)
+        });
+
+        // Subtract the LP tokens burned in every unwound pool
+        let mut allocations = self.get_allocations();
+        for (pool, lp_amount) in unwound_allocations {
+            if let Some(allocation) = allocations.iter_mut().find(|allocation| allocation.pool.is_same_pool(&pool)) {
+                allocation_service::record_withdrawn_liquidity(allocation, lp_amount);
+            }
+        }
+        self.set_allocations(allocations);
+
         // Update total shares
         self.decrease_total_shares(shares.clone());
 
         // Update user shares
-        let previous_user_shares = self.get_user_shares_by_principal(investor);
+        let previous_user_shares = self.get_user_shares_by_account(investor);
         let new_user_shares = previous_user_shares.clone() - shares.clone();
-        self.update_user_shares(investor.clone(), new_user_shares.clone());
+        self.update_user_shares(investor, new_user_shares.clone());
 
         // Update initial deposit proportional to the new shares
         let mut initial_deposit = self.get_initial_deposit();
@@ -667,7 +1390,7 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
         if new_user_initial_deposit == Nat::from(0u64) {
             initial_deposit.remove(&investor);
         } else {
-            initial_deposit.insert(investor.clone(), new_user_initial_deposit.clone());
+            initial_deposit.insert(investor, new_user_initial_deposit.clone());
         }
         self.set_initial_deposit(initial_deposit);
 
@@ -678,7 +1401,13 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
 
         if self.get_total_shares() == Nat::from(0u64) {
             self.set_current_liquidity(None);
-            self.set_position_id(None);
+
+            // Target weights are kept for the next deposit
+            let allocations = self.get_allocations()
+                .into_iter()
+                .map(|allocation| StrategyAllocation { position_id: None, lp_amount: None, ..allocation })
+                .collect();
+            self.set_allocations(allocations);
         }
 
         strategies_repo::save_strategy(self.clone_self());
@@ -691,6 +1420,14 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
 
     fn to_candid(&self) -> StrategyCandid;
 
+    /// Rebalance params of the strategy profile with the strategy overrides applied
+    fn get_rebalance_params(&self) -> Params {
+        smart_rebalance::profiles::params_for_profile(
+            self.get_profile(),
+            &self.get_params_overrides(),
+        )
+    }
+
     /// Converts the strategy into a StrategyResponse struct that can be returned to clients
     ///
     /// # Returns
@@ -701,9 +1438,12 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
     ///   * `description` - Description of what the strategy does
     ///   * `pools` - List of pool symbols this strategy can invest in
     ///   * `current_pool` - The pool currently being used, if any
+    ///   * `allocations` - Pools holding the strategy capital with their target weights
     ///   * `total_shares` - Total number of shares issued by this strategy
-    ///   * `user_shares` - Mapping of user principals to their share amounts
-    ///   * `initial_deposit` - Mapping of user principals to their initial deposits
+    ///   * `user_shares` - Mapping of user accounts to their share amounts
+    ///   * `initial_deposit` - Mapping of user accounts to their initial deposits
+    ///   * `fees` - Fee rates and accrued fee shares of the strategy
+    ///   * `share_price` - Last recorded value of a share, scaled by 10^12
     fn to_response(&self) -> StrategyResponse {
         StrategyResponse {
             name: self.get_name(),
@@ -720,7 +1460,13 @@ pub trait IStrategy: Send + Sync + BasicStrategy {
             current_liquidity: self.get_current_liquidity(),
             current_liquidity_updated_at: self.get_current_liquidity_updated_at(),
             position_id: self.get_position_id(),
+            allocations: self.get_allocations(),
             enabled: self.get_enabled(),
+            profile: self.get_profile(),
+            params_overrides: self.get_params_overrides(),
+            fees: self.get_fees(),
+            share_price: share_prices_repo::get_last_share_price_point(self.get_id())
+                .map(|point| point.share_price),
         }
     }
 
@@ -733,35 +1479,6 @@ impl Clone for Box<dyn IStrategy> {
     }
 }
 
-pub struct StrategyIterator<'a> {
-    inner: RefMut<'a, Vec<Box<dyn IStrategy>>>,
-    index: usize,
-}
-
-impl<'a> StrategyIterator<'a> {
-    pub fn new(trs: RefMut<'a, Vec<Box<dyn IStrategy>>>) -> Self {
-        StrategyIterator {
-            inner: trs,
-            index: 0,
-        }
-    }
-}
-
-impl<'a> Iterator for StrategyIterator<'a> {
-    type Item = Box<dyn IStrategy>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.inner.len() {
-            // Clone the item at the current index
-            let cloned_item = self.inner[self.index].clone();
-            self.index += 1;
-            Some(cloned_item)
-        } else {
-            None
-        }
-    }
-}
-
 impl Eq for dyn IStrategy {}
 
 impl PartialEq for dyn IStrategy {
@@ -1,58 +1,56 @@
 use candid::{CandidType, Deserialize};
 use serde::Serialize;
 
-use crate::strategies::r#impl::ck_btc_ck_usdt_strategy::ckBTCckUSDTStrategy;
-use crate::strategies::r#impl::ck_btc_strategy::ckBTCStrategy;
-use crate::strategies::r#impl::panda_icp_stategy::PandaTestStrategy;
-use crate::strategies::r#impl::icp_strategy::ICPStrategy;
-use crate::strategies::r#impl::icp_ck_usdt_strategy::IcpCkUSDTStrategy;
-use crate::strategies::r#impl::ics_icp_strategy::IcsStrategy;
-use crate::strategies::r#impl::icp_ck_eth_strategy::IcpCkETHStrategy;
-use crate::strategies::r#impl::ck_btc_icp_strategy::CkBtcIcpStrategy;
-use crate::strategies::r#impl::gldt_ck_usdt_strategy::GldtCkUsdtStrategy;
-use crate::strategies::r#impl::ck_link_icp_strategy::CkLinkIcpStrategy;
+use crate::strategies::r#impl::generic_strategy::GenericStrategy;
+use crate::impl_candid_storable;
 use crate::strategies::strategy::IStrategy;
 
 #[derive(Clone, Debug, CandidType, Serialize, Deserialize)]
 pub enum StrategyCandid {
-    #[allow(non_camel_case_types)]
-    ckBTCStrategyV(ckBTCStrategy),
-    ICPStrategyV(ICPStrategy),
-    #[allow(non_camel_case_types)]
-    PandaTestStrategyV(PandaTestStrategy),
-    IcpCkUSDTStrategyV(IcpCkUSDTStrategy),
-    #[allow(non_camel_case_types)]
-    IcsStrategyV(IcsStrategy),
-    #[allow(non_camel_case_types)]
-    ckBTCckUSDTStrategyV(ckBTCckUSDTStrategy),
-    #[allow(non_camel_case_types)]
-    IcpCkETHStrategyV(IcpCkETHStrategy),
-    #[allow(non_camel_case_types)]
-    CkBtcIcpStrategyV(CkBtcIcpStrategy),
-    #[allow(non_camel_case_types)]
-    GldtCkUsdtStrategyV(GldtCkUsdtStrategy),
-    #[allow(non_camel_case_types)]
-    CkLinkIcpStrategyV(CkLinkIcpStrategy),
+    GenericStrategyV(GenericStrategy),
+
+    // Variants of the former per-strategy types. They share the record layout of `GenericStrategy`
+    // and are only kept to decode existing records, see `strategies_repo::migrate_legacy_strategies`.
+    #[allow(non_camel_case_types)]
+    ckBTCStrategyV(GenericStrategy),
+    ICPStrategyV(GenericStrategy),
+    PandaTestStrategyV(GenericStrategy),
+    IcpCkUSDTStrategyV(GenericStrategy),
+    IcsStrategyV(GenericStrategy),
+    #[allow(non_camel_case_types)]
+    ckBTCckUSDTStrategyV(GenericStrategy),
+    IcpCkETHStrategyV(GenericStrategy),
+    CkBtcIcpStrategyV(GenericStrategy),
+    GldtCkUsdtStrategyV(GenericStrategy),
+    CkLinkIcpStrategyV(GenericStrategy),
+}
+
+impl_candid_storable!(StrategyCandid);
+
+impl StrategyCandid {
+    pub fn is_legacy(&self) -> bool {
+        !matches!(self, StrategyCandid::GenericStrategyV(_))
+    }
 }
 
 pub trait Candid {
     fn to_strategy(&self) -> Box<dyn IStrategy>;
 }
 
-//TODO maybe move to/from candid object + builders
 impl Candid for StrategyCandid {
     fn to_strategy(&self) -> Box<dyn IStrategy> {
         match self {
-            StrategyCandid::ckBTCStrategyV(strategy) => Box::new(strategy.clone()),
-            StrategyCandid::ICPStrategyV(strategy) => Box::new(strategy.clone()),
-            StrategyCandid::PandaTestStrategyV(strategy) => Box::new(strategy.clone()),
-            StrategyCandid::IcpCkUSDTStrategyV(strategy) => Box::new(strategy.clone()),
-            StrategyCandid::IcsStrategyV(strategy) => Box::new(strategy.clone()),
-            StrategyCandid::ckBTCckUSDTStrategyV(strategy) => Box::new(strategy.clone()),
-            StrategyCandid::IcpCkETHStrategyV(strategy) => Box::new(strategy.clone()),
-            StrategyCandid::CkBtcIcpStrategyV(strategy) => Box::new(strategy.clone()),
-            StrategyCandid::GldtCkUsdtStrategyV(strategy) => Box::new(strategy.clone()),
-            StrategyCandid::CkLinkIcpStrategyV(strategy) => Box::new(strategy.clone()),
+            StrategyCandid::GenericStrategyV(strategy)
+            | StrategyCandid::ckBTCStrategyV(strategy)
+            | StrategyCandid::ICPStrategyV(strategy)
+            | StrategyCandid::PandaTestStrategyV(strategy)
+            | StrategyCandid::IcpCkUSDTStrategyV(strategy)
+            | StrategyCandid::IcsStrategyV(strategy)
+            | StrategyCandid::ckBTCckUSDTStrategyV(strategy)
+            | StrategyCandid::IcpCkETHStrategyV(strategy)
+            | StrategyCandid::CkBtcIcpStrategyV(strategy)
+            | StrategyCandid::GldtCkUsdtStrategyV(strategy)
+            | StrategyCandid::CkLinkIcpStrategyV(strategy) => Box::new(strategy.clone()),
         }
     }
-}
\ No newline at end of file
+}
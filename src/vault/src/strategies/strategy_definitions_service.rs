@@ -0,0 +1,384 @@
+use std::collections::HashSet;
+
+use types::CanisterId;
+use types::pool::PoolTrait;
+use types::strategies::{StrategyId, Pool};
+use errors::internal_error::error::{InternalError, InternalErrorKind};
+use errors::internal_error::error_codes::module::areas::{
+    canisters as canister_area,
+    canisters::domains::vault as vault_domain,
+    canisters::domains::vault::components as vault_domain_components,
+};
+
+use crate::repository::strategies_repo;
+use crate::repository::strategy_definitions_repo::{self, StrategyDefinition};
+use crate::strategies::r#impl::generic_strategy::GenericStrategy;
+use crate::strategies::r#impl::description::default_strategy_definitions;
+use crate::types::types::{
+    CreateStrategyArgs,
+    StrategyDefinitionResponse,
+    StrategyPoolArgs,
+    UpdateStrategyMetadataArgs,
+    UpdateStrategyPoolArgs,
+};
+use crate::utils::service_resolver::get_service_resolver;
+
+// Module code: "03-01-06"
+errors::define_error_code_builder_fn!(
+    build_error_code,
+    canister_area::AREA_CODE,                     // Area code: "03"
+    vault_domain::DOMAIN_CODE,                    // Domain code: "01"
+    vault_domain_components::STRATEGY_DEFINITIONS // Component code: "06"
+);
+
+/// Stores the definitions of the built-in strategies that are not defined yet.
+///
+/// Built-in definitions go through the same validation as the runtime ones, invalid ones are not stored.
+pub fn init_strategy_definitions() {
+    for (strategy_id, definition) in default_strategy_definitions() {
+        if validate_definition(&definition).is_ok() {
+            strategy_definitions_repo::add_if_not_exists(strategy_id, definition);
+        }
+    }
+}
+
+pub fn get_strategy_definitions() -> Vec<StrategyDefinitionResponse> {
+    strategy_definitions_repo::get_strategy_definitions()
+        .into_iter()
+        .map(|(strategy_id, definition)| StrategyDefinitionResponse { strategy_id, definition })
+        .collect()
+}
+
+/// Defines a new strategy and adds it disabled to the vault.
+pub async fn create_strategy(args: CreateStrategyArgs) -> Result<StrategyDefinitionResponse, InternalError> {
+    let definition = StrategyDefinition {
+        name: args.name.clone(),
+        description: args.description.clone(),
+        base_token: args.base_token,
+        pools: args.pools.iter().map(build_pool).collect(),
+    };
+
+    validate_definition(&definition).map_err(|message| {
+        InternalError::validation(
+            build_error_code(InternalErrorKind::Validation, 1), // Error code: "03-01-06 02 01"
+            "strategy_definitions_service::create_strategy".to_string(),
+            message,
+            errors::error_extra! {
+                "args" => args,
+            },
+        )
+    })?;
+
+    validate_ledgers(&definition).await?;
+
+    // The id is taken after the ledger calls, so that concurrent calls don't get the same one
+    let strategy_id = strategy_definitions_repo::next_strategy_id();
+
+    strategy_definitions_repo::save_strategy_definition(strategy_id, definition.clone());
+    strategies_repo::add_if_not_exists(Box::new(GenericStrategy::new(strategy_id)));
+
+    Ok(StrategyDefinitionResponse { strategy_id, definition })
+}
+
+pub fn update_strategy_metadata(
+    args: UpdateStrategyMetadataArgs,
+) -> Result<StrategyDefinitionResponse, InternalError> {
+    let mut definition = get_definition(args.strategy_id, "update_strategy_metadata")?;
+
+    definition.name = args.name.clone();
+    definition.description = args.description.clone();
+
+    validate_definition(&definition).map_err(|message| {
+        InternalError::validation(
+            build_error_code(InternalErrorKind::Validation, 2), // Error code: "03-01-06 02 02"
+            "strategy_definitions_service::update_strategy_metadata".to_string(),
+            message,
+            errors::error_extra! {
+                "args" => args,
+            },
+        )
+    })?;
+
+    strategy_definitions_repo::save_strategy_definition(args.strategy_id, definition.clone());
+
+    Ok(StrategyDefinitionResponse { strategy_id: args.strategy_id, definition })
+}
+
+/// Adds a candidate pool to the strategy.
+pub async fn add_strategy_pool(args: UpdateStrategyPoolArgs) -> Result<StrategyDefinitionResponse, InternalError> {
+    let definition = add_pool(&args)?;
+
+    validate_ledgers(&definition).await?;
+
+    // The definition could have been changed during the ledger calls
+    let definition = add_pool(&args)?;
+    strategy_definitions_repo::save_strategy_definition(args.strategy_id, definition.clone());
+
+    Ok(StrategyDefinitionResponse { strategy_id: args.strategy_id, definition })
+}
+
+/// Removes a candidate pool from the strategy.
+///
+/// The pool is matched by its exact id, so that one of two entries of the same pool can be removed.
+/// Pools holding a strategy allocation can't be removed, unless another candidate still covers them.
+pub fn remove_strategy_pool(args: UpdateStrategyPoolArgs) -> Result<StrategyDefinitionResponse, InternalError> {
+    let pool = build_pool(&args.pool);
+    let mut definition = get_definition(args.strategy_id, "remove_strategy_pool")?;
+
+    if !definition.pools.iter().any(|p| p.id == pool.id) {
+        return Err(InternalError::not_found(
+            build_error_code(InternalErrorKind::NotFound, 4), // Error code: "03-01-06 01 04"
+            "strategy_definitions_service::remove_strategy_pool".to_string(),
+            "Pool is not a candidate pool of the strategy".to_string(),
+            errors::error_extra! {
+                "args" => args,
+            },
+        ));
+    }
+
+    definition.pools.retain(|p| p.id != pool.id);
+
+    let is_allocated = strategies_repo::get_strategy_by_id(args.strategy_id)
+        .is_some_and(|strategy| {
+            strategy.get_allocations()
+                .iter()
+                .any(|allocation| !definition.pools.iter().any(|p| p.is_same_pool(&allocation.pool)))
+        });
+
+    if is_allocated {
+        return Err(InternalError::business_logic(
+            build_error_code(InternalErrorKind::BusinessLogic, 5), // Error code: "03-01-06 03 05"
+            "strategy_definitions_service::remove_strategy_pool".to_string(),
+            "Pool holding a strategy allocation can't be removed".to_string(),
+            errors::error_extra! {
+                "args" => args,
+            },
+        ));
+    }
+
+    validate_definition(&definition).map_err(|message| {
+        InternalError::validation(
+            build_error_code(InternalErrorKind::Validation, 6), // Error code: "03-01-06 02 06"
+            "strategy_definitions_service::remove_strategy_pool".to_string(),
+            message,
+            errors::error_extra! {
+                "args" => args,
+            },
+        )
+    })?;
+
+    strategy_definitions_repo::save_strategy_definition(args.strategy_id, definition.clone());
+
+    Ok(StrategyDefinitionResponse { strategy_id: args.strategy_id, definition })
+}
+
+fn add_pool(args: &UpdateStrategyPoolArgs) -> Result<StrategyDefinition, InternalError> {
+    let mut definition = get_definition(args.strategy_id, "add_strategy_pool")?;
+    definition.pools.push(build_pool(&args.pool));
+
+    validate_definition(&definition).map_err(|message| {
+        InternalError::validation(
+            build_error_code(InternalErrorKind::Validation, 3), // Error code: "03-01-06 02 03"
+            "strategy_definitions_service::add_strategy_pool".to_string(),
+            message,
+            errors::error_extra! {
+                "args" => args,
+            },
+        )
+    })?;
+
+    Ok(definition)
+}
+
+fn get_definition(strategy_id: StrategyId, operation: &str) -> Result<StrategyDefinition, InternalError> {
+    strategy_definitions_repo::get_strategy_definition(strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(InternalErrorKind::NotFound, 7), // Error code: "03-01-06 01 07"
+                format!("strategy_definitions_service::{}", operation),
+                "Strategy definition not found".to_string(),
+                errors::error_extra! {
+                    "strategy_id" => strategy_id,
+                },
+            )
+        })
+}
+
+fn build_pool(args: &StrategyPoolArgs) -> Pool {
+    Pool::build(args.token0, args.token1, args.provider)
+}
+
+/// Checks that every token of the definition is an ICRC ledger.
+async fn validate_ledgers(definition: &StrategyDefinition) -> Result<(), InternalError> {
+    let icrc_ledger_client = get_service_resolver().icrc_ledger_client();
+
+    for token in get_tokens(definition) {
+        icrc_ledger_client.icrc1_decimals(token).await
+            .map_err(|error| {
+                InternalError::validation(
+                    build_error_code(InternalErrorKind::Validation, 8), // Error code: "03-01-06 02 08"
+                    "strategy_definitions_service::validate_ledgers".to_string(),
+                    "Token is not an ICRC ledger".to_string(),
+                    errors::error_extra! {
+                        "token" => token,
+                        "error" => error.message,
+                    },
+                )
+            })?;
+    }
+
+    Ok(())
+}
+
+fn get_tokens(definition: &StrategyDefinition) -> Vec<CanisterId> {
+    let mut seen = HashSet::new();
+
+    std::iter::once(definition.base_token)
+        .chain(definition.pools.iter().flat_map(|pool| [pool.token0, pool.token1]))
+        .filter(|token| seen.insert(*token))
+        .collect()
+}
+
+fn validate_definition(definition: &StrategyDefinition) -> Result<(), String> {
+    if definition.name.trim().is_empty() {
+        return Err("Strategy name must not be empty".to_string());
+    }
+
+    if definition.pools.is_empty() {
+        return Err("Strategy must have at least one pool".to_string());
+    }
+
+    for (index, pool) in definition.pools.iter().enumerate() {
+        if pool.token0 == pool.token1 {
+            return Err(format!("Pool {} must have two different tokens", pool.id));
+        }
+
+        if pool.token0 != definition.base_token && pool.token1 != definition.base_token {
+            return Err(format!("Pool {} doesn't contain the base token", pool.id));
+        }
+
+        if definition.pools[..index].iter().any(|p| p.is_same_pool(pool)) {
+            return Err(format!("Pool {} is added more than once", pool.id));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+
+    use types::exchange_id::ExchangeId;
+
+    use super::*;
+
+    fn token(id: u8) -> CanisterId {
+        Principal::from_slice(&[id; 29])
+    }
+
+    fn definition(pools: Vec<Pool>) -> StrategyDefinition {
+        StrategyDefinition {
+            name: "Strategy".to_string(),
+            description: "Description".to_string(),
+            base_token: token(1),
+            pools,
+        }
+    }
+
+    mod validate_definition {
+        use super::*;
+
+        #[test]
+        fn accepts_pools_with_base_token() {
+            let definition = definition(vec![
+                Pool::build(token(1), token(2), ExchangeId::KongSwap),
+                Pool::build(token(3), token(1), ExchangeId::ICPSwap),
+            ]);
+
+            assert!(validate_definition(&definition).is_ok());
+        }
+
+        #[test]
+        fn rejects_pool_without_base_token() {
+            let definition = definition(vec![
+                Pool::build(token(1), token(2), ExchangeId::KongSwap),
+                Pool::build(token(2), token(3), ExchangeId::KongSwap),
+            ]);
+
+            assert!(validate_definition(&definition).is_err());
+        }
+
+        #[test]
+        fn rejects_duplicate_pool() {
+            let definition = definition(vec![
+                Pool::build(token(1), token(2), ExchangeId::KongSwap),
+                Pool::build(token(2), token(1), ExchangeId::KongSwap),
+            ]);
+
+            assert!(validate_definition(&definition).is_err());
+        }
+
+        #[test]
+        fn rejects_empty_pools_and_name() {
+            assert!(validate_definition(&definition(vec![])).is_err());
+
+            let mut definition = definition(vec![Pool::build(token(1), token(2), ExchangeId::KongSwap)]);
+            definition.name = " ".to_string();
+
+            assert!(validate_definition(&definition).is_err());
+        }
+    }
+
+    mod remove_strategy_pool {
+        use super::*;
+
+        #[test]
+        fn removes_only_the_entry_with_the_pool_id() {
+            let kept = Pool::build(token(1), token(2), ExchangeId::KongSwap);
+            let reversed = Pool::build(token(2), token(1), ExchangeId::KongSwap);
+            strategy_definitions_repo::save_strategy_definition(900, definition(vec![kept.clone(), reversed]));
+
+            let response = remove_strategy_pool(UpdateStrategyPoolArgs {
+                strategy_id: 900,
+                pool: StrategyPoolArgs {
+                    token0: token(2),
+                    token1: token(1),
+                    provider: ExchangeId::KongSwap,
+                },
+            }).unwrap();
+
+            assert_eq!(response.definition.pools, vec![kept]);
+        }
+    }
+
+    mod default_strategy_definitions {
+        use super::*;
+
+        #[test]
+        fn are_valid() {
+            for (strategy_id, definition) in default_strategy_definitions() {
+                assert!(
+                    validate_definition(&definition).is_ok(),
+                    "Built-in strategy {} is invalid",
+                    strategy_id,
+                );
+            }
+        }
+    }
+
+    mod get_tokens {
+        use super::*;
+
+        #[test]
+        fn returns_unique_tokens() {
+            let definition = definition(vec![
+                Pool::build(token(1), token(2), ExchangeId::KongSwap),
+                Pool::build(token(1), token(2), ExchangeId::ICPSwap),
+                Pool::build(token(3), token(1), ExchangeId::KongSwap),
+            ]);
+
+            assert_eq!(get_tokens(&definition), vec![token(1), token(2), token(3)]);
+        }
+    }
+}
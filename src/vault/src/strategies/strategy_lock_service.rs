@@ -0,0 +1,209 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use candid::Principal;
+
+use types::strategies::StrategyId;
+use utils::util::current_timestamp_secs;
+use errors::internal_error::error::{InternalError, InternalErrorKind};
+use errors::internal_error::error_codes::module::areas::{
+    canisters as canister_area,
+    canisters::domains::vault as vault_domain,
+    canisters::domains::vault::components as vault_domain_components,
+};
+
+// Module code: "03-01-04"
+errors::define_error_code_builder_fn!(
+    build_error_code,
+    canister_area::AREA_CODE,               // Area code: "03"
+    vault_domain::DOMAIN_CODE,              // Domain code: "01"
+    vault_domain_components::OPERATION_LOCK // Component code: "04"
+);
+
+// The guard is released on drop, but a lock taken by an operation
+// that never completed is considered stale after this timeout
+const LOCK_TIMEOUT_SECS: u64 = 3600; // 1 hour
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StrategyOperation {
+    Deposit,
+    Withdraw,
+    Rebalance,
+    FeeAccrual,
+    Compounding,
+    Unwind,
+    Redemption,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Lock {
+    operation: StrategyOperation,
+    locked_at: u64,
+}
+
+impl Lock {
+    fn is_stale(&self, now: u64) -> bool {
+        now >= self.locked_at.saturating_add(LOCK_TIMEOUT_SECS)
+    }
+}
+
+thread_local! {
+    static STRATEGY_LOCKS: RefCell<HashMap<StrategyId, Lock>> = RefCell::new(HashMap::new());
+    static CALLER_LOCKS: RefCell<HashMap<Principal, Lock>> = RefCell::new(HashMap::new());
+}
+
+/// Holds the strategy (and the caller, if any) locked until it is dropped.
+#[derive(Debug)]
+pub struct StrategyLockGuard {
+    strategy_id: StrategyId,
+    caller: Option<Principal>,
+    locked_at: u64,
+}
+
+impl Drop for StrategyLockGuard {
+    fn drop(&mut self) {
+        // A stale lock could have been taken over, in this case it belongs to another operation
+        let is_own_lock = |lock: Option<&Lock>| lock.is_some_and(|lock| lock.locked_at == self.locked_at);
+
+        STRATEGY_LOCKS.with(|locks| {
+            let mut locks = locks.borrow_mut();
+            if is_own_lock(locks.get(&self.strategy_id)) {
+                locks.remove(&self.strategy_id);
+            }
+        });
+
+        if let Some(caller) = self.caller {
+            CALLER_LOCKS.with(|locks| {
+                let mut locks = locks.borrow_mut();
+                if is_own_lock(locks.get(&caller)) {
+                    locks.remove(&caller);
+                }
+            });
+        }
+    }
+}
+
+/// Locks the strategy and the caller for a state-mutating operation.
+///
+/// Deposits, withdrawals and rebalances of the same strategy are serialized,
+/// and a caller can't run several deposits or withdrawals at the same time.
+pub fn lock_strategy(
+    strategy_id: StrategyId,
+    caller: Option<Principal>,
+    operation: StrategyOperation,
+) -> Result<StrategyLockGuard, InternalError> {
+    try_lock(strategy_id, caller, operation, current_timestamp_secs())
+}
+
+/// Returns the operation currently holding the strategy lock.
+pub fn get_locked_operation(strategy_id: StrategyId) -> Option<StrategyOperation> {
+    let now = current_timestamp_secs();
+
+    STRATEGY_LOCKS.with(|locks| {
+        locks.borrow()
+            .get(&strategy_id)
+            .filter(|lock| !lock.is_stale(now))
+            .map(|lock| lock.operation)
+    })
+}
+
+fn try_lock(
+    strategy_id: StrategyId,
+    caller: Option<Principal>,
+    operation: StrategyOperation,
+    now: u64,
+) -> Result<StrategyLockGuard, InternalError> {
+    if let Some(caller) = caller {
+        let caller_lock = CALLER_LOCKS.with(|locks| locks.borrow().get(&caller).cloned())
+            .filter(|lock| !lock.is_stale(now));
+
+        if let Some(caller_lock) = caller_lock {
+            return Err(InternalError::business_logic(
+                build_error_code(InternalErrorKind::BusinessLogic, 1), // Error code: "03-01-04 03 01"
+                "strategy_lock_service::lock_strategy".to_string(),
+                "Another operation of the caller is already in progress".to_string(),
+                errors::error_extra! {
+                    "strategy_id" => strategy_id,
+                    "caller" => caller,
+                    "operation" => operation,
+                    "locked_operation" => caller_lock.operation,
+                },
+            ));
+        }
+    }
+
+    let strategy_lock = STRATEGY_LOCKS.with(|locks| locks.borrow().get(&strategy_id).cloned())
+        .filter(|lock| !lock.is_stale(now));
+
+    if let Some(strategy_lock) = strategy_lock {
+        return Err(InternalError::business_logic(
+            build_error_code(InternalErrorKind::BusinessLogic, 2), // Error code: "03-01-04 03 02"
+            "strategy_lock_service::lock_strategy".to_string(),
+            "Another operation of the strategy is already in progress".to_string(),
+            errors::error_extra! {
+                "strategy_id" => strategy_id,
+                "operation" => operation,
+                "locked_operation" => strategy_lock.operation,
+            },
+        ));
+    }
+
+    let lock = Lock { operation, locked_at: now };
+
+    STRATEGY_LOCKS.with(|locks| locks.borrow_mut().insert(strategy_id, lock));
+
+    if let Some(caller) = caller {
+        CALLER_LOCKS.with(|locks| locks.borrow_mut().insert(caller, lock));
+    }
+
+    Ok(StrategyLockGuard { strategy_id, caller, locked_at: now })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_principal(id: u8) -> Principal {
+        Principal::from_slice(&[id; 29])
+    }
+
+    mod try_lock {
+        use super::*;
+
+        #[test]
+        fn rejects_overlapping_strategy_operation() {
+            let _guard = try_lock(1, None, StrategyOperation::Rebalance, 1_000).unwrap();
+
+            assert!(try_lock(1, Some(fake_principal(1)), StrategyOperation::Deposit, 1_001).is_err());
+            assert!(try_lock(2, Some(fake_principal(1)), StrategyOperation::Deposit, 1_001).is_ok());
+        }
+
+        #[test]
+        fn rejects_overlapping_caller_operation() {
+            let user = fake_principal(2);
+            let _guard = try_lock(3, Some(user), StrategyOperation::Deposit, 1_000).unwrap();
+
+            assert!(try_lock(4, Some(user), StrategyOperation::Withdraw, 1_001).is_err());
+            assert!(try_lock(4, Some(fake_principal(3)), StrategyOperation::Withdraw, 1_001).is_ok());
+        }
+
+        #[test]
+        fn releases_locks_on_drop() {
+            let user = fake_principal(4);
+
+            drop(try_lock(5, Some(user), StrategyOperation::Withdraw, 1_000).unwrap());
+
+            assert!(try_lock(5, Some(user), StrategyOperation::Withdraw, 1_001).is_ok());
+        }
+
+        #[test]
+        fn takes_over_stale_lock() {
+            let stale_guard = try_lock(6, None, StrategyOperation::Rebalance, 1_000).unwrap();
+            let _guard = try_lock(6, None, StrategyOperation::Rebalance, 1_000 + LOCK_TIMEOUT_SECS).unwrap();
+
+            // Releasing the stale guard keeps the lock of the new operation
+            drop(stale_guard);
+
+            assert!(try_lock(6, None, StrategyOperation::Deposit, 1_000 + LOCK_TIMEOUT_SECS).is_err());
+        }
+    }
+}
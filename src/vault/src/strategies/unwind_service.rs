@@ -0,0 +1,400 @@
+use candid::Nat;
+use icrc_ledger_types::icrc1::account::Account;
+
+use types::context::Context;
+use types::pool::PoolTrait;
+use types::strategies::{StrategyAllocation, StrategyId};
+use utils::util::current_timestamp_secs;
+use errors::internal_error::error::{InternalError, InternalErrorKind};
+use errors::internal_error::error_codes::module::areas::{
+    canisters as canister_area,
+    canisters::domains::vault as vault_domain,
+    canisters::domains::vault::components as vault_domain_components,
+};
+
+use crate::event_records::event_record::Event;
+use crate::event_records::event_record_service;
+use crate::liquidity::liquidity_service;
+use crate::repository::strategies_repo;
+use crate::repository::unwinds_repo::{self, StrategyUnwind};
+use crate::repository::pending_withdrawals_repo::{PendingWithdrawalKind, PendingWithdrawalStatus};
+use crate::strategies::strategy::IStrategy;
+use crate::strategies::strategy_lock_service::{self, StrategyOperation};
+use crate::user::pending_withdrawals_service;
+use crate::user::user_service;
+use crate::types::types::StrategyRedeemResponse;
+
+// Module code: "03-01-09"
+errors::define_error_code_builder_fn!(
+    build_error_code,
+    canister_area::AREA_CODE,          // Area code: "03"
+    vault_domain::DOMAIN_CODE,         // Domain code: "01"
+    vault_domain_components::EMERGENCY // Component code: "09"
+);
+
+/// Exits every position of the strategy into its base token, which is parked in the vault.
+///
+/// Deposits, withdrawals and rebalances of the strategy are refused from the start of the unwind,
+/// the shares are redeemed for the parked amount with `redeem_unwound_shares` once it is completed.
+/// If a position can't be exited, the exited ones stay parked and the unwind can be resumed.
+pub async fn emergency_unwind(context: Context) -> Result<StrategyUnwind, InternalError> {
+    let strategy_id = context.strategy_id.unwrap();
+
+    let _lock = strategy_lock_service::lock_strategy(
+        strategy_id,
+        None,
+        StrategyOperation::Unwind,
+    )?;
+
+    let mut strategy = strategies_repo::get_strategy_by_id(strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(InternalErrorKind::NotFound, 6), // Error code: "03-01-09 01 06"
+                "unwind_service::emergency_unwind".to_string(),
+                "Strategy not found".to_string(),
+                errors::error_extra! {
+                    "context" => context,
+                },
+            )
+        })?;
+
+    let previous_unwind = unwinds_repo::get_strategy_unwind(strategy_id);
+
+    if previous_unwind.as_ref().is_some_and(|unwind| unwind.completed) {
+        return Err(InternalError::business_logic(
+            build_error_code(InternalErrorKind::BusinessLogic, 7), // Error code: "03-01-09 03 07"
+            "unwind_service::emergency_unwind".to_string(),
+            "Strategy is already unwound".to_string(),
+            errors::error_extra! {
+                "context" => context,
+            },
+        ));
+    }
+
+    // Shares are frozen by the first attempt, a resumed unwind exits the rest of the positions for them
+    let mut unwind = match previous_unwind {
+        Some(unwind) => unwind,
+        None => {
+            let total_shares = strategy.get_total_shares();
+
+            if total_shares == Nat::from(0u64) {
+                return Err(InternalError::business_logic(
+                    build_error_code(InternalErrorKind::BusinessLogic, 8), // Error code: "03-01-09 03 08"
+                    "unwind_service::emergency_unwind".to_string(),
+                    "No shares to unwind".to_string(),
+                    errors::error_extra! {
+                        "context" => context,
+                    },
+                ));
+            }
+
+            StrategyUnwind {
+                strategy_id,
+                token: strategy.get_base_token(),
+                balance: Nat::from(0u64),
+                shares: total_shares,
+                completed: false,
+                started_at: current_timestamp_secs(),
+                completed_at: None,
+            }
+        }
+    };
+
+    let allocations: Vec<StrategyAllocation> = strategy.get_allocations()
+        .into_iter()
+        .filter(|allocation| allocation.position_id.is_some())
+        .collect();
+
+    strategy.check_position_ownership(&allocations)?;
+
+    // Saved before any external call, from now on the strategy only accepts redemptions
+    unwinds_repo::save_strategy_unwind(unwind.clone());
+
+    // Event: Strategy unwind started
+    event_record_service::create_event_record(
+        Event::strategy_unwind_started(strategy_id.to_string(), unwind.shares.clone()),
+        context.correlation_id.clone(),
+        context.user,
+        context.strategy_id,
+    );
+
+    // Every position is exited whole, so the whole strategy supply is withdrawn
+    let total_shares = strategy.get_total_shares();
+
+    for allocation in allocations {
+        let withdraw_response = liquidity_service::withdraw_liquidity_from_pool_and_swap(
+            context.clone(),
+            total_shares.clone(),
+            total_shares.clone(),
+            allocation.pool.clone(),
+            allocation.liquidity_position(),
+        ).await
+            .map_err(|error| {
+                // Event: Strategy unwind failed
+                event_record_service::create_event_record(
+                    Event::strategy_unwind_failed(
+                        strategy_id.to_string(),
+                        unwind.shares.clone(),
+                        error.clone(),
+                    ),
+                    context.correlation_id.clone(),
+                    context.user,
+                    context.strategy_id,
+                );
+
+                error
+            })?;
+
+        unwind.balance += withdraw_response.amount;
+        unwinds_repo::save_strategy_unwind(unwind.clone());
+
+        // The exited position is removed, so a resumed unwind doesn't withdraw it again
+        strategy.reload_state();
+        let allocations = strategy.get_allocations()
+            .into_iter()
+            .map(|strategy_allocation| {
+                if strategy_allocation.pool.is_same_pool(&allocation.pool) {
+                    StrategyAllocation { position_id: None, lp_amount: None, ..strategy_allocation }
+                } else {
+                    strategy_allocation
+                }
+            })
+            .collect();
+        strategy.set_allocations(allocations);
+        strategies_repo::save_strategy(strategy.clone_self());
+    }
+
+    // Base token held outside of the positions is paid out together with the exited liquidity
+    strategy.reload_state();
+    unwind.balance += strategy.get_uninvested_balance();
+    strategy.set_uninvested_balance(Nat::from(0u64));
+    strategy.set_current_liquidity(None);
+    strategy.set_current_liquidity_updated_at(Some(current_timestamp_secs()));
+    strategies_repo::save_strategy(strategy.clone_self());
+
+    unwind.completed = true;
+    unwind.completed_at = Some(current_timestamp_secs());
+    unwinds_repo::save_strategy_unwind(unwind.clone());
+
+    // Event: Strategy unwind completed
+    event_record_service::create_event_record(
+        Event::strategy_unwind_completed(
+            strategy_id.to_string(),
+            unwind.shares.clone(),
+            unwind.balance.clone(),
+        ),
+        context.correlation_id,
+        context.user,
+        context.strategy_id,
+    );
+
+    Ok(unwind)
+}
+
+/// Burns every share of the context account in an unwound strategy and pays their pro-rata part of the parked amount.
+///
+/// The payout is claimable from the pending withdrawals if the transfer fails.
+pub async fn redeem_unwound_shares(context: Context) -> Result<StrategyRedeemResponse, InternalError> {
+    let strategy_id = context.strategy_id.unwrap();
+    let user = context.user.unwrap();
+    let account = context.account().unwrap();
+
+    let _lock = strategy_lock_service::lock_strategy(
+        strategy_id,
+        Some(user),
+        StrategyOperation::Redemption,
+    )?;
+
+    let mut unwind = unwinds_repo::get_strategy_unwind(strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(InternalErrorKind::NotFound, 9), // Error code: "03-01-09 01 09"
+                "unwind_service::redeem_unwound_shares".to_string(),
+                "Strategy is not unwound".to_string(),
+                errors::error_extra! {
+                    "context" => context,
+                },
+            )
+        })?;
+
+    if !unwind.completed {
+        return Err(InternalError::business_logic(
+            build_error_code(InternalErrorKind::BusinessLogic, 10), // Error code: "03-01-09 03 10"
+            "unwind_service::redeem_unwound_shares".to_string(),
+            "Strategy unwind is not completed".to_string(),
+            errors::error_extra! {
+                "context" => context,
+            },
+        ));
+    }
+
+    let mut strategy = strategies_repo::get_strategy_by_id(strategy_id)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(InternalErrorKind::NotFound, 11), // Error code: "03-01-09 01 11"
+                "unwind_service::redeem_unwound_shares".to_string(),
+                "Strategy not found".to_string(),
+                errors::error_extra! {
+                    "context" => context,
+                },
+            )
+        })?;
+
+    let user_shares = strategy.get_user_shares_by_account(account);
+
+    if user_shares == Nat::from(0u64) {
+        return Err(InternalError::business_logic(
+            build_error_code(InternalErrorKind::BusinessLogic, 12), // Error code: "03-01-09 03 12"
+            "unwind_service::redeem_unwound_shares".to_string(),
+            "No shares found for user".to_string(),
+            errors::error_extra! {
+                "context" => context,
+            },
+        ));
+    }
+
+    let (redeemed_shares, amount) = calculate_redemption(&unwind, &user_shares);
+
+    let redemption = pending_withdrawals_service::create_pending_withdrawal(
+        PendingWithdrawalKind::UnwindRedemption,
+        PendingWithdrawalStatus::Claimable,
+        &context,
+        account,
+        unwind.token,
+        redeemed_shares.clone(),
+        Some(amount.clone()),
+    );
+
+    // Shares are burned before the transfer, from now on the redeemed amount is claimable by the user
+    burn_user_shares(strategy.as_mut(), account, user_shares.clone());
+    strategies_repo::save_strategy(strategy);
+
+    user_service::record_withdraw(&context, unwind.token, amount.clone(), None);
+    user_service::record_shares_burned(&context, user_shares, None);
+
+    unwind.balance -= amount.clone();
+    unwind.shares -= redeemed_shares.clone();
+    unwinds_repo::save_strategy_unwind(unwind.clone());
+
+    // Event: Unwound shares redeemed
+    event_record_service::create_event_record(
+        Event::unwound_shares_redeemed(
+            strategy_id.to_string(),
+            redeemed_shares.clone(),
+            amount.clone(),
+        ),
+        context.correlation_id,
+        Some(user),
+        context.strategy_id,
+    );
+
+    pending_withdrawals_service::transfer_pending_withdrawal(redemption.id).await?;
+
+    Ok(StrategyRedeemResponse {
+        shares: redeemed_shares,
+        amount,
+        token: unwind.token,
+    })
+}
+
+pub fn get_strategy_unwinds() -> Vec<StrategyUnwind> {
+    unwinds_repo::get_strategy_unwinds()
+}
+
+/// Returns what a redemption of the shares of a user account would pay, if the strategy unwind is completed.
+pub fn preview_redemption(account: Account, strategy_id: StrategyId) -> Option<StrategyRedeemResponse> {
+    let unwind = unwinds_repo::get_strategy_unwind(strategy_id)
+        .filter(|unwind| unwind.completed)?;
+
+    let user_shares = strategies_repo::get_strategy_by_id(strategy_id)?
+        .get_user_shares()
+        .get(&account)
+        .cloned()?;
+
+    let (shares, amount) = calculate_redemption(&unwind, &user_shares);
+
+    Some(StrategyRedeemResponse {
+        shares,
+        amount,
+        token: unwind.token,
+    })
+}
+
+/// Returns the redeemed shares and their part of the parked amount.
+///
+/// Shares are capped to the ones not redeemed yet, so the parked amount can't be overdrawn.
+fn calculate_redemption(unwind: &StrategyUnwind, user_shares: &Nat) -> (Nat, Nat) {
+    let shares = user_shares.clone().min(unwind.shares.clone());
+
+    if unwind.shares == Nat::from(0u64) {
+        return (shares, Nat::from(0u64));
+    }
+
+    let amount = unwind.balance.clone() * shares.clone() / unwind.shares.clone();
+
+    (shares, amount)
+}
+
+fn burn_user_shares(strategy: &mut dyn IStrategy, account: Account, shares: Nat) {
+    let user_initial_deposit = strategy.get_initial_deposit()
+        .get(&account)
+        .cloned()
+        .unwrap_or(Nat::from(0u64));
+
+    strategy.decrease_total_shares(shares);
+    strategy.update_user_shares(account, Nat::from(0u64));
+    strategy.update_initial_deposit(account, Nat::from(0u64));
+    strategy.decrease_total_balance(user_initial_deposit);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use candid::Principal;
+
+    fn unwind(balance: u64, shares: u64) -> StrategyUnwind {
+        StrategyUnwind {
+            strategy_id: 1,
+            token: Principal::anonymous(),
+            balance: Nat::from(balance),
+            shares: Nat::from(shares),
+            completed: true,
+            started_at: 0,
+            completed_at: Some(0),
+        }
+    }
+
+    mod calculate_redemption {
+        use super::*;
+
+        #[test]
+        fn pays_pro_rata_of_parked_balance() {
+            let (shares, amount) = calculate_redemption(&unwind(1_000, 400), &Nat::from(100u64));
+
+            assert_eq!(shares, Nat::from(100u64));
+            assert_eq!(amount, Nat::from(250u64));
+        }
+
+        #[test]
+        fn keeps_share_value_after_redemptions() {
+            let mut unwind = unwind(1_000, 300);
+
+            let (first_shares, first_amount) = calculate_redemption(&unwind, &Nat::from(100u64));
+            unwind.balance -= first_amount.clone();
+            unwind.shares -= first_shares;
+
+            let (_, second_amount) = calculate_redemption(&unwind, &Nat::from(200u64));
+
+            assert_eq!(first_amount, Nat::from(333u64));
+            assert_eq!(second_amount, Nat::from(667u64));
+        }
+
+        #[test]
+        fn caps_shares_to_not_redeemed_ones() {
+            let (shares, amount) = calculate_redemption(&unwind(1_000, 50), &Nat::from(80u64));
+
+            assert_eq!(shares, Nat::from(50u64));
+            assert_eq!(amount, Nat::from(1_000u64));
+        }
+    }
+}
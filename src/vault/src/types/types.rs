@@ -1,12 +1,21 @@
-use candid::{CandidType, Deserialize, Nat};
+use candid::{CandidType, Deserialize, Int, Nat};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use serde::Serialize;
 
 use types::CanisterId;
+use types::exchange_id::ExchangeId;
 use types::strategies::StrategyId;
-use types::strategies::Pool;
+use types::strategies::{Pool, StrategyAllocation, StrategyProfile, StrategyParamsOverrides, StrategyFees};
+use smart_rebalance::types::Params;
 use errors::response_error::error::ResponseError;
 
 use crate::event_records::event_record::EventRecord;
+use crate::repository::rebalance_history_repo::RebalanceRecord;
+use crate::repository::share_prices_repo::SharePricePoint;
+use crate::repository::strategy_definitions_repo::StrategyDefinition;
+use crate::repository::emergency_repo::{PauseState, CircuitBreakerConfig};
+use crate::repository::unwinds_repo::StrategyUnwind;
+use crate::repository::user_transactions_repo::{UserTransaction, UserTransactionKind};
 
 #[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
 pub struct PoolData {
@@ -19,6 +28,10 @@ pub struct StrategyDepositArgs {
     pub strategy_id: StrategyId,
     pub ledger: CanisterId,
     pub amount: Nat,
+    /// Minimum base token amount received for a deposit in another token
+    pub min_amount_out: Option<Nat>,
+    /// Subaccount of the caller the amount is taken from, it holds the position
+    pub from_subaccount: Option<Subaccount>,
 }
 
 #[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
@@ -26,6 +39,14 @@ pub struct StrategyWithdrawArgs {
     pub strategy_id: StrategyId,
     pub ledger: CanisterId,
     pub percentage: Nat,
+    /// Token to receive instead of the base token
+    pub output_token: Option<CanisterId>,
+    /// Minimum output token amount received
+    pub min_amount_out: Option<Nat>,
+    /// Subaccount of the caller holding the position
+    pub subaccount: Option<Subaccount>,
+    /// Account receiving the withdrawn tokens, the account holding the position by default
+    pub to: Option<Account>,
 }
 
 #[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
@@ -40,6 +61,55 @@ pub struct StrategyDepositResponse {
 pub struct StrategyWithdrawResponse {
     pub amount: Nat,
     pub current_shares: Nat,
+    /// Token the withdrawn amount is paid in
+    pub token: CanisterId,
+    /// Why the amount is paid in the base token instead of the requested output token
+    pub zap_error: Option<ResponseError>,
+}
+
+/// Share token of a strategy, held in the share subaccount of the strategy.
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct StrategyShareToken {
+    pub strategy_id: StrategyId,
+    pub name: String,
+    pub symbol: String,
+    /// Decimals of the base token, none until its price was cached
+    pub decimals: Option<u8>,
+    pub total_supply: Nat,
+    pub subaccount: Subaccount,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct StrategyRedeemResponse {
+    pub shares: Nat,
+    pub amount: Nat,
+    pub token: CanisterId,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct StrategyDepositPreview {
+    /// Shares expected at the current NAV
+    pub shares: Nat,
+    /// Base token value expected to be added to the pools
+    pub amount: Nat,
+    /// Ledger fees deducted from the amount, valued in base token
+    pub fees: Nat,
+    pub price_impact_bps: u64,
+    /// Pool with the largest weight
+    pub pool: Pool,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct StrategyWithdrawPreview {
+    /// Shares that would be burned
+    pub shares: Nat,
+    /// Base token amount expected to be withdrawn
+    pub amount: Nat,
+    /// Ledger fees deducted from the amount, valued in base token
+    pub fees: Nat,
+    pub price_impact_bps: u64,
+    /// Pool with the largest weight
+    pub pool: Pool,
 }
 
 #[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
@@ -47,6 +117,120 @@ pub struct StrategyRebalanceResponse {
     pub previous_pool: Pool,
     pub current_pool: Pool,
     pub is_rebalanced: bool,
+    pub allocations: Vec<StrategyAllocation>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct RebalanceScheduleResponse {
+    pub strategy_id: StrategyId,
+    pub enabled: bool,
+    pub cooldown_secs: u64,
+    pub last_run_at: Option<u64>,
+    pub next_run_at: Option<u64>,
+    pub last_error: Option<String>,
+    pub in_progress: bool,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct UpdateRebalanceScheduleArgs {
+    pub strategy_id: StrategyId,
+    pub enabled: bool,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct UpdateStrategyRebalanceParamsArgs {
+    pub strategy_id: StrategyId,
+    pub profile: StrategyProfile,
+    pub params_overrides: StrategyParamsOverrides,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct StrategyRebalanceParamsResponse {
+    pub strategy_id: StrategyId,
+    pub profile: StrategyProfile,
+    pub params_overrides: StrategyParamsOverrides,
+    pub params: Params,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct UpdateStrategyFeesArgs {
+    pub strategy_id: StrategyId,
+    pub performance_fee_bps: u32,
+    pub management_fee_bps: u32,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct StrategyPoolArgs {
+    pub token0: CanisterId,
+    pub token1: CanisterId,
+    pub provider: ExchangeId,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct CreateStrategyArgs {
+    pub name: String,
+    pub description: String,
+    pub base_token: CanisterId,
+    pub pools: Vec<StrategyPoolArgs>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct UpdateStrategyMetadataArgs {
+    pub strategy_id: StrategyId,
+    pub name: String,
+    pub description: String,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct UpdateStrategyPoolArgs {
+    pub strategy_id: StrategyId,
+    pub pool: StrategyPoolArgs,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct StrategyDefinitionResponse {
+    pub strategy_id: StrategyId,
+    pub definition: StrategyDefinition,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct PoolCompoundResponse {
+    pub pool: Pool,
+    pub is_compounded: bool,
+    /// Value of the accumulated fees and estimated cost of compounding them in pool token0
+    pub fees_value: Nat,
+    pub cost: Nat,
+    /// Token amounts added back to the position
+    pub token_0_amount: Nat,
+    pub token_1_amount: Nat,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct StrategyCompoundResponse {
+    pub strategy_id: StrategyId,
+    pub pools: Vec<PoolCompoundResponse>,
+}
+
+/// Operations to pause or unpause, for one strategy or, without strategy, for all of them.
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct PauseOperationsArgs {
+    pub strategy_id: Option<StrategyId>,
+    pub deposit: bool,
+    pub withdraw: bool,
+    pub rebalance: bool,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct StrategyPauseResponse {
+    pub strategy_id: StrategyId,
+    pub pause: PauseState,
+}
+
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct EmergencyStateResponse {
+    pub global_pause: PauseState,
+    pub paused_strategies: Vec<StrategyPauseResponse>,
+    pub circuit_breaker: CircuitBreakerConfig,
 }
 
 // TODO: rename to UserPositionResponse
@@ -55,12 +239,39 @@ pub struct UserStrategyResponse {
     pub strategy_id: StrategyId,
     pub strategy_name: String,
     pub strategy_current_pool: Pool,
+    pub allocations: Vec<StrategyAllocation>,
     pub total_shares: Nat,
+    /// Subaccount of the user holding the shares, `None` for the default account
+    pub subaccount: Option<Subaccount>,
     pub user_shares: Nat,
     pub initial_deposit: Nat,
     pub users_count: u32,
 }
 
+/// Valuation of the shares of a user account in a strategy.
+#[derive(CandidType, Deserialize, Clone, Serialize, Debug)]
+pub struct UserPositionResponse {
+    pub strategy_id: StrategyId,
+    pub strategy_name: String,
+    pub base_token: CanisterId,
+    /// Subaccount of the user holding the shares, `None` for the default account
+    pub subaccount: Option<Subaccount>,
+    pub user_shares: Nat,
+    pub total_shares: Nat,
+    /// Part of the strategy TVL owned by the user, in basis points
+    pub tvl_share_bps: u64,
+    /// Current value of the shares in base token, empty until the position liquidity is known
+    pub value: Option<Nat>,
+    /// Value of the shares at the last cached USD price of the base token
+    pub value_usd: Option<f64>,
+    /// Base token amount deposited for the shares held
+    pub cost_basis: Nat,
+    pub unrealized_pnl: Option<Int>,
+    /// Withdrawn amount minus the cost basis of the withdrawn shares
+    pub realized_pnl: Int,
+    pub valued_at: Option<u64>,
+}
+
 #[derive(Clone, Debug, CandidType, Deserialize)]
 pub struct Icrc28TrustedOriginsResponse {
     pub trusted_origins: Vec<String>,
@@ -78,15 +289,78 @@ pub struct StrategyDepositResult(pub Result<StrategyDepositResponse, ResponseErr
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct StrategyWithdrawResult(pub Result<StrategyWithdrawResponse, ResponseError>);
 
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyDepositPreviewResult(pub Result<StrategyDepositPreview, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyWithdrawPreviewResult(pub Result<StrategyWithdrawPreview, ResponseError>);
+
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct GetEventRecordsResult(pub Result<EventRecordsPaginationResponse, ResponseError>);
 
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct StrategyRebalanceResult(pub Result<StrategyRebalanceResponse, ResponseError>);
 
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyCompoundResult(pub Result<StrategyCompoundResponse, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct RebalanceScheduleResult(pub Result<RebalanceScheduleResponse, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct GetRebalanceSchedulesResult(pub Result<Vec<RebalanceScheduleResponse>, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyRebalanceParamsResult(pub Result<StrategyRebalanceParamsResponse, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyFeesResult(pub Result<StrategyFees, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyDefinitionResult(pub Result<StrategyDefinitionResponse, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct GetStrategyDefinitionsResult(pub Result<Vec<StrategyDefinitionResponse>, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct PauseResult(pub Result<PauseState, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct CircuitBreakerConfigResult(pub Result<CircuitBreakerConfig, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyUnwindResult(pub Result<StrategyUnwind, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct StrategyRedeemResult(pub Result<StrategyRedeemResponse, ResponseError>);
+
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct EventRecordsPaginationResponse(pub ListItemsPaginationResponse<EventRecord>);
 
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct GetRebalanceHistoryResult(pub Result<RebalanceHistoryPaginationResponse, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct RebalanceHistoryPaginationResponse(pub ListItemsPaginationResponse<RebalanceRecord>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct GetSharePriceHistoryResult(pub Result<SharePriceHistoryPaginationResponse, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct SharePriceHistoryPaginationResponse(pub ListItemsPaginationResponse<SharePricePoint>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct GetUserTransactionsResult(pub Result<UserTransactionsPaginationResponse, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct UserTransactionsPaginationResponse(pub ListItemsPaginationResponse<UserTransaction>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct GetUserPositionsResult(pub Result<UserPositionsPaginationResponse, ResponseError>);
+
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct UserPositionsPaginationResponse(pub ListItemsPaginationResponse<UserPositionResponse>);
+
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub enum SortOrder {
     Asc,
@@ -101,6 +375,16 @@ pub struct ListItemsPaginationRequest {
     pub search: Option<String>,
 }
 
+/// Transactions of a user ledger to list, a filter left empty matches every transaction.
+#[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
+pub struct UserTransactionFilters {
+    pub kind: Option<UserTransactionKind>,
+    pub strategy_id: Option<StrategyId>,
+    pub from_timestamp: Option<u64>,
+    pub to_timestamp: Option<u64>,
+    pub pagination: ListItemsPaginationRequest,
+}
+
 #[derive(CandidType, Deserialize, Serialize, Clone, Debug)]
 pub struct ListItemsPaginationResponse<T> {
     pub items: Vec<T>,
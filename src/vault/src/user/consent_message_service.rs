@@ -0,0 +1,699 @@
+use candid::{Nat, Principal, decode_args};
+
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::icrc1::transfer::TransferArg;
+use icrc_ledger_types::icrc2::approve::ApproveArgs;
+use icrc_ledger_types::icrc2::transfer_from::TransferFromArgs;
+use icrc_ledger_types::icrc21::errors::{ErrorInfo, Icrc21Error};
+use icrc_ledger_types::icrc21::requests::{ConsentMessageMetadata, ConsentMessageRequest, DisplayMessageType};
+use icrc_ledger_types::icrc21::responses::{ConsentInfo, ConsentMessage, FieldsDisplay, Value};
+
+use swap::swap_service;
+use types::CanisterId;
+use types::context::position_subaccount;
+use types::strategies::{Pool, StrategyId};
+use utils::util::{current_timestamp_secs, nat_to_u64};
+
+use crate::repository::strategies_repo;
+use crate::repository::pending_withdrawals_repo::PendingWithdrawalStatus;
+use crate::service;
+use crate::strategies::share_token_service;
+use crate::strategies::unwind_service;
+use crate::types::types::{ListItemsPaginationRequest, StrategyDepositArgs, StrategyWithdrawArgs};
+use crate::user::pending_withdrawals_service;
+use crate::user::zap_service;
+use crate::utils::service_resolver::get_service_resolver;
+
+/// Languages the consent messages are translated to, the first one is the fallback.
+const SUPPORTED_LANGUAGES: [Language; 2] = [Language::En, Language::Es];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Language {
+    En,
+    Es,
+}
+
+impl Language {
+    /// Matches the primary subtag of a BCP 47 language tag, e.g. "es" for "es-AR".
+    fn from_tag(tag: &str) -> Self {
+        let primary_subtag = tag.split(['-', '_']).next().unwrap_or_default().to_lowercase();
+
+        SUPPORTED_LANGUAGES.iter().copied()
+            .find(|language| language.tag() == primary_subtag)
+            .unwrap_or(SUPPORTED_LANGUAGES[0])
+    }
+
+    fn tag(&self) -> &'static str {
+        match self {
+            Language::En => "en",
+            Language::Es => "es",
+        }
+    }
+
+    fn labels(&self) -> &'static Labels {
+        match self {
+            Language::En => &EN_LABELS,
+            Language::Es => &ES_LABELS,
+        }
+    }
+}
+
+struct Labels {
+    deposit_intent: &'static str,
+    withdraw_intent: &'static str,
+    claim_intent: &'static str,
+    redeem_intent: &'static str,
+    preview_deposit_intent: &'static str,
+    preview_withdraw_intent: &'static str,
+    user_strategies_intent: &'static str,
+    event_records_intent: &'static str,
+    transfer_shares_intent: &'static str,
+    approve_shares_intent: &'static str,
+    transfer_shares_from_intent: &'static str,
+    register_share_account_intent: &'static str,
+    strategy: &'static str,
+    pool: &'static str,
+    amount: &'static str,
+    percentage: &'static str,
+    output_token: &'static str,
+    min_amount_out: &'static str,
+    expected_shares: &'static str,
+    expected_amount: &'static str,
+    fees: &'static str,
+    price_impact: &'static str,
+    quoted_at: &'static str,
+    user: &'static str,
+    from: &'static str,
+    to: &'static str,
+    spender: &'static str,
+    expires_at: &'static str,
+    position: &'static str,
+    share_account: &'static str,
+    page: &'static str,
+    search: &'static str,
+    no_pending_withdrawals: &'static str,
+}
+
+const EN_LABELS: Labels = Labels {
+    deposit_intent: "Deposit into strategy",
+    withdraw_intent: "Withdraw from strategy",
+    claim_intent: "Claim pending withdrawals",
+    redeem_intent: "Redeem shares of unwound strategy",
+    preview_deposit_intent: "Preview deposit",
+    preview_withdraw_intent: "Preview withdrawal",
+    user_strategies_intent: "View user positions",
+    event_records_intent: "View event records",
+    transfer_shares_intent: "Transfer strategy shares",
+    approve_shares_intent: "Approve spending of strategy shares",
+    transfer_shares_from_intent: "Transfer approved strategy shares",
+    register_share_account_intent: "Register share account",
+    strategy: "Strategy",
+    pool: "Pool",
+    amount: "Amount",
+    percentage: "Percentage",
+    output_token: "Output token",
+    min_amount_out: "Minimum amount out",
+    expected_shares: "Expected shares",
+    expected_amount: "Expected amount",
+    fees: "Fees",
+    price_impact: "Price impact",
+    quoted_at: "Quoted at",
+    user: "User",
+    from: "From",
+    to: "To",
+    spender: "Spender",
+    expires_at: "Expires at",
+    position: "Position",
+    share_account: "Share account",
+    page: "Page",
+    search: "Search",
+    no_pending_withdrawals: "No pending withdrawals",
+};
+
+const ES_LABELS: Labels = Labels {
+    deposit_intent: "Depositar en la estrategia",
+    withdraw_intent: "Retirar de la estrategia",
+    claim_intent: "Reclamar retiros pendientes",
+    redeem_intent: "Canjear participaciones de la estrategia liquidada",
+    preview_deposit_intent: "Simular depósito",
+    preview_withdraw_intent: "Simular retiro",
+    user_strategies_intent: "Ver posiciones del usuario",
+    event_records_intent: "Ver registros de eventos",
+    transfer_shares_intent: "Transferir participaciones de la estrategia",
+    approve_shares_intent: "Aprobar el gasto de participaciones de la estrategia",
+    transfer_shares_from_intent: "Transferir participaciones aprobadas de la estrategia",
+    register_share_account_intent: "Registrar cuenta de participaciones",
+    strategy: "Estrategia",
+    pool: "Pool",
+    amount: "Cantidad",
+    percentage: "Porcentaje",
+    output_token: "Token de salida",
+    min_amount_out: "Cantidad mínima recibida",
+    expected_shares: "Participaciones estimadas",
+    expected_amount: "Cantidad estimada",
+    fees: "Comisiones",
+    price_impact: "Impacto en el precio",
+    quoted_at: "Cotizado el",
+    user: "Usuario",
+    from: "Desde",
+    to: "Hacia",
+    spender: "Autorizado",
+    expires_at: "Vence el",
+    position: "Posición",
+    share_account: "Cuenta de participaciones",
+    page: "Página",
+    search: "Búsqueda",
+    no_pending_withdrawals: "No hay retiros pendientes",
+};
+
+#[derive(Clone, Debug)]
+struct TokenInfo {
+    symbol: String,
+    decimals: u8,
+}
+
+/// Consent message fields, rendered as fields or as generic display text.
+struct ConsentMessageBuilder {
+    intent: String,
+    fields: Vec<(String, Value)>,
+}
+
+impl ConsentMessageBuilder {
+    fn new(intent: &str) -> Self {
+        Self { intent: intent.to_string(), fields: Vec::new() }
+    }
+
+    fn text(&mut self, name: &str, content: String) {
+        self.fields.push((name.to_string(), Value::Text { content }));
+    }
+
+    /// Amounts above `u64` can't be a `TokenAmount`, they are shown as formatted text.
+    fn token_amount(&mut self, name: &str, amount: &Nat, token: &TokenInfo) {
+        let value = if *amount <= Nat::from(u64::MAX) {
+            Value::TokenAmount {
+                decimals: token.decimals,
+                amount: nat_to_u64(amount),
+                symbol: token.symbol.clone(),
+            }
+        } else {
+            Value::Text { content: format!("{} {}", format_token_amount(amount, token.decimals), token.symbol) }
+        };
+
+        self.fields.push((name.to_string(), value));
+    }
+
+    fn timestamp(&mut self, name: &str, timestamp_secs: u64) {
+        self.fields.push((name.to_string(), Value::TimestampSeconds { amount: timestamp_secs }));
+    }
+
+    fn build(self, device_spec: Option<&DisplayMessageType>, utc_offset_minutes: Option<i16>) -> ConsentMessage {
+        if device_spec == Some(&DisplayMessageType::FieldsDisplay) {
+            return ConsentMessage::FieldsDisplayMessage(FieldsDisplay {
+                intent: self.intent,
+                fields: self.fields,
+            });
+        }
+
+        let mut message = format!("# {}", self.intent);
+
+        for (name, value) in self.fields {
+            let content = match value {
+                Value::TokenAmount { decimals, amount, symbol } => {
+                    format!("{} {symbol}", format_token_amount(&Nat::from(amount), decimals))
+                }
+                Value::TimestampSeconds { amount } => format_timestamp(amount, utc_offset_minutes.unwrap_or(0)),
+                Value::DurationSeconds { amount } => format!("{amount}s"),
+                Value::Text { content } => content,
+            };
+
+            message.push_str(&format!("\n\n**{name}:** {content}"));
+        }
+
+        ConsentMessage::GenericDisplayMessage(message)
+    }
+}
+
+/// Builds the consent message of a user-facing update method.
+///
+/// Ledgers are shown with their symbol and decimals, and `deposit` and `withdraw`
+/// include their preview when it is available.
+pub async fn build_consent_message(
+    user: Principal,
+    request: ConsentMessageRequest,
+) -> Result<ConsentInfo, Icrc21Error> {
+    let preferences = request.user_preferences;
+    let language = Language::from_tag(&preferences.metadata.language);
+    let labels = language.labels();
+
+    let builder = match request.method.as_str() {
+        "deposit" => {
+            let (args,): (StrategyDepositArgs,) = decode_args(&request.arg)
+                .map_err(|error| decode_error("deposit", error))?;
+
+            deposit_message(labels, user, args).await
+        }
+        "withdraw" => {
+            let (args,): (StrategyWithdrawArgs,) = decode_args(&request.arg)
+                .map_err(|error| decode_error("withdraw", error))?;
+
+            withdraw_message(labels, user, args).await
+        }
+        "claim_pending_withdrawals" => claim_message(labels, user).await,
+        "redeem_unwound_shares" => {
+            let (strategy_id, subaccount): (StrategyId, Option<Subaccount>) = decode_args(&request.arg)
+                .map_err(|error| decode_error("redeem_unwound_shares", error))?;
+
+            let mut builder = ConsentMessageBuilder::new(labels.redeem_intent);
+            builder.text(labels.strategy, get_strategy_name(strategy_id));
+            if let Some(redemption) = unwind_service::preview_redemption(Account { owner: user, subaccount }, strategy_id) {
+                builder.text(labels.expected_shares, redemption.shares.to_string());
+                builder.token_amount(labels.expected_amount, &redemption.amount, &get_token_info(redemption.token).await);
+            }
+            builder
+        }
+        "preview_deposit" => {
+            let (strategy_id, ledger, amount): (StrategyId, CanisterId, Nat) = decode_args(&request.arg)
+                .map_err(|error| decode_error("preview_deposit", error))?;
+
+            let mut builder = ConsentMessageBuilder::new(labels.preview_deposit_intent);
+            add_strategy_fields(&mut builder, labels, strategy_id).await;
+            builder.token_amount(labels.amount, &amount, &get_token_info(ledger).await);
+            builder
+        }
+        "preview_withdraw" => {
+            let (strategy_id, percentage, _subaccount): (StrategyId, Nat, Option<Subaccount>) = decode_args(&request.arg)
+                .map_err(|error| decode_error("preview_withdraw", error))?;
+
+            let mut builder = ConsentMessageBuilder::new(labels.preview_withdraw_intent);
+            add_strategy_fields(&mut builder, labels, strategy_id).await;
+            builder.text(labels.percentage, format!("{percentage}%"));
+            builder
+        }
+        "user_strategies" => {
+            let (user,): (Principal,) = decode_args(&request.arg)
+                .map_err(|error| decode_error("user_strategies", error))?;
+
+            let mut builder = ConsentMessageBuilder::new(labels.user_strategies_intent);
+            builder.text(labels.user, user.to_text());
+            builder
+        }
+        "icrc1_transfer" => {
+            let (args,): (TransferArg,) = decode_args(&request.arg)
+                .map_err(|error| decode_error("icrc1_transfer", error))?;
+
+            let from = Account { owner: user, subaccount: args.from_subaccount };
+
+            let mut builder = ConsentMessageBuilder::new(labels.transfer_shares_intent);
+            add_shares_fields(&mut builder, labels, &from, &args.amount).await;
+            builder.text(labels.from, from.to_string());
+            builder.text(labels.to, args.to.to_string());
+            builder
+        }
+        "icrc2_approve" => {
+            let (args,): (ApproveArgs,) = decode_args(&request.arg)
+                .map_err(|error| decode_error("icrc2_approve", error))?;
+
+            let from = Account { owner: user, subaccount: args.from_subaccount };
+
+            let mut builder = ConsentMessageBuilder::new(labels.approve_shares_intent);
+            add_shares_fields(&mut builder, labels, &from, &args.amount).await;
+            builder.text(labels.from, from.to_string());
+            builder.text(labels.spender, args.spender.to_string());
+            if let Some(expires_at) = args.expires_at {
+                builder.timestamp(labels.expires_at, expires_at / 1_000_000_000);
+            }
+            builder
+        }
+        "icrc2_transfer_from" => {
+            let (args,): (TransferFromArgs,) = decode_args(&request.arg)
+                .map_err(|error| decode_error("icrc2_transfer_from", error))?;
+
+            let spender = Account { owner: user, subaccount: args.spender_subaccount };
+
+            let mut builder = ConsentMessageBuilder::new(labels.transfer_shares_from_intent);
+            add_shares_fields(&mut builder, labels, &args.from, &args.amount).await;
+            builder.text(labels.from, args.from.to_string());
+            builder.text(labels.to, args.to.to_string());
+            builder.text(labels.spender, spender.to_string());
+            builder
+        }
+        "register_share_account" => {
+            let (strategy_id, subaccount): (StrategyId, Option<Subaccount>) = decode_args(&request.arg)
+                .map_err(|error| decode_error("register_share_account", error))?;
+
+            let position = Account { owner: user, subaccount: position_subaccount(subaccount) };
+
+            let mut builder = ConsentMessageBuilder::new(labels.register_share_account_intent);
+            builder.text(labels.strategy, get_strategy_name(strategy_id));
+            builder.text(labels.position, position.to_string());
+            if let Some(share_account) = share_token_service::get_share_account(strategy_id, position) {
+                builder.text(labels.share_account, share_account.to_string());
+            }
+            builder
+        }
+        "get_event_records" => {
+            let (pagination,): (ListItemsPaginationRequest,) = decode_args(&request.arg)
+                .map_err(|error| decode_error("get_event_records", error))?;
+
+            let mut builder = ConsentMessageBuilder::new(labels.event_records_intent);
+            builder.text(labels.page, format!("{} ({})", pagination.page, pagination.page_size));
+            if let Some(search) = pagination.search {
+                builder.text(labels.search, search);
+            }
+            builder
+        }
+        unknown => {
+            return Err(Icrc21Error::UnsupportedCanisterCall(ErrorInfo {
+                description: format!("Unsupported method for consent message: {unknown}"),
+            }));
+        }
+    };
+
+    Ok(ConsentInfo {
+        consent_message: builder.build(
+            preferences.device_spec.as_ref(),
+            preferences.metadata.utc_offset_minutes,
+        ),
+        metadata: ConsentMessageMetadata {
+            language: language.tag().to_string(),
+            utc_offset_minutes: preferences.metadata.utc_offset_minutes,
+        },
+    })
+}
+
+async fn deposit_message(labels: &Labels, user: Principal, args: StrategyDepositArgs) -> ConsentMessageBuilder {
+    let mut builder = ConsentMessageBuilder::new(labels.deposit_intent);
+    add_strategy_fields(&mut builder, labels, args.strategy_id).await;
+    builder.token_amount(labels.amount, &args.amount, &get_token_info(args.ledger).await);
+    builder.text(labels.from, Account { owner: user, subaccount: args.from_subaccount }.to_string());
+
+    let base_token = strategies_repo::get_strategy_by_id(args.strategy_id)
+        .map(|strategy| strategy.get_base_token());
+
+    if let (Some(min_amount_out), Some(base_token)) = (args.min_amount_out, base_token) {
+        builder.token_amount(labels.min_amount_out, &min_amount_out, &get_token_info(base_token).await);
+    }
+
+    if let (Ok(preview), Some(base_token)) = (
+        service::preview_deposit(args.strategy_id, args.ledger, args.amount).await,
+        base_token,
+    ) {
+        builder.text(labels.expected_shares, preview.shares.to_string());
+        builder.token_amount(labels.fees, &preview.fees, &get_token_info(base_token).await);
+        builder.text(labels.price_impact, format_bps(preview.price_impact_bps));
+        builder.timestamp(labels.quoted_at, current_timestamp_secs());
+    }
+
+    builder
+}
+
+async fn withdraw_message(labels: &Labels, user: Principal, args: StrategyWithdrawArgs) -> ConsentMessageBuilder {
+    let position = Account { owner: user, subaccount: args.subaccount };
+
+    let mut builder = ConsentMessageBuilder::new(labels.withdraw_intent);
+    add_strategy_fields(&mut builder, labels, args.strategy_id).await;
+    builder.text(labels.percentage, format!("{}%", args.percentage));
+    builder.text(labels.from, position.to_string());
+    builder.text(labels.to, args.to.unwrap_or(position).to_string());
+
+    let output_token_info = match args.output_token {
+        Some(output_token) => Some(get_token_info(output_token).await),
+        None => None,
+    };
+
+    if let Some(output_token_info) = output_token_info.as_ref() {
+        builder.text(labels.output_token, output_token_info.symbol.clone());
+
+        if let Some(min_amount_out) = args.min_amount_out.as_ref() {
+            builder.token_amount(labels.min_amount_out, min_amount_out, output_token_info);
+        }
+    }
+
+    let base_token = strategies_repo::get_strategy_by_id(args.strategy_id)
+        .map(|strategy| strategy.get_base_token());
+
+    if let (Ok(preview), Some(base_token)) = (
+        service::preview_withdraw(position, args.strategy_id, args.percentage).await,
+        base_token,
+    ) {
+        let base_token_info = get_token_info(base_token).await;
+
+        // The withdrawn base token amount is swapped to the output token
+        let output_amount = match (args.output_token, output_token_info) {
+            (Some(output_token), Some(output_token_info)) => quote_output_amount(base_token, output_token, &preview.amount).await
+                .map(|amount| (amount, output_token_info)),
+            _ => None,
+        };
+
+        match output_amount {
+            Some((amount, output_token_info)) => builder.token_amount(labels.expected_amount, &amount, &output_token_info),
+            None => builder.token_amount(labels.expected_amount, &preview.amount, &base_token_info),
+        }
+        builder.token_amount(labels.fees, &preview.fees, &base_token_info);
+        builder.text(labels.price_impact, format_bps(preview.price_impact_bps));
+        builder.timestamp(labels.quoted_at, current_timestamp_secs());
+    }
+
+    builder
+}
+
+/// Output token amount of a withdrawal, quoted like its zap after the swap fees.
+async fn quote_output_amount(base_token: CanisterId, output_token: CanisterId, amount: &Nat) -> Option<Nat> {
+    let service_resolver = get_service_resolver();
+
+    let base_token_fee = service_resolver.icrc_ledger_client()
+        .icrc1_fee(base_token)
+        .await
+        .ok()?;
+    let amount_in = zap_service::calculate_zap_amount_in(amount, &base_token_fee)?;
+
+    swap_service::quote_swap_icrc2_optimal(
+        service_resolver.provider_impls(),
+        service_resolver.icrc_ledger_client(),
+        base_token,
+        output_token,
+        amount_in,
+    ).await
+        .ok()
+        .map(|quote| Nat::from(quote.amount_out))
+}
+
+async fn claim_message(labels: &Labels, user: Principal) -> ConsentMessageBuilder {
+    let mut builder = ConsentMessageBuilder::new(labels.claim_intent);
+
+    let claimable_withdrawals: Vec<_> = pending_withdrawals_service::get_user_pending_withdrawals(user)
+        .into_iter()
+        .filter(|withdrawal| withdrawal.status == PendingWithdrawalStatus::Claimable)
+        .collect();
+
+    if claimable_withdrawals.is_empty() {
+        builder.text(labels.amount, labels.no_pending_withdrawals.to_string());
+    }
+
+    for withdrawal in claimable_withdrawals {
+        let strategy_name = get_strategy_name(withdrawal.strategy_id);
+        let amount = withdrawal.amount.unwrap_or_default();
+
+        builder.token_amount(&strategy_name, &amount, &get_token_info(withdrawal.token).await);
+    }
+
+    builder
+}
+
+async fn add_strategy_fields(builder: &mut ConsentMessageBuilder, labels: &Labels, strategy_id: StrategyId) {
+    builder.text(labels.strategy, get_strategy_name(strategy_id));
+
+    let current_pool = strategies_repo::get_strategy_by_id(strategy_id)
+        .and_then(|strategy| strategy.get_current_pool());
+
+    if let Some(pool) = current_pool {
+        builder.text(labels.pool, format_pool(&pool).await);
+    }
+}
+
+/// Strategy of the share account and the amount of shares, with the decimals of the base token.
+async fn add_shares_fields(builder: &mut ConsentMessageBuilder, labels: &Labels, share_account: &Account, amount: &Nat) {
+    match share_token_service::get_subaccount_strategy_id(share_account.subaccount.as_ref()) {
+        Some(strategy_id) => {
+            builder.text(labels.strategy, get_strategy_name(strategy_id));
+            builder.token_amount(labels.amount, amount, &get_share_token_info(strategy_id).await);
+        }
+        None => builder.text(labels.amount, amount.to_string()),
+    }
+}
+
+/// Symbol of the shares of the strategy with the decimals of its base token.
+async fn get_share_token_info(strategy_id: StrategyId) -> TokenInfo {
+    let decimals = match strategies_repo::get_strategy_by_id(strategy_id) {
+        Some(strategy) => get_token_info(strategy.get_base_token()).await.decimals,
+        None => 0,
+    };
+
+    TokenInfo {
+        symbol: share_token_service::strategy_symbol(strategy_id),
+        decimals,
+    }
+}
+
+fn get_strategy_name(strategy_id: StrategyId) -> String {
+    strategies_repo::get_strategy_by_id(strategy_id)
+        .map(|strategy| format!("{} (#{strategy_id})", strategy.get_name()))
+        .unwrap_or_else(|| format!("#{strategy_id}"))
+}
+
+async fn format_pool(pool: &Pool) -> String {
+    let token0 = get_token_info(pool.token0).await;
+    let token1 = get_token_info(pool.token1).await;
+
+    format!("{}/{} ({})", token0.symbol, token1.symbol, pool.provider)
+}
+
+/// Symbol and decimals of the ledger, the principal and raw amounts are shown if they can't be fetched.
+async fn get_token_info(ledger: CanisterId) -> TokenInfo {
+    let icrc_ledger_client = get_service_resolver().icrc_ledger_client();
+
+    match (
+        icrc_ledger_client.icrc1_symbol(ledger).await,
+        icrc_ledger_client.icrc1_decimals(ledger).await,
+    ) {
+        (Ok(symbol), Ok(decimals)) => TokenInfo { symbol, decimals },
+        _ => TokenInfo { symbol: ledger.to_text(), decimals: 0 },
+    }
+}
+
+fn decode_error(method: &str, error: candid::Error) -> Icrc21Error {
+    Icrc21Error::ConsentMessageUnavailable(ErrorInfo {
+        description: format!("Can't decode {method} args: {error}"),
+    })
+}
+
+/// Formats a token amount with its decimals, without trailing zeros, e.g. "1.5" for 150000000 with 8 decimals.
+fn format_token_amount(amount: &Nat, decimals: u8) -> String {
+    let digits = amount.0.to_string();
+    let decimals = decimals as usize;
+
+    if decimals == 0 {
+        return digits;
+    }
+
+    let padded = format!("{digits:0>width$}", width = decimals + 1);
+    let (whole, fraction) = padded.split_at(padded.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{whole}.{fraction}")
+    }
+}
+
+fn format_bps(bps: u64) -> String {
+    format!("{}.{:02}%", bps / 100, bps % 100)
+}
+
+/// Formats a timestamp as "YYYY-MM-DD HH:MM UTC±HH:MM" in the offset of the user.
+fn format_timestamp(timestamp_secs: u64, utc_offset_minutes: i16) -> String {
+    let local_secs = timestamp_secs as i64 + utc_offset_minutes as i64 * 60;
+    let days = local_secs.div_euclid(86_400);
+    let secs_of_day = local_secs.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    let offset_sign = if utc_offset_minutes < 0 { '-' } else { '+' };
+    let offset = utc_offset_minutes.unsigned_abs();
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC{offset_sign}{:02}:{:02}",
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        offset / 60,
+        offset % 60,
+    )
+}
+
+/// Converts days since 1970-01-01 to a (year, month, day) date of the proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod format_token_amount {
+        use super::*;
+
+        #[test]
+        fn places_the_decimal_point() {
+            assert_eq!(format_token_amount(&Nat::from(150_000_000u64), 8), "1.5");
+            assert_eq!(format_token_amount(&Nat::from(123_456_789u64), 8), "1.23456789");
+        }
+
+        #[test]
+        fn pads_amounts_below_one() {
+            assert_eq!(format_token_amount(&Nat::from(1_000u64), 6), "0.001");
+        }
+
+        #[test]
+        fn drops_the_fraction_of_whole_amounts() {
+            assert_eq!(format_token_amount(&Nat::from(200_000_000u64), 8), "2");
+            assert_eq!(format_token_amount(&Nat::from(0u64), 8), "0");
+        }
+
+        #[test]
+        fn keeps_raw_amount_without_decimals() {
+            assert_eq!(format_token_amount(&Nat::from(42u64), 0), "42");
+        }
+    }
+
+    mod format_timestamp {
+        use super::*;
+
+        #[test]
+        fn formats_in_utc() {
+            assert_eq!(format_timestamp(0, 0), "1970-01-01 00:00 UTC+00:00");
+            assert_eq!(format_timestamp(1_709_210_096, 0), "2024-02-29 12:34 UTC+00:00");
+        }
+
+        #[test]
+        fn applies_the_utc_offset() {
+            assert_eq!(format_timestamp(1_709_210_096, 120), "2024-02-29 14:34 UTC+02:00");
+            assert_eq!(format_timestamp(1_709_210_096, -810), "2024-02-28 23:04 UTC-13:30");
+        }
+    }
+
+    mod language_from_tag {
+        use super::*;
+
+        #[test]
+        fn matches_the_primary_subtag() {
+            assert_eq!(Language::from_tag("es-AR"), Language::Es);
+            assert_eq!(Language::from_tag("EN"), Language::En);
+        }
+
+        #[test]
+        fn falls_back_to_english() {
+            assert_eq!(Language::from_tag("fr"), Language::En);
+            assert_eq!(Language::from_tag(""), Language::En);
+        }
+    }
+
+    mod format_bps {
+        use super::*;
+
+        #[test]
+        fn formats_as_percentage() {
+            assert_eq!(format_bps(15), "0.15%");
+            assert_eq!(format_bps(1_250), "12.50%");
+        }
+    }
+}
@@ -1 +1,5 @@
 pub mod user_service;
+pub mod pending_withdrawals_service;
+pub mod zap_service;
+pub mod consent_message_service;
+pub mod user_positions_service;
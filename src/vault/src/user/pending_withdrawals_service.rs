@@ -0,0 +1,658 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::time::Duration;
+use candid::{Nat, Principal};
+use ic_cdk_timers::TimerId;
+use icrc_ledger_types::icrc1::account::Account;
+use icrc_ledger_types::icrc1::transfer::Memo;
+
+use types::CanisterId;
+use types::context::Context;
+use utils::token_transfer::icrc1_transfer_to_user;
+use utils::util::current_timestamp_secs;
+use errors::internal_error::error::{InternalError, InternalErrorKind};
+use errors::internal_error::error_codes::module::areas::{
+    canisters as canister_area,
+    canisters::domains::vault as vault_domain,
+    canisters::domains::vault::components as vault_domain_components,
+};
+
+use crate::event_records::event_record::Event;
+use crate::event_records::event_record_service;
+use crate::repository::runtime_config_repo;
+use crate::strategies::strategy_lock_service;
+use crate::user::user_service;
+use crate::utils::service_resolver::get_service_resolver;
+use crate::repository::pending_withdrawals_repo::{
+    self,
+    PendingWithdrawal,
+    PendingWithdrawalKind,
+    PendingWithdrawalStatus,
+};
+
+// Module code: "03-01-01"
+errors::define_error_code_builder_fn!(
+    build_error_code,
+    canister_area::AREA_CODE,     // Area code: "03"
+    vault_domain::DOMAIN_CODE,    // Domain code: "01"
+    vault_domain_components::CORE // Component code: "01"
+);
+
+// The ledger deduplicates transfers created in the last 24 hours,
+// one hour of it is kept as margin for the clock drift and the call latency
+const TRANSFER_DEDUPLICATION_WINDOW_NANOS: u64 = 23 * 3600 * 1_000_000_000;
+// A started withdrawal is in progress while its strategy is locked,
+// it is left started past this delay only if its call trapped
+const STALE_STARTED_WITHDRAWAL_SECS: u64 = 3600;
+
+thread_local! {
+    static PENDING_WITHDRAWALS_RETRY_TIMER_ID: RefCell<Option<TimerId>> = RefCell::new(None);
+    static TRANSFERS_IN_FLIGHT: RefCell<HashSet<u64>> = RefCell::new(HashSet::new());
+}
+
+/// Holds the transfer of a withdrawal in flight until it is dropped.
+///
+/// The guard is also dropped when the call traps, so the withdrawal can be sent again by the retry.
+struct TransferGuard {
+    id: u64,
+}
+
+impl Drop for TransferGuard {
+    fn drop(&mut self) {
+        TRANSFERS_IN_FLIGHT.with(|transfers| transfers.borrow_mut().remove(&self.id));
+    }
+}
+
+fn start_transfer(id: u64) -> Option<TransferGuard> {
+    TRANSFERS_IN_FLIGHT.with(|transfers| transfers.borrow_mut().insert(id))
+        .then_some(TransferGuard { id })
+}
+
+fn set_timer_interval(
+    interval: Duration,
+    func: impl FnMut() + 'static,
+) -> TimerId {
+    ic_cdk_timers::set_timer_interval(interval, func)
+}
+
+pub fn start_pending_withdrawals_retry_timer(interval: u64) {
+    let timer_id = set_timer_interval(Duration::from_secs(interval), || {
+        ic_cdk::spawn(async {
+            reconcile_started_withdrawals();
+            retry_transferring_withdrawals().await;
+            retry_claimable_withdrawals().await;
+        });
+    });
+
+    PENDING_WITHDRAWALS_RETRY_TIMER_ID.with(|cell| {
+        cell.replace(Some(timer_id));
+    });
+}
+
+pub fn stop_pending_withdrawals_retry_timer() {
+    PENDING_WITHDRAWALS_RETRY_TIMER_ID.with(|timer_id| {
+        if let Some(timer_id) = timer_id.borrow_mut().take() {
+            ic_cdk_timers::clear_timer(timer_id);
+        }
+    });
+}
+
+/// Persists a withdrawal of the context user and strategy before any external call is made for it.
+pub fn create_pending_withdrawal(
+    kind: PendingWithdrawalKind,
+    status: PendingWithdrawalStatus,
+    context: &Context,
+    destination: Account,
+    token: CanisterId,
+    shares: Nat,
+    amount: Option<Nat>,
+) -> PendingWithdrawal {
+    let now = current_timestamp_secs();
+
+    let withdrawal = PendingWithdrawal {
+        id: pending_withdrawals_repo::next_id(),
+        kind,
+        status,
+        user: context.user.unwrap(),
+        strategy_id: context.strategy_id.unwrap(),
+        token,
+        shares,
+        amount,
+        attempts: 0,
+        last_error: None,
+        created_at: now,
+        updated_at: now,
+        correlation_id: Some(context.correlation_id.clone()),
+        destination: Some(destination),
+        transfer_created_at: None,
+        transfer_memo: None,
+    };
+
+    pending_withdrawals_repo::save_pending_withdrawal(withdrawal.clone());
+
+    withdrawal
+}
+
+/// Refunds the accepted tokens held by the vault to the account they were deposited from.
+pub async fn refund_deposit(context: &Context, ledger: CanisterId, amount: Nat) {
+    let refund = create_pending_withdrawal(
+        PendingWithdrawalKind::DepositRefund,
+        PendingWithdrawalStatus::Claimable,
+        context,
+        context.account().unwrap(),
+        ledger,
+        Nat::from(0u64),
+        Some(amount),
+    );
+
+    // Refund stays claimable if the transfer fails
+    let _ = transfer_pending_withdrawal(refund.id).await;
+}
+
+/// Refunds the part of a deposit the vault can verify it still holds after a failed operation.
+///
+/// The tokens that left the vault balance during the operation may be held by the DEX,
+/// so they are not paid from the vault balance but recorded as unconfirmed for a manual review.
+pub async fn refund_verified_deposit(
+    context: &Context,
+    ledger: CanisterId,
+    held_amount: Nat,
+    refund_amount: Nat,
+    balance_before: Option<Nat>,
+) {
+    let balance_after = get_vault_balance(ledger).await;
+    let verified_amount = calculate_verified_amount(
+        &held_amount,
+        &refund_amount,
+        balance_before.as_ref(),
+        balance_after.as_ref(),
+    );
+
+    if verified_amount < refund_amount {
+        create_pending_withdrawal(
+            PendingWithdrawalKind::DepositRefund,
+            PendingWithdrawalStatus::Unconfirmed,
+            context,
+            context.account().unwrap(),
+            ledger,
+            Nat::from(0u64),
+            Some(refund_amount - verified_amount.clone()),
+        );
+    }
+
+    if verified_amount > Nat::from(0u64) {
+        refund_deposit(context, ledger, verified_amount).await;
+    }
+}
+
+/// Balance of the vault in the token, none if the ledger can't be queried.
+pub async fn get_vault_balance(ledger: CanisterId) -> Option<Nat> {
+    get_service_resolver()
+        .icrc_ledger_client()
+        .icrc1_balance_of(ledger, Account::from(ic_cdk::id()))
+        .await
+        .ok()
+}
+
+/// Returns the part of the refund amount still held by the vault after a failed operation.
+///
+/// The vault held `held_amount` of the deposit when `balance_before` was read, the tokens spent
+/// since then are taken from it. Nothing is verified without both balances.
+///
+/// Other strategies may move the same token during the operation, a decrease of the balance
+/// is then attributed to the deposit and left to the manual review.
+fn calculate_verified_amount(
+    held_amount: &Nat,
+    refund_amount: &Nat,
+    balance_before: Option<&Nat>,
+    balance_after: Option<&Nat>,
+) -> Nat {
+    let (balance_before, balance_after) = match (balance_before, balance_after) {
+        (Some(before), Some(after)) => (before, after),
+        _ => return Nat::from(0u64),
+    };
+
+    let spent_amount = if balance_before > balance_after {
+        balance_before.clone() - balance_after.clone()
+    } else {
+        Nat::from(0u64)
+    };
+
+    let remaining_amount = if *held_amount > spent_amount {
+        held_amount.clone() - spent_amount
+    } else {
+        Nat::from(0u64)
+    };
+
+    remaining_amount.min(refund_amount.clone())
+}
+
+/// Marks a started withdrawal as failed when no tokens were withdrawn from the pool.
+pub fn fail_pending_withdrawal(id: u64, error: &InternalError) {
+    if let Some(mut withdrawal) = pending_withdrawals_repo::get_pending_withdrawal(id) {
+        withdrawal.status = PendingWithdrawalStatus::Failed;
+        withdrawal.last_error = Some(error.message.clone());
+        withdrawal.updated_at = current_timestamp_secs();
+
+        pending_withdrawals_repo::save_pending_withdrawal(withdrawal);
+    }
+}
+
+/// Makes the withdrawn amount claimable, so it stays owned by the user until it is transferred.
+///
+/// The token can differ from the one the withdrawal was created with, if the withdrawn amount was swapped.
+pub fn set_pending_withdrawal_amount(id: u64, token: CanisterId, amount: Nat) {
+    if let Some(mut withdrawal) = pending_withdrawals_repo::get_pending_withdrawal(id) {
+        withdrawal.token = token;
+        withdrawal.amount = Some(amount);
+        withdrawal.status = PendingWithdrawalStatus::Claimable;
+        withdrawal.updated_at = current_timestamp_secs();
+
+        pending_withdrawals_repo::save_pending_withdrawal(withdrawal);
+    }
+}
+
+/// Transfers a claimable withdrawal to its destination account.
+///
+/// The withdrawal is marked as transferring before the call, so a concurrent claim or retry
+/// can't transfer it twice. It becomes claimable again only if the ledger rejected the transfer.
+pub async fn transfer_pending_withdrawal(id: u64) -> Result<PendingWithdrawal, InternalError> {
+    let withdrawal = pending_withdrawals_repo::get_pending_withdrawal(id)
+        .filter(|withdrawal| withdrawal.status == PendingWithdrawalStatus::Claimable)
+        .ok_or_else(|| {
+            InternalError::not_found(
+                build_error_code(InternalErrorKind::NotFound, 9), // Error code: "03-01-01 01 09"
+                "pending_withdrawals_service::transfer_pending_withdrawal".to_string(),
+                "Claimable withdrawal not found".to_string(),
+                errors::error_extra! {
+                    "id" => id,
+                },
+            )
+        })?;
+
+    let guard = start_transfer(id).ok_or_else(|| transfer_in_flight_error(id))?;
+
+    send_transfer(withdrawal, guard).await
+}
+
+/// Sends again the transfers whose outcome is unknown, with their creation time and memo,
+/// so the ledger returns the block of a transfer that was already made.
+///
+/// This covers failed calls and calls that trapped after the withdrawal was marked as transferring.
+/// Transfers that can't be deduplicated by the ledger anymore, or that were sent without
+/// a creation time before it was kept, are left for a manual review.
+pub async fn retry_transferring_withdrawals() {
+    let transferring_ids = pending_withdrawals_repo::get_pending_withdrawals_by_status(
+        PendingWithdrawalStatus::Transferring
+    )
+        .into_iter()
+        .map(|withdrawal| withdrawal.id)
+        .collect::<Vec<_>>();
+
+    for id in transferring_ids {
+        let guard = match start_transfer(id) {
+            Some(guard) => guard,
+            None => continue,
+        };
+
+        // The withdrawal could have been completed by the call that was in flight
+        let mut withdrawal = match pending_withdrawals_repo::get_pending_withdrawal(id)
+            .filter(|withdrawal| withdrawal.status == PendingWithdrawalStatus::Transferring)
+        {
+            Some(withdrawal) => withdrawal,
+            None => continue,
+        };
+
+        if !can_deduplicate(&withdrawal, ic_cdk::api::time()) {
+            withdrawal.status = PendingWithdrawalStatus::Unconfirmed;
+            withdrawal.updated_at = current_timestamp_secs();
+            pending_withdrawals_repo::save_pending_withdrawal(withdrawal);
+            continue;
+        }
+
+        let _ = send_transfer(withdrawal, guard).await;
+    }
+}
+
+async fn send_transfer(
+    mut withdrawal: PendingWithdrawal,
+    _guard: TransferGuard,
+) -> Result<PendingWithdrawal, InternalError> {
+    let previous_status = withdrawal.status;
+    prepare_transfer(&mut withdrawal, ic_cdk::api::time());
+
+    withdrawal.status = PendingWithdrawalStatus::Transferring;
+    withdrawal.attempts += 1;
+    withdrawal.updated_at = current_timestamp_secs();
+    pending_withdrawals_repo::save_pending_withdrawal(withdrawal.clone());
+
+    let environment = runtime_config_repo::get_current_env();
+
+    let result = icrc1_transfer_to_user(
+        &environment,
+        withdrawal.get_destination(),
+        withdrawal.token,
+        withdrawal.amount.clone().unwrap_or(Nat::from(0u64)),
+        withdrawal.transfer_created_at,
+        withdrawal.transfer_memo.clone(),
+    ).await;
+
+    withdrawal.updated_at = current_timestamp_secs();
+    withdrawal.status = get_status_after_transfer(previous_status, &result);
+
+    match result {
+        Ok(block_index) => {
+            withdrawal.last_error = None;
+            pending_withdrawals_repo::save_pending_withdrawal(withdrawal.clone());
+            user_service::record_claim(&withdrawal, block_index);
+
+            Ok(withdrawal)
+        }
+        Err(error) => {
+            withdrawal.last_error = Some(error.message.clone());
+            pending_withdrawals_repo::save_pending_withdrawal(withdrawal);
+
+            Err(error)
+        }
+    }
+}
+
+/// Sets the creation time and memo sent with every attempt of the transfer.
+///
+/// A claimable withdrawal was never possibly transferred, so it gets a new creation time
+/// once the ledger can't deduplicate the previous one anymore.
+fn prepare_transfer(withdrawal: &mut PendingWithdrawal, now: u64) {
+    let is_expired = withdrawal.status == PendingWithdrawalStatus::Claimable
+        && !can_deduplicate(withdrawal, now);
+
+    if withdrawal.transfer_created_at.is_none() || is_expired {
+        withdrawal.transfer_created_at = Some(now);
+    }
+
+    if withdrawal.transfer_memo.is_none() {
+        withdrawal.transfer_memo = Some(Memo::from(withdrawal.id));
+    }
+}
+
+fn can_deduplicate(withdrawal: &PendingWithdrawal, now: u64) -> bool {
+    withdrawal.transfer_created_at
+        .is_some_and(|created_at| now < created_at.saturating_add(TRANSFER_DEDUPLICATION_WINDOW_NANOS))
+}
+
+/// Returns the status of a withdrawal after an attempt of its transfer.
+///
+/// A failed call may have made the transfer, so the withdrawal stays transferring and is sent again.
+/// A transfer rejected by the ledger was not made, but an earlier attempt with an unknown outcome
+/// could have been, so only a withdrawal that was claimable becomes claimable again.
+fn get_status_after_transfer(
+    previous_status: PendingWithdrawalStatus,
+    result: &Result<Nat, InternalError>,
+) -> PendingWithdrawalStatus {
+    match result {
+        Ok(_) => PendingWithdrawalStatus::Completed,
+        Err(error) if matches!(error.kind, InternalErrorKind::BusinessLogic)
+            && previous_status == PendingWithdrawalStatus::Claimable => PendingWithdrawalStatus::Claimable,
+        Err(_) => PendingWithdrawalStatus::Transferring,
+    }
+}
+
+fn transfer_in_flight_error(id: u64) -> InternalError {
+    InternalError::business_logic(
+        build_error_code(InternalErrorKind::BusinessLogic, 20), // Error code: "03-01-01 03 20"
+        "pending_withdrawals_service::transfer_pending_withdrawal".to_string(),
+        "Transfer of the withdrawal is already in flight".to_string(),
+        errors::error_extra! {
+            "id" => id,
+        },
+    )
+}
+
+/// Transfers all claimable withdrawals of the user and returns their updated state.
+pub async fn claim_pending_withdrawals(user: Principal) -> Vec<PendingWithdrawal> {
+    let claimable_ids = pending_withdrawals_repo::get_user_pending_withdrawals(user)
+        .into_iter()
+        .filter(|withdrawal| withdrawal.status == PendingWithdrawalStatus::Claimable)
+        .map(|withdrawal| withdrawal.id)
+        .collect::<Vec<_>>();
+
+    let mut withdrawals = Vec::new();
+
+    for id in claimable_ids {
+        let _ = transfer_pending_withdrawal(id).await;
+
+        if let Some(withdrawal) = pending_withdrawals_repo::get_pending_withdrawal(id) {
+            withdrawals.push(withdrawal);
+        }
+    }
+
+    withdrawals
+}
+
+pub fn get_user_pending_withdrawals(user: Principal) -> Vec<PendingWithdrawal> {
+    pending_withdrawals_repo::get_user_pending_withdrawals(user)
+}
+
+/// Moves the withdrawals left started by a trapped call to unconfirmed, so an admin can settle them.
+///
+/// The liquidity could have been removed and the shares burned before the trap,
+/// so the tokens held for the user are not known.
+pub fn reconcile_started_withdrawals() {
+    let now = current_timestamp_secs();
+
+    for mut withdrawal in pending_withdrawals_repo::get_pending_withdrawals_by_status(PendingWithdrawalStatus::Started) {
+        let is_strategy_locked = strategy_lock_service::get_locked_operation(withdrawal.strategy_id).is_some();
+
+        if !is_stale_started_withdrawal(&withdrawal, is_strategy_locked, now) {
+            continue;
+        }
+
+        let error = InternalError::business_logic(
+            build_error_code(InternalErrorKind::BusinessLogic, 21), // Error code: "03-01-01 03 21"
+            "pending_withdrawals_service::reconcile_started_withdrawals".to_string(),
+            "Withdrawal was interrupted before its amount was known".to_string(),
+            errors::error_extra! {
+                "id" => withdrawal.id,
+                "user" => withdrawal.user,
+                "strategy_id" => withdrawal.strategy_id,
+            },
+        );
+
+        withdrawal.status = PendingWithdrawalStatus::Unconfirmed;
+        withdrawal.last_error = Some(error.message.clone());
+        withdrawal.updated_at = now;
+        pending_withdrawals_repo::save_pending_withdrawal(withdrawal.clone());
+
+        // Event: Strategy withdraw failed
+        event_record_service::create_event_record(
+            Event::strategy_withdraw_failed(
+                withdrawal.strategy_id.to_string(),
+                None,
+                Some(withdrawal.shares),
+                error,
+            ),
+            withdrawal.correlation_id.unwrap_or_else(|| now.to_string()),
+            Some(withdrawal.user),
+            Some(withdrawal.strategy_id),
+        );
+    }
+}
+
+fn is_stale_started_withdrawal(withdrawal: &PendingWithdrawal, is_strategy_locked: bool, now: u64) -> bool {
+    withdrawal.status == PendingWithdrawalStatus::Started
+        && !is_strategy_locked
+        && now.saturating_sub(withdrawal.updated_at) >= STALE_STARTED_WITHDRAWAL_SECS
+}
+
+pub async fn retry_claimable_withdrawals() {
+    let claimable_ids = pending_withdrawals_repo::get_pending_withdrawals_by_status(
+        PendingWithdrawalStatus::Claimable
+    )
+        .into_iter()
+        .map(|withdrawal| withdrawal.id)
+        .collect::<Vec<_>>();
+
+    for id in claimable_ids {
+        let _ = transfer_pending_withdrawal(id).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR_NANOS: u64 = 3600 * 1_000_000_000;
+
+    fn withdrawal(status: PendingWithdrawalStatus, transfer_created_at: Option<u64>) -> PendingWithdrawal {
+        PendingWithdrawal {
+            id: 7,
+            kind: PendingWithdrawalKind::Withdraw,
+            status,
+            user: Principal::from_slice(&[1; 29]),
+            strategy_id: 1,
+            token: Principal::from_slice(&[100; 29]),
+            shares: Nat::from(10u64),
+            amount: Some(Nat::from(1_000u64)),
+            attempts: 0,
+            last_error: None,
+            created_at: 0,
+            updated_at: 0,
+            correlation_id: None,
+            destination: None,
+            transfer_created_at,
+            transfer_memo: None,
+        }
+    }
+
+    fn error(kind: InternalErrorKind) -> InternalError {
+        InternalError::new(
+            1,
+            kind,
+            "test".to_string(),
+            "error".to_string(),
+            None,
+        )
+    }
+
+    mod prepare_transfer {
+        use super::*;
+
+        #[test]
+        fn keeps_creation_time_and_memo_between_attempts() {
+            let mut withdrawal = withdrawal(PendingWithdrawalStatus::Claimable, None);
+
+            prepare_transfer(&mut withdrawal, 10 * HOUR_NANOS);
+            prepare_transfer(&mut withdrawal, 11 * HOUR_NANOS);
+
+            assert_eq!(withdrawal.transfer_created_at, Some(10 * HOUR_NANOS));
+            assert_eq!(withdrawal.transfer_memo, Some(Memo::from(7u64)));
+        }
+
+        #[test]
+        fn renews_expired_creation_time_of_claimable_withdrawal_only() {
+            let mut claimable = withdrawal(PendingWithdrawalStatus::Claimable, Some(HOUR_NANOS));
+            let mut transferring = withdrawal(PendingWithdrawalStatus::Transferring, Some(HOUR_NANOS));
+
+            prepare_transfer(&mut claimable, 30 * HOUR_NANOS);
+            prepare_transfer(&mut transferring, 30 * HOUR_NANOS);
+
+            assert_eq!(claimable.transfer_created_at, Some(30 * HOUR_NANOS));
+            assert_eq!(transferring.transfer_created_at, Some(HOUR_NANOS));
+        }
+    }
+
+    mod can_deduplicate {
+        use super::*;
+
+        #[test]
+        fn requires_creation_time_within_window() {
+            assert!(can_deduplicate(&withdrawal(PendingWithdrawalStatus::Transferring, Some(HOUR_NANOS)), 23 * HOUR_NANOS));
+            assert!(!can_deduplicate(&withdrawal(PendingWithdrawalStatus::Transferring, Some(HOUR_NANOS)), 24 * HOUR_NANOS));
+            assert!(!can_deduplicate(&withdrawal(PendingWithdrawalStatus::Transferring, None), HOUR_NANOS));
+        }
+    }
+
+    mod calculate_verified_amount {
+        use super::*;
+
+        fn nat(value: u64) -> Nat {
+            Nat::from(value)
+        }
+
+        #[test]
+        fn refunds_whole_amount_if_only_fees_were_spent() {
+            let amount = calculate_verified_amount(&nat(1_000), &nat(980), Some(&nat(5_000)), Some(&nat(4_990)));
+
+            assert_eq!(amount, nat(980));
+        }
+
+        #[test]
+        fn refunds_only_what_is_left_of_the_deposit() {
+            let amount = calculate_verified_amount(&nat(1_000), &nat(1_000), Some(&nat(5_000)), Some(&nat(4_600)));
+
+            assert_eq!(amount, nat(600));
+        }
+
+        #[test]
+        fn refunds_nothing_if_the_balance_dropped_by_more_than_the_deposit() {
+            let amount = calculate_verified_amount(&nat(1_000), &nat(1_000), Some(&nat(5_000)), Some(&nat(3_000)));
+
+            assert_eq!(amount, nat(0));
+        }
+
+        #[test]
+        fn refunds_nothing_without_both_balances() {
+            assert_eq!(calculate_verified_amount(&nat(1_000), &nat(1_000), None, Some(&nat(5_000))), nat(0));
+            assert_eq!(calculate_verified_amount(&nat(1_000), &nat(1_000), Some(&nat(5_000)), None), nat(0));
+        }
+    }
+
+    mod is_stale_started_withdrawal {
+        use super::*;
+
+        #[test]
+        fn requires_unlocked_strategy_after_the_delay() {
+            let started = withdrawal(PendingWithdrawalStatus::Started, None);
+
+            assert!(is_stale_started_withdrawal(&started, false, STALE_STARTED_WITHDRAWAL_SECS));
+            assert!(!is_stale_started_withdrawal(&started, true, STALE_STARTED_WITHDRAWAL_SECS));
+            assert!(!is_stale_started_withdrawal(&started, false, STALE_STARTED_WITHDRAWAL_SECS - 1));
+        }
+
+        #[test]
+        fn ignores_other_statuses() {
+            let claimable = withdrawal(PendingWithdrawalStatus::Claimable, None);
+
+            assert!(!is_stale_started_withdrawal(&claimable, false, STALE_STARTED_WITHDRAWAL_SECS));
+        }
+    }
+
+    mod get_status_after_transfer {
+        use super::*;
+
+        #[test]
+        fn completes_transferred_withdrawal() {
+            let status = get_status_after_transfer(PendingWithdrawalStatus::Transferring, &Ok(Nat::from(1u64)));
+
+            assert_eq!(status, PendingWithdrawalStatus::Completed);
+        }
+
+        #[test]
+        fn makes_claimable_only_after_ledger_rejection() {
+            let rejected = Err(error(InternalErrorKind::BusinessLogic));
+            let failed_call = Err(error(InternalErrorKind::ExternalService));
+
+            assert_eq!(
+                get_status_after_transfer(PendingWithdrawalStatus::Claimable, &rejected),
+                PendingWithdrawalStatus::Claimable,
+            );
+            assert_eq!(
+                get_status_after_transfer(PendingWithdrawalStatus::Claimable, &failed_call),
+                PendingWithdrawalStatus::Transferring,
+            );
+            assert_eq!(
+                get_status_after_transfer(PendingWithdrawalStatus::Transferring, &rejected),
+                PendingWithdrawalStatus::Transferring,
+            );
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use candid::{Int, Nat, Principal};
+use icrc_ledger_types::icrc1::account::Account;
+
+use utils::util::{nat_to_f64, nat_to_u64};
+
+use crate::repository::strategies_repo;
+use crate::repository::token_prices_repo;
+use crate::repository::unwinds_repo;
+use crate::repository::user_transactions_repo::{self, UserTransaction, UserTransactionKind};
+use crate::strategies::strategy::IStrategy;
+use crate::strategies::unwind_service;
+use crate::types::types::{
+    ListItemsPaginationRequest,
+    ListItemsPaginationResponse,
+    SortOrder,
+    UserPositionResponse,
+};
+
+const BPS: u64 = 10_000;
+
+/// Values the shares of a user, one position per strategy and account of the user holding shares.
+///
+/// Positions are valued from the cached position liquidity and token prices, so no calls are made.
+pub fn get_user_positions(
+    user: Principal,
+    pagination: ListItemsPaginationRequest,
+) -> ListItemsPaginationResponse<UserPositionResponse> {
+    let mut positions: Vec<(Box<dyn IStrategy>, Account)> = Vec::new();
+
+    for strategy in strategies_repo::get_user_strategies(user) {
+        let mut accounts: Vec<Account> = strategy.get_user_shares()
+            .into_iter()
+            .filter(|(account, shares)| account.owner == user && *shares > Nat::from(0u64))
+            .map(|(account, _)| account)
+            .collect();
+
+        // The default account comes first, then the subaccounts in order
+        accounts.sort_by_key(|account| account.effective_subaccount().clone());
+
+        for account in accounts {
+            positions.push((strategy.clone_self(), account));
+        }
+    }
+
+    if let SortOrder::Desc = pagination.sort_order {
+        positions.reverse();
+    }
+
+    let total = positions.len() as u64;
+    let skip = pagination.page.saturating_sub(1).saturating_mul(pagination.page_size) as usize;
+
+    let items = positions
+        .iter()
+        .skip(skip)
+        .take(pagination.page_size as usize)
+        .map(|(strategy, account)| build_user_position(strategy.as_ref(), *account))
+        .collect();
+
+    ListItemsPaginationResponse {
+        items,
+        total,
+        page: pagination.page,
+        page_size: pagination.page_size,
+    }
+}
+
+fn build_user_position(strategy: &dyn IStrategy, account: Account) -> UserPositionResponse {
+    let strategy_id = strategy.get_id();
+    let base_token = strategy.get_base_token();
+    let user_shares = strategy.get_user_shares_by_account(account);
+    let total_shares = strategy.get_total_shares();
+    let cost_basis = strategy.get_initial_deposit().get(&account).cloned().unwrap_or(Nat::from(0u64));
+
+    // Shares of an unwound strategy are worth their part of the parked balance
+    let (value, valued_at) = match unwinds_repo::get_strategy_unwind(strategy_id) {
+        Some(unwind) if unwind.completed => (
+            unwind_service::preview_redemption(account, strategy_id).map(|redemption| redemption.amount),
+            unwind.completed_at,
+        ),
+        _ => (
+            calculate_shares_value(strategy.get_current_liquidity(), &user_shares, &total_shares),
+            strategy.get_current_liquidity_updated_at(),
+        ),
+    };
+
+    let value_usd = value.as_ref().and_then(|value| {
+        token_prices_repo::get_token_price(base_token)
+            .map(|price| to_usd(value, price.decimals, price.usd_price))
+    });
+
+    let transactions = user_transactions_repo::get_account_strategy_transactions(account, strategy_id);
+
+    UserPositionResponse {
+        strategy_id,
+        strategy_name: strategy.get_name(),
+        base_token,
+        subaccount: account.subaccount,
+        tvl_share_bps: calculate_tvl_share_bps(&user_shares, &total_shares),
+        unrealized_pnl: value.as_ref().map(|value| Int::from(value.clone()) - Int::from(cost_basis.clone())),
+        realized_pnl: calculate_realized_pnl(&transactions, &cost_basis),
+        value,
+        value_usd,
+        user_shares,
+        total_shares,
+        cost_basis,
+        valued_at,
+    }
+}
+
+fn calculate_shares_value(liquidity: Option<Nat>, user_shares: &Nat, total_shares: &Nat) -> Option<Nat> {
+    if *total_shares == Nat::from(0u64) {
+        return None;
+    }
+
+    liquidity.map(|liquidity| liquidity * user_shares.clone() / total_shares.clone())
+}
+
+fn calculate_tvl_share_bps(user_shares: &Nat, total_shares: &Nat) -> u64 {
+    if *total_shares == Nat::from(0u64) {
+        return 0;
+    }
+
+    nat_to_u64(&(user_shares.clone() * Nat::from(BPS) / total_shares.clone()))
+}
+
+/// Returns the withdrawn amount minus the cost basis of the withdrawn shares.
+///
+/// The cost basis left is the remaining initial deposit, so the withdrawn shares cost
+/// the deposited and received cost basis minus it and minus the cost basis sent with
+/// transferred shares. Share mints without amount are fees and cost nothing.
+fn calculate_realized_pnl(transactions: &[UserTransaction], cost_basis: &Nat) -> Int {
+    let sum_amounts = |kind: UserTransactionKind| transactions
+        .iter()
+        .filter(|transaction| transaction.kind == kind)
+        .filter_map(|transaction| transaction.amount.clone())
+        .fold(Nat::from(0u64), |total, amount| total + amount);
+
+    let acquired = sum_amounts(UserTransactionKind::SharesMinted)
+        + sum_amounts(UserTransactionKind::SharesTransferredIn);
+    let withdrawn = sum_amounts(UserTransactionKind::Withdraw);
+    let kept_or_sent = cost_basis.clone() + sum_amounts(UserTransactionKind::SharesTransferredOut);
+
+    let withdrawn_cost = if acquired > kept_or_sent {
+        acquired - kept_or_sent
+    } else {
+        Nat::from(0u64)
+    };
+
+    Int::from(withdrawn) - Int::from(withdrawn_cost)
+}
+
+fn to_usd(amount: &Nat, decimals: u8, usd_price: f64) -> f64 {
+    nat_to_f64(amount) / 10f64.powi(decimals as i32) * usd_price
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(kind: UserTransactionKind, amount: Option<u64>) -> UserTransaction {
+        UserTransaction {
+            id: 0,
+            kind,
+            strategy_id: 1,
+            subaccount: None,
+            token: None,
+            amount: amount.map(Nat::from),
+            shares: None,
+            nav: None,
+            block_index: None,
+            correlation_id: "1".to_string(),
+            timestamp: 0,
+        }
+    }
+
+    mod calculate_shares_value {
+        use super::*;
+
+        #[test]
+        fn values_user_part_of_liquidity() {
+            let value = calculate_shares_value(
+                Some(Nat::from(1_000u64)),
+                &Nat::from(25u64),
+                &Nat::from(100u64),
+            );
+
+            assert_eq!(value, Some(Nat::from(250u64)));
+        }
+
+        #[test]
+        fn is_empty_without_liquidity_or_shares() {
+            assert_eq!(calculate_shares_value(None, &Nat::from(25u64), &Nat::from(100u64)), None);
+            assert_eq!(calculate_shares_value(Some(Nat::from(1_000u64)), &Nat::from(0u64), &Nat::from(0u64)), None);
+        }
+    }
+
+    mod calculate_tvl_share_bps {
+        use super::*;
+
+        #[test]
+        fn returns_basis_points_of_total_shares() {
+            assert_eq!(calculate_tvl_share_bps(&Nat::from(1u64), &Nat::from(3u64)), 3_333);
+            assert_eq!(calculate_tvl_share_bps(&Nat::from(3u64), &Nat::from(3u64)), 10_000);
+            assert_eq!(calculate_tvl_share_bps(&Nat::from(0u64), &Nat::from(0u64)), 0);
+        }
+    }
+
+    mod calculate_realized_pnl {
+        use super::*;
+
+        #[test]
+        fn compares_withdrawn_amount_with_withdrawn_cost() {
+            let transactions = vec![
+                transaction(UserTransactionKind::SharesMinted, Some(1_000)),
+                transaction(UserTransactionKind::SharesMinted, None),
+                transaction(UserTransactionKind::Withdraw, Some(700)),
+                transaction(UserTransactionKind::Claim, Some(700)),
+            ];
+
+            // 400 of the 1000 deposited are still invested, the withdrawn 600 returned 700
+            let pnl = calculate_realized_pnl(&transactions, &Nat::from(400u64));
+
+            assert_eq!(pnl, Int::from(100));
+        }
+
+        #[test]
+        fn excludes_cost_basis_of_transferred_shares() {
+            let transactions = vec![
+                transaction(UserTransactionKind::SharesMinted, Some(1_000)),
+                transaction(UserTransactionKind::SharesTransferredOut, Some(500)),
+                transaction(UserTransactionKind::SharesTransferredIn, Some(200)),
+                transaction(UserTransactionKind::Withdraw, Some(450)),
+            ];
+
+            // 300 of the 700 held are still invested, the withdrawn 400 returned 450
+            let pnl = calculate_realized_pnl(&transactions, &Nat::from(300u64));
+
+            assert_eq!(pnl, Int::from(50));
+        }
+
+        #[test]
+        fn is_zero_without_withdrawals() {
+            let transactions = vec![transaction(UserTransactionKind::SharesMinted, Some(1_000))];
+
+            assert_eq!(calculate_realized_pnl(&transactions, &Nat::from(1_000u64)), Int::from(0));
+        }
+    }
+
+    mod to_usd {
+        use super::*;
+
+        #[test]
+        fn scales_by_decimals_and_price() {
+            assert_eq!(to_usd(&Nat::from(250_000_000u64), 8, 4.0), 10.0);
+        }
+    }
+}
@@ -1,34 +1,17 @@
-use std::cell::RefCell;
 use candid::{Nat, Principal};
-use ic_cdk::api::time;
-use ic_cdk::caller;
+use icrc_ledger_types::icrc1::account::Account;
 
 use types::CanisterId;
 use types::context::Context;
 use errors::internal_error::error::InternalError;
-use ::utils::util::nat_to_u64;
-use types::strategies::StrategyId;
+use utils::util::current_timestamp_secs;
 
+use crate::repository::pending_withdrawals_repo::PendingWithdrawal;
+use crate::repository::user_transactions_repo::{self, UserTransaction, UserTransactionKind};
+use crate::types::types::{ListItemsPaginationResponse, UserTransactionFilters};
 use crate::utils::service_resolver::get_service_resolver;
 
-thread_local! {
-    pub static USER_ACCOUNTS: RefCell<Vec<UserAccount>> = RefCell::new(Default::default());
-}
-
-struct UserAccount {
-    user_id: Principal,
-    deposits: Vec<UserDeposit>
-}
-
-#[allow(unused)]
-struct UserDeposit {
-    amount: Nat,
-    strategy: StrategyId,
-    ledger: CanisterId,
-    block_index: u64,
-    timestamp: u64
-}
-
+/// Transfers the deposited amount from the user account to the vault and records it in the user ledger.
 pub async fn accept_deposit(
     context: Context,
     amount: Nat,
@@ -39,31 +22,126 @@ pub async fn accept_deposit(
     let user = context.user.unwrap();
 
     let block_index = icrc_ledger_client.icrc2_transfer_from(
-        user,
+        context.account().unwrap(),
         ledger,
         amount.clone()
     ).await?;
 
-    let deposit = UserDeposit {
+    user_transactions_repo::add_user_transaction(user, UserTransaction {
+        token: Some(ledger),
+        amount: Some(amount),
+        block_index: Some(block_index),
+        ..new_transaction(UserTransactionKind::Deposit, &context)
+    });
+
+    Ok(())
+}
+
+/// Records the shares credited to the account for a base token amount, or for the fees without amount.
+pub fn record_shares_minted(
+    account: Account,
+    context: &Context,
+    shares: Nat,
+    amount: Option<Nat>,
+    nav: Nat,
+) {
+    user_transactions_repo::add_user_transaction(account.owner, UserTransaction {
+        subaccount: account.subaccount,
+        shares: Some(shares),
         amount,
-        strategy: context.strategy_id.unwrap(),
-        ledger: ledger.into(),
-        block_index: nat_to_u64(&block_index),
-        timestamp: time()
+        nav: Some(nav),
+        ..new_transaction(UserTransactionKind::SharesMinted, context)
+    });
+}
+
+/// Records the base token withdrawn from the strategy positions for the user.
+pub fn record_withdraw(context: &Context, token: CanisterId, amount: Nat, nav: Option<Nat>) {
+    user_transactions_repo::add_user_transaction(context.user.unwrap(), UserTransaction {
+        token: Some(token),
+        amount: Some(amount),
+        nav,
+        ..new_transaction(UserTransactionKind::Withdraw, context)
+    });
+}
+
+/// Records the shares burned for a withdrawal or a redemption.
+pub fn record_shares_burned(context: &Context, shares: Nat, nav: Option<Nat>) {
+    user_transactions_repo::add_user_transaction(context.user.unwrap(), UserTransaction {
+        shares: Some(shares),
+        nav,
+        ..new_transaction(UserTransactionKind::SharesBurned, context)
+    });
+}
+
+/// Records the shares moved between two positions with their cost basis,
+/// each row keeps the subaccount of its position.
+pub fn record_shares_transferred(
+    from: Account,
+    to: Account,
+    context: &Context,
+    shares: Nat,
+    cost_basis: Nat,
+    block_index: Nat,
+) {
+    let transaction = UserTransaction {
+        shares: Some(shares),
+        amount: Some(cost_basis),
+        block_index: Some(block_index),
+        subaccount: from.subaccount,
+        ..new_transaction(UserTransactionKind::SharesTransferredOut, context)
     };
 
-    USER_ACCOUNTS.with(|accounts| {
-        let mut accounts = accounts.borrow_mut();
-        let index = accounts.iter().position(|a| a.user_id == user);
-        if let Some(index) = index {
-            accounts[index].deposits.push(deposit);
-        } else {
-            accounts.push(UserAccount {
-                user_id: caller(),
-                deposits: vec![deposit]
-            });
-        }
+    user_transactions_repo::add_user_transaction(from.owner, transaction.clone());
+    user_transactions_repo::add_user_transaction(to.owner, UserTransaction {
+        kind: UserTransactionKind::SharesTransferredIn,
+        subaccount: to.subaccount,
+        ..transaction
     });
+}
 
-    Ok(())
+/// Records the transfer of a pending withdrawal to its user.
+pub fn record_claim(withdrawal: &PendingWithdrawal, block_index: Nat) {
+    // Withdrawals created before the correlation ID was kept get a new one
+    let context = match withdrawal.correlation_id.clone() {
+        Some(correlation_id) => Context::new(correlation_id, Some(withdrawal.user), Some(withdrawal.strategy_id)),
+        None => Context::generate(Some(withdrawal.user), Some(withdrawal.strategy_id)),
+    };
+
+    user_transactions_repo::add_user_transaction(withdrawal.user, UserTransaction {
+        token: Some(withdrawal.token),
+        amount: withdrawal.amount.clone(),
+        shares: Some(withdrawal.shares.clone()).filter(|shares| *shares > Nat::from(0u64)),
+        block_index: Some(block_index),
+        ..new_transaction(UserTransactionKind::Claim, &context)
+    });
+}
+
+pub fn get_user_transactions(
+    user: Principal,
+    filters: UserTransactionFilters,
+) -> ListItemsPaginationResponse<UserTransaction> {
+    let (items, total) = user_transactions_repo::get_user_transactions(user, &filters);
+
+    ListItemsPaginationResponse {
+        items,
+        total,
+        page: filters.pagination.page,
+        page_size: filters.pagination.page_size,
+    }
+}
+
+fn new_transaction(kind: UserTransactionKind, context: &Context) -> UserTransaction {
+    UserTransaction {
+        id: 0,
+        kind,
+        strategy_id: context.strategy_id.unwrap_or_default(),
+        subaccount: context.subaccount,
+        token: None,
+        amount: None,
+        shares: None,
+        nav: None,
+        block_index: None,
+        correlation_id: context.correlation_id.clone(),
+        timestamp: current_timestamp_secs(),
+    }
 }
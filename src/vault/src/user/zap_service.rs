@@ -0,0 +1,363 @@
+use candid::Nat;
+
+use types::CanisterId;
+use types::context::Context;
+use types::exchange_id::ExchangeId;
+use swap::swap_service;
+use errors::internal_error::error::{InternalError, InternalErrorKind};
+use errors::internal_error::error_codes::module::areas::{
+    canisters as canister_area,
+    canisters::domains::vault as vault_domain,
+    canisters::domains::vault::components as vault_domain_components,
+};
+
+use crate::event_records::event_record::Event;
+use crate::event_records::event_record_service;
+use crate::strategies::emergency_service;
+use crate::utils::service_resolver::get_service_resolver;
+
+// Module code: "03-01-01"
+errors::define_error_code_builder_fn!(
+    build_error_code,
+    canister_area::AREA_CODE,     // Area code: "03"
+    vault_domain::DOMAIN_CODE,    // Domain code: "01"
+    vault_domain_components::CORE // Component code: "01"
+);
+
+/// Swap between the base token of a strategy and another token chosen by the user,
+/// for deposits in and withdrawals to that token.
+#[derive(Clone, Debug)]
+pub struct ZapQuote {
+    pub token_in: CanisterId,
+    pub token_out: CanisterId,
+    /// Amount left for the swap after the transfer fees
+    pub amount_in: Nat,
+    pub min_amount_out: Nat,
+    /// Amount quoted for the swap
+    pub amount_out: Nat,
+    pub provider: ExchangeId,
+}
+
+/// Failed zap with the tokens to pay back to the user.
+///
+/// The output tokens of a swap below the minimum amount are held by the vault, the input tokens
+/// of a failed swap may have reached the DEX, so only the part still held by the vault is refunded.
+#[derive(Clone, Debug)]
+pub struct ZapFailure {
+    pub ledger: CanisterId,
+    pub amount: Nat,
+    pub error: InternalError,
+}
+
+/// Checks that the amount can be swapped to the output token for at least the minimum amount.
+pub async fn quote_zap(
+    context: Context,
+    token_in: CanisterId,
+    token_out: CanisterId,
+    amount: Nat,
+    min_amount_out: Option<Nat>,
+) -> Result<ZapQuote, InternalError> {
+    let service_resolver = get_service_resolver();
+
+    let min_amount_out = min_amount_out.ok_or_else(|| {
+        InternalError::validation(
+            build_error_code(InternalErrorKind::Validation, 10), // Error code: "03-01-01 02 10"
+            "zap_service::quote_zap".to_string(),
+            "Minimum amount out is required to swap to another token than the base token".to_string(),
+            errors::error_extra! {
+                "context" => context,
+                "token_in" => token_in,
+                "token_out" => token_out,
+                "amount" => amount,
+            },
+        )
+    })?;
+
+    let token_in_fee = service_resolver.icrc_ledger_client()
+        .icrc1_fee(token_in)
+        .await?;
+
+    let amount_in = calculate_zap_amount_in(&amount, &token_in_fee)
+        .ok_or_else(|| {
+            InternalError::validation(
+                build_error_code(InternalErrorKind::Validation, 11), // Error code: "03-01-01 02 11"
+                "zap_service::quote_zap".to_string(),
+                "Amount doesn't cover the swap fees".to_string(),
+                errors::error_extra! {
+                    "context" => context,
+                    "token_in" => token_in,
+                    "amount" => amount,
+                    "token_in_fee" => token_in_fee,
+                },
+            )
+        })?;
+
+    let quote = swap_service::quote_swap_icrc2_optimal(
+        service_resolver.provider_impls(),
+        service_resolver.icrc_ledger_client(),
+        token_in,
+        token_out,
+        amount_in.clone(),
+    ).await
+        .map_err(|error| {
+            InternalError::validation(
+                build_error_code(InternalErrorKind::Validation, 12), // Error code: "03-01-01 02 12"
+                "zap_service::quote_zap".to_string(),
+                "No swap route from the token to the output token".to_string(),
+                errors::error_extra! {
+                    "context" => context,
+                    "token_in" => token_in,
+                    "token_out" => token_out,
+                    "amount_in" => amount_in,
+                    "error" => error.message,
+                },
+            )
+        })?;
+
+    let quoted_amount_out = Nat::from(quote.amount_out);
+
+    check_quoted_amount_out(&context, token_in, token_out, &amount_in, &quoted_amount_out, &min_amount_out)?;
+
+    Ok(ZapQuote {
+        token_in,
+        token_out,
+        amount_in,
+        min_amount_out,
+        amount_out: quoted_amount_out,
+        provider: quote.provider,
+    })
+}
+
+/// Rejects a quote below the minimum amount out before any token is moved.
+fn check_quoted_amount_out(
+    context: &Context,
+    token_in: CanisterId,
+    token_out: CanisterId,
+    amount_in: &Nat,
+    quoted_amount_out: &Nat,
+    min_amount_out: &Nat,
+) -> Result<(), InternalError> {
+    if quoted_amount_out < min_amount_out {
+        return Err(InternalError::validation(
+            build_error_code(InternalErrorKind::Validation, 13), // Error code: "03-01-01 02 13"
+            "zap_service::quote_zap".to_string(),
+            "Quoted amount is below the minimum amount out".to_string(),
+            errors::error_extra! {
+                "context" => context,
+                "token_in" => token_in,
+                "token_out" => token_out,
+                "amount_in" => amount_in,
+                "quoted_amount_out" => quoted_amount_out,
+                "min_amount_out" => min_amount_out,
+            },
+        ));
+    }
+
+    Ok(())
+}
+
+/// Swaps the quoted amount to the output token with the quoted provider.
+///
+/// Returns the amount received, or the tokens held by the vault if the swap fails
+/// or returns less than the minimum amount.
+pub async fn zap(
+    context: Context,
+    quote: ZapQuote,
+) -> Result<Nat, ZapFailure> {
+    let service_resolver = get_service_resolver();
+    let strategy_id = context.strategy_id.unwrap().to_string();
+
+    // Event: Strategy zap started
+    event_record_service::create_event_record(
+        Event::strategy_zap_started(
+            strategy_id.clone(),
+            quote.token_in,
+            quote.token_out,
+            quote.amount_in.clone(),
+            quote.min_amount_out.clone(),
+        ),
+        context.correlation_id.clone(),
+        context.user,
+        context.strategy_id,
+    );
+
+    let zap_failed = |ledger: CanisterId, amount: Nat, error: InternalError| {
+        // Event: Strategy zap failed
+        event_record_service::create_event_record(
+            Event::strategy_zap_failed(
+                strategy_id.clone(),
+                quote.token_in,
+                quote.token_out,
+                quote.amount_in.clone(),
+                error.clone(),
+            ),
+            context.correlation_id.clone(),
+            context.user,
+            context.strategy_id,
+        );
+
+        ZapFailure { ledger, amount, error }
+    };
+
+    let swap_response = swap_service::swap_icrc2(
+        service_resolver.provider_impls(),
+        service_resolver.icrc_ledger_client(),
+        quote.token_in,
+        quote.token_out,
+        quote.amount_in.clone(),
+        quote.provider,
+    ).await
+        .map_err(|error| zap_failed(quote.token_in, quote.amount_in.clone(), error))?;
+
+    let amount_out = Nat::from(swap_response.amount_out);
+
+    emergency_service::check_swap_slippage(context.strategy_id.unwrap(), &quote.amount_out, &amount_out);
+
+    // The swapped tokens are paid in the output token
+    if amount_out < quote.min_amount_out {
+        let error = InternalError::business_logic(
+            build_error_code(InternalErrorKind::BusinessLogic, 14), // Error code: "03-01-01 03 14"
+            "zap_service::zap".to_string(),
+            "Swapped amount is below the minimum amount out".to_string(),
+            errors::error_extra! {
+                "context" => context,
+                "token_in" => quote.token_in,
+                "token_out" => quote.token_out,
+                "amount_in" => quote.amount_in,
+                "amount_out" => amount_out,
+                "min_amount_out" => quote.min_amount_out,
+            },
+        );
+
+        return Err(zap_failed(quote.token_out, amount_out, error));
+    }
+
+    // Event: Strategy zap completed
+    event_record_service::create_event_record(
+        Event::strategy_zap_completed(
+            strategy_id,
+            quote.token_in,
+            quote.token_out,
+            quote.amount_in.clone(),
+            amount_out.clone(),
+        ),
+        context.correlation_id.clone(),
+        context.user,
+        context.strategy_id,
+    );
+
+    Ok(amount_out)
+}
+
+/// Returns the token and amount to pay for a zap to the output token,
+/// with the zap error if the tokens of the failed zap are paid instead.
+pub fn get_zap_payout(
+    token_out: CanisterId,
+    zap_result: Result<Nat, ZapFailure>,
+) -> (CanisterId, Nat, Option<InternalError>) {
+    match zap_result {
+        Ok(amount_out) => (token_out, amount_out, None),
+        Err(failure) => (failure.ledger, failure.amount, Some(failure.error)),
+    }
+}
+
+/// Amount left for the swap after the approval fee and the transfer fee of the swap.
+pub fn calculate_zap_amount_in(amount: &Nat, token_fee: &Nat) -> Option<Nat> {
+    let fees = token_fee.clone() * Nat::from(2u64);
+
+    if *amount > fees {
+        Some(amount.clone() - fees)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use candid::Principal;
+
+    fn token(id: u8) -> CanisterId {
+        Principal::from_slice(&[id; 29])
+    }
+
+    mod check_quoted_amount_out {
+        use super::*;
+
+        fn check(quoted_amount_out: u64, min_amount_out: u64) -> Result<(), InternalError> {
+            check_quoted_amount_out(
+                &Context::new("test".to_string(), None, Some(1)),
+                token(1),
+                token(2),
+                &Nat::from(1_000u64),
+                &Nat::from(quoted_amount_out),
+                &Nat::from(min_amount_out),
+            )
+        }
+
+        #[test]
+        fn rejects_quote_below_min_amount_out() {
+            let error = check(899, 900).unwrap_err();
+
+            assert!(matches!(error.kind, InternalErrorKind::Validation));
+            assert_eq!(error.message, "Quoted amount is below the minimum amount out");
+        }
+
+        #[test]
+        fn accepts_quote_at_min_amount_out() {
+            assert!(check(900, 900).is_ok());
+            assert!(check(901, 900).is_ok());
+        }
+    }
+
+    mod get_zap_payout {
+        use super::*;
+
+        #[test]
+        fn pays_swapped_amount_in_output_token() {
+            let payout = get_zap_payout(token(2), Ok(Nat::from(500u64)));
+
+            assert_eq!(payout.0, token(2));
+            assert_eq!(payout.1, Nat::from(500u64));
+            assert!(payout.2.is_none());
+        }
+
+        #[test]
+        fn falls_back_to_base_token_with_the_zap_error() {
+            let failure = ZapFailure {
+                ledger: token(1),
+                amount: Nat::from(1_000u64),
+                error: InternalError::business_logic(
+                    1,
+                    "test".to_string(),
+                    "Swap failed".to_string(),
+                    None,
+                ),
+            };
+
+            let payout = get_zap_payout(token(2), Err(failure));
+
+            assert_eq!(payout.0, token(1));
+            assert_eq!(payout.1, Nat::from(1_000u64));
+            assert_eq!(payout.2.map(|error| error.message), Some("Swap failed".to_string()));
+        }
+    }
+
+    mod calculate_zap_amount_in {
+        use super::*;
+
+        #[test]
+        fn reserves_approval_and_transfer_fees() {
+            let amount_in = calculate_zap_amount_in(&Nat::from(1_000u64), &Nat::from(10u64));
+
+            assert_eq!(amount_in, Some(Nat::from(980u64)));
+        }
+
+        #[test]
+        fn rejects_amount_not_covering_fees() {
+            assert_eq!(calculate_zap_amount_in(&Nat::from(20u64), &Nat::from(10u64)), None);
+            assert_eq!(calculate_zap_amount_in(&Nat::from(0u64), &Nat::from(0u64)), None);
+        }
+    }
+}